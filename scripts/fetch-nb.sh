@@ -0,0 +1,40 @@
+#!/bin/sh
+# downloads the nb script pinned in src-tauri/src/nb.rs into the bundle's
+# resources and checks it against the pinned sha256. does nothing when the
+# resource is already up to date. fails when no sha256 is pinned, the
+# download fails or the script does not match, so a build never ships an
+# unverified script.
+set -eu
+
+root="$(cd "$(dirname "$0")/.." && pwd)"
+nb_rs="$root/src-tauri/src/nb.rs"
+target="$root/src-tauri/resources/bin/nb"
+
+version="$(sed -n 's/^const NB_VERSION: &str = "\(.*\)";$/\1/p' "$nb_rs")"
+sha256="$(sed -n 's/^const NB_SHA256: Option<&str> = Some("\(.*\)");$/\1/p' "$nb_rs")"
+
+if [ -z "$sha256" ]; then
+  rm -f "$target"
+  echo "no sha256 pinned for nb $version in src-tauri/src/nb.rs" >&2
+  exit 1
+fi
+
+if [ -f "$target" ] && [ "$(shasum -a 256 "$target" | cut -d ' ' -f 1)" = "$sha256" ]; then
+  exit 0
+fi
+rm -f "$target"
+
+echo "fetching nb $version"
+if ! curl -fsSL -o "$target.download" "https://raw.githubusercontent.com/xwmx/nb/$version/nb"; then
+  rm -f "$target.download"
+  echo "failed to fetch nb $version" >&2
+  exit 1
+fi
+actual="$(shasum -a 256 "$target.download" | cut -d ' ' -f 1)"
+if [ "$actual" != "$sha256" ]; then
+  rm -f "$target.download"
+  echo "nb $version failed verification: expected sha256 $sha256, got $actual" >&2
+  exit 1
+fi
+chmod +x "$target.download"
+mv "$target.download" "$target"
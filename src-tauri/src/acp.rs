@@ -1,23 +1,53 @@
 use std::{
-    collections::HashMap, io::Write as IoWrite, path::PathBuf, str::FromStr, sync::Arc,
+    collections::{HashMap, VecDeque},
+    io::Write as IoWrite,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
     time::Duration,
 };
 
 use sacp::{
     schema::{
-        AvailableCommandInput, CancelNotification, ContentBlock, CurrentModeUpdate,
-        InitializeRequest, PermissionOptionKind, PlanEntryStatus, ProtocolVersion,
-        RequestPermissionOutcome, RequestPermissionRequest, RequestPermissionResponse,
+        AvailableCommandInput, CancelNotification, ClientCapabilities, ContentBlock,
+        CreateTerminalRequest, CreateTerminalResponse, CurrentModeUpdate, EmbeddedResource,
+        EmbeddedResourceResource, FileSystemCapability, InitializeRequest,
+        KillTerminalCommandRequest, KillTerminalCommandResponse, PermissionOptionKind,
+        PlanEntryStatus, PromptRequest, ProtocolVersion, ReadTextFileRequest, ReadTextFileResponse,
+        ReleaseTerminalRequest, ReleaseTerminalResponse, RequestPermissionOutcome,
+        RequestPermissionRequest, RequestPermissionResponse, ResourceLink,
         SelectedPermissionOutcome, SessionNotification, SessionUpdate, SetSessionModeRequest,
-        StopReason, ToolCall, ToolCallContent, ToolCallLocation, ToolCallStatus, ToolKind,
+        StopReason, TerminalExitStatus, TerminalId, TerminalOutputRequest, TerminalOutputResponse,
+        ToolCall, ToolCallContent, ToolCallLocation, ToolCallStatus, ToolKind,
+        WaitForTerminalExitRequest, WaitForTerminalExitResponse, WriteTextFileRequest,
+        WriteTextFileResponse,
     },
     util::MatchMessage,
     ClientToAgent, SessionMessage,
 };
 use sacp_tokio::{AcpAgent, LineDirection};
 use serde::{Deserialize, Serialize};
-use tauri::{ipc::Channel, AppHandle, Emitter, Manager, State};
-use tokio::sync::{mpsc, oneshot};
+use tauri::{
+    ipc::{Channel, InvokeResponseBody},
+    AppHandle, Emitter, Manager, State,
+};
+use tokio::sync::{mpsc, oneshot, watch};
+
+use crate::{
+    audit::{self, AuditEntry, AuditEventKind},
+    checkpoints,
+    constants::{
+        PERMISSION_RULES_KEY, PERMISSION_TIMEOUT_KEY, PROXY_SETTINGS_KEY, THINKING_LEVELS_KEY,
+        VAULT_ONLY_AGENTS_KEY,
+    },
+    encryption,
+    file_watcher::{self, FileChange},
+    fs_cache,
+    journal::{self, JournalOp, JournalOrigin},
+    locks, nb, secrets,
+    utils::{get_base_dir, get_setting, set_setting, write_atomic},
+    vault_commands,
+};
 
 type AcpProcessLog = Arc<std::sync::Mutex<std::io::BufWriter<std::fs::File>>>;
 
@@ -72,6 +102,11 @@ pub struct AcpState(pub(crate) tokio::sync::Mutex<AcpStateInner>);
 pub(crate) struct AcpStateInner {
     /// One entry per running agent process, keyed by caller-provided agent_id.
     agents: HashMap<String, AgentHandle>,
+    /// Recent stderr lines per agent_id. Kept after the process exits so the
+    /// tail is still available when reporting a crash; replaced on reconnect.
+    stderr_tails: HashMap<String, CapturedStderr>,
+    /// Recent finished prompts per agent_id, oldest first. Kept across reconnects.
+    prompt_records: HashMap<String, VecDeque<PromptRecord>>,
 }
 
 struct AgentHandle {
@@ -89,10 +124,43 @@ impl Default for AcpState {
     fn default() -> Self {
         Self(tokio::sync::Mutex::new(AcpStateInner {
             agents: HashMap::new(),
+            stderr_tails: HashMap::new(),
+            prompt_records: HashMap::new(),
         }))
     }
 }
 
+/// Per-agent timeout overrides passed to `acp_connect`. Unset fields use the defaults.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentTimeouts {
+    pub initialize_secs: Option<u64>,
+    pub session_new_secs: Option<u64>,
+    /// Fail a streaming prompt after this long without any session update.
+    /// Disabled when unset.
+    pub prompt_inactivity_secs: Option<u64>,
+}
+
+impl AgentTimeouts {
+    fn initialize(&self) -> Duration {
+        self.initialize_secs
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_INITIALIZE_TIMEOUT)
+    }
+
+    fn session_new(&self) -> Duration {
+        self.session_new_secs
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_SESSION_NEW_TIMEOUT)
+    }
+
+    fn prompt_inactivity(&self) -> Option<Duration> {
+        self.prompt_inactivity_secs
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs)
+    }
+}
+
 #[derive(Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AgentInfo {
@@ -103,6 +171,63 @@ pub struct AgentInfo {
     pub log_file: Option<String>,
 }
 
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentDiagnostics {
+    pub agent_id: String,
+    pub running: bool,
+    /// Last stderr lines written by the agent process, oldest first.
+    pub stderr_tail: Vec<String>,
+    pub log_file: Option<String>,
+}
+
+/// Metrics of one finished prompt turn.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptRecord {
+    pub session_id: String,
+    pub started_at_ms: i64,
+    pub ended_at_ms: i64,
+    /// Agent message and thought chunks received.
+    pub chunk_count: u64,
+    pub tool_call_count: u64,
+    /// ACP stop reason, or "error" when the turn failed before the agent stopped.
+    pub stop_reason: String,
+}
+
+/// Aggregates over the recent prompts of an agent, as returned by `acp_get_stats`.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentStats {
+    pub agent_id: String,
+    pub prompt_count: u64,
+    pub total_duration_ms: i64,
+    pub average_duration_ms: i64,
+    pub max_duration_ms: i64,
+    pub total_chunks: u64,
+    pub total_tool_calls: u64,
+    /// Number of prompts per stop reason (end_turn, max_tokens, refusal, cancelled, error...).
+    pub stop_reasons: HashMap<String, u64>,
+    /// The records the aggregates were computed from, oldest first.
+    pub recent: Vec<PromptRecord>,
+}
+
+/// Bookkeeping for a session created on an agent, as listed by `acp_list_sessions`.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionMetadata {
+    pub session_id: String,
+    pub cwd: String,
+    pub created_at_ms: i64,
+    pub current_mode_id: Option<String>,
+    pub current_model_id: Option<String>,
+    /// Reasoning effort last applied with `acp_set_thinking`, if any.
+    pub thinking_level: Option<String>,
+    pub prompt_count: u64,
+    /// Whether a prompt is currently streaming in this session.
+    pub prompt_running: bool,
+}
+
 #[derive(Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct AuthMethodInfo {
@@ -120,6 +245,7 @@ pub struct SessionInfo {
     pub available_commands: Vec<SlashCommandInfo>,
     pub available_models: Vec<ModelInfoData>,
     pub current_model_id: Option<String>,
+    pub config_options: Vec<ConfigOptionInfo>,
 }
 
 #[derive(Clone, Serialize)]
@@ -138,6 +264,25 @@ pub struct SlashCommandInfo {
     pub input_hint: Option<String>,
 }
 
+/// A session config option (e.g. a select with a current value), passed through
+/// from the wire mostly as-is since the schema is still unstable.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigOptionInfo {
+    pub id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(rename = "type", default)]
+    pub kind: Option<String>,
+    #[serde(default)]
+    pub current_value: Option<serde_json::Value>,
+    #[serde(default)]
+    pub options: Option<serde_json::Value>,
+}
+
 #[derive(Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ModelInfoData {
@@ -147,11 +292,123 @@ pub struct ModelInfoData {
     pub description: Option<String>,
 }
 
-#[derive(Clone, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct AcpConnectionError {
-    kind: String,
-    message: String,
+/// Error returned by every `acp_*` command. Serialized as `{ kind, message, ... }`
+/// so the frontend can branch on `kind` instead of re-parsing strings.
+#[derive(Clone, Debug, Serialize, thiserror::Error)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AcpError {
+    #[error("{message}")]
+    AuthRequired { message: String },
+    #[error("{message}")]
+    Timeout { message: String },
+    #[error("{message}")]
+    #[serde(rename_all = "camelCase")]
+    AgentNotConnected { agent_id: String, message: String },
+    #[error("{message}")]
+    #[serde(rename_all = "camelCase")]
+    SessionNotFound { session_id: String, message: String },
+    #[error("{message}")]
+    Wire {
+        code: i32,
+        message: String,
+        data: Option<serde_json::Value>,
+    },
+    #[error("{message}")]
+    Internal { message: String },
+}
+
+impl AcpError {
+    fn internal(message: impl Into<String>) -> Self {
+        Self::Internal {
+            message: message.into(),
+        }
+    }
+
+    fn timeout(message: impl Into<String>) -> Self {
+        Self::Timeout {
+            message: message.into(),
+        }
+    }
+
+    fn agent_not_connected(agent_id: &str) -> Self {
+        Self::AgentNotConnected {
+            agent_id: agent_id.to_string(),
+            message: format!("agent '{agent_id}' is not connected"),
+        }
+    }
+
+    fn agent_not_running(agent_id: &str) -> Self {
+        Self::AgentNotConnected {
+            agent_id: agent_id.to_string(),
+            message: format!("agent '{agent_id}' is not running"),
+        }
+    }
+
+    fn session_not_found(session_id: &str) -> Self {
+        Self::SessionNotFound {
+            session_id: session_id.to_string(),
+            message: format!("session '{session_id}' not found"),
+        }
+    }
+
+    fn from_wire(error: &RawJsonRpcError) -> Self {
+        let message = extract_wire_error_detail(error);
+        if error.code == -32000 {
+            Self::AuthRequired { message }
+        } else {
+            Self::Wire {
+                code: error.code,
+                message,
+                data: error.data.clone(),
+            }
+        }
+    }
+
+    fn from_sacp(error: &sacp::Error) -> Self {
+        use sacp::schema::ErrorCode;
+        let message = clean_sacp_error_message(&error.to_string());
+        if matches!(error.code, ErrorCode::AuthRequired) {
+            return Self::AuthRequired { message };
+        }
+        let code = serde_json::to_value(&error.code)
+            .ok()
+            .and_then(|value| value.as_i64())
+            .map(|code| code as i32)
+            .unwrap_or(-32603);
+        Self::Wire {
+            code,
+            message,
+            data: None,
+        }
+    }
+
+    /// Whether a failed request is worth retrying: internal and implementation-defined
+    /// server errors usually mean the agent is still warming up. Auth and
+    /// malformed-request errors are permanent.
+    fn is_retryable(&self) -> bool {
+        match self {
+            Self::Wire { code, .. } => *code == -32603 || (-32099..=-32001).contains(code),
+            _ => false,
+        }
+    }
+
+    /// Short kind label used for crash events and logs.
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::AuthRequired { .. } => "auth_required",
+            Self::Timeout { .. } => "timeout",
+            Self::AgentNotConnected { .. } => "agent_not_connected",
+            Self::SessionNotFound { .. } => "session_not_found",
+            Self::Wire { .. } => "wire",
+            Self::Internal { .. } => "internal",
+        }
+    }
+}
+
+impl From<String> for AcpError {
+    fn from(message: String) -> Self {
+        Self::internal(message)
+    }
 }
 
 #[derive(Clone, Serialize)]
@@ -177,6 +434,86 @@ pub struct DiffInfo {
     pub new_text: Option<String>,
 }
 
+/// A remembered "allow always" permission decision, persisted in the settings store.
+/// Matches future requests from the same agent for the same tool kind whose
+/// locations all fall under `path_prefix` (an empty prefix matches location-less requests).
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionRule {
+    pub agent_id: String,
+    pub tool_kind: String,
+    pub path_prefix: String,
+}
+
+/// App-level proxy configuration injected into every agent process's environment.
+/// Empty values are treated as unset.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProxySettings {
+    pub http_proxy: Option<String>,
+    pub https_proxy: Option<String>,
+    pub no_proxy: Option<String>,
+}
+
+impl ProxySettings {
+    /// Environment variables for the configured proxies, in both the upper- and
+    /// lowercase spellings since tools disagree on which one they read.
+    fn env_vars(&self) -> Vec<(String, String)> {
+        [
+            ("HTTP_PROXY", &self.http_proxy),
+            ("HTTPS_PROXY", &self.https_proxy),
+            ("NO_PROXY", &self.no_proxy),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let value = value.as_deref()?.trim();
+            (!value.is_empty()).then_some((name, value))
+        })
+        .flat_map(|(name, value)| {
+            [
+                (name.to_string(), value.to_string()),
+                (name.to_lowercase(), value.to_string()),
+            ]
+        })
+        .collect()
+    }
+}
+
+/// What the backend answers on the user's behalf when a permission request times out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionTimeoutDecision {
+    /// Select the agent's "reject once" option (cancel if it offers none).
+    #[default]
+    RejectOnce,
+    /// Respond with the cancelled outcome.
+    Cancel,
+}
+
+/// How long a permission request may stay unanswered before the default decision is sent.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PermissionTimeoutSettings {
+    /// Seconds to wait for the user. 0 waits forever.
+    pub timeout_secs: u64,
+    pub default_decision: PermissionTimeoutDecision,
+}
+
+impl Default for PermissionTimeoutSettings {
+    fn default() -> Self {
+        Self {
+            timeout_secs: DEFAULT_PERMISSION_TIMEOUT.as_secs(),
+            default_decision: PermissionTimeoutDecision::default(),
+        }
+    }
+}
+
+impl PermissionTimeoutSettings {
+    fn timeout(&self) -> Option<Duration> {
+        (self.timeout_secs > 0).then(|| Duration::from_secs(self.timeout_secs))
+    }
+}
+
 #[derive(Clone, Serialize)]
 #[serde(rename_all = "camelCase", tag = "event", content = "data")]
 pub enum AgentEvent {
@@ -204,6 +541,13 @@ pub enum AgentEvent {
         kind: Option<String>,
         options: Vec<PermissionOptionInfo>,
     },
+    /// The user did not answer `request_id` in time and the default decision was sent.
+    /// `option_id` is the selected option, or `None` when the request was cancelled.
+    #[serde(rename_all = "camelCase")]
+    PermissionTimedOut {
+        request_id: String,
+        option_id: Option<String>,
+    },
     PlanUpdate {
         entries: Vec<PlanEntryInfo>,
     },
@@ -215,11 +559,33 @@ pub enum AgentEvent {
         commands: Vec<SlashCommandInfo>,
     },
     #[serde(rename_all = "camelCase")]
+    ConfigOptionsUpdate {
+        config_options: Vec<ConfigOptionInfo>,
+    },
+    /// A `resource_link` content block pointing at a note inside the vault.
+    /// `path` is relative to the vault root so it can be opened in an editor tab.
+    ResourceLink {
+        path: String,
+        title: String,
+    },
+    #[serde(rename_all = "camelCase")]
     Done {
         stop_reason: String,
+        /// Wall-clock duration of the turn.
+        duration_ms: i64,
+        tool_call_count: u64,
+        chunk_count: u64,
+        /// The response was cut off by the agent's token limit.
+        truncated: bool,
+        /// The turn stopped at a limit (tokens or turn requests) rather than finishing,
+        /// so sending "continue" would pick up where it left off.
+        can_continue: bool,
     },
     Error {
         message: String,
+        /// Set for classified failures, e.g. "timeout" for prompt inactivity.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        kind: Option<String>,
     },
 }
 
@@ -233,36 +599,74 @@ struct AgentCrashPayload {
 
 enum AgentCommand {
     GetInfo {
-        respond_to: oneshot::Sender<Result<AgentInfo, String>>,
+        respond_to: oneshot::Sender<Result<AgentInfo, AcpError>>,
     },
     NewSession {
         cwd: String,
-        respond_to: oneshot::Sender<Result<SessionInfo, String>>,
+        thinking_level: Option<String>,
+        respond_to: oneshot::Sender<Result<SessionInfo, AcpError>>,
     },
     Prompt {
         session_id: String,
         text: String,
+        /// Mentioned notes and folders, sent after the text.
+        mentions: Vec<EmbeddedResource>,
+        vault_root: Option<PathBuf>,
+        app_handle: AppHandle,
         on_event: Channel<AgentEvent>,
-        respond_to: oneshot::Sender<Result<(), String>>,
+        respond_to: oneshot::Sender<Result<(), AcpError>>,
     },
     RespondPermission {
         request_id: String,
         option_id: String,
-        respond_to: oneshot::Sender<Result<(), String>>,
+        respond_to: oneshot::Sender<Result<(), AcpError>>,
     },
     Cancel {
         session_id: String,
-        respond_to: oneshot::Sender<Result<(), String>>,
+        respond_to: oneshot::Sender<Result<(), AcpError>>,
     },
     SetMode {
         session_id: String,
         mode_id: String,
-        respond_to: oneshot::Sender<Result<(), String>>,
+        respond_to: oneshot::Sender<Result<(), AcpError>>,
     },
     SetModel {
         session_id: String,
         model_id: String,
-        respond_to: oneshot::Sender<Result<(), String>>,
+        respond_to: oneshot::Sender<Result<(), AcpError>>,
+    },
+    SetThinking {
+        session_id: String,
+        level: String,
+        respond_to: oneshot::Sender<Result<(), AcpError>>,
+    },
+    SetConfigOption {
+        session_id: String,
+        config_id: String,
+        value: serde_json::Value,
+        respond_to: oneshot::Sender<Result<Vec<ConfigOptionInfo>, AcpError>>,
+    },
+    ResumeStream {
+        session_id: String,
+        on_event: Channel<AgentEvent>,
+        respond_to: oneshot::Sender<Result<(), AcpError>>,
+    },
+    ListSessions {
+        respond_to: oneshot::Sender<Vec<SessionMetadata>>,
+    },
+    ListCommands {
+        session_id: String,
+        respond_to: oneshot::Sender<Result<Vec<SlashCommandInfo>, AcpError>>,
+    },
+    Subscribe {
+        session_id: String,
+        on_event: Channel<AgentEvent>,
+        respond_to: oneshot::Sender<Result<String, AcpError>>,
+    },
+    Unsubscribe {
+        session_id: String,
+        subscription_id: String,
+        respond_to: oneshot::Sender<Result<(), AcpError>>,
     },
 }
 
@@ -279,20 +683,191 @@ struct PendingPermission {
 #[derive(Default)]
 struct RuntimeShared {
     active_streams: HashMap<String, ActiveStream>,
+    sessions: HashMap<String, SessionMetadata>,
     pending_permissions: HashMap<String, PendingPermission>,
     next_permission_request_id: u64,
+    terminals: ClientTerminals,
+    next_terminal_id: u64,
+    subscribers: HashMap<String, SessionSubscribers>,
+    next_subscription_id: u64,
+    session_commands: SessionCommands,
 }
 
 #[derive(Clone)]
 #[allow(dead_code)]
 struct ActiveStream {
     session_id: String,
+    stream: PromptStream,
+}
+
+/// Channels of other windows following a session, keyed by subscription id.
+/// Shared by every prompt stream of the session.
+type SessionSubscribers = Arc<std::sync::Mutex<Vec<(String, Channel<AgentEvent>)>>>;
+
+/// Event stream of a running prompt. Recent events are buffered so a reloaded
+/// webview can replay them and re-attach a fresh channel via `acp_resume_stream`.
+/// Events are also broadcast to the session's subscribers.
+#[derive(Clone)]
+struct PromptStream(Arc<std::sync::Mutex<PromptStreamState>>);
+
+struct PromptStreamState {
     channel: Channel<AgentEvent>,
+    subscribers: SessionSubscribers,
+    buffer: VecDeque<AgentEvent>,
+    /// Message or thought chunk being coalesced, with the time its first part arrived.
+    pending: Option<(AgentEvent, std::time::Instant)>,
+}
+
+impl PromptStream {
+    fn new(channel: Channel<AgentEvent>, subscribers: SessionSubscribers) -> Self {
+        let stream = Self(Arc::new(std::sync::Mutex::new(PromptStreamState {
+            channel,
+            subscribers,
+            buffer: VecDeque::new(),
+            pending: None,
+        })));
+
+        // Flushes coalesced text that no later event pushed out. Stops once the
+        // stream is dropped.
+        let weak = Arc::downgrade(&stream.0);
+        tauri::async_runtime::spawn(async move {
+            loop {
+                tokio::time::sleep(CHUNK_COALESCE_WINDOW).await;
+                let Some(state) = weak.upgrade() else {
+                    break;
+                };
+                if let Ok(mut state) = state.lock() {
+                    let due = state
+                        .pending
+                        .as_ref()
+                        .is_some_and(|(_, since)| since.elapsed() >= CHUNK_COALESCE_WINDOW);
+                    if due {
+                        state.flush_pending();
+                    }
+                };
+            }
+        });
+
+        stream
+    }
+
+    /// Sends the event to the attached channel and all subscribers. Message and
+    /// thought chunks are coalesced for up to `CHUNK_COALESCE_WINDOW` (or
+    /// `CHUNK_COALESCE_MAX_BYTES`) so fast agents don't flood the IPC channel;
+    /// any other event flushes them first to keep the order intact.
+    fn send(&self, event: AgentEvent) {
+        let Ok(mut state) = self.0.lock() else {
+            return;
+        };
+        // Some(full) when the event was appended to the pending chunk
+        let merged = match (&mut state.pending, &event) {
+            (
+                Some((AgentEvent::MessageChunk { text: pending }, _)),
+                AgentEvent::MessageChunk { text },
+            )
+            | (
+                Some((AgentEvent::ThinkingChunk { text: pending }, _)),
+                AgentEvent::ThinkingChunk { text },
+            ) => {
+                pending.push_str(text);
+                Some(pending.len() >= CHUNK_COALESCE_MAX_BYTES)
+            }
+            _ => None,
+        };
+        match merged {
+            Some(full) => {
+                if full {
+                    state.flush_pending();
+                }
+            }
+            None => {
+                state.flush_pending();
+                if matches!(
+                    event,
+                    AgentEvent::MessageChunk { .. } | AgentEvent::ThinkingChunk { .. }
+                ) {
+                    state.pending = Some((event, std::time::Instant::now()));
+                } else {
+                    state.emit(event);
+                }
+            }
+        }
+    }
+
+    /// Replays the buffered events to a new subscriber so it catches up with the prompt.
+    fn replay_to(&self, channel: &Channel<AgentEvent>) -> Result<(), AcpError> {
+        let mut state = self
+            .0
+            .lock()
+            .map_err(|_| AcpError::internal("prompt stream lock poisoned"))?;
+        state.flush_pending();
+        for event in state.buffer.iter().cloned() {
+            channel
+                .send(event)
+                .map_err(|error| AcpError::internal(format!("failed to replay event: {error}")))?;
+        }
+        Ok(())
+    }
+
+    /// Replays the buffered events to `channel` and makes it the live channel.
+    fn reattach(&self, channel: Channel<AgentEvent>) -> Result<(), AcpError> {
+        let mut state = self
+            .0
+            .lock()
+            .map_err(|_| AcpError::internal("prompt stream lock poisoned"))?;
+        state.flush_pending();
+        for event in state.buffer.iter().cloned() {
+            channel
+                .send(event)
+                .map_err(|error| AcpError::internal(format!("failed to replay event: {error}")))?;
+        }
+        state.channel = channel;
+        Ok(())
+    }
+}
+
+impl PromptStreamState {
+    fn flush_pending(&mut self) {
+        if let Some((event, _)) = self.pending.take() {
+            self.emit(event);
+        }
+    }
+
+    /// Buffers the event for replay and forwards it. A dead channel (e.g. the webview
+    /// reloaded) does not abort the prompt; dead subscribers are dropped.
+    fn emit(&mut self, event: AgentEvent) {
+        if self.buffer.len() == STREAM_REPLAY_BUFFER_EVENTS {
+            self.buffer.pop_front();
+        }
+        self.buffer.push_back(event.clone());
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.retain(|(subscription_id, channel)| {
+                let alive = channel.send(event.clone()).is_ok();
+                if !alive {
+                    log::debug!("[acp] dropping closed subscription {subscription_id}");
+                }
+                alive
+            });
+        }
+        if let Err(error) = self.channel.send(event) {
+            log::debug!("[acp] prompt stream channel closed: {error}");
+        }
+    }
 }
 
-type InitSender = Arc<tokio::sync::Mutex<Option<oneshot::Sender<Result<AgentInfo, String>>>>>;
+type InitSender = Arc<tokio::sync::Mutex<Option<oneshot::Sender<Result<AgentInfo, AcpError>>>>>;
 
 const MAX_AGENT_PROCESSES: usize = 5;
+const STDERR_TAIL_LINES: usize = 200;
+const STREAM_REPLAY_BUFFER_EVENTS: usize = 2048;
+const CHUNK_COALESCE_WINDOW: Duration = Duration::from_millis(16);
+const CHUNK_COALESCE_MAX_BYTES: usize = 4096;
+const DEFAULT_INITIALIZE_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_SESSION_NEW_TIMEOUT: Duration = Duration::from_secs(60);
+const DEFAULT_PERMISSION_TIMEOUT: Duration = Duration::from_secs(300);
+const MAX_PROMPT_RECORDS_PER_AGENT: usize = 500;
+const SESSION_NEW_RETRIES: u32 = 3;
+const SESSION_NEW_RETRY_BASE_DELAY: Duration = Duration::from_millis(250);
 
 fn compute_agent_id(command: &str, env: &HashMap<String, String>) -> String {
     use std::collections::hash_map::DefaultHasher;
@@ -314,8 +889,10 @@ pub async fn acp_connect(
     state: State<'_, AcpState>,
     command: String,
     env: HashMap<String, String>,
-) -> Result<AgentInfo, String> {
+    timeouts: Option<AgentTimeouts>,
+) -> Result<AgentInfo, AcpError> {
     let agent_id = compute_agent_id(&command, &env);
+    let timeouts = timeouts.unwrap_or_default();
     log::info!("[acp] acp_connect agent_id={agent_id} command='{command}'");
     if let Some(existing_tx) = {
         let mut inner = state.0.lock().await;
@@ -350,9 +927,13 @@ pub async fn acp_connect(
     let (command_tx, command_rx) = mpsc::channel(64);
     let (init_tx, init_rx) = oneshot::channel();
     let captured_error: CapturedError = Arc::new(std::sync::Mutex::new(None));
+    let captured_stderr: CapturedStderr = Arc::new(std::sync::Mutex::new(VecDeque::new()));
 
     {
         let mut inner = state.0.lock().await;
+        inner
+            .stderr_tails
+            .insert(agent_id.clone(), captured_stderr.clone());
         inner.agents.insert(
             agent_id.clone(),
             AgentHandle {
@@ -367,28 +948,33 @@ pub async fn acp_connect(
     let spawned_agent_id = agent_id.clone();
     let spawned_app_handle = app_handle.clone();
     let spawned_captured_error = captured_error.clone();
+    let spawned_captured_stderr = captured_stderr.clone();
     tauri::async_runtime::spawn(async move {
         run_agent_task(
             spawned_app_handle,
             spawned_agent_id,
             command,
             env,
+            timeouts,
             command_rx,
             init_tx,
             spawned_captured_error,
+            spawned_captured_stderr,
         )
         .await;
     });
 
-    let connect_result = match tokio::time::timeout(Duration::from_secs(30), init_rx).await {
+    let connect_result = match tokio::time::timeout(timeouts.initialize(), init_rx).await {
         Ok(Ok(result)) => result,
         Ok(Err(_)) => {
             remove_agent_handle(&state, &agent_id).await;
-            Err("failed to receive initialization result".to_string())
+            Err(AcpError::internal(
+                "failed to receive initialization result",
+            ))
         }
         Err(_) => {
             remove_agent_handle(&state, &agent_id).await;
-            Err("agent initialization timed out".to_string())
+            Err(AcpError::timeout("agent initialization timed out"))
         }
     };
 
@@ -406,38 +992,32 @@ pub async fn acp_connect(
 
 #[tauri::command]
 pub async fn acp_new_session(
+    app_handle: AppHandle,
     state: State<'_, AcpState>,
     agent_id: String,
     cwd: String,
-) -> Result<SessionInfo, String> {
+) -> Result<SessionInfo, AcpError> {
     log::info!("[acp] acp_new_session agent_id={agent_id}");
     let (command_tx, captured_error) = get_agent_handle_parts(&state, &agent_id).await?;
+    let thinking_level = get_thinking_level_default(&app_handle, &agent_id);
     let (respond_to, response_rx) = oneshot::channel();
     command_tx
-        .send(AgentCommand::NewSession { cwd, respond_to })
+        .send(AgentCommand::NewSession {
+            cwd,
+            thinking_level,
+            respond_to,
+        })
         .await
-        .map_err(|_| format!("agent '{agent_id}' is not running"))?;
+        .map_err(|_| AcpError::agent_not_running(&agent_id))?;
     let result = response_rx.await.map_err(|_| {
         // The respond_to sender was dropped (agent crashed/exited during session creation).
         // Check if we captured a JSON-RPC error from the wire before the crash.
         if let Ok(guard) = captured_error.lock() {
             if let Some(wire_err) = guard.as_ref() {
-                let kind = if wire_err.code == -32000 {
-                    "auth_required"
-                } else if wire_err.code == -32603 {
-                    "internal"
-                } else {
-                    "unknown"
-                };
-                let detail = extract_wire_error_detail(wire_err);
-                let conn_err = AcpConnectionError {
-                    kind: kind.to_string(),
-                    message: detail,
-                };
-                return connection_error_to_string(&conn_err);
+                return AcpError::from_wire(wire_err);
             }
         }
-        format!("agent '{agent_id}' did not respond")
+        AcpError::internal(format!("agent '{agent_id}' did not respond"))
     })?;
 
     match &result {
@@ -453,29 +1033,38 @@ pub async fn acp_new_session(
 
 #[tauri::command]
 pub async fn acp_prompt(
+    app_handle: AppHandle,
     state: State<'_, AcpState>,
     agent_id: String,
     session_id: String,
     text: String,
+    mentions: Option<Vec<String>>,
     on_event: Channel<AgentEvent>,
-) -> Result<(), String> {
+) -> Result<(), AcpError> {
     let prompt_len = text.chars().count();
     log::info!("[acp] acp_prompt agent_id={agent_id} session_id={session_id} chars={prompt_len}");
     let command_tx = get_agent_command_tx(&state, &agent_id).await?;
+    let text = vault_commands::expand_prompt(&app_handle, text).await?;
+    let mentions =
+        vault_commands::expand_mentions(&app_handle, &mentions.unwrap_or_default()).await?;
+    let vault_root = get_base_dir(&app_handle).ok();
     let (respond_to, response_rx) = oneshot::channel();
     let session_id_log = session_id.clone();
     command_tx
         .send(AgentCommand::Prompt {
             session_id,
             text,
+            mentions,
+            vault_root,
+            app_handle,
             on_event,
             respond_to,
         })
         .await
-        .map_err(|_| format!("agent '{agent_id}' is not running"))?;
+        .map_err(|_| AcpError::agent_not_running(&agent_id))?;
     let result = response_rx
         .await
-        .map_err(|_| format!("agent '{agent_id}' did not respond"))?;
+        .map_err(|_| AcpError::internal(format!("agent '{agent_id}' did not respond")))?;
 
     match &result {
         Ok(()) => {
@@ -489,93 +1078,306 @@ pub async fn acp_prompt(
     result
 }
 
+/// One (agent, session) pair targeted by `acp_prompt_many`.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptTarget {
+    pub agent_id: String,
+    pub session_id: String,
+}
+
+/// An `AgentEvent` tagged with the prompt it came from. Serialized as the event
+/// (`{ event, data }`) plus `agentId` and `sessionId`.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SourcedAgentEvent {
+    pub agent_id: String,
+    pub session_id: String,
+    #[serde(flatten)]
+    pub event: serde_json::Value,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PromptTargetResult {
+    #[serde(flatten)]
+    pub target: PromptTarget,
+    pub error: Option<AcpError>,
+}
+
+/// Wraps `on_event` in a per-target channel that tags every event with its source.
+fn sourced_event_channel(
+    on_event: Channel<SourcedAgentEvent>,
+    target: &PromptTarget,
+) -> Channel<AgentEvent> {
+    let target = target.clone();
+    Channel::new(move |body| {
+        let event = match body {
+            InvokeResponseBody::Json(json) => serde_json::from_str(&json)?,
+            InvokeResponseBody::Raw(bytes) => serde_json::from_slice(&bytes)?,
+        };
+        on_event.send(SourcedAgentEvent {
+            agent_id: target.agent_id.clone(),
+            session_id: target.session_id.clone(),
+            event,
+        })
+    })
+}
+
+/// Sends the same prompt to several sessions at once, e.g. to compare agents or
+/// models side by side. Events from every target arrive on `on_event`, tagged with
+/// their agent and session. Resolves once every prompt finished; one failing
+/// target does not stop the others.
 #[tauri::command]
-pub async fn acp_respond_permission(
+pub async fn acp_prompt_many(
+    app_handle: AppHandle,
     state: State<'_, AcpState>,
-    agent_id: String,
-    request_id: String,
-    option_id: String,
-) -> Result<(), String> {
+    targets: Vec<PromptTarget>,
+    text: String,
+    mentions: Option<Vec<String>>,
+    on_event: Channel<SourcedAgentEvent>,
+) -> Result<Vec<PromptTargetResult>, AcpError> {
     log::info!(
-        "[acp] acp_respond_permission agent_id={agent_id} request_id={request_id} option_id={option_id}"
+        "[acp] acp_prompt_many targets={} chars={}",
+        targets.len(),
+        text.chars().count()
     );
-    let command_tx = get_agent_command_tx(&state, &agent_id).await?;
-    let (respond_to, response_rx) = oneshot::channel();
-    command_tx
-        .send(AgentCommand::RespondPermission {
-            request_id,
-            option_id,
-            respond_to,
-        })
-        .await
-        .map_err(|_| format!("agent '{agent_id}' is not running"))?;
-    let result = response_rx
-        .await
-        .map_err(|_| format!("agent '{agent_id}' did not respond"))?;
+    let text = vault_commands::expand_prompt(&app_handle, text).await?;
+    let mentions =
+        vault_commands::expand_mentions(&app_handle, &mentions.unwrap_or_default()).await?;
+    let vault_root = get_base_dir(&app_handle).ok();
+
+    let mut pending = Vec::new();
+    for target in targets {
+        let sent = async {
+            let command_tx = get_agent_command_tx(&state, &target.agent_id).await?;
+            let (respond_to, response_rx) = oneshot::channel();
+            command_tx
+                .send(AgentCommand::Prompt {
+                    session_id: target.session_id.clone(),
+                    text: text.clone(),
+                    mentions: mentions.clone(),
+                    vault_root: vault_root.clone(),
+                    app_handle: app_handle.clone(),
+                    on_event: sourced_event_channel(on_event.clone(), &target),
+                    respond_to,
+                })
+                .await
+                .map_err(|_| AcpError::agent_not_running(&target.agent_id))?;
+            Ok::<_, AcpError>(response_rx)
+        }
+        .await;
+        pending.push((target, sent));
+    }
 
-    match &result {
-        Ok(()) => log::info!("[acp] acp_respond_permission agent_id={agent_id} -> done"),
-        Err(error) => {
-            log::warn!("[acp] acp_respond_permission agent_id={agent_id} -> error: {error}")
+    let mut results = Vec::new();
+    for (target, sent) in pending {
+        let result = match sent {
+            Ok(response_rx) => response_rx.await.unwrap_or_else(|_| {
+                Err(AcpError::internal(format!(
+                    "agent '{}' did not respond",
+                    target.agent_id
+                )))
+            }),
+            Err(error) => Err(error),
+        };
+        if let Err(error) = &result {
+            log::warn!(
+                "[acp] acp_prompt_many agent_id={} session_id={} -> error: {error}",
+                target.agent_id,
+                target.session_id
+            );
         }
+        results.push(PromptTargetResult {
+            target,
+            error: result.err(),
+        });
     }
 
-    result
+    log::info!("[acp] acp_prompt_many -> done");
+    Ok(results)
 }
 
+/// Replays the buffered events of a running prompt to `on_event` and re-attaches
+/// it as the live stream, e.g. after the webview reloaded mid-prompt.
 #[tauri::command]
-pub async fn acp_cancel(
+pub async fn acp_resume_stream(
     state: State<'_, AcpState>,
     agent_id: String,
     session_id: String,
-) -> Result<(), String> {
-    log::info!("[acp] acp_cancel agent_id={agent_id} session_id={session_id}");
+    on_event: Channel<AgentEvent>,
+) -> Result<(), AcpError> {
+    log::info!("[acp] acp_resume_stream agent_id={agent_id} session_id={session_id}");
     let command_tx = get_agent_command_tx(&state, &agent_id).await?;
     let (respond_to, response_rx) = oneshot::channel();
     command_tx
-        .send(AgentCommand::Cancel {
+        .send(AgentCommand::ResumeStream {
             session_id,
+            on_event,
             respond_to,
         })
         .await
-        .map_err(|_| format!("agent '{agent_id}' is not running"))?;
+        .map_err(|_| AcpError::agent_not_running(&agent_id))?;
     let result = response_rx
         .await
-        .map_err(|_| format!("agent '{agent_id}' did not respond"))?;
+        .map_err(|_| AcpError::internal(format!("agent '{agent_id}' did not respond")))?;
 
-    match &result {
-        Ok(()) => log::info!("[acp] acp_cancel agent_id={agent_id} -> done"),
-        Err(error) => log::warn!("[acp] acp_cancel agent_id={agent_id} -> error: {error}"),
+    if let Err(error) = &result {
+        log::warn!("[acp] acp_resume_stream agent_id={agent_id} -> error: {error}");
     }
 
     result
 }
 
+/// Follows a session from another window: every event of the session's prompts,
+/// whichever window sent them, is also delivered to `on_event`. If a prompt is
+/// running, its buffered events are replayed first. Returns the subscription id.
 #[tauri::command]
-pub async fn acp_set_mode(
+pub async fn acp_subscribe_session(
     state: State<'_, AcpState>,
     agent_id: String,
     session_id: String,
-    mode_id: String,
-) -> Result<(), String> {
-    log::info!("[acp] acp_set_mode agent_id={agent_id} session_id={session_id} mode_id={mode_id}");
+    on_event: Channel<AgentEvent>,
+) -> Result<String, AcpError> {
+    log::info!("[acp] acp_subscribe_session agent_id={agent_id} session_id={session_id}");
     let command_tx = get_agent_command_tx(&state, &agent_id).await?;
     let (respond_to, response_rx) = oneshot::channel();
     command_tx
-        .send(AgentCommand::SetMode {
+        .send(AgentCommand::Subscribe {
             session_id,
-            mode_id,
+            on_event,
             respond_to,
         })
         .await
-        .map_err(|_| format!("agent '{agent_id}' is not running"))?;
+        .map_err(|_| AcpError::agent_not_running(&agent_id))?;
     let result = response_rx
         .await
-        .map_err(|_| format!("agent '{agent_id}' did not respond"))?;
+        .map_err(|_| AcpError::internal(format!("agent '{agent_id}' did not respond")))?;
 
     match &result {
-        Ok(()) => log::info!("[acp] acp_set_mode agent_id={agent_id} -> done"),
-        Err(error) => log::warn!("[acp] acp_set_mode agent_id={agent_id} -> error: {error}"),
+        Ok(subscription_id) => {
+            log::info!("[acp] acp_subscribe_session agent_id={agent_id} -> {subscription_id}")
+        }
+        Err(error) => {
+            log::warn!("[acp] acp_subscribe_session agent_id={agent_id} -> error: {error}")
+        }
+    }
+
+    result
+}
+
+#[tauri::command]
+pub async fn acp_unsubscribe_session(
+    state: State<'_, AcpState>,
+    agent_id: String,
+    session_id: String,
+    subscription_id: String,
+) -> Result<(), AcpError> {
+    log::info!(
+        "[acp] acp_unsubscribe_session agent_id={agent_id} session_id={session_id} subscription_id={subscription_id}"
+    );
+    let command_tx = get_agent_command_tx(&state, &agent_id).await?;
+    let (respond_to, response_rx) = oneshot::channel();
+    command_tx
+        .send(AgentCommand::Unsubscribe {
+            session_id,
+            subscription_id,
+            respond_to,
+        })
+        .await
+        .map_err(|_| AcpError::agent_not_running(&agent_id))?;
+    response_rx
+        .await
+        .map_err(|_| AcpError::internal(format!("agent '{agent_id}' did not respond")))?
+}
+
+#[tauri::command]
+pub async fn acp_respond_permission(
+    state: State<'_, AcpState>,
+    agent_id: String,
+    request_id: String,
+    option_id: String,
+) -> Result<(), AcpError> {
+    log::info!(
+        "[acp] acp_respond_permission agent_id={agent_id} request_id={request_id} option_id={option_id}"
+    );
+    let command_tx = get_agent_command_tx(&state, &agent_id).await?;
+    let (respond_to, response_rx) = oneshot::channel();
+    command_tx
+        .send(AgentCommand::RespondPermission {
+            request_id,
+            option_id,
+            respond_to,
+        })
+        .await
+        .map_err(|_| AcpError::agent_not_running(&agent_id))?;
+    let result = response_rx
+        .await
+        .map_err(|_| AcpError::internal(format!("agent '{agent_id}' did not respond")))?;
+
+    match &result {
+        Ok(()) => log::info!("[acp] acp_respond_permission agent_id={agent_id} -> done"),
+        Err(error) => {
+            log::warn!("[acp] acp_respond_permission agent_id={agent_id} -> error: {error}")
+        }
+    }
+
+    result
+}
+
+#[tauri::command]
+pub async fn acp_cancel(
+    state: State<'_, AcpState>,
+    agent_id: String,
+    session_id: String,
+) -> Result<(), AcpError> {
+    log::info!("[acp] acp_cancel agent_id={agent_id} session_id={session_id}");
+    let command_tx = get_agent_command_tx(&state, &agent_id).await?;
+    let (respond_to, response_rx) = oneshot::channel();
+    command_tx
+        .send(AgentCommand::Cancel {
+            session_id,
+            respond_to,
+        })
+        .await
+        .map_err(|_| AcpError::agent_not_running(&agent_id))?;
+    let result = response_rx
+        .await
+        .map_err(|_| AcpError::internal(format!("agent '{agent_id}' did not respond")))?;
+
+    match &result {
+        Ok(()) => log::info!("[acp] acp_cancel agent_id={agent_id} -> done"),
+        Err(error) => log::warn!("[acp] acp_cancel agent_id={agent_id} -> error: {error}"),
+    }
+
+    result
+}
+
+#[tauri::command]
+pub async fn acp_set_mode(
+    state: State<'_, AcpState>,
+    agent_id: String,
+    session_id: String,
+    mode_id: String,
+) -> Result<(), AcpError> {
+    log::info!("[acp] acp_set_mode agent_id={agent_id} session_id={session_id} mode_id={mode_id}");
+    let command_tx = get_agent_command_tx(&state, &agent_id).await?;
+    let (respond_to, response_rx) = oneshot::channel();
+    command_tx
+        .send(AgentCommand::SetMode {
+            session_id,
+            mode_id,
+            respond_to,
+        })
+        .await
+        .map_err(|_| AcpError::agent_not_running(&agent_id))?;
+    let result = response_rx
+        .await
+        .map_err(|_| AcpError::internal(format!("agent '{agent_id}' did not respond")))?;
+
+    match &result {
+        Ok(()) => log::info!("[acp] acp_set_mode agent_id={agent_id} -> done"),
+        Err(error) => log::warn!("[acp] acp_set_mode agent_id={agent_id} -> error: {error}"),
     }
 
     result
@@ -587,7 +1389,7 @@ pub async fn acp_set_model(
     agent_id: String,
     session_id: String,
     model_id: String,
-) -> Result<(), String> {
+) -> Result<(), AcpError> {
     log::info!(
         "[acp] acp_set_model agent_id={agent_id} session_id={session_id} model_id={model_id}"
     );
@@ -600,10 +1402,10 @@ pub async fn acp_set_model(
             respond_to,
         })
         .await
-        .map_err(|_| format!("agent '{agent_id}' is not running"))?;
+        .map_err(|_| AcpError::agent_not_running(&agent_id))?;
     let result = response_rx
         .await
-        .map_err(|_| format!("agent '{agent_id}' did not respond"))?;
+        .map_err(|_| AcpError::internal(format!("agent '{agent_id}' did not respond")))?;
 
     match &result {
         Ok(()) => log::info!("[acp] acp_set_model agent_id={agent_id} -> done"),
@@ -613,42 +1415,409 @@ pub async fn acp_set_model(
     result
 }
 
+/// Sets the reasoning effort / thinking budget of a session. Agents that support it
+/// accept it through the unstable `session/set_thinking` request. The level is
+/// remembered as the default for new sessions of the same agent.
+#[tauri::command]
+pub async fn acp_set_thinking(
+    app_handle: AppHandle,
+    state: State<'_, AcpState>,
+    agent_id: String,
+    session_id: String,
+    level: String,
+) -> Result<(), AcpError> {
+    log::info!("[acp] acp_set_thinking agent_id={agent_id} session_id={session_id} level={level}");
+    let command_tx = get_agent_command_tx(&state, &agent_id).await?;
+    let (respond_to, response_rx) = oneshot::channel();
+    command_tx
+        .send(AgentCommand::SetThinking {
+            session_id,
+            level: level.clone(),
+            respond_to,
+        })
+        .await
+        .map_err(|_| AcpError::agent_not_running(&agent_id))?;
+    let result = response_rx
+        .await
+        .map_err(|_| AcpError::internal(format!("agent '{agent_id}' did not respond")))?;
+
+    match &result {
+        Ok(()) => {
+            log::info!("[acp] acp_set_thinking agent_id={agent_id} -> done");
+            let mut defaults: HashMap<String, String> =
+                get_setting(&app_handle, THINKING_LEVELS_KEY).unwrap_or_default();
+            defaults.insert(agent_id, level);
+            set_setting(&app_handle, THINKING_LEVELS_KEY, &defaults)?;
+        }
+        Err(error) => log::warn!("[acp] acp_set_thinking agent_id={agent_id} -> error: {error}"),
+    }
+
+    result
+}
+
+/// Thinking level last chosen for an agent, applied to its new sessions.
+fn get_thinking_level_default(app_handle: &AppHandle, agent_id: &str) -> Option<String> {
+    get_setting::<HashMap<String, String>>(app_handle, THINKING_LEVELS_KEY)
+        .ok()
+        .and_then(|mut defaults| defaults.remove(agent_id))
+}
+
+#[tauri::command]
+pub async fn acp_set_config_option(
+    state: State<'_, AcpState>,
+    agent_id: String,
+    session_id: String,
+    key: String,
+    value: serde_json::Value,
+) -> Result<Vec<ConfigOptionInfo>, AcpError> {
+    log::info!(
+        "[acp] acp_set_config_option agent_id={agent_id} session_id={session_id} key={key} value={value}"
+    );
+    let command_tx = get_agent_command_tx(&state, &agent_id).await?;
+    let (respond_to, response_rx) = oneshot::channel();
+    command_tx
+        .send(AgentCommand::SetConfigOption {
+            session_id,
+            config_id: key,
+            value,
+            respond_to,
+        })
+        .await
+        .map_err(|_| AcpError::agent_not_running(&agent_id))?;
+    let result = response_rx
+        .await
+        .map_err(|_| AcpError::internal(format!("agent '{agent_id}' did not respond")))?;
+
+    match &result {
+        Ok(_) => log::info!("[acp] acp_set_config_option agent_id={agent_id} -> done"),
+        Err(error) => {
+            log::warn!("[acp] acp_set_config_option agent_id={agent_id} -> error: {error}")
+        }
+    }
+
+    result
+}
+
+/// Lists the sessions created on a running agent, oldest first, so other windows
+/// can discover and reuse them.
+#[tauri::command]
+pub async fn acp_list_sessions(
+    state: State<'_, AcpState>,
+    agent_id: String,
+) -> Result<Vec<SessionMetadata>, AcpError> {
+    let command_tx = get_agent_command_tx(&state, &agent_id).await?;
+    let (respond_to, response_rx) = oneshot::channel();
+    command_tx
+        .send(AgentCommand::ListSessions { respond_to })
+        .await
+        .map_err(|_| AcpError::agent_not_running(&agent_id))?;
+    response_rx
+        .await
+        .map_err(|_| AcpError::internal(format!("agent '{agent_id}' did not respond")))
+}
+
+/// Returns the slash commands a session currently offers: the agent's latest
+/// `available_commands_update` for the session plus the vault commands.
+#[tauri::command]
+pub async fn acp_list_commands(
+    state: State<'_, AcpState>,
+    agent_id: String,
+    session_id: String,
+) -> Result<Vec<SlashCommandInfo>, AcpError> {
+    let command_tx = get_agent_command_tx(&state, &agent_id).await?;
+    let (respond_to, response_rx) = oneshot::channel();
+    command_tx
+        .send(AgentCommand::ListCommands {
+            session_id,
+            respond_to,
+        })
+        .await
+        .map_err(|_| AcpError::agent_not_running(&agent_id))?;
+    response_rx
+        .await
+        .map_err(|_| AcpError::internal(format!("agent '{agent_id}' did not respond")))?
+}
+
+/// Returns the recent stderr output of an agent process, including after it crashed.
+#[tauri::command]
+pub async fn acp_get_agent_diagnostics(
+    state: State<'_, AcpState>,
+    agent_id: String,
+) -> Result<AgentDiagnostics, AcpError> {
+    let inner = state.0.lock().await;
+    let handle = inner.agents.get(&agent_id);
+    let stderr_tail = inner
+        .stderr_tails
+        .get(&agent_id)
+        .ok_or_else(|| AcpError::agent_not_connected(&agent_id))?
+        .lock()
+        .map(|tail| tail.iter().cloned().collect())
+        .unwrap_or_default();
+
+    Ok(AgentDiagnostics {
+        agent_id: agent_id.clone(),
+        running: handle.is_some(),
+        stderr_tail,
+        log_file: handle
+            .and_then(|handle| handle.log_file.as_ref())
+            .map(|path| path.to_string_lossy().to_string()),
+    })
+}
+
+/// Prompt duration and outcome statistics for an agent over its recent prompts.
+#[tauri::command]
+pub async fn acp_get_stats(
+    state: State<'_, AcpState>,
+    agent_id: String,
+) -> Result<AgentStats, AcpError> {
+    let inner = state.0.lock().await;
+    let recent: Vec<PromptRecord> = inner
+        .prompt_records
+        .get(&agent_id)
+        .map(|records| records.iter().cloned().collect())
+        .unwrap_or_default();
+
+    let durations = recent
+        .iter()
+        .map(|record| record.ended_at_ms - record.started_at_ms);
+    let total_duration_ms: i64 = durations.clone().sum();
+    let mut stop_reasons: HashMap<String, u64> = HashMap::new();
+    for record in &recent {
+        *stop_reasons.entry(record.stop_reason.clone()).or_default() += 1;
+    }
+
+    Ok(AgentStats {
+        agent_id,
+        prompt_count: recent.len() as u64,
+        total_duration_ms,
+        average_duration_ms: total_duration_ms / (recent.len().max(1) as i64),
+        max_duration_ms: durations.max().unwrap_or(0),
+        total_chunks: recent.iter().map(|record| record.chunk_count).sum(),
+        total_tool_calls: recent.iter().map(|record| record.tool_call_count).sum(),
+        stop_reasons,
+        recent,
+    })
+}
+
+async fn record_prompt(app_handle: &AppHandle, agent_id: &str, record: PromptRecord) {
+    let Some(state) = app_handle.try_state::<AcpState>() else {
+        return;
+    };
+    let mut inner = state.0.lock().await;
+    let records = inner
+        .prompt_records
+        .entry(agent_id.to_string())
+        .or_default();
+    records.push_back(record);
+    while records.len() > MAX_PROMPT_RECORDS_PER_AGENT {
+        records.pop_front();
+    }
+}
+
+#[tauri::command]
+pub async fn acp_get_proxy_settings(app_handle: AppHandle) -> Result<ProxySettings, AcpError> {
+    Ok(get_setting(&app_handle, PROXY_SETTINGS_KEY).unwrap_or_default())
+}
+
+/// Saves the proxy settings. Agents started afterwards pick them up;
+/// running agents keep their environment until they are reconnected.
+#[tauri::command]
+pub async fn acp_set_proxy_settings(
+    app_handle: AppHandle,
+    settings: ProxySettings,
+) -> Result<(), AcpError> {
+    log::info!(
+        "[acp] acp_set_proxy_settings http={} https={} no_proxy={}",
+        settings.http_proxy.is_some(),
+        settings.https_proxy.is_some(),
+        settings.no_proxy.is_some()
+    );
+    set_setting(&app_handle, PROXY_SETTINGS_KEY, &settings).map_err(AcpError::from)
+}
+
+/// Whether the agent is restricted to the vault (see `acp_set_vault_only`).
+#[tauri::command]
+pub async fn acp_get_vault_only(app_handle: AppHandle, agent_id: String) -> Result<bool, AcpError> {
+    Ok(is_vault_only(&app_handle, &agent_id))
+}
+
+/// Restricts an agent to the vault. Permission requests touching paths outside
+/// the vault are rejected without asking, file system requests outside the
+/// vault are refused, and terminals are refused altogether: a command started
+/// in the vault can still reach any file, so this is no OS-level sandbox.
+/// Applies to running agents immediately.
+#[tauri::command]
+pub async fn acp_set_vault_only(
+    app_handle: AppHandle,
+    agent_id: String,
+    enabled: bool,
+) -> Result<(), AcpError> {
+    log::info!("[acp] acp_set_vault_only agent_id={agent_id} enabled={enabled}");
+    let mut agents: Vec<String> =
+        get_setting(&app_handle, VAULT_ONLY_AGENTS_KEY).unwrap_or_default();
+    agents.retain(|id| id != &agent_id);
+    if enabled {
+        agents.push(agent_id);
+    }
+    set_setting(&app_handle, VAULT_ONLY_AGENTS_KEY, &agents).map_err(AcpError::from)
+}
+
+fn is_vault_only(app_handle: &AppHandle, agent_id: &str) -> bool {
+    get_setting::<Vec<String>>(app_handle, VAULT_ONLY_AGENTS_KEY)
+        .is_some_and(|agents| agents.iter().any(|id| id == agent_id))
+}
+
+/// Checks a path an agent wants to touch against the vault-only restriction.
+/// Returns the explanation to send back to the agent when it is refused.
+fn check_vault_only_path(
+    app_handle: &AppHandle,
+    agent_id: &str,
+    path: &Path,
+) -> Result<(), String> {
+    if !is_vault_only(app_handle, agent_id) {
+        return Ok(());
+    }
+    let vault_root = get_base_dir(app_handle)?;
+    if is_inside_vault(path, &vault_root) {
+        Ok(())
+    } else {
+        Err(format!(
+            "agent '{agent_id}' is restricted to the vault ({}); access to '{}' was refused",
+            vault_root.display(),
+            path.display()
+        ))
+    }
+}
+
+/// Lexically resolves `.` and `..` in an absolute path. Relative paths give `None`.
+pub(crate) fn normalize_absolute(path: &Path) -> Option<PathBuf> {
+    if !path.is_absolute() {
+        return None;
+    }
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            std::path::Component::CurDir => {}
+            other => normalized.push(other),
+        }
+    }
+    Some(normalized)
+}
+
+/// Resolves `.`, `..` and symlinks in an absolute path. Components that don't
+/// exist yet (a file about to be created) are appended to the resolved
+/// deepest existing ancestor. Dangling symlinks give `None`, since writing
+/// through them would create their target wherever it points.
+pub(crate) fn resolve_absolute(path: &Path) -> Option<PathBuf> {
+    let normalized = normalize_absolute(path)?;
+    let mut existing = normalized.as_path();
+    let mut missing = Vec::new();
+    loop {
+        match existing.canonicalize() {
+            Ok(mut resolved) => {
+                resolved.extend(missing.iter().rev());
+                return Some(resolved);
+            }
+            Err(_) if existing.symlink_metadata().is_ok() => return None,
+            Err(_) => {
+                missing.push(existing.file_name()?.to_os_string());
+                existing = existing.parent()?;
+            }
+        }
+    }
+}
+
+/// Resolves `.`, `..` and symlinks on both sides, so neither `<vault>/../secret`
+/// nor a symlink inside the vault pointing elsewhere can slip through.
+pub(crate) fn is_inside_vault(path: &Path, vault_root: &Path) -> bool {
+    let vault_root = vault_root
+        .canonicalize()
+        .unwrap_or_else(|_| vault_root.to_path_buf());
+    resolve_absolute(path).is_some_and(|resolved| resolved.starts_with(&vault_root))
+}
+
+#[tauri::command]
+pub async fn acp_get_permission_timeout(
+    app_handle: AppHandle,
+) -> Result<PermissionTimeoutSettings, AcpError> {
+    Ok(get_setting(&app_handle, PERMISSION_TIMEOUT_KEY).unwrap_or_default())
+}
+
+/// Saves the permission timeout. Applies to permission requests received afterwards.
+#[tauri::command]
+pub async fn acp_set_permission_timeout(
+    app_handle: AppHandle,
+    settings: PermissionTimeoutSettings,
+) -> Result<(), AcpError> {
+    log::info!(
+        "[acp] acp_set_permission_timeout timeout_secs={} default_decision={:?}",
+        settings.timeout_secs,
+        settings.default_decision
+    );
+    set_setting(&app_handle, PERMISSION_TIMEOUT_KEY, &settings).map_err(AcpError::from)
+}
+
+#[tauri::command]
+pub async fn acp_list_permission_rules(
+    app_handle: AppHandle,
+) -> Result<Vec<PermissionRule>, AcpError> {
+    Ok(get_setting(&app_handle, PERMISSION_RULES_KEY).unwrap_or_default())
+}
+
+#[tauri::command]
+pub async fn acp_clear_permission_rules(
+    app_handle: AppHandle,
+    agent_id: Option<String>,
+) -> Result<(), AcpError> {
+    log::info!("[acp] acp_clear_permission_rules agent_id={agent_id:?}");
+    let mut rules: Vec<PermissionRule> =
+        get_setting(&app_handle, PERMISSION_RULES_KEY).unwrap_or_default();
+    match agent_id {
+        Some(agent_id) => rules.retain(|rule| rule.agent_id != agent_id),
+        None => rules.clear(),
+    }
+    set_setting(&app_handle, PERMISSION_RULES_KEY, &rules).map_err(AcpError::from)
+}
+
 #[allow(clippy::type_complexity)]
 async fn get_agent_command_tx(
     state: &State<'_, AcpState>,
     agent_id: &str,
-) -> Result<mpsc::Sender<AgentCommand>, String> {
+) -> Result<mpsc::Sender<AgentCommand>, AcpError> {
     let mut inner = state.0.lock().await;
     if let Some(handle) = inner.agents.get_mut(agent_id) {
         handle.last_used = std::time::Instant::now();
         Ok(handle.command_tx.clone())
     } else {
-        Err(format!("agent '{agent_id}' is not connected"))
+        Err(AcpError::agent_not_connected(agent_id))
     }
 }
 
 async fn get_agent_handle_parts(
     state: &State<'_, AcpState>,
     agent_id: &str,
-) -> Result<(mpsc::Sender<AgentCommand>, CapturedError), String> {
+) -> Result<(mpsc::Sender<AgentCommand>, CapturedError), AcpError> {
     let mut inner = state.0.lock().await;
     if let Some(handle) = inner.agents.get_mut(agent_id) {
         handle.last_used = std::time::Instant::now();
         Ok((handle.command_tx.clone(), handle.captured_error.clone()))
     } else {
-        Err(format!("agent '{agent_id}' is not connected"))
+        Err(AcpError::agent_not_connected(agent_id))
     }
 }
 
-async fn request_agent_info(command_tx: mpsc::Sender<AgentCommand>) -> Result<AgentInfo, String> {
+async fn request_agent_info(command_tx: mpsc::Sender<AgentCommand>) -> Result<AgentInfo, AcpError> {
     let (respond_to, response_rx) = oneshot::channel();
     command_tx
         .send(AgentCommand::GetInfo { respond_to })
         .await
-        .map_err(|_| "agent is not running".to_string())?;
+        .map_err(|_| AcpError::internal("agent is not running"))?;
     response_rx
         .await
-        .map_err(|_| "agent did not respond".to_string())?
+        .map_err(|_| AcpError::internal("agent did not respond"))?
 }
 
 async fn remove_agent_handle(state: &AcpState, agent_id: &str) {
@@ -656,14 +1825,17 @@ async fn remove_agent_handle(state: &AcpState, agent_id: &str) {
     inner.agents.remove(agent_id);
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn run_agent_task(
     app_handle: AppHandle,
     agent_id: String,
     command: String,
     env: HashMap<String, String>,
+    timeouts: AgentTimeouts,
     command_rx: mpsc::Receiver<AgentCommand>,
-    init_tx: oneshot::Sender<Result<AgentInfo, String>>,
+    init_tx: oneshot::Sender<Result<AgentInfo, AcpError>>,
     captured_error: CapturedError,
+    captured_stderr: CapturedStderr,
 ) {
     let init_sender = Arc::new(tokio::sync::Mutex::new(Some(init_tx)));
     let shared = Arc::new(tokio::sync::Mutex::new(RuntimeShared::default()));
@@ -675,7 +1847,7 @@ async fn run_agent_task(
         Err(message) => {
             log::error!("[acp] failed to create log file for agent_id={agent_id}: {message}");
             if let Some(init_tx) = init_sender.lock().await.take() {
-                let _ = init_tx.send(Err(message.clone()));
+                let _ = init_tx.send(Err(AcpError::internal(message.clone())));
             }
             emit_agent_crashed(&app_handle, &agent_id, &message);
             remove_agent_handle_from_app_state(&app_handle, &agent_id).await;
@@ -686,18 +1858,23 @@ async fn run_agent_task(
     log::info!("[acp] process started agent_id={agent_id} log_file={log_path_string}");
     set_agent_log_file(&app_handle, &agent_id, log_path).await;
 
-    let (acp_agent, captured_models, captured_commands) = match build_agent(
+    let proxy: ProxySettings = get_setting(&app_handle, PROXY_SETTINGS_KEY).unwrap_or_default();
+    let session_commands = shared.lock().await.session_commands.clone();
+    let (acp_agent, captured_models, captured_commands, captured_config_options) = match build_agent(
         &agent_id,
         command,
         env,
+        &proxy,
         captured_error.clone(),
+        captured_stderr,
+        session_commands,
         process_log.clone(),
     ) {
         Ok(agent) => agent,
         Err(message) => {
             log::error!("[acp][{agent_id}] failed to build agent command: {message}");
             if let Some(init_tx) = init_sender.lock().await.take() {
-                let _ = init_tx.send(Err(message.clone()));
+                let _ = init_tx.send(Err(AcpError::internal(message.clone())));
             }
             emit_agent_crashed(&app_handle, &agent_id, &message);
             remove_agent_handle_from_app_state(&app_handle, &agent_id).await;
@@ -707,11 +1884,24 @@ async fn run_agent_task(
 
     let shared_for_permissions = shared.clone();
     let permission_agent_id = agent_id.clone();
+    let permission_app_handle = app_handle.clone();
+    let terminal_agent_id = agent_id.clone();
+    let terminal_app_handle = app_handle.clone();
+    let fs_read_agent_id = agent_id.clone();
+    let fs_read_app_handle = app_handle.clone();
+    let fs_write_agent_id = agent_id.clone();
+    let fs_write_app_handle = app_handle.clone();
+    let shared_for_terminals = shared.clone();
+    let shared_for_terminal_output = shared.clone();
+    let shared_for_terminal_wait = shared.clone();
+    let shared_for_terminal_kill = shared.clone();
+    let shared_for_terminal_release = shared.clone();
     let connection_result = ClientToAgent::builder()
         .name("flowrite")
         .on_receive_request(
             async move |request: RequestPermissionRequest, request_cx, _cx| {
                 handle_permission_request(
+                    permission_app_handle.clone(),
                     permission_agent_id.clone(),
                     shared_for_permissions.clone(),
                     request,
@@ -721,6 +1911,70 @@ async fn run_agent_task(
             },
             sacp::on_receive_request!(),
         )
+        .on_receive_request(
+            async move |request: ReadTextFileRequest, request_cx, _cx| {
+                handle_read_text_file(
+                    fs_read_app_handle.clone(),
+                    fs_read_agent_id.clone(),
+                    request,
+                    request_cx,
+                )
+                .await
+            },
+            sacp::on_receive_request!(),
+        )
+        .on_receive_request(
+            async move |request: WriteTextFileRequest, request_cx, _cx| {
+                handle_write_text_file(
+                    fs_write_app_handle.clone(),
+                    fs_write_agent_id.clone(),
+                    request,
+                    request_cx,
+                )
+                .await
+            },
+            sacp::on_receive_request!(),
+        )
+        .on_receive_request(
+            async move |request: CreateTerminalRequest, request_cx, _cx| {
+                handle_create_terminal(
+                    terminal_app_handle.clone(),
+                    terminal_agent_id.clone(),
+                    shared_for_terminals.clone(),
+                    request,
+                    request_cx,
+                )
+                .await
+            },
+            sacp::on_receive_request!(),
+        )
+        .on_receive_request(
+            async move |request: TerminalOutputRequest, request_cx, _cx| {
+                handle_terminal_output(shared_for_terminal_output.clone(), request, request_cx)
+                    .await
+            },
+            sacp::on_receive_request!(),
+        )
+        .on_receive_request(
+            async move |request: WaitForTerminalExitRequest, request_cx, _cx| {
+                handle_wait_for_terminal_exit(shared_for_terminal_wait.clone(), request, request_cx)
+                    .await
+            },
+            sacp::on_receive_request!(),
+        )
+        .on_receive_request(
+            async move |request: KillTerminalCommandRequest, request_cx, _cx| {
+                handle_kill_terminal(shared_for_terminal_kill.clone(), request, request_cx).await
+            },
+            sacp::on_receive_request!(),
+        )
+        .on_receive_request(
+            async move |request: ReleaseTerminalRequest, request_cx, _cx| {
+                handle_release_terminal(shared_for_terminal_release.clone(), request, request_cx)
+                    .await
+            },
+            sacp::on_receive_request!(),
+        )
         .connect_to(acp_agent);
 
     let loop_agent_id = agent_id.clone();
@@ -738,7 +1992,9 @@ async fn run_agent_task(
                         init_sender,
                         captured_models,
                         captured_commands,
+                        captured_config_options,
                         log_path_string,
+                        timeouts,
                     )
                 }
             })
@@ -752,29 +2008,28 @@ async fn run_agent_task(
 
     remove_agent_handle_from_app_state(&app_handle, &agent_id).await;
 
+    // Prefer a structured error captured from the wire over the raw transport error
+    let wire_err = captured_error.lock().ok().and_then(|guard| guard.clone());
+
     if let Some(init_tx) = init_sender.lock().await.take() {
-        let fallback = "agent connection ended unexpectedly".to_string();
-        let init_message = match run_result.as_ref() {
-            Ok(()) => fallback,
-            Err(error) => error.clone(),
+        let init_error = match (wire_err.as_ref(), run_result.as_ref()) {
+            (Some(wire_err), _) => AcpError::from_wire(wire_err),
+            (None, Err(error)) => AcpError::internal(clean_sacp_error_message(error)),
+            (None, Ok(())) => AcpError::internal("agent connection ended unexpectedly"),
         };
-        let _ = init_tx.send(Err(init_message));
+        let _ = init_tx.send(Err(init_error));
     }
 
     if let Err(message) = run_result {
         log::error!("[acp] process crashed agent_id={agent_id}: {message}");
-        // Check if we captured a structured error from the wire for a better crash message
-        let wire_err = captured_error.lock().ok().and_then(|guard| guard.clone());
         if let Some(wire_err) = wire_err {
-            let kind = if wire_err.code == -32000 {
-                "auth_required"
-            } else if wire_err.code == -32603 {
-                "internal"
-            } else {
-                "crashed"
+            let error = AcpError::from_wire(&wire_err);
+            let kind = match &error {
+                AcpError::Wire { code: -32603, .. } => "internal",
+                AcpError::Wire { .. } => "crashed",
+                other => other.kind(),
             };
-            let detail = extract_wire_error_detail(&wire_err);
-            emit_agent_crashed_with_kind(&app_handle, &agent_id, kind, &detail);
+            emit_agent_crashed_with_kind(&app_handle, &agent_id, kind, &error.to_string());
         } else {
             let clean = clean_sacp_error_message(&message);
             emit_agent_crashed(&app_handle, &agent_id, &clean);
@@ -793,17 +2048,37 @@ async fn run_agent_command_loop(
     init_sender: InitSender,
     captured_models: CapturedModels,
     captured_commands: CapturedCommands,
+    captured_config_options: CapturedConfigOptions,
     log_path_string: String,
+    timeouts: AgentTimeouts,
 ) -> Result<(), sacp::Error> {
-    let init_request = InitializeRequest::new(ProtocolVersion::LATEST);
-    let init_response = tokio::time::timeout(
-        Duration::from_secs(30),
+    let init_request = InitializeRequest::new(ProtocolVersion::LATEST).client_capabilities(
+        ClientCapabilities::new()
+            .terminal(true)
+            .fs(FileSystemCapability::new()
+                .read_text_file(true)
+                .write_text_file(true)),
+    );
+    let init_response = match tokio::time::timeout(
+        timeouts.initialize(),
         cx.send_request(init_request).block_task(),
     )
     .await
-    .map_err(|_| sacp::util::internal_error("agent initialization timed out"))??;
+    {
+        Ok(response) => response?,
+        Err(_) => {
+            if let Some(init_tx) = init_sender.lock().await.take() {
+                let _ = init_tx.send(Err(AcpError::timeout("agent initialization timed out")));
+            }
+            return Err(sacp::util::internal_error("agent initialization timed out"));
+        }
+    };
 
     let mut info = to_agent_info(&agent_id, &init_response);
+    let embedded_context = init_response
+        .agent_capabilities
+        .prompt_capabilities
+        .embedded_context;
     info.log_file = Some(log_path_string);
     if let Some(init_tx) = init_sender.lock().await.take() {
         let _ = init_tx.send(Ok(info.clone()));
@@ -827,14 +2102,42 @@ async fn run_agent_command_loop(
                     AgentCommand::GetInfo { respond_to } => {
                         let _ = respond_to.send(Ok(info.clone()));
                     }
-                    AgentCommand::NewSession { cwd, respond_to } => {
-                        let session = cx
-                            .build_session(PathBuf::from(cwd.clone()))
-                            .block_task()
-                            .start_session()
+                    AgentCommand::NewSession {
+                        cwd,
+                        thinking_level,
+                        respond_to,
+                    } => {
+                        let mut attempt = 0;
+                        let session = loop {
+                            let result = tokio::time::timeout(
+                                timeouts.session_new(),
+                                cx.build_session(PathBuf::from(cwd.clone()))
+                                    .block_task()
+                                    .start_session(),
+                            )
                             .await;
+                            match result {
+                                Ok(Err(error))
+                                    if attempt < SESSION_NEW_RETRIES
+                                        && AcpError::from_sacp(&error).is_retryable() =>
+                                {
+                                    let delay = SESSION_NEW_RETRY_BASE_DELAY * 2u32.pow(attempt);
+                                    attempt += 1;
+                                    log::warn!(
+                                        "[acp] session/new failed agent_id={agent_id}, retrying in {}ms ({attempt}/{SESSION_NEW_RETRIES}): {error}",
+                                        delay.as_millis()
+                                    );
+                                    tokio::time::sleep(delay).await;
+                                }
+                                other => break other,
+                            }
+                        };
                         match session {
-                            Ok(session) => {
+                            Err(_) => {
+                                log::error!("[acp] session/new timed out agent_id={agent_id}");
+                                let _ = respond_to.send(Err(AcpError::timeout("session creation timed out")));
+                            }
+                            Ok(Ok(session)) => {
                                 let session_id = session.session_id().0.to_string();
                                 // Brief yield to let the I/O task process any notifications
                                 // that arrive right after session/new (e.g., available_commands_update)
@@ -847,43 +2150,93 @@ async fn run_agent_command_loop(
                                     .lock()
                                     .ok()
                                     .and_then(|mut guard| guard.take());
-                                let session_info =
-                                    to_session_info(&session, wire_models, wire_commands);
+                                let wire_config_options = captured_config_options
+                                    .lock()
+                                    .ok()
+                                    .and_then(|mut guard| guard.take());
+                                let session_info = to_session_info(
+                                    &session,
+                                    wire_models,
+                                    wire_commands,
+                                    wire_config_options,
+                                );
+                                // Best effort: agents without thinking control reject the request.
+                                let thinking_level = match thinking_level {
+                                    Some(level) => match cx
+                                        .send_request(SetSessionThinkingRequest {
+                                            session_id: session_id.clone(),
+                                            level: level.clone(),
+                                        })
+                                        .block_task()
+                                        .await
+                                    {
+                                        Ok(_) => Some(level),
+                                        Err(error) => {
+                                            log::warn!(
+                                                "[acp] default thinking level not applied agent_id={agent_id} session_id={session_id}: {error}"
+                                            );
+                                            None
+                                        }
+                                    },
+                                    None => None,
+                                };
+                                shared.lock().await.sessions.insert(
+                                    session_id.clone(),
+                                    SessionMetadata {
+                                        session_id: session_id.clone(),
+                                        cwd: cwd.clone(),
+                                        created_at_ms: chrono::Utc::now().timestamp_millis(),
+                                        current_mode_id: session_info.current_mode_id.clone(),
+                                        current_model_id: session_info.current_model_id.clone(),
+                                        thinking_level,
+                                        prompt_count: 0,
+                                        prompt_running: false,
+                                    },
+                                );
                                 sessions.insert(session_id, session);
                                 let _ = respond_to.send(Ok(session_info));
                             }
-                            Err(error) => {
+                            Ok(Err(error)) => {
                                 log::error!("[acp] session/new failed agent_id={agent_id}: {error}");
-                                let conn_err = sacp_error_to_connection_error(&error);
-                                let _ = respond_to.send(Err(connection_error_to_string(&conn_err)));
+                                let _ = respond_to.send(Err(AcpError::from_sacp(&error)));
                             }
                         }
                     }
                     AgentCommand::Prompt {
                         session_id,
                         text,
+                        mentions,
+                        vault_root,
+                        app_handle,
                         on_event,
                         respond_to,
                     } => {
                         if active_prompts.contains_key(&session_id) {
-                            let _ = respond_to.send(Err("prompt already in progress".to_string()));
+                            let _ = respond_to.send(Err(AcpError::internal("prompt already in progress")));
                             continue;
                         }
                         if text.trim().is_empty() {
-                            let _ = respond_to.send(Err("prompt text cannot be empty".to_string()));
+                            let _ = respond_to.send(Err(AcpError::internal("prompt text cannot be empty")));
                             continue;
                         }
                         let Some(session) = sessions.remove(&session_id) else {
-                            let _ = respond_to.send(Err(format!("session '{session_id}' not found")));
+                            let _ = respond_to.send(Err(AcpError::session_not_found(&session_id)));
                             continue;
                         };
 
-                        set_active_stream_for_session(
-                            &shared,
-                            session_id.clone(),
-                            on_event.clone(),
-                        )
-                        .await;
+                        if let Some(metadata) = shared.lock().await.sessions.get_mut(&session_id) {
+                            metadata.prompt_count += 1;
+                        }
+                        let subscribers = shared
+                            .lock()
+                            .await
+                            .subscribers
+                            .entry(session_id.clone())
+                            .or_default()
+                            .clone();
+                        let stream = PromptStream::new(on_event, subscribers);
+                        set_active_stream_for_session(&shared, session_id.clone(), stream.clone())
+                            .await;
                         active_prompts.insert(
                             session_id.clone(),
                             ActivePromptHandle {
@@ -891,18 +2244,25 @@ async fn run_agent_command_loop(
                             },
                         );
 
+                        let prompt = prompt_blocks(text, mentions, embedded_context);
                         let task_agent_id = agent_id.clone();
                         let task_session_id = session_id.clone();
                         let task_shared = shared.clone();
                         let task_return_tx = session_return_tx.clone();
+                        let task_cx = cx.clone();
+                        let inactivity_timeout = timeouts.prompt_inactivity();
                         tauri::async_runtime::spawn(async move {
                             prompt_reader_task(
                                 task_agent_id,
                                 task_session_id,
                                 session,
-                                text,
-                                on_event,
+                                prompt,
+                                vault_root,
+                                app_handle,
+                                inactivity_timeout,
+                                stream,
                                 respond_to,
+                                task_cx,
                                 task_shared,
                                 task_return_tx,
                             )
@@ -924,7 +2284,7 @@ async fn run_agent_command_loop(
                     } => {
                         let send_result = cx
                             .send_notification(CancelNotification::new(session_id.clone()))
-                            .map_err(|error| error.to_string());
+                            .map_err(|error| AcpError::from_sacp(&error));
                         cancel_pending_permissions_for_session(&shared, &session_id).await;
                         let _ = respond_to.send(send_result);
                     }
@@ -934,9 +2294,9 @@ async fn run_agent_command_loop(
                         respond_to,
                     } => {
                         if active_prompts.contains_key(&session_id) {
-                            let _ = respond_to.send(Err(
-                                "cannot change mode while a prompt is running".to_string()
-                            ));
+                            let _ = respond_to.send(Err(AcpError::internal(
+                                "cannot change mode while a prompt is running",
+                            )));
                             continue;
                         }
                         let mode_result = cx
@@ -946,8 +2306,11 @@ async fn run_agent_command_loop(
                             ))
                             .block_task()
                             .await
-                            .map_err(|error| error.to_string())
+                            .map_err(|error| AcpError::from_sacp(&error))
                             .map(|_| ());
+                        if mode_result.is_ok() {
+                            record_session_mode(&shared, &session_id, mode_id).await;
+                        }
                         let _ = respond_to.send(mode_result);
                     }
                     AgentCommand::SetModel {
@@ -956,9 +2319,9 @@ async fn run_agent_command_loop(
                         respond_to,
                     } => {
                         if active_prompts.contains_key(&session_id) {
-                            let _ = respond_to.send(Err(
-                                "cannot change model while a prompt is running".to_string()
-                            ));
+                            let _ = respond_to.send(Err(AcpError::internal(
+                                "cannot change model while a prompt is running",
+                            )));
                             continue;
                         }
                         let model_result = cx
@@ -968,10 +2331,173 @@ async fn run_agent_command_loop(
                             })
                             .block_task()
                             .await
-                            .map_err(|error| error.to_string())
+                            .map_err(|error| AcpError::from_sacp(&error))
                             .map(|_| ());
+                        if model_result.is_ok() {
+                            if let Some(metadata) = shared.lock().await.sessions.get_mut(&session_id) {
+                                metadata.current_model_id = Some(model_id);
+                            }
+                        }
                         let _ = respond_to.send(model_result);
                     }
+                    AgentCommand::SetThinking {
+                        session_id,
+                        level,
+                        respond_to,
+                    } => {
+                        if active_prompts.contains_key(&session_id) {
+                            let _ = respond_to.send(Err(AcpError::internal(
+                                "cannot change thinking level while a prompt is running",
+                            )));
+                            continue;
+                        }
+                        let thinking_result = cx
+                            .send_request(SetSessionThinkingRequest {
+                                session_id: session_id.clone(),
+                                level: level.clone(),
+                            })
+                            .block_task()
+                            .await
+                            .map_err(|error| AcpError::from_sacp(&error))
+                            .map(|_| ());
+                        if thinking_result.is_ok() {
+                            if let Some(metadata) = shared.lock().await.sessions.get_mut(&session_id) {
+                                metadata.thinking_level = Some(level);
+                            }
+                        }
+                        let _ = respond_to.send(thinking_result);
+                    }
+                    AgentCommand::SetConfigOption {
+                        session_id,
+                        config_id,
+                        value,
+                        respond_to,
+                    } => {
+                        if active_prompts.contains_key(&session_id) {
+                            let _ = respond_to.send(Err(AcpError::internal(
+                                "cannot change config options while a prompt is running",
+                            )));
+                            continue;
+                        }
+                        let config_result = cx
+                            .send_request(SetSessionConfigOptionRequest {
+                                session_id: session_id.clone(),
+                                config_id,
+                                value,
+                            })
+                            .block_task()
+                            .await
+                            .map_err(|error| AcpError::from_sacp(&error))
+                            .map(|response| response.config_options);
+                        let _ = respond_to.send(config_result);
+                    }
+                    AgentCommand::ResumeStream {
+                        session_id,
+                        on_event,
+                        respond_to,
+                    } => {
+                        let stream = shared
+                            .lock()
+                            .await
+                            .active_streams
+                            .get(&session_id)
+                            .map(|active| active.stream.clone());
+                        let resume_result = match stream {
+                            Some(stream) => stream.reattach(on_event),
+                            None => Err(AcpError::internal(format!(
+                                "no prompt is running for session '{session_id}'"
+                            ))),
+                        };
+                        let _ = respond_to.send(resume_result);
+                    }
+                    AgentCommand::Subscribe {
+                        session_id,
+                        on_event,
+                        respond_to,
+                    } => {
+                        let mut runtime = shared.lock().await;
+                        if !runtime.sessions.contains_key(&session_id) {
+                            let _ = respond_to.send(Err(AcpError::session_not_found(&session_id)));
+                            continue;
+                        }
+                        // Catch up with a prompt that is already streaming.
+                        if let Some(active) = runtime.active_streams.get(&session_id) {
+                            if let Err(error) = active.stream.replay_to(&on_event) {
+                                let _ = respond_to.send(Err(error));
+                                continue;
+                            }
+                        }
+                        runtime.next_subscription_id += 1;
+                        let subscription_id = format!("subscription-{}", runtime.next_subscription_id);
+                        if let Ok(mut subscribers) = runtime
+                            .subscribers
+                            .entry(session_id)
+                            .or_default()
+                            .lock()
+                        {
+                            subscribers.push((subscription_id.clone(), on_event));
+                        }
+                        let _ = respond_to.send(Ok(subscription_id));
+                    }
+                    AgentCommand::Unsubscribe {
+                        session_id,
+                        subscription_id,
+                        respond_to,
+                    } => {
+                        let removed = shared
+                            .lock()
+                            .await
+                            .subscribers
+                            .get(&session_id)
+                            .and_then(|subscribers| subscribers.lock().ok().map(|mut subscribers| {
+                                let before = subscribers.len();
+                                subscribers.retain(|(id, _)| id != &subscription_id);
+                                subscribers.len() != before
+                            }))
+                            .unwrap_or(false);
+                        let unsubscribe_result = if removed {
+                            Ok(())
+                        } else {
+                            Err(AcpError::internal(format!(
+                                "subscription '{subscription_id}' not found"
+                            )))
+                        };
+                        let _ = respond_to.send(unsubscribe_result);
+                    }
+                    AgentCommand::ListCommands {
+                        session_id,
+                        respond_to,
+                    } => {
+                        let runtime = shared.lock().await;
+                        if !runtime.sessions.contains_key(&session_id) {
+                            let _ = respond_to.send(Err(AcpError::session_not_found(&session_id)));
+                            continue;
+                        }
+                        let mut commands = runtime
+                            .session_commands
+                            .lock()
+                            .ok()
+                            .and_then(|commands| commands.get(&session_id).cloned())
+                            .unwrap_or_default();
+                        vault_commands::merge_vault_commands(&mut commands);
+                        let _ = respond_to.send(Ok(commands));
+                    }
+                    AgentCommand::ListSessions { respond_to } => {
+                        let runtime = shared.lock().await;
+                        let mut listed: Vec<SessionMetadata> = runtime
+                            .sessions
+                            .values()
+                            .cloned()
+                            .map(|metadata| SessionMetadata {
+                                prompt_running: runtime
+                                    .active_streams
+                                    .contains_key(&metadata.session_id),
+                                ..metadata
+                            })
+                            .collect();
+                        listed.sort_by_key(|metadata| metadata.created_at_ms);
+                        let _ = respond_to.send(listed);
+                    }
                 }
             }
             Some((session_id, session)) = session_return_rx.recv() => {
@@ -990,36 +2516,89 @@ async fn prompt_reader_task(
     agent_id: String,
     session_id: String,
     mut session: sacp::ActiveSession<'static, sacp::link::ClientToAgent>,
-    text: String,
-    on_event: Channel<AgentEvent>,
-    respond_to: oneshot::Sender<Result<(), String>>,
+    prompt: Vec<ContentBlock>,
+    vault_root: Option<PathBuf>,
+    app_handle: AppHandle,
+    inactivity_timeout: Option<Duration>,
+    on_event: PromptStream,
+    respond_to: oneshot::Sender<Result<(), AcpError>>,
+    cx: sacp::JrConnectionCx<sacp::link::ClientToAgent>,
     shared: Arc<tokio::sync::Mutex<RuntimeShared>>,
     return_tx: mpsc::Sender<(
         String,
         sacp::ActiveSession<'static, sacp::link::ClientToAgent>,
     )>,
 ) {
-    match session.send_prompt(text) {
-        Ok(()) => {}
+    let mut stop_rx = match send_prompt(&session, prompt) {
+        Ok(stop_rx) => stop_rx,
         Err(error) => {
             log::error!(
                 "[acp] failed to send prompt agent_id={agent_id} session_id={session_id}: {error}"
             );
-            let _ = respond_to.send(Err(format!("failed to send prompt to agent: {error}")));
+            let _ = respond_to.send(Err(AcpError::internal(format!(
+                "failed to send prompt to agent: {error}"
+            ))));
             clear_active_stream_for_session(&shared, &session_id).await;
             let _ = return_tx.send((session_id, session)).await;
             return;
         }
-    }
+    };
 
+    let started_at_ms = chrono::Utc::now().timestamp_millis();
+    let mut chunk_count: u64 = 0;
+    let mut final_stop_reason: Option<String> = None;
+    let terminals = shared.lock().await.terminals.clone();
     let mut tool_calls: HashMap<String, ToolCall> = HashMap::new();
     let mut update_count: usize = 0;
     let mut saw_visible_output = false;
     let mut respond_to = Some(respond_to);
+    let mut timed_out = false;
 
     loop {
-        match session.read_update().await {
+        let next_update = read_prompt_update(&mut session, &mut stop_rx);
+        let update = match inactivity_timeout {
+            Some(window) => match tokio::time::timeout(window, next_update).await {
+                Ok(update) => update,
+                Err(_) if has_pending_permission(&shared, &session_id).await => {
+                    // Waiting on the user, not the agent.
+                    continue;
+                }
+                Err(_) if !timed_out => {
+                    // Fail the prompt and ask the agent to stop; keep reading so the
+                    // session is left in a clean state once the agent acknowledges.
+                    timed_out = true;
+                    let message =
+                        format!("agent produced no updates for {} seconds", window.as_secs());
+                    log::warn!("[acp] agent_id={agent_id} session_id={session_id} {message}");
+                    on_event.send(AgentEvent::Error {
+                        message: message.clone(),
+                        kind: Some("timeout".to_string()),
+                    });
+                    if let Some(tx) = respond_to.take() {
+                        let _ = tx.send(Err(AcpError::timeout(message)));
+                    }
+                    if let Err(error) =
+                        cx.send_notification(CancelNotification::new(session_id.clone()))
+                    {
+                        log::warn!("[acp] failed to cancel timed out prompt: {error}");
+                    }
+                    cancel_pending_permissions_for_session(&shared, &session_id).await;
+                    continue;
+                }
+                Err(_) => {
+                    log::warn!(
+                        "[acp] agent_id={agent_id} session_id={session_id} did not acknowledge cancel"
+                    );
+                    break;
+                }
+            },
+            None => next_update.await,
+        };
+
+        match update {
             Ok(SessionMessage::StopReason(stop_reason)) => {
+                let truncated = matches!(stop_reason, StopReason::MaxTokens);
+                let can_continue = truncated || matches!(stop_reason, StopReason::MaxTurnRequests);
                 let stop_reason_text = stop_reason_to_string(stop_reason);
                 if !saw_visible_output {
                     let message = format!(
@@ -1027,12 +2606,19 @@ async fn prompt_reader_task(
                         stop_reason_text
                     );
                     log::warn!("[acp] agent_id={agent_id} session_id={session_id} {message}");
-                    let _ = on_event.send(AgentEvent::Error {
+                    on_event.send(AgentEvent::Error {
                         message: message.clone(),
+                        kind: None,
                     });
                 }
-                let _ = on_event.send(AgentEvent::Done {
+                final_stop_reason = Some(stop_reason_text.clone());
+                on_event.send(AgentEvent::Done {
                     stop_reason: stop_reason_text,
+                    duration_ms: chrono::Utc::now().timestamp_millis() - started_at_ms,
+                    tool_call_count: tool_calls.len() as u64,
+                    chunk_count,
+                    truncated,
+                    can_continue,
                 });
                 if let Some(tx) = respond_to.take() {
                     let _ = tx.send(Ok(()));
@@ -1043,10 +2629,22 @@ async fn prompt_reader_task(
                 update_count += 1;
                 let handled = MatchMessage::new(message_cx)
                     .if_notification(async |notification: SessionNotification| {
+                        match &notification.update {
+                            SessionUpdate::CurrentModeUpdate(update) => {
+                                let mode_id = update.current_mode_id.0.to_string();
+                                record_session_mode(&shared, &session_id, mode_id).await;
+                            }
+                            SessionUpdate::AgentMessageChunk(_)
+                            | SessionUpdate::AgentThoughtChunk(_) => chunk_count += 1,
+                            _ => {}
+                        }
                         handle_session_notification_in_reader(
                             &agent_id,
                             &session_id,
+                            vault_root.as_deref(),
+                            &app_handle,
                             &on_event,
+                            &terminals,
                             &mut tool_calls,
                             update_count,
                             &mut saw_visible_output,
@@ -1062,11 +2660,12 @@ async fn prompt_reader_task(
                         "[acp][{agent_id}][session:{session_id}] failed to process session/update: {error}"
                     );
                     let message = format!("failed to handle session update: {error}");
-                    let _ = on_event.send(AgentEvent::Error {
+                    on_event.send(AgentEvent::Error {
                         message: message.clone(),
+                        kind: None,
                     });
                     if let Some(tx) = respond_to.take() {
-                        let _ = tx.send(Err(message));
+                        let _ = tx.send(Err(AcpError::internal(message)));
                     }
                     break;
                 }
@@ -1076,11 +2675,12 @@ async fn prompt_reader_task(
                     "[acp][{agent_id}][session:{session_id}] failed while reading prompt updates: {error}"
                 );
                 let message = format!("failed reading prompt updates: {error}");
-                let _ = on_event.send(AgentEvent::Error {
+                on_event.send(AgentEvent::Error {
                     message: message.clone(),
+                    kind: None,
                 });
                 if let Some(tx) = respond_to.take() {
-                    let _ = tx.send(Err(message));
+                    let _ = tx.send(Err(AcpError::internal(message)));
                 }
                 break;
             }
@@ -1088,14 +2688,98 @@ async fn prompt_reader_task(
         }
     }
 
+    record_prompt(
+        &app_handle,
+        &agent_id,
+        PromptRecord {
+            session_id: session_id.clone(),
+            started_at_ms,
+            ended_at_ms: chrono::Utc::now().timestamp_millis(),
+            chunk_count,
+            tool_call_count: tool_calls.len() as u64,
+            stop_reason: final_stop_reason.unwrap_or_else(|| "error".to_string()),
+        },
+    )
+    .await;
     clear_active_stream_for_session(&shared, &session_id).await;
     let _ = return_tx.send((session_id, session)).await;
 }
 
+/// Builds the prompt: the text followed by the mentioned resources. Agents
+/// without the `embeddedContext` prompt capability get resource links instead,
+/// which every agent accepts, and read the files through the fs capability.
+fn prompt_blocks(
+    text: String,
+    mentions: Vec<EmbeddedResource>,
+    embedded_context: bool,
+) -> Vec<ContentBlock> {
+    let mut blocks = vec![ContentBlock::from(text)];
+    for resource in mentions {
+        if embedded_context {
+            blocks.push(ContentBlock::Resource(resource));
+            continue;
+        }
+        let uri = match resource.resource {
+            EmbeddedResourceResource::TextResourceContents(contents) => contents.uri,
+            EmbeddedResourceResource::BlobResourceContents(contents) => contents.uri,
+            _ => continue,
+        };
+        let name = uri
+            .trim_end_matches('/')
+            .rsplit('/')
+            .next()
+            .unwrap_or(&uri)
+            .to_string();
+        blocks.push(ContentBlock::ResourceLink(ResourceLink::new(name, uri)));
+    }
+    blocks
+}
+
+/// Sends a `session/prompt` with arbitrary content blocks; the session's own
+/// `send_prompt` only takes text. The returned receiver resolves with the
+/// agent's stop reason.
+fn send_prompt(
+    session: &sacp::ActiveSession<'static, sacp::link::ClientToAgent>,
+    prompt: Vec<ContentBlock>,
+) -> Result<oneshot::Receiver<Result<StopReason, sacp::Error>>, sacp::Error> {
+    let (stop_tx, stop_rx) = oneshot::channel();
+    session
+        .connection_cx()
+        .send_request_to(
+            sacp::AgentPeer,
+            PromptRequest::new(session.session_id().clone(), prompt),
+        )
+        .on_receiving_result(async move |result| {
+            let _ = stop_tx.send(result.map(|response| response.stop_reason));
+            Ok(())
+        })?;
+    Ok(stop_rx)
+}
+
+/// Reads the next session update, or the stop reason once the prompt request
+/// completes. Updates already queued are read first so none are dropped.
+async fn read_prompt_update(
+    session: &mut sacp::ActiveSession<'static, sacp::link::ClientToAgent>,
+    stop_rx: &mut oneshot::Receiver<Result<StopReason, sacp::Error>>,
+) -> Result<SessionMessage, sacp::Error> {
+    tokio::select! {
+        biased;
+        update = session.read_update() => update,
+        stop = stop_rx => match stop {
+            Ok(result) => result.map(SessionMessage::StopReason),
+            Err(_) => Err(sacp::util::internal_error("prompt response was dropped")),
+        },
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 fn handle_session_notification_in_reader(
-    _agent_id: &str,
-    _session_id: &str,
-    on_event: &Channel<AgentEvent>,
+    agent_id: &str,
+    session_id: &str,
+    vault_root: Option<&Path>,
+    app_handle: &AppHandle,
+    on_event: &PromptStream,
+    terminals: &ClientTerminals,
     tool_calls: &mut HashMap<String, ToolCall>,
     _update_count: usize,
     saw_visible_output: &mut bool,
@@ -1108,11 +2792,23 @@ fn handle_session_notification_in_reader(
                 if !text_content.text.is_empty() {
                     *saw_visible_output = true;
                 }
-                on_event
-                    .send(AgentEvent::MessageChunk {
-                        text: text_content.text,
-                    })
-                    .map_err(sacp::util::internal_error)?;
+                on_event.send(AgentEvent::MessageChunk {
+                    text: text_content.text,
+                });
+            }
+            ContentBlock::ResourceLink(link) => {
+                let title = link.title.unwrap_or(link.name);
+                let event = match vault_root
+                    .and_then(|root| resource_link_to_vault_path(&link.uri, root))
+                {
+                    Some(path) => AgentEvent::ResourceLink { path, title },
+                    // links outside the vault are shown as plain markdown links
+                    None => AgentEvent::MessageChunk {
+                        text: format!("[{title}]({})", link.uri),
+                    },
+                };
+                *saw_visible_output = true;
+                on_event.send(event);
             }
             other => {
                 let placeholder = format!(
@@ -1120,9 +2816,7 @@ fn handle_session_notification_in_reader(
                     content_block_kind(&other)
                 );
                 *saw_visible_output = true;
-                on_event
-                    .send(AgentEvent::MessageChunk { text: placeholder })
-                    .map_err(sacp::util::internal_error)?;
+                on_event.send(AgentEvent::MessageChunk { text: placeholder });
             }
         },
         SessionUpdate::AgentThoughtChunk(chunk) => {
@@ -1130,21 +2824,18 @@ fn handle_session_notification_in_reader(
                 if !text_content.text.is_empty() {
                     *saw_visible_output = true;
                 }
-                on_event
-                    .send(AgentEvent::ThinkingChunk {
-                        text: text_content.text,
-                    })
-                    .map_err(sacp::util::internal_error)?;
+                on_event.send(AgentEvent::ThinkingChunk {
+                    text: text_content.text,
+                });
             }
         }
         SessionUpdate::ToolCall(tool_call) => {
             let id = tool_call.tool_call_id.0.to_string();
             tool_calls.insert(id.clone(), tool_call);
             if let Some(current) = tool_calls.get(&id) {
+                link_tool_call_terminals(terminals, current, on_event);
                 *saw_visible_output = true;
-                on_event
-                    .send(tool_call_to_event(current))
-                    .map_err(sacp::util::internal_error)?;
+                on_event.send(tool_call_to_event(current, terminals));
             }
         }
         SessionUpdate::ToolCallUpdate(update) => {
@@ -1152,11 +2843,20 @@ fn handle_session_notification_in_reader(
             let tool_call = tool_calls
                 .entry(id.clone())
                 .or_insert_with(|| ToolCall::new(update.tool_call_id.clone(), "tool"));
+            let completed = update.fields.status == Some(ToolCallStatus::Completed);
+            let failed = update.fields.status == Some(ToolCallStatus::Failed);
             tool_call.update(update.fields);
+            if completed || failed {
+                audit_tool_call(app_handle, agent_id, session_id, tool_call);
+            }
+            if completed {
+                if let Some(vault_root) = vault_root {
+                    handle_completed_tool_call(app_handle, vault_root, tool_call);
+                }
+            }
+            link_tool_call_terminals(terminals, tool_call, on_event);
             *saw_visible_output = true;
-            on_event
-                .send(tool_call_to_event(tool_call))
-                .map_err(sacp::util::internal_error)?;
+            on_event.send(tool_call_to_event(tool_call, terminals));
         }
         SessionUpdate::Plan(plan) => {
             let entries = plan
@@ -1168,21 +2868,17 @@ fn handle_session_notification_in_reader(
                 })
                 .collect();
             *saw_visible_output = true;
-            on_event
-                .send(AgentEvent::PlanUpdate { entries })
-                .map_err(sacp::util::internal_error)?;
+            on_event.send(AgentEvent::PlanUpdate { entries });
         }
         SessionUpdate::CurrentModeUpdate(CurrentModeUpdate {
             current_mode_id, ..
         }) => {
-            on_event
-                .send(AgentEvent::ModeUpdate {
-                    current_mode_id: current_mode_id.0.to_string(),
-                })
-                .map_err(sacp::util::internal_error)?;
+            on_event.send(AgentEvent::ModeUpdate {
+                current_mode_id: current_mode_id.0.to_string(),
+            });
         }
         SessionUpdate::AvailableCommandsUpdate(update) => {
-            let commands = update
+            let mut commands: Vec<SlashCommandInfo> = update
                 .available_commands
                 .into_iter()
                 .map(|command| SlashCommandInfo {
@@ -1194,11 +2890,17 @@ fn handle_session_notification_in_reader(
                     }),
                 })
                 .collect();
-            on_event
-                .send(AgentEvent::CommandsUpdate { commands })
-                .map_err(sacp::util::internal_error)?;
+            vault_commands::merge_vault_commands(&mut commands);
+            on_event.send(AgentEvent::CommandsUpdate { commands });
+        }
+        SessionUpdate::ConfigOptionUpdate(update) => {
+            // the schema is unstable, so read the options back out of the wire shape
+            let config_options = serde_json::to_value(&update)
+                .ok()
+                .and_then(|value| parse_config_options(&value))
+                .unwrap_or_default();
+            on_event.send(AgentEvent::ConfigOptionsUpdate { config_options });
         }
-        SessionUpdate::ConfigOptionUpdate(_) => {}
         _ => {}
     }
 
@@ -1206,11 +2908,73 @@ fn handle_session_notification_in_reader(
 }
 
 async fn handle_permission_request(
-    _agent_id: String,
+    app_handle: AppHandle,
+    agent_id: String,
     shared: Arc<tokio::sync::Mutex<RuntimeShared>>,
     request: RequestPermissionRequest,
     request_cx: sacp::JrRequestCx<RequestPermissionResponse>,
 ) -> Result<(), sacp::Error> {
+    let tool_kind = request
+        .tool_call
+        .fields
+        .kind
+        .map(tool_kind_to_string)
+        .unwrap_or_else(|| "other".to_string());
+    let location_paths = request
+        .tool_call
+        .fields
+        .locations
+        .as_ref()
+        .map(|locations| {
+            locations
+                .iter()
+                .map(|location| location.path.to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    let outside_vault = location_paths
+        .iter()
+        .find_map(|path| check_vault_only_path(&app_handle, &agent_id, Path::new(path)).err());
+    if let Some(reason) = outside_vault {
+        log::info!("[acp][{agent_id}] rejecting {tool_kind} permission: {reason}");
+        let reject_option = request
+            .options
+            .iter()
+            .find(|option| matches!(option.kind, PermissionOptionKind::RejectOnce))
+            .map(|option| option.option_id.0.to_string());
+        let outcome = match reject_option {
+            Some(option_id) => {
+                RequestPermissionOutcome::Selected(SelectedPermissionOutcome::new(option_id))
+            }
+            None => RequestPermissionOutcome::Cancelled,
+        };
+        audit_permission(
+            &app_handle,
+            &agent_id,
+            &request,
+            &tool_kind,
+            "sandbox_rejected",
+        );
+        return request_cx.respond(RequestPermissionResponse::new(outcome));
+    }
+
+    if let Some(option_id) = find_remembered_allow_option(
+        &app_handle,
+        &agent_id,
+        &tool_kind,
+        &location_paths,
+        &request,
+    ) {
+        log::info!(
+            "[acp][{agent_id}] auto-allowing {tool_kind} permission from remembered decision"
+        );
+        audit_permission(&app_handle, &agent_id, &request, &tool_kind, "remembered");
+        return request_cx.respond(RequestPermissionResponse::new(
+            RequestPermissionOutcome::Selected(SelectedPermissionOutcome::new(option_id)),
+        ));
+    }
+
     let (decision_tx, decision_rx) = oneshot::channel::<Option<String>>();
     let request_id;
     let mut should_wait = false;
@@ -1245,27 +3009,86 @@ async fn handle_permission_request(
                     })
                     .collect(),
             };
-            if stream.channel.send(event).is_ok() {
-                should_wait = true;
-            }
+            stream.stream.send(event);
+            should_wait = true;
         }
     }
 
     if !should_wait {
         let mut runtime = shared.lock().await;
         runtime.pending_permissions.remove(&request_id);
+        audit_permission(&app_handle, &agent_id, &request, &tool_kind, "cancelled");
         return request_cx.respond(RequestPermissionResponse::new(
             RequestPermissionOutcome::Cancelled,
         ));
     }
 
-    let selected_option = decision_rx.await.ok().flatten();
+    let timeout_settings: PermissionTimeoutSettings =
+        get_setting(&app_handle, PERMISSION_TIMEOUT_KEY).unwrap_or_default();
+    let mut timed_out = false;
+    let selected_option = match timeout_settings.timeout() {
+        Some(timeout) => match tokio::time::timeout(timeout, decision_rx).await {
+            Ok(decision) => decision.ok().flatten(),
+            Err(_) => {
+                let option_id = match timeout_settings.default_decision {
+                    PermissionTimeoutDecision::RejectOnce => request
+                        .options
+                        .iter()
+                        .find(|option| matches!(option.kind, PermissionOptionKind::RejectOnce))
+                        .map(|option| option.option_id.0.to_string()),
+                    PermissionTimeoutDecision::Cancel => None,
+                };
+                log::warn!(
+                    "[acp][{agent_id}] permission {request_id} timed out after {}s, responding with {}",
+                    timeout.as_secs(),
+                    option_id.as_deref().unwrap_or("cancelled")
+                );
+                let mut runtime = shared.lock().await;
+                runtime.pending_permissions.remove(&request_id);
+                if let Some(stream) = runtime.active_streams.get(&request_session_id) {
+                    stream.stream.send(AgentEvent::PermissionTimedOut {
+                        request_id: request_id.clone(),
+                        option_id: option_id.clone(),
+                    });
+                }
+                timed_out = true;
+                option_id
+            }
+        },
+        None => decision_rx.await.ok().flatten(),
+    };
 
     {
         let mut runtime = shared.lock().await;
         runtime.pending_permissions.remove(&request_id);
     }
 
+    if let Some(option_id) = selected_option.as_deref() {
+        let allow_always = request.options.iter().any(|option| {
+            option.option_id.0.to_string() == option_id
+                && matches!(option.kind, PermissionOptionKind::AllowAlways)
+        });
+        if allow_always {
+            remember_permission_decision(&app_handle, &agent_id, &tool_kind, &location_paths);
+        }
+    }
+
+    let outcome = if timed_out {
+        "timed_out".to_string()
+    } else {
+        selected_option
+            .as_deref()
+            .and_then(|option_id| {
+                request
+                    .options
+                    .iter()
+                    .find(|option| option.option_id.0.to_string() == option_id)
+            })
+            .map(|option| permission_option_kind_to_string(option.kind))
+            .unwrap_or_else(|| "cancelled".to_string())
+    };
+    audit_permission(&app_handle, &agent_id, &request, &tool_kind, &outcome);
+
     let response = match selected_option {
         Some(option_id) => RequestPermissionResponse::new(RequestPermissionOutcome::Selected(
             SelectedPermissionOutcome::new(option_id),
@@ -1276,6 +3099,224 @@ async fn handle_permission_request(
     request_cx.respond(response)
 }
 
+fn audit_permission(
+    app_handle: &AppHandle,
+    agent_id: &str,
+    request: &RequestPermissionRequest,
+    tool_kind: &str,
+    outcome: &str,
+) {
+    audit::record(
+        app_handle,
+        AuditEntry {
+            time_ms: 0,
+            kind: AuditEventKind::Permission,
+            agent_id: agent_id.to_string(),
+            session_id: request.session_id.0.to_string(),
+            tool_call_id: request.tool_call.tool_call_id.0.to_string(),
+            tool_kind: tool_kind.to_string(),
+            title: request.tool_call.fields.title.clone(),
+            locations: request
+                .tool_call
+                .fields
+                .locations
+                .iter()
+                .flatten()
+                .map(|location| location.path.to_string_lossy().to_string())
+                .collect(),
+            outcome: outcome.to_string(),
+        },
+    );
+}
+
+fn audit_tool_call(app_handle: &AppHandle, agent_id: &str, session_id: &str, tool_call: &ToolCall) {
+    audit::record(
+        app_handle,
+        AuditEntry {
+            time_ms: 0,
+            kind: AuditEventKind::ToolCall,
+            agent_id: agent_id.to_string(),
+            session_id: session_id.to_string(),
+            tool_call_id: tool_call.tool_call_id.0.to_string(),
+            tool_kind: tool_kind_to_string(tool_call.kind),
+            title: Some(tool_call.title.clone()),
+            locations: tool_call
+                .locations
+                .iter()
+                .map(|location| location.path.to_string_lossy().to_string())
+                .collect(),
+            outcome: tool_call_status_to_string(tool_call.status),
+        },
+    );
+}
+
+/// Returns the option to auto-select if a remembered rule covers this request.
+fn find_remembered_allow_option(
+    app_handle: &AppHandle,
+    agent_id: &str,
+    tool_kind: &str,
+    location_paths: &[String],
+    request: &RequestPermissionRequest,
+) -> Option<String> {
+    let rules: Vec<PermissionRule> = get_setting(app_handle, PERMISSION_RULES_KEY)?;
+    let covered = rules
+        .iter()
+        .any(|rule| permission_rule_matches(rule, agent_id, tool_kind, location_paths));
+    if !covered {
+        return None;
+    }
+
+    // prefer the agent's "always" option so it can also skip asking, then "once"
+    let find_option = |kind: fn(&PermissionOptionKind) -> bool| {
+        request
+            .options
+            .iter()
+            .find(|option| kind(&option.kind))
+            .map(|option| option.option_id.0.to_string())
+    };
+    find_option(|kind| matches!(kind, PermissionOptionKind::AllowAlways))
+        .or_else(|| find_option(|kind| matches!(kind, PermissionOptionKind::AllowOnce)))
+}
+
+fn permission_rule_matches(
+    rule: &PermissionRule,
+    agent_id: &str,
+    tool_kind: &str,
+    location_paths: &[String],
+) -> bool {
+    if rule.agent_id != agent_id || rule.tool_kind != tool_kind {
+        return false;
+    }
+    if rule.path_prefix.is_empty() {
+        return location_paths.is_empty();
+    }
+    // normalized first, so `<prefix>/../secret` does not match the prefix
+    !location_paths.is_empty()
+        && location_paths
+            .iter()
+            .all(|path| is_inside_vault(Path::new(path), Path::new(&rule.path_prefix)))
+}
+
+fn remember_permission_decision(
+    app_handle: &AppHandle,
+    agent_id: &str,
+    tool_kind: &str,
+    location_paths: &[String],
+) {
+    // a relative path could never match a rule again, so there is nothing to remember
+    if location_paths
+        .iter()
+        .any(|path| normalize_absolute(Path::new(path)).is_none())
+    {
+        log::info!("[acp][{agent_id}] not remembering {tool_kind} permission for relative paths");
+        return;
+    }
+    let path_prefix = common_parent_dir(location_paths);
+    if path_prefix == "/" {
+        log::info!(
+            "[acp][{agent_id}] not remembering {tool_kind} permission spanning the filesystem root"
+        );
+        return;
+    }
+
+    let mut rules: Vec<PermissionRule> =
+        get_setting(app_handle, PERMISSION_RULES_KEY).unwrap_or_default();
+    if rules
+        .iter()
+        .any(|rule| permission_rule_matches(rule, agent_id, tool_kind, location_paths))
+    {
+        return;
+    }
+
+    log::info!("[acp][{agent_id}] remembering {tool_kind} permission for '{path_prefix}'");
+    rules.push(PermissionRule {
+        agent_id: agent_id.to_string(),
+        tool_kind: tool_kind.to_string(),
+        path_prefix,
+    });
+    if let Err(error) = set_setting(app_handle, PERMISSION_RULES_KEY, &rules) {
+        log::warn!("[acp][{agent_id}] failed to persist permission rule: {error}");
+    }
+}
+
+/// Deepest directory containing every path, or "" when there are no paths.
+fn common_parent_dir(paths: &[String]) -> String {
+    let mut common: Option<PathBuf> = None;
+    for path in paths {
+        let path = normalize_absolute(Path::new(path)).unwrap_or_else(|| PathBuf::from(path));
+        let parent = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        common = Some(match common {
+            None => parent,
+            Some(current) => current
+                .ancestors()
+                .find(|ancestor| parent.starts_with(ancestor))
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+        });
+    }
+    common
+        .map(|path| path.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// Converts a `file://` resource link into a vault-relative path.
+/// Returns None for other schemes and for files outside the vault.
+fn resource_link_to_vault_path(uri: &str, vault_root: &Path) -> Option<String> {
+    let url = tauri::Url::parse(uri).ok()?;
+    if url.scheme() != "file" {
+        return None;
+    }
+    let file_path = url.to_file_path().ok()?;
+    let relative = file_path.strip_prefix(vault_root).ok()?;
+    if relative.as_os_str().is_empty() {
+        return None;
+    }
+    Some(relative.to_string_lossy().to_string())
+}
+
+/// Handles a completed edit/delete/move tool call that touched vault files:
+/// records it in the change journal (attributed to the agent) and emits a
+/// file watcher event right away so open editors refresh without waiting for
+/// the debounced watcher.
+fn handle_completed_tool_call(app_handle: &AppHandle, vault_root: &Path, tool_call: &ToolCall) {
+    let (op, kind) = match tool_call.kind {
+        ToolKind::Edit | ToolKind::Move => (JournalOp::Update, "modify"),
+        ToolKind::Delete => (JournalOp::Delete, "delete"),
+        _ => return,
+    };
+    let mut paths: Vec<String> = tool_call
+        .locations
+        .iter()
+        .map(|location| location.path.as_path())
+        .chain(tool_call.content.iter().filter_map(|item| match item {
+            ToolCallContent::Diff(diff) => Some(diff.path.as_path()),
+            _ => None,
+        }))
+        .filter_map(|path| path.strip_prefix(vault_root).ok())
+        .filter(|relative| !relative.as_os_str().is_empty())
+        .map(|relative| relative.to_string_lossy().to_string())
+        .collect();
+    paths.sort();
+    paths.dedup();
+    if paths.is_empty() {
+        return;
+    }
+
+    for path in &paths {
+        journal::record(vault_root, op, path, None, JournalOrigin::Agent);
+    }
+    file_watcher::emit_file_changes(
+        app_handle,
+        paths
+            .into_iter()
+            .map(|path| FileChange {
+                path,
+                kind: kind.to_string(),
+            })
+            .collect(),
+    );
+}
+
 fn content_block_kind(content: &ContentBlock) -> &'static str {
     match content {
         ContentBlock::Text(_) => "text",
@@ -1287,8 +3328,8 @@ fn content_block_kind(content: &ContentBlock) -> &'static str {
     }
 }
 
-fn tool_call_to_event(tool_call: &ToolCall) -> AgentEvent {
-    let content = tool_call_content_to_string(&tool_call.content);
+fn tool_call_to_event(tool_call: &ToolCall, terminals: &ClientTerminals) -> AgentEvent {
+    let content = tool_call_content_to_string(&tool_call.content, terminals);
     let locations = tool_call_locations_to_strings(&tool_call.locations);
     let diff_data = tool_call_diff_data(&tool_call.content);
     AgentEvent::ToolCallUpdate {
@@ -1315,7 +3356,10 @@ fn tool_call_diff_data(content: &[ToolCallContent]) -> Option<DiffInfo> {
     None
 }
 
-fn tool_call_content_to_string(content: &[ToolCallContent]) -> Option<String> {
+fn tool_call_content_to_string(
+    content: &[ToolCallContent],
+    terminals: &ClientTerminals,
+) -> Option<String> {
     let mut lines = Vec::new();
     for item in content {
         match item {
@@ -1328,7 +3372,11 @@ fn tool_call_content_to_string(content: &[ToolCallContent]) -> Option<String> {
                 lines.push(format!("diff: {}", diff.path.to_string_lossy()));
             }
             ToolCallContent::Terminal(terminal) => {
-                lines.push(format!("terminal: {}", terminal.terminal_id.0));
+                let terminal_id = terminal.terminal_id.0.to_string();
+                match terminal_output_snapshot(terminals, &terminal_id) {
+                    Some(output) => lines.push(output),
+                    None => lines.push(format!("terminal: {terminal_id}")),
+                }
             }
             _ => {}
         }
@@ -1387,6 +3435,7 @@ fn to_session_info(
     session: &sacp::ActiveSession<'static, sacp::link::ClientToAgent>,
     wire_models: Option<RawSessionModels>,
     wire_commands: Option<Vec<SlashCommandInfo>>,
+    wire_config_options: Option<Vec<ConfigOptionInfo>>,
 ) -> SessionInfo {
     let (available_modes, current_mode_id) = session
         .modes()
@@ -1406,7 +3455,8 @@ fn to_session_info(
         })
         .unwrap_or_else(|| (Vec::new(), None));
 
-    let available_commands = wire_commands.unwrap_or_default();
+    let mut available_commands = wire_commands.unwrap_or_default();
+    vault_commands::merge_vault_commands(&mut available_commands);
 
     let (available_models, current_model_id) = match wire_models {
         Some(models) => {
@@ -1431,22 +3481,37 @@ fn to_session_info(
         available_commands,
         available_models,
         current_model_id,
+        config_options: wire_config_options.unwrap_or_default(),
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn build_agent(
     _agent_id: &str,
     command: String,
     env: HashMap<String, String>,
+    proxy: &ProxySettings,
     captured_error: CapturedError,
+    captured_stderr: CapturedStderr,
+    session_commands: SessionCommands,
     process_log: AcpProcessLog,
-) -> Result<(AcpAgent, CapturedModels, CapturedCommands), String> {
+) -> Result<
+    (
+        AcpAgent,
+        CapturedModels,
+        CapturedCommands,
+        CapturedConfigOptions,
+    ),
+    String,
+> {
     let parsed = AcpAgent::from_str(&command)
         .map_err(|error| format!("invalid command '{command}': {error}"))?;
     let mut server = parsed.into_server();
     match &mut server {
         sacp::schema::McpServer::Stdio(stdio) => {
             for (name, value) in env {
+                // `keychain:<name>` values are resolved here so raw keys never cross IPC
+                let value = secrets::resolve_env_value(&value)?;
                 if let Some(existing) = stdio.env.iter_mut().find(|variable| variable.name == name)
                 {
                     existing.value = value;
@@ -1454,6 +3519,20 @@ fn build_agent(
                     stdio.env.push(sacp::schema::EnvVariable::new(name, value));
                 }
             }
+            // proxy settings apply unless the command or caller set them explicitly
+            for (name, value) in proxy.env_vars() {
+                if !stdio.env.iter().any(|variable| variable.name == name) {
+                    stdio.env.push(sacp::schema::EnvVariable::new(name, value));
+                }
+            }
+            // the login-shell environment fills in whatever the command and caller don't set
+            for (name, value) in crate::login_shell_env() {
+                if !stdio.env.iter().any(|variable| &variable.name == name) {
+                    stdio
+                        .env
+                        .push(sacp::schema::EnvVariable::new(name.clone(), value.clone()));
+                }
+            }
         }
         _ => {
             return Err(
@@ -1465,6 +3544,8 @@ fn build_agent(
     let captured_models_for_callback = captured_models.clone();
     let captured_commands: CapturedCommands = Arc::new(std::sync::Mutex::new(None));
     let captured_commands_for_callback = captured_commands.clone();
+    let captured_config_options: CapturedConfigOptions = Arc::new(std::sync::Mutex::new(None));
+    let captured_config_options_for_callback = captured_config_options.clone();
     let captured_error_for_callback = captured_error.clone();
     let agent = AcpAgent::new(server).with_debug(move |line, direction| {
         let direction_str = match direction {
@@ -1473,6 +3554,14 @@ fn build_agent(
             LineDirection::Stderr => "stderr",
         };
         write_acp_log(&process_log, direction_str, line);
+        if matches!(direction, LineDirection::Stderr) {
+            if let Ok(mut tail) = captured_stderr.lock() {
+                if tail.len() == STDERR_TAIL_LINES {
+                    tail.pop_front();
+                }
+                tail.push_back(line.to_string());
+            }
+        }
         if matches!(direction, LineDirection::Stdout) {
             if let Ok(rpc) = serde_json::from_str::<RawJsonRpcResponse>(line) {
                 if let Some(result) = rpc.result {
@@ -1485,6 +3574,11 @@ fn build_agent(
                                     *guard = Some(models);
                                 }
                             }
+                            if let Some(config_options) = session_result.config_options {
+                                if let Ok(mut guard) = captured_config_options_for_callback.lock() {
+                                    *guard = Some(config_options);
+                                }
+                            }
                         }
                     }
                 }
@@ -1516,6 +3610,16 @@ fn build_agent(
                                         }
                                     })
                                     .collect();
+                                // Recorded per session on every update, whether or not a
+                                // prompt is streaming, for `acp_list_commands`.
+                                if let Some(session_id) =
+                                    val.pointer("/params/sessionId").and_then(|id| id.as_str())
+                                {
+                                    if let Ok(mut guard) = session_commands.lock() {
+                                        guard
+                                            .insert(session_id.to_string(), slash_commands.clone());
+                                    }
+                                }
                                 if let Ok(mut guard) = captured_commands_for_callback.lock() {
                                     *guard = Some(slash_commands);
                                 }
@@ -1526,27 +3630,519 @@ fn build_agent(
             }
         }
     });
-    Ok((agent, captured_models, captured_commands))
+    Ok((
+        agent,
+        captured_models,
+        captured_commands,
+        captured_config_options,
+    ))
+}
+
+/// Terminals the agent asked the client to run (`terminal/create`), keyed by terminal id.
+/// Shared between the request handlers and the prompt readers, which stream
+/// output into the tool call that references the terminal.
+type ClientTerminals = Arc<std::sync::Mutex<HashMap<String, ClientTerminal>>>;
+
+/// Output kept per terminal when the agent does not set `outputByteLimit`.
+const DEFAULT_TERMINAL_OUTPUT_LIMIT: usize = 1024 * 1024;
+
+struct ClientTerminal {
+    output: Arc<std::sync::Mutex<TerminalOutputState>>,
+    exit_rx: watch::Receiver<Option<TerminalExit>>,
+    kill_tx: Option<oneshot::Sender<()>>,
+}
+
+struct TerminalOutputState {
+    text: String,
+    truncated: bool,
+    byte_limit: usize,
+    /// Tool call rendering this terminal and the stream of the prompt it belongs to.
+    tool_call: Option<(String, PromptStream)>,
+}
+
+#[derive(Clone)]
+struct TerminalExit {
+    exit_code: Option<u32>,
+    signal: Option<String>,
+}
+
+impl TerminalExit {
+    fn to_schema(&self) -> TerminalExitStatus {
+        TerminalExitStatus::new()
+            .exit_code(self.exit_code)
+            .signal(self.signal.clone())
+    }
+}
+
+impl TerminalOutputState {
+    /// Appends output, dropping the oldest bytes beyond the limit, and pushes
+    /// the new output to the linked tool call.
+    fn append(&mut self, chunk: &str) {
+        self.text.push_str(chunk);
+        if self.text.len() > self.byte_limit {
+            let mut cut = self.text.len() - self.byte_limit;
+            while !self.text.is_char_boundary(cut) {
+                cut += 1;
+            }
+            self.text.drain(..cut);
+            self.truncated = true;
+        }
+        self.emit();
+    }
+
+    fn emit(&self) {
+        if let Some((tool_call_id, stream)) = &self.tool_call {
+            // Title and kind are left empty so the UI keeps the ones it already has.
+            stream.send(AgentEvent::ToolCallUpdate {
+                tool_call_id: tool_call_id.clone(),
+                title: String::new(),
+                kind: String::new(),
+                status: "in_progress".to_string(),
+                content: Some(self.text.clone()),
+                locations: None,
+                diff_data: None,
+            });
+        }
+    }
+}
+
+/// Routes future output of the terminals referenced by a tool call to that tool call,
+/// until the tool call finishes.
+fn link_tool_call_terminals(
+    terminals: &ClientTerminals,
+    tool_call: &ToolCall,
+    stream: &PromptStream,
+) {
+    let Ok(terminals) = terminals.lock() else {
+        return;
+    };
+    let finished = matches!(
+        tool_call.status,
+        ToolCallStatus::Completed | ToolCallStatus::Failed
+    );
+    for item in &tool_call.content {
+        let ToolCallContent::Terminal(terminal) = item else {
+            continue;
+        };
+        if let Some(client_terminal) = terminals.get(&terminal.terminal_id.0.to_string()) {
+            if let Ok(mut output) = client_terminal.output.lock() {
+                output.tool_call =
+                    (!finished).then(|| (tool_call.tool_call_id.0.to_string(), stream.clone()));
+            }
+        }
+    }
+}
+
+fn terminal_output_snapshot(terminals: &ClientTerminals, terminal_id: &str) -> Option<String> {
+    let terminals = terminals.lock().ok()?;
+    let output = terminals.get(terminal_id)?.output.lock().ok()?;
+    Some(output.text.clone())
+}
+
+fn spawn_terminal_reader(
+    mut reader: impl tokio::io::AsyncRead + Unpin + Send + 'static,
+    output: Arc<std::sync::Mutex<TerminalOutputState>>,
+) {
+    tauri::async_runtime::spawn(async move {
+        use tokio::io::AsyncReadExt;
+        let mut buffer = [0u8; 8192];
+        loop {
+            match reader.read(&mut buffer).await {
+                Ok(0) | Err(_) => break,
+                Ok(read) => {
+                    let chunk = String::from_utf8_lossy(&buffer[..read]);
+                    if let Ok(mut output) = output.lock() {
+                        output.append(&chunk);
+                    }
+                }
+            }
+        }
+    });
+}
+
+async fn handle_create_terminal(
+    app_handle: AppHandle,
+    agent_id: String,
+    shared: Arc<tokio::sync::Mutex<RuntimeShared>>,
+    request: CreateTerminalRequest,
+    request_cx: sacp::JrRequestCx<CreateTerminalResponse>,
+) -> Result<(), sacp::Error> {
+    log::info!(
+        "[acp][{agent_id}] terminal/create command={} args={:?}",
+        request.command,
+        request.args
+    );
+    if is_vault_only(&app_handle, &agent_id) {
+        // a cwd inside the vault would not stop the command from reading or
+        // writing anywhere else, so vault-only agents get no terminals
+        let reason = format!(
+            "agent '{agent_id}' is restricted to the vault; terminals are not available to it"
+        );
+        log::info!("[acp][{agent_id}] refusing terminal/create: {reason}");
+        return request_cx.respond_with_error(sacp::util::internal_error(reason));
+    }
+    let mut command = tokio::process::Command::new(&request.command);
+    command
+        .args(&request.args)
+        .stdin(std::process::Stdio::null())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .kill_on_drop(true);
+    for (name, value) in crate::login_shell_env() {
+        command.env(name, value);
+    }
+    for variable in &request.env {
+        command.env(&variable.name, &variable.value);
+    }
+    if let Some(cwd) = &request.cwd {
+        command.current_dir(cwd);
+    }
+
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(error) => {
+            log::warn!("[acp][{agent_id}] terminal/create failed: {error}");
+            return request_cx.respond_with_error(sacp::util::internal_error(format!(
+                "failed to start '{}': {error}",
+                request.command
+            )));
+        }
+    };
+
+    let output = Arc::new(std::sync::Mutex::new(TerminalOutputState {
+        text: String::new(),
+        truncated: false,
+        byte_limit: request
+            .output_byte_limit
+            .map(|limit| limit as usize)
+            .unwrap_or(DEFAULT_TERMINAL_OUTPUT_LIMIT),
+        tool_call: None,
+    }));
+    if let Some(stdout) = child.stdout.take() {
+        spawn_terminal_reader(stdout, output.clone());
+    }
+    if let Some(stderr) = child.stderr.take() {
+        spawn_terminal_reader(stderr, output.clone());
+    }
+
+    let (exit_tx, exit_rx) = watch::channel(None);
+    let (kill_tx, kill_rx) = oneshot::channel::<()>();
+    tauri::async_runtime::spawn(async move {
+        let status = tokio::select! {
+            status = child.wait() => status,
+            // killed by the agent, or the terminal was released / the agent went away
+            _ = kill_rx => {
+                let _ = child.kill().await;
+                child.wait().await
+            }
+        };
+        let exit = match status {
+            Ok(status) => {
+                use std::os::unix::process::ExitStatusExt;
+                TerminalExit {
+                    exit_code: status.code().map(|code| code as u32),
+                    signal: status.signal().map(|signal| signal.to_string()),
+                }
+            }
+            Err(_) => TerminalExit {
+                exit_code: None,
+                signal: None,
+            },
+        };
+        let _ = exit_tx.send(Some(exit));
+    });
+
+    let terminal_id = {
+        let mut runtime = shared.lock().await;
+        runtime.next_terminal_id += 1;
+        let terminal_id = format!("terminal-{}", runtime.next_terminal_id);
+        if let Ok(mut terminals) = runtime.terminals.lock() {
+            terminals.insert(
+                terminal_id.clone(),
+                ClientTerminal {
+                    output,
+                    exit_rx,
+                    kill_tx: Some(kill_tx),
+                },
+            );
+        }
+        terminal_id
+    };
+
+    request_cx.respond(CreateTerminalResponse::new(TerminalId::new(terminal_id)))
+}
+
+async fn get_terminal_parts(
+    shared: &Arc<tokio::sync::Mutex<RuntimeShared>>,
+    terminal_id: &str,
+) -> Option<(
+    Arc<std::sync::Mutex<TerminalOutputState>>,
+    watch::Receiver<Option<TerminalExit>>,
+)> {
+    let terminals = shared.lock().await.terminals.clone();
+    let terminals = terminals.lock().ok()?;
+    let terminal = terminals.get(terminal_id)?;
+    Some((terminal.output.clone(), terminal.exit_rx.clone()))
+}
+
+fn terminal_not_found(terminal_id: &str) -> sacp::Error {
+    sacp::util::internal_error(format!("terminal '{terminal_id}' not found"))
+}
+
+async fn handle_terminal_output(
+    shared: Arc<tokio::sync::Mutex<RuntimeShared>>,
+    request: TerminalOutputRequest,
+    request_cx: sacp::JrRequestCx<TerminalOutputResponse>,
+) -> Result<(), sacp::Error> {
+    let terminal_id = request.terminal_id.0.to_string();
+    let Some((output, exit_rx)) = get_terminal_parts(&shared, &terminal_id).await else {
+        return request_cx.respond_with_error(terminal_not_found(&terminal_id));
+    };
+    let (text, truncated) = output
+        .lock()
+        .map(|output| (output.text.clone(), output.truncated))
+        .unwrap_or_default();
+    let exit_status = exit_rx.borrow().as_ref().map(TerminalExit::to_schema);
+    request_cx.respond(TerminalOutputResponse::new(text, truncated).exit_status(exit_status))
+}
+
+async fn handle_wait_for_terminal_exit(
+    shared: Arc<tokio::sync::Mutex<RuntimeShared>>,
+    request: WaitForTerminalExitRequest,
+    request_cx: sacp::JrRequestCx<WaitForTerminalExitResponse>,
+) -> Result<(), sacp::Error> {
+    let terminal_id = request.terminal_id.0.to_string();
+    let Some((_, mut exit_rx)) = get_terminal_parts(&shared, &terminal_id).await else {
+        return request_cx.respond_with_error(terminal_not_found(&terminal_id));
+    };
+    // Waiting must not block other requests on this connection.
+    tauri::async_runtime::spawn(async move {
+        let exit = match exit_rx.wait_for(|exit| exit.is_some()).await {
+            Ok(exit) => exit.clone(),
+            Err(_) => None,
+        };
+        let exit = exit.unwrap_or(TerminalExit {
+            exit_code: None,
+            signal: None,
+        });
+        let _ = request_cx.respond(WaitForTerminalExitResponse::new(exit.to_schema()));
+    });
+    Ok(())
+}
+
+async fn handle_kill_terminal(
+    shared: Arc<tokio::sync::Mutex<RuntimeShared>>,
+    request: KillTerminalCommandRequest,
+    request_cx: sacp::JrRequestCx<KillTerminalCommandResponse>,
+) -> Result<(), sacp::Error> {
+    let terminal_id = request.terminal_id.0.to_string();
+    let terminals = shared.lock().await.terminals.clone();
+    let kill_tx = terminals.lock().ok().and_then(|mut terminals| {
+        terminals
+            .get_mut(&terminal_id)
+            .map(|terminal| terminal.kill_tx.take())
+    });
+    match kill_tx {
+        Some(kill_tx) => {
+            if let Some(kill_tx) = kill_tx {
+                let _ = kill_tx.send(());
+            }
+            request_cx.respond(KillTerminalCommandResponse::new())
+        }
+        None => request_cx.respond_with_error(terminal_not_found(&terminal_id)),
+    }
+}
+
+async fn handle_release_terminal(
+    shared: Arc<tokio::sync::Mutex<RuntimeShared>>,
+    request: ReleaseTerminalRequest,
+    request_cx: sacp::JrRequestCx<ReleaseTerminalResponse>,
+) -> Result<(), sacp::Error> {
+    let terminal_id = request.terminal_id.0.to_string();
+    let terminals = shared.lock().await.terminals.clone();
+    let released = terminals
+        .lock()
+        .ok()
+        .and_then(|mut terminals| terminals.remove(&terminal_id));
+    match released {
+        // dropping the kill sender stops the command if it is still running
+        Some(_) => request_cx.respond(ReleaseTerminalResponse::new()),
+        None => request_cx.respond_with_error(terminal_not_found(&terminal_id)),
+    }
+}
+
+async fn handle_read_text_file(
+    app_handle: AppHandle,
+    agent_id: String,
+    request: ReadTextFileRequest,
+    request_cx: sacp::JrRequestCx<ReadTextFileResponse>,
+) -> Result<(), sacp::Error> {
+    if let Err(reason) = check_vault_only_path(&app_handle, &agent_id, &request.path) {
+        log::info!("[acp][{agent_id}] refusing fs/read_text_file: {reason}");
+        return request_cx.respond_with_error(sacp::util::internal_error(reason));
+    }
+    let content = match read_agent_file(&app_handle, &request.path).await {
+        Ok(content) => content,
+        Err(error) => return request_cx.respond_with_error(sacp::util::internal_error(error)),
+    };
+    // `line` is 1-based; `limit` caps the number of lines returned
+    let content = match (request.line, request.limit) {
+        (None, None) => content,
+        (line, limit) => content
+            .lines()
+            .skip(line.unwrap_or(1).saturating_sub(1) as usize)
+            .take(limit.map(|limit| limit as usize).unwrap_or(usize::MAX))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    };
+    request_cx.respond(ReadTextFileResponse::new(content))
+}
+
+/// The vault-relative path of an absolute path inside the vault.
+fn vault_relative_path(vault_root: &Path, path: &Path) -> Option<String> {
+    path.strip_prefix(vault_root)
+        .ok()
+        .map(|relative| relative.to_string_lossy().to_string())
+        .filter(|relative| !relative.is_empty())
+}
+
+/// Reads a file for `fs/read_text_file`. Notes in encrypted folders are
+/// decrypted like they are for the editor.
+async fn read_agent_file(app_handle: &AppHandle, path: &Path) -> Result<String, String> {
+    let path = normalize_absolute(path)
+        .ok_or_else(|| format!("'{}' is not an absolute path", path.display()))?;
+    let bytes = tokio::fs::read(&path)
+        .await
+        .map_err(|e| format!("failed to read '{}': {e}", path.display()))?;
+    let vault_root = get_base_dir(app_handle)?;
+    let bytes = match vault_relative_path(&vault_root, &path) {
+        Some(_) => encryption::decrypt_if_needed(&vault_root, bytes)?,
+        None => bytes,
+    };
+    String::from_utf8(bytes).map_err(|e| format!("failed to read '{}': {e}", path.display()))
+}
+
+/// Writes a file for `fs/write_text_file` through a temp file, so a crash
+/// never leaves it truncated. Notes in the vault are also encrypted when
+/// needed, recorded in the change journal as agent edits and checkpointed.
+async fn write_agent_file(
+    app_handle: &AppHandle,
+    path: &Path,
+    content: &str,
+) -> Result<(), String> {
+    let path = normalize_absolute(path)
+        .ok_or_else(|| format!("'{}' is not an absolute path", path.display()))?;
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("failed to create '{}': {e}", parent.display()))?;
+    }
+    let vault_root = get_base_dir(app_handle)?;
+    let Some(relative_path) = vault_relative_path(&vault_root, &path) else {
+        return write_atomic(&path, content.as_bytes(), false)
+            .await
+            .map_err(|e| format!("failed to write '{}': {e}", path.display()));
+    };
+
+    let existed = path.exists();
+    let bytes = encryption::encrypt_if_needed(&vault_root, &relative_path, content.as_bytes())?;
+    write_atomic(&path, &bytes, false)
+        .await
+        .map_err(|e| format!("failed to write '{}': {e}", path.display()))?;
+    fs_cache::invalidate_file(&vault_root, &relative_path);
+    if existed {
+        journal::record(
+            &vault_root,
+            JournalOp::Update,
+            &relative_path,
+            None,
+            JournalOrigin::Agent,
+        );
+        checkpoints::schedule(app_handle, &relative_path);
+    } else {
+        journal::record(
+            &vault_root,
+            JournalOp::Create,
+            &relative_path,
+            None,
+            JournalOrigin::Agent,
+        );
+        nb::reconcile_and_checkpoint_paths(
+            app_handle,
+            vec![relative_path.clone()],
+            format!("[nb] Add: {relative_path}"),
+        );
+    }
+    Ok(())
+}
+
+/// refuses writes to notes the user locked read-only
+fn check_unlocked_path(app_handle: &AppHandle, path: &Path) -> Result<(), String> {
+    let Ok(vault_root) = get_base_dir(app_handle) else {
+        return Ok(());
+    };
+    let Some(relative_path) =
+        normalize_absolute(path).and_then(|path| vault_relative_path(&vault_root, &path))
+    else {
+        return Ok(());
+    };
+    locks::ensure_unlocked(&vault_root, &relative_path)
+        .map_err(|e| format!("{e}; unlock it in flowrite to allow changes"))
+}
+
+async fn handle_write_text_file(
+    app_handle: AppHandle,
+    agent_id: String,
+    request: WriteTextFileRequest,
+    request_cx: sacp::JrRequestCx<WriteTextFileResponse>,
+) -> Result<(), sacp::Error> {
+    if let Err(reason) = check_vault_only_path(&app_handle, &agent_id, &request.path) {
+        log::info!("[acp][{agent_id}] refusing fs/write_text_file: {reason}");
+        return request_cx.respond_with_error(sacp::util::internal_error(reason));
+    }
+    if let Err(reason) = check_unlocked_path(&app_handle, &request.path) {
+        log::info!("[acp][{agent_id}] refusing fs/write_text_file: {reason}");
+        return request_cx.respond_with_error(sacp::util::internal_error(reason));
+    }
+    match write_agent_file(&app_handle, &request.path, &request.content).await {
+        Ok(()) => request_cx.respond(WriteTextFileResponse::new()),
+        Err(error) => request_cx.respond_with_error(sacp::util::internal_error(error)),
+    }
 }
 
 async fn resolve_permission_selection(
     shared: &Arc<tokio::sync::Mutex<RuntimeShared>>,
     request_id: String,
     selection: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), AcpError> {
     let pending = {
         let mut runtime = shared.lock().await;
         runtime.pending_permissions.remove(&request_id)
     };
 
     let Some(pending) = pending else {
-        return Err(format!("permission request '{request_id}' not found"));
+        return Err(AcpError::internal(format!(
+            "permission request '{request_id}' not found"
+        )));
     };
 
-    pending
-        .decision_tx
-        .send(selection)
-        .map_err(|_| format!("permission request '{request_id}' is no longer waiting"))
+    pending.decision_tx.send(selection).map_err(|_| {
+        AcpError::internal(format!(
+            "permission request '{request_id}' is no longer waiting"
+        ))
+    })
+}
+
+async fn has_pending_permission(
+    shared: &Arc<tokio::sync::Mutex<RuntimeShared>>,
+    session_id: &str,
+) -> bool {
+    shared
+        .lock()
+        .await
+        .pending_permissions
+        .values()
+        .any(|p| p.session_id == session_id)
 }
 
 async fn cancel_pending_permissions_for_session(
@@ -1591,16 +4187,22 @@ async fn cancel_all_pending_permissions(shared: &Arc<tokio::sync::Mutex<RuntimeS
 async fn set_active_stream_for_session(
     shared: &Arc<tokio::sync::Mutex<RuntimeShared>>,
     session_id: String,
-    channel: Channel<AgentEvent>,
+    stream: PromptStream,
 ) {
     let mut runtime = shared.lock().await;
-    runtime.active_streams.insert(
-        session_id.clone(),
-        ActiveStream {
-            session_id,
-            channel,
-        },
-    );
+    runtime
+        .active_streams
+        .insert(session_id.clone(), ActiveStream { session_id, stream });
+}
+
+async fn record_session_mode(
+    shared: &Arc<tokio::sync::Mutex<RuntimeShared>>,
+    session_id: &str,
+    mode_id: String,
+) {
+    if let Some(metadata) = shared.lock().await.sessions.get_mut(session_id) {
+        metadata.current_mode_id = Some(mode_id);
+    }
 }
 
 async fn clear_active_stream_for_session(
@@ -1697,24 +4299,6 @@ fn permission_option_kind_to_string(kind: PermissionOptionKind) -> String {
     }
 }
 
-fn sacp_error_to_connection_error(error: &sacp::Error) -> AcpConnectionError {
-    use sacp::schema::ErrorCode;
-    let kind = match error.code {
-        ErrorCode::AuthRequired => "auth_required",
-        ErrorCode::InternalError => "internal",
-        _ => "unknown",
-    };
-    let message = clean_sacp_error_message(&error.to_string());
-    AcpConnectionError {
-        kind: kind.to_string(),
-        message,
-    }
-}
-
-fn connection_error_to_string(error: &AcpConnectionError) -> String {
-    serde_json::to_string(error).unwrap_or_else(|_| error.message.clone())
-}
-
 /// Types for wire-level capture of models from session/new response.
 #[derive(Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -1722,6 +4306,8 @@ struct RawSessionNewResult {
     session_id: Option<String>,
     #[serde(default)]
     models: Option<RawSessionModels>,
+    #[serde(default)]
+    config_options: Option<Vec<ConfigOptionInfo>>,
 }
 
 #[derive(Clone, Deserialize)]
@@ -1798,6 +4384,16 @@ fn clean_sacp_error_message(raw: &str) -> String {
 type CapturedModels = Arc<std::sync::Mutex<Option<RawSessionModels>>>;
 type CapturedError = Arc<std::sync::Mutex<Option<RawJsonRpcError>>>;
 type CapturedCommands = Arc<std::sync::Mutex<Option<Vec<SlashCommandInfo>>>>;
+/// Latest slash commands per session id, as captured from the wire.
+type SessionCommands = Arc<std::sync::Mutex<HashMap<String, Vec<SlashCommandInfo>>>>;
+type CapturedConfigOptions = Arc<std::sync::Mutex<Option<Vec<ConfigOptionInfo>>>>;
+type CapturedStderr = Arc<std::sync::Mutex<VecDeque<String>>>;
+
+/// Reads `configOptions` from a wire-shaped session payload.
+fn parse_config_options(value: &serde_json::Value) -> Option<Vec<ConfigOptionInfo>> {
+    let options = value.get("configOptions")?;
+    serde_json::from_value(options.clone()).ok()
+}
 
 /// Wire-level command data from session/update notifications.
 #[derive(Clone, Deserialize)]
@@ -1852,3 +4448,95 @@ impl sacp::JrResponsePayload for SetSessionModelResponse {
         serde_json::from_value(value).map_err(sacp::Error::into_internal_error)
     }
 }
+
+/// Custom request type for session/set_thinking (unstable, not in the sacp schema).
+/// `level` is agent-defined, e.g. "low" / "medium" / "high" or a token budget.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetSessionThinkingRequest {
+    session_id: String,
+    level: String,
+}
+
+impl sacp::JrMessage for SetSessionThinkingRequest {
+    fn method(&self) -> &str {
+        "session/set_thinking"
+    }
+
+    fn to_untyped_message(&self) -> Result<sacp::UntypedMessage, sacp::Error> {
+        sacp::UntypedMessage::new(self.method(), self)
+    }
+
+    fn parse_message(method: &str, params: &impl Serialize) -> Option<Result<Self, sacp::Error>> {
+        if method != "session/set_thinking" {
+            return None;
+        }
+        let value = serde_json::to_value(params).ok()?;
+        Some(serde_json::from_value(value).map_err(sacp::Error::into_internal_error))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SetSessionThinkingResponse {}
+
+impl sacp::JrRequest for SetSessionThinkingRequest {
+    type Response = SetSessionThinkingResponse;
+}
+
+impl sacp::JrResponsePayload for SetSessionThinkingResponse {
+    fn into_json(self, _method: &str) -> Result<serde_json::Value, sacp::Error> {
+        serde_json::to_value(self).map_err(sacp::Error::into_internal_error)
+    }
+
+    fn from_value(_method: &str, value: serde_json::Value) -> Result<Self, sacp::Error> {
+        serde_json::from_value(value).map_err(sacp::Error::into_internal_error)
+    }
+}
+
+/// Custom request type for session/set_config_option (unstable in the sacp schema).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetSessionConfigOptionRequest {
+    session_id: String,
+    config_id: String,
+    value: serde_json::Value,
+}
+
+impl sacp::JrMessage for SetSessionConfigOptionRequest {
+    fn method(&self) -> &str {
+        "session/set_config_option"
+    }
+
+    fn to_untyped_message(&self) -> Result<sacp::UntypedMessage, sacp::Error> {
+        sacp::UntypedMessage::new(self.method(), self)
+    }
+
+    fn parse_message(method: &str, params: &impl Serialize) -> Option<Result<Self, sacp::Error>> {
+        if method != "session/set_config_option" {
+            return None;
+        }
+        let value = serde_json::to_value(params).ok()?;
+        Some(serde_json::from_value(value).map_err(sacp::Error::into_internal_error))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SetSessionConfigOptionResponse {
+    #[serde(default)]
+    config_options: Vec<ConfigOptionInfo>,
+}
+
+impl sacp::JrRequest for SetSessionConfigOptionRequest {
+    type Response = SetSessionConfigOptionResponse;
+}
+
+impl sacp::JrResponsePayload for SetSessionConfigOptionResponse {
+    fn into_json(self, _method: &str) -> Result<serde_json::Value, sacp::Error> {
+        serde_json::to_value(self).map_err(sacp::Error::into_internal_error)
+    }
+
+    fn from_value(_method: &str, value: serde_json::Value) -> Result<Self, sacp::Error> {
+        serde_json::from_value(value).map_err(sacp::Error::into_internal_error)
+    }
+}
@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, WebviewWindow};
+use tokio::fs;
+
+use crate::{
+    constants::{AGENT_BINDINGS_FILE_NAME, DEFAULT_AGENTS_KEY, VAULT_CONFIG_DIR_NAME},
+    utils::{get_base_dir, get_setting, set_setting},
+};
+
+/// maps a vault folder to the agent profile (and optionally mode/model)
+/// that chats opened from notes in that folder should start with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AgentBinding {
+    /// vault-relative folder, e.g. "work" or "projects/acme". empty binds the whole vault.
+    pub folder: String,
+    pub agent_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_id: Option<String>,
+}
+
+/// everything needed to connect a new chat panel without the frontend
+/// reconstructing the `acp_connect` parameters
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DefaultAgentConfig {
+    pub command: String,
+    /// env for the agent process. secrets should be `keychain:<name>` references.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_id: Option<String>,
+}
+
+/// stored in settings under `default-agents`
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct DefaultAgents {
+    /// keyed by vault-relative folder. "" is the default for the whole vault.
+    folders: HashMap<String, DefaultAgentConfig>,
+    /// keyed by workspace window label, dropped when the window closes
+    windows: HashMap<String, DefaultAgentConfig>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct AgentBindingsFile {
+    #[serde(default)]
+    bindings: Vec<AgentBinding>,
+}
+
+// -----------------------------------------
+// config file
+// -----------------------------------------
+
+/// reads the bindings from `<vault>/.flowrite/agent-bindings.json`.
+/// a missing file means no bindings.
+async fn load_bindings(app_handle: &AppHandle) -> Result<Vec<AgentBinding>, String> {
+    let path = get_base_dir(app_handle)?
+        .join(VAULT_CONFIG_DIR_NAME)
+        .join(AGENT_BINDINGS_FILE_NAME);
+
+    let content = match fs::read_to_string(&path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("failed to read agent bindings: {e}")),
+    };
+
+    let file: AgentBindingsFile = serde_json::from_str(&content)
+        .map_err(|e| format!("failed to parse agent bindings: {e}"))?;
+    Ok(file.bindings)
+}
+
+async fn save_bindings(app_handle: &AppHandle, bindings: Vec<AgentBinding>) -> Result<(), String> {
+    let config_dir = get_base_dir(app_handle)?.join(VAULT_CONFIG_DIR_NAME);
+    fs::create_dir_all(&config_dir)
+        .await
+        .map_err(|e| format!("failed to create vault config directory: {e}"))?;
+
+    let content = serde_json::to_string_pretty(&AgentBindingsFile { bindings })
+        .map_err(|e| format!("failed to serialize agent bindings: {e}"))?;
+    fs::write(config_dir.join(AGENT_BINDINGS_FILE_NAME), content)
+        .await
+        .map_err(|e| format!("failed to write agent bindings: {e}"))
+}
+
+/// normalizes a folder or note path to a vault-relative path without surrounding slashes.
+/// absolute paths outside the vault yield None.
+fn to_vault_relative(base_dir: &Path, path: &str) -> Option<String> {
+    let path = Path::new(path);
+    let relative = if path.is_absolute() {
+        path.strip_prefix(base_dir).ok()?
+    } else {
+        path
+    };
+    Some(relative.to_string_lossy().trim_matches('/').to_string())
+}
+
+/// true if `path` is `folder` itself or lives underneath it (component-wise)
+fn folder_contains(folder: &str, path: &str) -> bool {
+    folder.is_empty() || path == folder || path.starts_with(&format!("{folder}/"))
+}
+
+fn load_default_agents(app_handle: &AppHandle) -> DefaultAgents {
+    get_setting(app_handle, DEFAULT_AGENTS_KEY).unwrap_or_default()
+}
+
+/// forgets the default agent of a closed workspace window
+pub fn clear_window_default_agent(app_handle: &AppHandle, label: &str) {
+    let mut defaults = load_default_agents(app_handle);
+    if defaults.windows.remove(label).is_some() {
+        if let Err(e) = set_setting(app_handle, DEFAULT_AGENTS_KEY, &defaults) {
+            log::warn!("failed to clear default agent for window {label}: {e}");
+        }
+    }
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+#[tauri::command]
+pub async fn get_agent_bindings(app_handle: AppHandle) -> Result<Vec<AgentBinding>, String> {
+    load_bindings(&app_handle).await
+}
+
+/// replaces the vault's folder bindings. folders are normalized to vault-relative paths.
+#[tauri::command]
+pub async fn set_agent_bindings(
+    app_handle: AppHandle,
+    bindings: Vec<AgentBinding>,
+) -> Result<(), String> {
+    log::info!("saving {} agent bindings", bindings.len());
+    let base_dir = get_base_dir(&app_handle)?;
+
+    let bindings = bindings
+        .into_iter()
+        .map(|binding| {
+            let folder = to_vault_relative(&base_dir, &binding.folder)
+                .ok_or_else(|| format!("folder '{}' is outside the vault", binding.folder))?;
+            Ok(AgentBinding { folder, ..binding })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    save_bindings(&app_handle, bindings).await
+}
+
+/// returns the binding for the most specific folder containing `path`
+/// (vault-relative or absolute), or None if no binding applies.
+#[tauri::command]
+pub async fn resolve_agent_for_path(
+    app_handle: AppHandle,
+    path: String,
+) -> Result<Option<AgentBinding>, String> {
+    let base_dir = get_base_dir(&app_handle)?;
+    let Some(relative) = to_vault_relative(&base_dir, &path) else {
+        return Ok(None);
+    };
+
+    let binding = load_bindings(&app_handle)
+        .await?
+        .into_iter()
+        .filter_map(|binding| {
+            let folder = binding.folder.trim_matches('/').to_string();
+            folder_contains(&folder, &relative).then_some((folder.len(), binding))
+        })
+        .max_by_key(|(specificity, _)| *specificity)
+        .map(|(_, binding)| binding);
+
+    if let Some(binding) = &binding {
+        log::info!(
+            "resolved agent for {relative}: {} (folder '{}')",
+            binding.agent_id,
+            binding.folder
+        );
+    }
+
+    Ok(binding)
+}
+
+/// returns the default agent for a new chat panel in the calling window.
+/// with `path` (a note or folder), the most specific folder default containing it
+/// wins; otherwise the window's default, then the vault-wide default.
+#[tauri::command]
+pub async fn get_default_agent(
+    app_handle: AppHandle,
+    window: WebviewWindow,
+    path: Option<String>,
+) -> Result<Option<DefaultAgentConfig>, String> {
+    let base_dir = get_base_dir(&app_handle)?;
+    let mut defaults = load_default_agents(&app_handle);
+
+    let folder_default = path
+        .and_then(|path| to_vault_relative(&base_dir, &path))
+        .and_then(|relative| {
+            defaults
+                .folders
+                .keys()
+                .filter(|folder| !folder.is_empty() && folder_contains(folder, &relative))
+                .max_by_key(|folder| folder.len())
+                .cloned()
+        })
+        .and_then(|folder| defaults.folders.remove(&folder));
+
+    Ok(folder_default
+        .or_else(|| defaults.windows.remove(window.label()))
+        .or_else(|| defaults.folders.remove("")))
+}
+
+/// sets (or with no `config`, clears) a default agent. with `folder` (vault-relative
+/// or absolute, "" for the whole vault) it applies to that folder, otherwise to the
+/// calling workspace window.
+#[tauri::command]
+pub async fn set_default_agent(
+    app_handle: AppHandle,
+    window: WebviewWindow,
+    folder: Option<String>,
+    config: Option<DefaultAgentConfig>,
+) -> Result<(), String> {
+    let mut defaults = load_default_agents(&app_handle);
+    let (scope, key) = match folder {
+        Some(folder) => {
+            let base_dir = get_base_dir(&app_handle)?;
+            let relative = to_vault_relative(&base_dir, &folder)
+                .ok_or_else(|| format!("folder '{folder}' is outside the vault"))?;
+            log::info!("setting default agent for folder '{relative}'");
+            (&mut defaults.folders, relative)
+        }
+        None => {
+            log::info!("setting default agent for window {}", window.label());
+            (&mut defaults.windows, window.label().to_string())
+        }
+    };
+    match config {
+        Some(config) => {
+            scope.insert(key, config);
+        }
+        None => {
+            scope.remove(&key);
+        }
+    }
+
+    set_setting(&app_handle, DEFAULT_AGENTS_KEY, &defaults)
+}
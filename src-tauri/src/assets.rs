@@ -0,0 +1,274 @@
+#![allow(deprecated)]
+
+use std::path::Path;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use cocoa::base::{id, nil};
+use cocoa::foundation::{NSAutoreleasePool, NSString};
+use objc::{class, msg_send, sel, sel_impl};
+use serde::Serialize;
+use tauri::{AppHandle, WebviewWindow};
+use tokio::fs;
+
+use crate::{
+    constants::ASSETS_DIR_NAME,
+    journal::{self, JournalOp, JournalOrigin},
+    nb,
+    utils::{get_base_dir, resolve_path},
+    vaults,
+};
+
+/// a binary stored in the vault, with what a note needs to embed it
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedAsset {
+    /// vault-relative path of the stored file
+    pub path: String,
+    /// link relative to the note directory, e.g. `assets/screenshot.png`
+    pub link: String,
+    /// ready-to-insert markdown, e.g. `![screenshot](assets/screenshot.png)`
+    pub markdown: String,
+}
+
+/// binary file content for previews
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Asset {
+    /// base64 encoded content
+    pub data: String,
+    pub mime_type: String,
+}
+
+fn mime_type_from_extension(path: &str) -> &'static str {
+    let extension = path
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "tif" | "tiff" => "image/tiff",
+        "heic" => "image/heic",
+        "pdf" => "application/pdf",
+        "md" => "text/markdown",
+        "txt" => "text/plain",
+        "csv" => "text/csv",
+        "json" | "canvas" => "application/json",
+        _ => "application/octet-stream",
+    }
+}
+
+/// mime type from the file's magic bytes, falling back to its extension.
+/// unknown utf-8 content is reported as text.
+pub fn detect_mime_type(path: &str, bytes: &[u8]) -> &'static str {
+    const SIGNATURES: &[(&[u8], &str)] = &[
+        (b"\x89PNG\r\n\x1a\n", "image/png"),
+        (b"\xff\xd8\xff", "image/jpeg"),
+        (b"GIF87a", "image/gif"),
+        (b"GIF89a", "image/gif"),
+        (b"%PDF-", "application/pdf"),
+        (b"II*\x00", "image/tiff"),
+        (b"MM\x00*", "image/tiff"),
+    ];
+    if let Some((_, mime_type)) = SIGNATURES
+        .iter()
+        .find(|(signature, _)| bytes.starts_with(signature))
+    {
+        return mime_type;
+    }
+    if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        return "image/webp";
+    }
+
+    match mime_type_from_extension(path) {
+        "application/octet-stream" if std::str::from_utf8(bytes).is_ok() => "text/plain",
+        mime_type => mime_type,
+    }
+}
+
+/// escapes characters that would break a markdown link target
+pub(crate) fn encode_link(link: &str) -> String {
+    link.replace('%', "%25")
+        .replace(' ', "%20")
+        .replace('(', "%28")
+        .replace(')', "%29")
+}
+
+/// writes `bytes` to `<dir>/assets/<filename>` (suffixing taken names),
+/// checkpoints it and returns the link for embedding in notes inside `dir`
+pub async fn store_asset(
+    app_handle: &AppHandle,
+    dir: &str,
+    filename: &str,
+    bytes: &[u8],
+) -> Result<SavedAsset, String> {
+    let filename = Path::new(filename)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .filter(|name| !name.starts_with('.'))
+        .ok_or_else(|| format!("invalid asset file name '{filename}'"))?;
+    let dir = dir.trim_matches('/');
+    let assets_dir = if dir.is_empty() {
+        ASSETS_DIR_NAME.to_string()
+    } else {
+        format!("{dir}/{ASSETS_DIR_NAME}")
+    };
+
+    let base_dir = get_base_dir(app_handle)?;
+    fs::create_dir_all(resolve_path(app_handle, &assets_dir)?)
+        .await
+        .map_err(|e| format!("failed to create assets directory: {e}"))?;
+    let path = nb::available_path(&base_dir, &assets_dir, filename);
+    fs::write(resolve_path(app_handle, &path)?, bytes)
+        .await
+        .map_err(|e| format!("failed to write asset {path}: {e}"))?;
+
+    journal::record(
+        &base_dir,
+        JournalOp::Create,
+        &path,
+        None,
+        JournalOrigin::User,
+    );
+    nb::reconcile_and_checkpoint_paths(app_handle, vec![path.clone()], format!("[nb] Add: {path}"));
+
+    let link = encode_link(path.strip_prefix(&format!("{dir}/")).unwrap_or(&path));
+    let alt = Path::new(&path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    Ok(SavedAsset {
+        markdown: format!("![{alt}]({link})"),
+        path,
+        link,
+    })
+}
+
+// -----------------------------------------
+// clipboard
+// -----------------------------------------
+
+/// `NSBitmapImageFileTypePNG`
+const NS_BITMAP_IMAGE_FILE_TYPE_PNG: u64 = 4;
+
+/// copies an NSData into a Vec
+unsafe fn ns_data_bytes(data: id) -> Vec<u8> {
+    let length: usize = msg_send![data, length];
+    let bytes: *const u8 = msg_send![data, bytes];
+    if bytes.is_null() || length == 0 {
+        return Vec::new();
+    }
+    std::slice::from_raw_parts(bytes, length).to_vec()
+}
+
+/// the image on the general pasteboard as png. tiff data (what most apps and
+/// screenshots put on the pasteboard) is converted.
+fn read_clipboard_png() -> Option<Vec<u8>> {
+    unsafe {
+        let pool = NSAutoreleasePool::new(nil);
+        let pasteboard: id = msg_send![class!(NSPasteboard), generalPasteboard];
+
+        let png_type = NSString::alloc(nil).init_str("public.png").autorelease();
+        let tiff_type = NSString::alloc(nil).init_str("public.tiff").autorelease();
+
+        let png: id = msg_send![pasteboard, dataForType: png_type];
+        let result = if !png.is_null() {
+            Some(ns_data_bytes(png))
+        } else {
+            let tiff: id = msg_send![pasteboard, dataForType: tiff_type];
+            if tiff.is_null() {
+                None
+            } else {
+                let image_rep: id = msg_send![class!(NSBitmapImageRep), imageRepWithData: tiff];
+                if image_rep.is_null() {
+                    None
+                } else {
+                    let properties: id = msg_send![class!(NSDictionary), dictionary];
+                    let png: id = msg_send![image_rep,
+                        representationUsingType: NS_BITMAP_IMAGE_FILE_TYPE_PNG
+                        properties: properties];
+                    (!png.is_null()).then(|| ns_data_bytes(png))
+                }
+            }
+        };
+
+        pool.drain();
+        result.filter(|bytes| !bytes.is_empty())
+    }
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+/// stores a base64 encoded attachment next to the notes in `dir`
+#[tauri::command]
+pub async fn save_asset(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    dir: String,
+    filename: String,
+    base64: String,
+) -> Result<SavedAsset, String> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("saving asset {filename} for {dir}");
+
+        let bytes = BASE64
+            .decode(base64.trim())
+            .map_err(|e| format!("invalid asset data: {e}"))?;
+        let asset = store_asset(&app_handle, &dir, &filename, &bytes).await?;
+
+        log::info!("saved asset: {}", asset.path);
+
+        Ok(asset)
+    })
+    .await
+}
+
+/// reads an attachment from the vault as base64
+#[tauri::command]
+pub async fn read_asset(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    path: String,
+) -> Result<Asset, String> {
+    vaults::scoped(&webview_window, async move {
+        let file_path = resolve_path(&app_handle, &path)?;
+        let bytes = fs::read(&file_path)
+            .await
+            .map_err(|e| format!("failed to read asset {path}: {e}"))?;
+
+        Ok(Asset {
+            mime_type: detect_mime_type(&path, &bytes).to_string(),
+            data: BASE64.encode(bytes),
+        })
+    })
+    .await
+}
+
+/// saves the image on the clipboard (e.g. a screenshot) into the assets folder
+/// of `target_dir` and returns the markdown to embed it
+#[tauri::command]
+pub async fn save_clipboard_image(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    target_dir: String,
+) -> Result<SavedAsset, String> {
+    vaults::scoped(&webview_window, async move {
+        let bytes = tokio::task::spawn_blocking(read_clipboard_png)
+            .await
+            .map_err(|e| format!("failed to read clipboard: {e}"))?
+            .ok_or_else(|| "the clipboard does not contain an image".to_string())?;
+
+        let filename = format!(
+            "pasted-image-{}.png",
+            chrono::Local::now().format("%Y-%m-%d-%H%M%S")
+        );
+        log::info!("saving clipboard image as {filename} for {target_dir}");
+        store_asset(&app_handle, &target_dir, &filename, &bytes).await
+    })
+    .await
+}
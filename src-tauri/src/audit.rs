@@ -0,0 +1,129 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tokio::{fs, io::AsyncWriteExt, sync::Mutex};
+
+use crate::constants::AUDIT_LOG_FILE_NAME;
+
+/// serializes appends so concurrent agents never interleave lines
+static AUDIT_LOCK: Mutex<()> = Mutex::const_new(());
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEventKind {
+    /// a tool call that finished (completed or failed)
+    ToolCall,
+    /// a decision on a permission request, by the user or on their behalf
+    Permission,
+}
+
+/// one line of the agent audit log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub time_ms: i64,
+    pub kind: AuditEventKind,
+    pub agent_id: String,
+    pub session_id: String,
+    pub tool_call_id: String,
+    pub tool_kind: String,
+    #[serde(default)]
+    pub title: Option<String>,
+    /// absolute paths the tool call touched
+    #[serde(default)]
+    pub locations: Vec<String>,
+    /// tool status ("completed", "failed") or the permission outcome
+    /// (the selected option kind, "cancelled", "remembered", "sandbox_rejected", "timed_out")
+    pub outcome: String,
+}
+
+fn get_audit_log_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let app_data = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("failed to get app data directory: {e}"))?;
+    Ok(app_data.join(AUDIT_LOG_FILE_NAME))
+}
+
+async fn append(path: PathBuf, entry: &AuditEntry) -> Result<(), String> {
+    let mut line =
+        serde_json::to_string(entry).map_err(|e| format!("failed to serialize entry: {e}"))?;
+    line.push('\n');
+
+    let _guard = AUDIT_LOCK.lock().await;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("failed to create app data directory: {e}"))?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .await
+        .map_err(|e| format!("failed to open audit log: {e}"))?;
+    file.write_all(line.as_bytes())
+        .await
+        .map_err(|e| format!("failed to write audit log: {e}"))
+}
+
+/// appends an entry to the audit log in the background, stamped with the current time.
+/// failures are logged and never affect the agent.
+pub fn record(app_handle: &AppHandle, entry: AuditEntry) {
+    let path = match get_audit_log_path(app_handle) {
+        Ok(path) => path,
+        Err(e) => {
+            log::warn!("failed to record audit entry: {e}");
+            return;
+        }
+    };
+    let entry = AuditEntry {
+        time_ms: chrono::Utc::now().timestamp_millis(),
+        ..entry
+    };
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = append(path, &entry).await {
+            log::warn!(
+                "failed to record audit entry for {}: {e}",
+                entry.tool_call_id
+            );
+        }
+    });
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+/// returns audit entries recorded between `from_ms` and `to_ms` (unix ms, inclusive),
+/// oldest first. either bound can be omitted.
+#[tauri::command]
+pub async fn read_audit_log(
+    app_handle: AppHandle,
+    from_ms: Option<i64>,
+    to_ms: Option<i64>,
+) -> Result<Vec<AuditEntry>, String> {
+    let path = get_audit_log_path(&app_handle)?;
+    let content = match fs::read_to_string(&path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("failed to read audit log: {e}")),
+    };
+
+    let entries = content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str::<AuditEntry>(line) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                log::warn!("skipping malformed audit log line: {e}");
+                None
+            }
+        })
+        .filter(|entry| from_ms.is_none_or(|from| entry.time_ms >= from))
+        .filter(|entry| to_ms.is_none_or(|to| entry.time_ms <= to))
+        .collect();
+
+    Ok(entries)
+}
@@ -0,0 +1,390 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State, WebviewWindow};
+use tokio::{fs, sync::Mutex};
+
+use crate::{
+    constants::{AUTOSAVE_FLUSHED_EVENT, AUTOSAVE_POLICY_KEY, AUTOSAVE_STAGED_FILE_NAME},
+    encryption, nb,
+    utils::{get_base_dir, get_setting, resolve_path, write_atomic},
+    vaults,
+};
+
+/// monotonically increasing id for staged writes, used to debounce idle flushes
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+/// serializes writes of the staged content file, so an older snapshot never
+/// replaces a newer one
+static PERSIST_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// when staged content is written to disk. stored in settings under `autosave-policy`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AutosavePolicy {
+    pub enabled: bool,
+    /// flush a note after it has not been re-staged for this long (0 disables)
+    pub idle_ms: u64,
+    /// flush everything staged on this interval (0 disables)
+    pub interval_ms: u64,
+    /// flush everything staged when a window loses focus
+    pub on_focus_loss: bool,
+}
+
+impl Default for AutosavePolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            idle_ms: 2_000,
+            interval_ms: 30_000,
+            on_focus_loss: true,
+        }
+    }
+}
+
+struct StagedContent {
+    content: String,
+    generation: u64,
+}
+
+/// a staged note: its vault and vault-relative path. the vault is part of the
+/// key so windows showing different vaults never share staged content, and
+/// the note is written to its vault even after a switch.
+type StagedKey = (PathBuf, String);
+
+/// staged content as kept in the app data directory until it is written, so
+/// a crash or forced quit does not lose it
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PersistedContent {
+    vault_dir: PathBuf,
+    path: String,
+    /// base64 of the content, encrypted like the note itself
+    data: String,
+}
+
+/// dirty note contents streamed from the editors
+#[derive(Default)]
+pub struct AutosaveState(Mutex<HashMap<StagedKey, StagedContent>>);
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AutosaveFlushedPayload {
+    path: String,
+    reason: String,
+}
+
+fn get_policy(app_handle: &AppHandle) -> AutosavePolicy {
+    get_setting(app_handle, AUTOSAVE_POLICY_KEY).unwrap_or_default()
+}
+
+// -----------------------------------------
+// persistence
+// -----------------------------------------
+
+fn get_staged_file_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let app_data = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("failed to get app data directory: {e}"))?;
+    Ok(app_data.join(AUTOSAVE_STAGED_FILE_NAME))
+}
+
+/// writes everything currently staged to the staged content file, or removes
+/// the file once nothing is staged
+async fn persist(app_handle: &AppHandle) {
+    let Some(state) = app_handle.try_state::<AutosaveState>() else {
+        return;
+    };
+    if let Err(e) = persist_inner(app_handle, &state).await {
+        log::error!("failed to persist staged content: {e}");
+    }
+}
+
+async fn persist_inner(app_handle: &AppHandle, state: &AutosaveState) -> Result<(), String> {
+    let file_path = get_staged_file_path(app_handle)?;
+    let _guard = PERSIST_LOCK.lock().await;
+
+    let entries: Vec<PersistedContent> = state
+        .0
+        .lock()
+        .await
+        .iter()
+        .filter_map(|((vault_dir, path), staged)| {
+            match encryption::encrypt_if_needed(vault_dir, path, staged.content.as_bytes()) {
+                Ok(bytes) => Some(PersistedContent {
+                    vault_dir: vault_dir.clone(),
+                    path: path.clone(),
+                    data: BASE64.encode(bytes),
+                }),
+                Err(e) => {
+                    log::warn!("not persisting staged content for {path}: {e}");
+                    None
+                }
+            }
+        })
+        .collect();
+
+    if entries.is_empty() {
+        return match fs::remove_file(&file_path).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("failed to remove staged content file: {e}"))
+            }
+            _ => Ok(()),
+        };
+    }
+    let json = serde_json::to_vec(&entries)
+        .map_err(|e| format!("failed to serialize staged content: {e}"))?;
+    if let Some(parent) = file_path.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("failed to create app data directory: {e}"))?;
+    }
+    write_atomic(&file_path, &json, false)
+        .await
+        .map_err(|e| format!("failed to write staged content file: {e}"))
+}
+
+async fn load_persisted(app_handle: &AppHandle) -> Result<Vec<PersistedContent>, String> {
+    let file_path = get_staged_file_path(app_handle)?;
+    match fs::read(&file_path).await {
+        Ok(json) => serde_json::from_slice(&json)
+            .map_err(|e| format!("failed to parse staged content file: {e}")),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(format!("failed to read staged content file: {e}")),
+    }
+}
+
+fn decode_persisted(entry: &PersistedContent) -> Result<String, String> {
+    let bytes = BASE64
+        .decode(&entry.data)
+        .map_err(|e| format!("invalid staged content: {e}"))?;
+    let bytes = encryption::decrypt_if_needed(&entry.vault_dir, bytes)?;
+    String::from_utf8(bytes).map_err(|e| format!("staged content is not utf-8: {e}"))
+}
+
+/// stages the content a previous run persisted but never wrote (crash, forced
+/// quit) and writes it to the notes
+pub fn restore_staged(app_handle: &AppHandle) {
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let entries = match load_persisted(&handle).await {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::error!("failed to restore staged content: {e}");
+                return;
+            }
+        };
+        if entries.is_empty() {
+            return;
+        }
+        let Some(state) = handle.try_state::<AutosaveState>() else {
+            return;
+        };
+
+        log::info!("restoring {} staged notes", entries.len());
+        {
+            let mut staged = state.0.lock().await;
+            for entry in entries {
+                match decode_persisted(&entry) {
+                    Ok(content) => {
+                        staged
+                            .entry((entry.vault_dir, entry.path))
+                            .or_insert(StagedContent {
+                                content,
+                                generation: NEXT_GENERATION.fetch_add(1, Ordering::SeqCst),
+                            });
+                    }
+                    Err(e) => {
+                        log::error!("failed to restore staged content for {}: {e}", entry.path)
+                    }
+                }
+            }
+        }
+        flush_all(&handle, "restore").await;
+    });
+}
+
+// -----------------------------------------
+// flushing
+// -----------------------------------------
+
+/// writes a staged note to disk (with checkpoint) and notifies the windows
+/// showing its vault. `generation` skips the flush if the note was re-staged
+/// in the meantime.
+async fn flush_path(
+    app_handle: &AppHandle,
+    vault_dir: &Path,
+    path: &str,
+    generation: Option<u64>,
+    reason: &str,
+) {
+    let Some(state) = app_handle.try_state::<AutosaveState>() else {
+        return;
+    };
+
+    let key = (vault_dir.to_path_buf(), path.to_string());
+    let staged = {
+        let mut staged = state.0.lock().await;
+        let is_current = staged
+            .get(&key)
+            .is_some_and(|entry| generation.is_none_or(|g| g == entry.generation));
+        if is_current {
+            staged.remove(&key)
+        } else {
+            None
+        }
+    };
+    let Some(staged) = staged else {
+        return;
+    };
+
+    log::info!("autosave ({reason}): {path}");
+    let written = vaults::within(
+        vault_dir.to_path_buf(),
+        nb::update_file(app_handle, path, &staged.content, false),
+    )
+    .await;
+    match written {
+        Ok(()) => {
+            let payload = AutosaveFlushedPayload {
+                path: path.to_string(),
+                reason: reason.to_string(),
+            };
+            if let Err(e) =
+                vaults::emit_to_vault(app_handle, vault_dir, AUTOSAVE_FLUSHED_EVENT, payload)
+            {
+                log::error!("failed to emit autosave event: {e}");
+            }
+        }
+        Err(e) => {
+            log::error!("autosave failed for {path}: {e}");
+            // keep the content staged unless newer content arrived meanwhile
+            state.0.lock().await.entry(key).or_insert(staged);
+        }
+    }
+    persist(app_handle).await;
+}
+
+/// flushes every staged note, in every vault
+pub async fn flush_all(app_handle: &AppHandle, reason: &str) {
+    let Some(state) = app_handle.try_state::<AutosaveState>() else {
+        return;
+    };
+    let keys: Vec<StagedKey> = state.0.lock().await.keys().cloned().collect();
+    for (vault_dir, path) in keys {
+        flush_path(app_handle, &vault_dir, &path, None, reason).await;
+    }
+}
+
+/// flushes staged notes when a window loses focus, if the policy asks for it
+pub async fn flush_on_focus_loss(app_handle: &AppHandle) {
+    let policy = get_policy(app_handle);
+    if policy.enabled && policy.on_focus_loss {
+        flush_all(app_handle, "focus-loss").await;
+    }
+}
+
+/// starts the periodic flush loop. the policy is re-read on every tick.
+pub fn start_interval_flush(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let policy = get_policy(&app_handle);
+            let interval = match policy.interval_ms {
+                0 => AutosavePolicy::default().interval_ms,
+                ms => ms,
+            };
+            tokio::time::sleep(Duration::from_millis(interval)).await;
+
+            if policy.enabled && policy.interval_ms > 0 {
+                flush_all(&app_handle, "interval").await;
+            }
+        }
+    });
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+/// stages the latest editor content for a note of the calling window's vault.
+/// the backend decides when to write it according to the autosave policy.
+#[tauri::command]
+pub async fn stage_content(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    state: State<'_, AutosaveState>,
+    path: String,
+    content: String,
+) -> Result<(), String> {
+    vaults::scoped(&webview_window, async move {
+        resolve_path(&app_handle, &path)?;
+        let vault_dir = get_base_dir(&app_handle)?;
+
+        let generation = NEXT_GENERATION.fetch_add(1, Ordering::SeqCst);
+        state.0.lock().await.insert(
+            (vault_dir.clone(), path.clone()),
+            StagedContent {
+                content,
+                generation,
+            },
+        );
+        persist(&app_handle).await;
+
+        let policy = get_policy(&app_handle);
+        if policy.enabled && policy.idle_ms > 0 {
+            let handle = app_handle.clone();
+            tauri::async_runtime::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(policy.idle_ms)).await;
+                flush_path(&handle, &vault_dir, &path, Some(generation), "idle").await;
+            });
+        }
+
+        Ok(())
+    })
+    .await
+}
+
+/// writes staged content now: a single note of the calling window's vault, or
+/// everything when `path` is omitted
+#[tauri::command]
+pub async fn flush_staged(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    path: Option<String>,
+) -> Result<(), String> {
+    vaults::scoped(&webview_window, async move {
+        match path {
+            Some(path) => {
+                let vault_dir = get_base_dir(&app_handle)?;
+                flush_path(&app_handle, &vault_dir, &path, None, "explicit").await
+            }
+            None => flush_all(&app_handle, "explicit").await,
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// drops staged content of the calling window's vault without writing it
+/// (e.g. the user discarded changes)
+#[tauri::command]
+pub async fn discard_staged(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    state: State<'_, AutosaveState>,
+    path: String,
+) -> Result<(), String> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("discarding staged content: {path}");
+        let vault_dir = get_base_dir(&app_handle)?;
+        state.0.lock().await.remove(&(vault_dir, path));
+        persist(&app_handle).await;
+        Ok(())
+    })
+    .await
+}
@@ -0,0 +1,238 @@
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, WebviewWindow};
+
+use crate::{
+    constants::CHECKPOINT_POLICY_KEY,
+    nb, preflight,
+    utils::{get_base_dir, get_setting},
+    vaults, vcs,
+};
+
+/// when a saved edit becomes a git checkpoint
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CheckpointMode {
+    /// edits are never checkpointed on their own; they are only committed
+    /// along with other operations
+    Off,
+    /// every save is checkpointed right away
+    PerSave,
+    /// edits are batched over `window_ms`
+    #[default]
+    Debounced,
+    /// edits wait for `create_snapshot` or `flush_checkpoints`
+    Manual,
+}
+
+/// how edits are grouped into checkpoints. stored in settings under
+/// `checkpoint-policy`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CheckpointPolicy {
+    pub mode: CheckpointMode,
+    /// in debounced mode, edits within this long of the first pending edit
+    /// share one checkpoint
+    pub window_ms: u64,
+    /// in debounced mode, checkpoint pending edits when a window loses focus
+    pub on_focus_loss: bool,
+}
+
+impl Default for CheckpointPolicy {
+    fn default() -> Self {
+        Self {
+            mode: CheckpointMode::default(),
+            window_ms: 30_000,
+            on_focus_loss: true,
+        }
+    }
+}
+
+/// id of each batch, so a window timer never flushes a later batch early
+static NEXT_BATCH: AtomicU64 = AtomicU64::new(1);
+
+/// edited notes waiting for one checkpoint
+struct Batch {
+    id: u64,
+    paths: BTreeSet<String>,
+}
+
+/// open batches, per vault
+static PENDING: Mutex<Option<HashMap<PathBuf, Batch>>> = Mutex::new(None);
+
+fn with_pending<T>(f: impl FnOnce(&mut HashMap<PathBuf, Batch>) -> T) -> T {
+    let mut pending = PENDING.lock().unwrap();
+    f(pending.get_or_insert_with(HashMap::new))
+}
+
+fn get_policy(app_handle: &AppHandle) -> CheckpointPolicy {
+    get_setting(app_handle, CHECKPOINT_POLICY_KEY).unwrap_or_default()
+}
+
+/// "[nb] Edit: <path>" for one note; several notes get a count with the
+/// paths listed in the body
+fn batch_message(paths: &BTreeSet<String>) -> String {
+    match paths.iter().next() {
+        Some(path) if paths.len() == 1 => format!("[nb] Edit: {path}"),
+        _ => {
+            let list: Vec<String> = paths.iter().map(|path| format!("- {path}")).collect();
+            format!("[nb] Edit: {} notes\n\n{}", paths.len(), list.join("\n"))
+        }
+    }
+}
+
+/// checkpoints the pending edits of one vault. `batch` skips the flush if
+/// that batch was already flushed.
+async fn flush_vault(app_handle: &AppHandle, vault_dir: PathBuf, batch: Option<u64>) {
+    let taken = with_pending(|pending| {
+        let is_current = pending
+            .get(&vault_dir)
+            .is_some_and(|pending| batch.is_none_or(|id| id == pending.id));
+        if is_current {
+            pending.remove(&vault_dir)
+        } else {
+            None
+        }
+    });
+    let Some(taken) = taken else {
+        return;
+    };
+    let message = batch_message(&taken.paths);
+    vaults::within(vault_dir, async {
+        let paths: Vec<String> = taken.paths.into_iter().collect();
+        if let Err(failure) = nb::git_checkpoint_paths(app_handle, &paths, &message).await {
+            nb::report_failure(app_handle, "git checkpoint", failure);
+        }
+    })
+    .await;
+}
+
+/// queues a checkpoint for an edited note in the caller's vault, according
+/// to the checkpoint policy. in debounced mode the first edit of a batch
+/// starts the window; everything edited before it closes is committed together.
+pub fn schedule(app_handle: &AppHandle, path: &str) {
+    let policy = get_policy(app_handle);
+    if policy.mode == CheckpointMode::Off {
+        return;
+    }
+    let Some(vault_dir) = vaults::active_vault_dir() else {
+        return;
+    };
+    let new_batch = with_pending(|pending| {
+        let batch = pending.entry(vault_dir.clone()).or_insert_with(|| Batch {
+            id: NEXT_BATCH.fetch_add(1, Ordering::SeqCst),
+            paths: BTreeSet::new(),
+        });
+        batch.paths.insert(path.to_string());
+        (batch.paths.len() == 1).then_some(batch.id)
+    });
+    let Some(batch) = new_batch else {
+        return;
+    };
+
+    let delay = match policy.mode {
+        CheckpointMode::PerSave => 0,
+        CheckpointMode::Debounced => policy.window_ms,
+        CheckpointMode::Off | CheckpointMode::Manual => return,
+    };
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(delay)).await;
+        flush_vault(&handle, vault_dir, Some(batch)).await;
+    });
+}
+
+/// checkpoints every pending edit in every vault
+pub async fn flush_all(app_handle: &AppHandle) {
+    let vault_dirs: Vec<PathBuf> = with_pending(|pending| pending.keys().cloned().collect());
+    for vault_dir in vault_dirs {
+        flush_vault(app_handle, vault_dir, None).await;
+    }
+}
+
+/// checkpoints pending edits when a window loses focus, if asked to
+pub async fn flush_on_focus_loss(app_handle: &AppHandle) {
+    let policy = get_policy(app_handle);
+    if policy.mode == CheckpointMode::Debounced && policy.on_focus_loss {
+        flush_all(app_handle).await;
+    }
+}
+
+/// checkpoints pending edits before the app quits, unless they are waiting
+/// for a manual snapshot
+pub async fn flush_on_quit(app_handle: &AppHandle) {
+    if get_policy(app_handle).mode != CheckpointMode::Manual {
+        flush_all(app_handle).await;
+    }
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+/// checkpoints all pending edits now, e.g. on an explicit save
+#[tauri::command]
+pub async fn flush_checkpoints(app_handle: AppHandle) -> Result<(), String> {
+    log::info!("flushing pending checkpoints");
+    flush_all(&app_handle).await;
+    Ok(())
+}
+
+/// commits every change in the window's vault as a named snapshot,
+/// including edits still waiting for a checkpoint
+#[tauri::command]
+pub async fn create_snapshot(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    message: String,
+) -> Result<(), String> {
+    vaults::scoped(&webview_window, async move {
+        let message = message.trim().to_string();
+        log::info!("creating snapshot: {message}");
+        if message.is_empty() {
+            return Err("snapshot message cannot be empty".to_string());
+        }
+
+        // the snapshot commits the pending edits too, so they are put back
+        // if it fails
+        let taken = vaults::active_vault_dir()
+            .and_then(|vault_dir| with_pending(|pending| pending.remove(&vault_dir)));
+        let result = async {
+            nb::reconcile_index(&app_handle).await?;
+
+            // the commit stores a new object for every changed file
+            let vault_dir = get_base_dir(&app_handle)?;
+            let changes = nb::with_repo(&app_handle, vcs::status).await?;
+            let root = vault_dir.clone();
+            let bytes = tauri::async_runtime::spawn_blocking(move || {
+                changes
+                    .iter()
+                    .filter_map(|change| std::fs::metadata(root.join(&change.path)).ok())
+                    .map(|metadata| metadata.len())
+                    .sum::<u64>()
+            })
+            .await
+            .unwrap_or(0);
+            preflight::check(&vault_dir, bytes).await?;
+
+            nb::git_checkpoint(&app_handle, &message).await?;
+            Ok::<_, String>(())
+        }
+        .await;
+        if let Err(e) = result {
+            for path in taken.into_iter().flat_map(|batch| batch.paths) {
+                schedule(&app_handle, &path);
+            }
+            return Err(e);
+        }
+
+        log::info!("created snapshot: {message}");
+        Ok(())
+    })
+    .await
+}
@@ -1,26 +1,39 @@
 #![allow(deprecated)]
 
+use std::collections::{BTreeMap, HashMap};
+
 use cocoa::base::{id, BOOL, YES};
 use objc::{msg_send, sel, sel_impl};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use tauri::{
+    ipc::Channel,
+    menu::MenuItemKind,
     utils::config::WindowEffectsConfig,
     window::{Effect, EffectState},
-    AppHandle, Manager, WebviewUrl, WebviewWindow, WebviewWindowBuilder,
+    AppHandle, Emitter, Manager, WebviewUrl, WebviewWindow, WebviewWindowBuilder,
+};
+use tokio::{
+    fs,
+    io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt},
 };
-use tokio::fs;
 
 use crate::{
+    assets::{self, Asset},
     constants::{
-        SYSTEM_PROMPT_FILE_NAME, WORKSPACE_WINDOW_HEIGHT, WORKSPACE_WINDOW_LABEL_PREFIX,
-        WORKSPACE_WINDOW_MIN_HEIGHT, WORKSPACE_WINDOW_MIN_WIDTH, WORKSPACE_WINDOW_WIDTH,
+        FOCUS_MODE_CHANGED_EVENT, SYSTEM_PROMPT_FILE_NAME, WORKSPACE_WINDOW_HEIGHT,
+        WORKSPACE_WINDOW_LABEL_PREFIX, WORKSPACE_WINDOW_MIN_HEIGHT, WORKSPACE_WINDOW_MIN_WIDTH,
+        WORKSPACE_WINDOW_WIDTH,
     },
-    nb,
-    utils::resolve_path,
-    PendingFiles,
+    encryption, file_watcher, frontmatter, fs_cache,
+    locks::{self, FileOpError},
+    nb, note_ids, recents,
+    utils::{content_hash, get_base_dir, resolve_path, validate_path_name, write_atomic},
+    vault_ignore, vault_settings, vaults, FocusModeWindows, PendingFiles, NEW_FILE_MENU_ID,
+    NEW_WINDOW_MENU_ID, OPEN_FILE_MENU_ID,
 };
 
-#[derive(Serialize)]
+#[derive(Clone, Serialize)]
 pub struct FSEntry {
     // relative path from base directory
     pub path: String,
@@ -53,16 +66,121 @@ pub fn set_traffic_lights_visible(window: WebviewWindow, visible: bool) {
     }
 }
 
+// -----------------------------------------
+// focus mode
+// -----------------------------------------
+
+/// menu items disabled while the focused window is in focus mode
+const FOCUS_MODE_DISABLED_MENU_IDS: &[&str] =
+    &[NEW_FILE_MENU_ID, NEW_WINDOW_MENU_ID, OPEN_FILE_MENU_ID];
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FocusModePayload {
+    enabled: bool,
+    block_notifications: bool,
+}
+
+/// toggles distraction-free focus mode for a workspace window: hides the
+/// traffic lights, enters native full screen (or drops the title bar when
+/// `fullscreen` is false), and disables window/file creation menu items.
+/// the window is notified via `focus-mode-changed` so it can mute notifications.
+#[tauri::command]
+pub fn set_focus_mode(
+    app_handle: AppHandle,
+    window: WebviewWindow,
+    enabled: bool,
+    fullscreen: Option<bool>,
+    block_notifications: Option<bool>,
+) -> Result<(), String> {
+    let label = window.label().to_string();
+    log::info!("setting focus mode for {label}: {enabled}");
+
+    set_traffic_lights_visible(window.clone(), !enabled);
+
+    if fullscreen.unwrap_or(true) {
+        window
+            .set_fullscreen(enabled)
+            .map_err(|e| format!("failed to toggle full screen: {e}"))?;
+    } else {
+        window
+            .set_decorations(!enabled)
+            .map_err(|e| format!("failed to toggle title bar: {e}"))?;
+    }
+
+    if let Some(state) = app_handle.try_state::<FocusModeWindows>() {
+        let mut windows = state.0.lock().unwrap();
+        if enabled {
+            windows.insert(label.clone());
+        } else {
+            windows.remove(&label);
+        }
+    }
+    sync_focus_mode_menu(&app_handle, &label);
+
+    let payload = FocusModePayload {
+        enabled,
+        block_notifications: enabled && block_notifications.unwrap_or(true),
+    };
+    window
+        .emit(FOCUS_MODE_CHANGED_EVENT, payload)
+        .map_err(|e| format!("failed to emit focus mode event: {e}"))?;
+
+    Ok(())
+}
+
+/// enables or disables focus-mode-sensitive menu items for the given (focused) window
+pub fn sync_focus_mode_menu(app_handle: &AppHandle, label: &str) {
+    let in_focus_mode = app_handle
+        .try_state::<FocusModeWindows>()
+        .is_some_and(|state| state.0.lock().unwrap().contains(label));
+
+    let Some(menu) = app_handle.menu() else {
+        return;
+    };
+    for item in menu.items().unwrap_or_default() {
+        let MenuItemKind::Submenu(submenu) = item else {
+            continue;
+        };
+        for id in FOCUS_MODE_DISABLED_MENU_IDS {
+            if let Some(MenuItemKind::MenuItem(menu_item)) = submenu.get(*id) {
+                let _ = menu_item.set_enabled(!in_focus_mode);
+            }
+        }
+    }
+}
+
+/// forgets focus mode for a closed window and restores the menu
+pub fn clear_focus_mode(app_handle: &AppHandle, label: &str) {
+    let was_enabled = app_handle
+        .try_state::<FocusModeWindows>()
+        .is_some_and(|state| state.0.lock().unwrap().remove(label));
+    if was_enabled {
+        sync_focus_mode_menu(app_handle, label);
+    }
+}
+
 // -----------------------------------------
 // workspace window commands
 // -----------------------------------------
 
-/// creates a new workspace window with a unique label
+/// creates a new workspace window with a unique label, showing the registered
+/// vault `vault_id` or (by default) following the active vault
 #[tauri::command]
-pub fn create_workspace_window(app_handle: AppHandle) -> Result<String, String> {
+pub fn create_workspace_window(
+    app_handle: AppHandle,
+    vault_id: Option<String>,
+) -> Result<String, String> {
     let label = generate_workspace_label();
     log::info!("creating workspace window: {label}");
 
+    // pin before the window loads so its first listing is already scoped
+    if let Some(vault_id) = vault_id {
+        let vault = vaults::find_vault(&app_handle, &vault_id)?;
+        vaults::assign_window(&label, Some(vault.path.into()));
+        file_watcher::init_file_watcher(app_handle.clone());
+    }
+
     WebviewWindowBuilder::new(&app_handle, &label, WebviewUrl::App("#/workspace".into()))
         .title("flowrite")
         .inner_size(WORKSPACE_WINDOW_WIDTH, WORKSPACE_WINDOW_HEIGHT)
@@ -110,7 +228,7 @@ pub fn show_or_create_workspace_window(app_handle: &AppHandle) {
         let _ = window.set_focus();
     } else {
         // no workspace window exists, create one
-        let _ = create_workspace_window(app_handle.clone());
+        let _ = create_workspace_window(app_handle.clone(), None);
     }
 }
 
@@ -122,336 +240,1606 @@ pub fn take_pending_files(state: tauri::State<PendingFiles>) -> Vec<String> {
 }
 
 // -----------------------------------------
-// file management commands
+// os file drops
 // -----------------------------------------
 
+/// `NSPasteboardNameDrag` / `NSFilenamesPboardType`
+const DRAG_PASTEBOARD_NAME: &str = "Apple CFPasteboard drag";
+const FILENAMES_PASTEBOARD_TYPE: &str = "NSFilenamesPboardType";
+
+/// returns the filesystem paths of the os files in the current (or last) drag.
+/// the native drag-drop handler is disabled so dockview's html5 dnd keeps working,
+/// which leaves webview drop events with opaque `File` objects only. the drag
+/// pasteboard still holds the real paths, so the frontend calls this on drop.
 #[tauri::command]
-pub async fn create_dir(app_handle: AppHandle, path: String) -> Result<(), String> {
-    log::info!("creating directory: {path}");
+pub async fn read_dropped_paths(app_handle: AppHandle) -> Result<Vec<String>, String> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    app_handle
+        .run_on_main_thread(move || {
+            let _ = tx.send(read_drag_pasteboard_paths());
+        })
+        .map_err(|e| format!("failed to read drag pasteboard: {e}"))?;
+    let paths = rx
+        .await
+        .map_err(|e| format!("failed to read drag pasteboard: {e}"))?;
 
-    let dir_path = resolve_path(&app_handle, &path)?;
+    log::info!("dropped paths from os: {paths:?}");
+    Ok(paths)
+}
 
-    fs::create_dir_all(&dir_path)
-        .await
-        .map_err(|e| format!("failed to create directory '{path}': {e}"))?;
+fn read_drag_pasteboard_paths() -> Vec<String> {
+    use cocoa::base::nil;
+    use cocoa::foundation::NSString;
+    use objc::{class, runtime::Object};
 
-    log::info!("created directory: {path}");
+    let mut paths = Vec::new();
+    unsafe {
+        let name: id = NSString::alloc(nil).init_str(DRAG_PASTEBOARD_NAME);
+        let pasteboard: id = msg_send![class!(NSPasteboard), pasteboardWithName: name];
+        let file_type: id = NSString::alloc(nil).init_str(FILENAMES_PASTEBOARD_TYPE);
+        let _: () = msg_send![name, release];
 
-    Ok(())
+        let list: id = if pasteboard.is_null() {
+            nil
+        } else {
+            msg_send![pasteboard, propertyListForType: file_type]
+        };
+        let _: () = msg_send![file_type, release];
+        if list.is_null() {
+            return paths;
+        }
+
+        let count: usize = msg_send![list, count];
+        for i in 0..count {
+            let item: *mut Object = msg_send![list, objectAtIndex: i];
+            let utf8 = NSString::UTF8String(item);
+            if !utf8.is_null() {
+                paths.push(
+                    std::ffi::CStr::from_ptr(utf8)
+                        .to_string_lossy()
+                        .into_owned(),
+                );
+            }
+        }
+    }
+    paths
 }
 
+// -----------------------------------------
+// file management commands
+// -----------------------------------------
+
 #[tauri::command]
-pub async fn list_dir(
+pub async fn create_dir(
     app_handle: AppHandle,
+    webview_window: WebviewWindow,
     path: String,
-    recursive: Option<bool>,
-) -> Result<Vec<FSEntry>, String> {
-    let recursive = recursive.unwrap_or(false);
-    log::info!("listing directory: {path} (recursive: {recursive})");
-
-    let dir_path = resolve_path(&app_handle, &path)?;
+) -> Result<(), String> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("creating directory: {path}");
 
-    if !dir_path.exists() {
-        return Err(format!("directory '{path}' does not exist"));
-    }
+        validate_path_name(&path)?;
+        let dir_path = resolve_path(&app_handle, &path)?;
 
-    let mut files = Vec::new();
-    list_dir_inner(&dir_path, &path, recursive, &mut files).await?;
+        fs::create_dir_all(&dir_path)
+            .await
+            .map_err(|e| format!("failed to create directory '{path}': {e}"))?;
 
-    log::info!("listed {} entries in '{path}'", files.len());
+        log::info!("created directory: {path}");
 
-    Ok(files)
+        Ok(())
+    })
+    .await
 }
 
-/// internal recursive directory listing helper
-async fn list_dir_inner(
-    dir_path: &std::path::Path,
-    relative_prefix: &str,
-    recursive: bool,
-    files: &mut Vec<FSEntry>,
-) -> Result<(), String> {
-    let mut entries = fs::read_dir(dir_path)
-        .await
-        .map_err(|e| format!("failed to read directory '{}': {e}", relative_prefix))?;
-
-    while let Some(entry) = entries
-        .next_entry()
-        .await
-        .map_err(|e| format!("failed to read directory entry: {e}"))?
-    {
-        let entry_path = entry.path();
-        if let Some(name) = entry_path.file_name().and_then(|s| s.to_str()) {
-            // skip hidden files/directories (starting with .)
-            if name.starts_with('.') {
-                continue;
-            }
-
-            let metadata = fs::metadata(&entry_path)
-                .await
-                .map_err(|e| format!("failed to read metadata for '{name}': {e}"))?;
-
-            let is_dir = metadata.is_dir();
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ListDirSort {
+    /// by path, case-insensitive, so folders stay next to their contents
+    #[default]
+    Name,
+    Mtime,
+    Size,
+}
 
-            // skip non-.md files (only show markdown files and directories)
-            if !is_dir && !name.ends_with(".md") {
-                continue;
-            }
+/// server-side sorting and filtering for `list_dir`. patterns are globs
+/// matched against vault-relative paths (`*` stays within a folder, `**`
+/// crosses folders).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListDirOptions {
+    pub sort_by: Option<ListDirSort>,
+    #[serde(default)]
+    pub descending: bool,
+    /// only files with one of these extensions (without the dot)
+    pub extensions: Option<Vec<String>>,
+    /// only files matching this pattern
+    pub glob: Option<String>,
+    /// entries to leave out; an excluded folder hides everything inside it
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
 
-            let size_bytes = metadata.len();
-
-            let created = metadata
-                .created()
-                .map_err(|e| format!("failed to get creation time for '{name}': {e}"))?;
-            let created_time_ms = created
-                .duration_since(std::time::UNIX_EPOCH)
-                .map_err(|e| format!("failed to convert creation time for '{name}': {e}"))?
-                .as_millis() as u64;
-
-            let modified = metadata
-                .modified()
-                .map_err(|e| format!("failed to get modification time for '{name}': {e}"))?;
-            let modified_time_ms = modified
-                .duration_since(std::time::UNIX_EPOCH)
-                .map_err(|e| format!("failed to convert modification time for '{name}': {e}"))?
-                .as_millis() as u64;
-
-            // construct full relative path
-            let entry_relative_path = if relative_prefix.is_empty() {
-                name.to_string()
-            } else {
-                format!("{}/{}", relative_prefix, name)
-            };
+const GLOB_OPTIONS: glob::MatchOptions = glob::MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
 
-            files.push(FSEntry {
-                path: entry_relative_path.clone(),
-                is_dir,
-                size_bytes,
-                created_time_ms,
-                modified_time_ms,
-            });
+fn compile_pattern(pattern: &str) -> Result<glob::Pattern, String> {
+    glob::Pattern::new(pattern).map_err(|e| format!("invalid pattern '{pattern}': {e}"))
+}
 
-            // recurse into subdirectories if recursive flag is set
-            if recursive && is_dir {
-                Box::pin(list_dir_inner(
-                    &entry_path,
-                    &entry_relative_path,
-                    true,
-                    files,
-                ))
-                .await?;
-            }
+/// filters and sorts `entries` in place. folders are only affected by `exclude`.
+fn apply_list_dir_options(
+    entries: &mut Vec<FSEntry>,
+    options: &ListDirOptions,
+) -> Result<(), String> {
+    let glob = options.glob.as_deref().map(compile_pattern).transpose()?;
+    let exclude = options
+        .exclude
+        .iter()
+        .map(|pattern| compile_pattern(pattern))
+        .collect::<Result<Vec<_>, _>>()?;
+    let extensions: Option<Vec<String>> = options.extensions.as_ref().map(|extensions| {
+        extensions
+            .iter()
+            .map(|extension| extension.trim_start_matches('.').to_lowercase())
+            .collect()
+    });
+
+    let excluded = |path: &str| {
+        // the entry itself or any folder above it
+        let ancestors = path.match_indices('/').map(|(at, _)| &path[..at]);
+        std::iter::once(path).chain(ancestors).any(|path| {
+            exclude
+                .iter()
+                .any(|pattern| pattern.matches_with(path, GLOB_OPTIONS))
+        })
+    };
+    entries.retain(|entry| {
+        if excluded(&entry.path) {
+            return false;
+        }
+        if entry.is_dir {
+            return true;
+        }
+        let extension_matches = match &extensions {
+            Some(extensions) => entry
+                .path
+                .rsplit('/')
+                .next()
+                .and_then(|name| name.rsplit_once('.'))
+                .is_some_and(|(_, extension)| extensions.contains(&extension.to_lowercase())),
+            None => true,
+        };
+        let glob_matches = match &glob {
+            Some(glob) => glob.matches_with(&entry.path, GLOB_OPTIONS),
+            None => true,
+        };
+        extension_matches && glob_matches
+    });
+
+    if let Some(sort_by) = options.sort_by {
+        match sort_by {
+            ListDirSort::Name => entries.sort_by_cached_key(|entry| entry.path.to_lowercase()),
+            ListDirSort::Mtime => entries.sort_by_key(|entry| entry.modified_time_ms),
+            ListDirSort::Size => entries.sort_by_key(|entry| entry.size_bytes),
+        }
+        if options.descending {
+            entries.reverse();
         }
     }
-
     Ok(())
 }
 
+/// lists a folder of the vault. `options` sorts and filters the entries
+/// before they are returned; without it they come in directory order.
 #[tauri::command]
-pub async fn delete_dir(app_handle: AppHandle, path: String) -> Result<(), String> {
-    log::info!("deleting directory: {path}");
+pub async fn list_dir(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    path: String,
+    recursive: Option<bool>,
+    options: Option<ListDirOptions>,
+) -> Result<Vec<FSEntry>, String> {
+    vaults::scoped(&webview_window, async move {
+        let recursive = recursive.unwrap_or(false);
+        log::info!("listing directory: {path} (recursive: {recursive})");
 
-    nb::delete(&app_handle, &path).await?;
+        let dir_path = resolve_path(&app_handle, &path)?;
 
-    log::info!("deleted directory: {path}");
+        if !dir_path.exists() {
+            return Err(format!("directory '{path}' does not exist"));
+        }
 
-    Ok(())
-}
+        let mut files = Vec::new();
+        list_dir_inner(&dir_path, &path, recursive, &mut files).await?;
+        if let Some(options) = options {
+            apply_list_dir_options(&mut files, &options)?;
+        }
 
-#[tauri::command]
-pub async fn rename_dir(
-    app_handle: AppHandle,
-    old_path: String,
-    new_path: String,
-) -> Result<(), String> {
-    log::info!("renaming directory: {old_path} -> {new_path}");
+        log::info!("listed {} entries in '{path}'", files.len());
 
-    nb::rename(&app_handle, &old_path, &new_path).await?;
+        Ok(files)
+    })
+    .await
+}
 
-    log::info!("renamed directory: {old_path} -> {new_path}");
+/// entries per `list_dir_stream` batch unless the caller asks otherwise
+const LIST_DIR_BATCH_SIZE: usize = 500;
+
+/// result of `list_dir_stream`, after every batch has been sent
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListDirPage {
+    /// entries sent in this call
+    pub listed: usize,
+    /// pass as `cursor` to continue after `limit` was reached; none when done
+    pub next_cursor: Option<String>,
+}
 
-    Ok(())
+/// true if `path` sorts after `cursor` in the listing order (a depth-first
+/// walk with siblings sorted by name), i.e. was not listed yet
+fn after_cursor(path: &str, cursor: &str) -> bool {
+    path.split('/').gt(cursor.split('/'))
 }
 
+/// `list_dir` for huge vaults: sends entries in batches over `on_batch` as
+/// they are read, in a stable order (depth-first, siblings sorted by name).
+/// with `limit`, stops after that many entries and returns a cursor to
+/// continue from.
 #[tauri::command]
-pub async fn create_file(
+#[allow(clippy::too_many_arguments)]
+pub async fn list_dir_stream(
     app_handle: AppHandle,
+    webview_window: WebviewWindow,
     path: String,
-    content: Option<String>,
-) -> Result<FSEntry, String> {
-    log::info!("creating file: {path}");
+    recursive: Option<bool>,
+    batch_size: Option<usize>,
+    limit: Option<usize>,
+    cursor: Option<String>,
+    on_batch: Channel<Vec<FSEntry>>,
+) -> Result<ListDirPage, String> {
+    vaults::scoped(&webview_window, async move {
+        let recursive = recursive.unwrap_or(false);
+        let batch_size = batch_size.unwrap_or(LIST_DIR_BATCH_SIZE).max(1);
+        log::info!("streaming directory: {path} (recursive: {recursive})");
+
+        let dir_path = resolve_path(&app_handle, &path)?;
+        if !dir_path.exists() {
+            return Err(format!("directory '{path}' does not exist"));
+        }
 
-    let file_path = resolve_path(&app_handle, &path)?;
+        let send = |batch: Vec<FSEntry>| {
+            on_batch
+                .send(batch)
+                .map_err(|e| format!("failed to send entries of '{path}': {e}"))
+        };
+
+        // entries still to visit, next one last
+        let mut pending: Vec<(String, std::path::PathBuf)> = Vec::new();
+        // folder to read next, its entries go on top of `pending`
+        let mut next_dir = Some((path.clone(), dir_path));
+        let mut batch = Vec::with_capacity(batch_size);
+        let mut listed = 0;
+        let mut last_listed = None;
+        let mut truncated = false;
+        loop {
+            if let Some((relative_prefix, dir_path)) = next_dir.take() {
+                let mut children = Vec::new();
+                let mut entries = fs::read_dir(&dir_path)
+                    .await
+                    .map_err(|e| format!("failed to read directory '{relative_prefix}': {e}"))?;
+                while let Some(entry) = entries
+                    .next_entry()
+                    .await
+                    .map_err(|e| format!("failed to read directory entry: {e}"))?
+                {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    // skip hidden files/directories (starting with .)
+                    if name.starts_with('.') {
+                        continue;
+                    }
+                    let relative_path = if relative_prefix.is_empty() {
+                        name
+                    } else {
+                        format!("{relative_prefix}/{name}")
+                    };
+                    children.push((relative_path, entry.path()));
+                }
+                children.sort_by(|a, b| b.0.cmp(&a.0));
+                pending.extend(children);
+            }
 
-    // check if file already exists
-    if file_path.exists() {
-        return Err(format!("file '{path}' already exists"));
-    }
+            let Some((relative_path, entry_path)) = pending.pop() else {
+                break;
+            };
+            // skip what earlier pages listed, but walk into the folder the
+            // cursor is in
+            if let Some(cursor) = cursor.as_deref() {
+                if !after_cursor(&relative_path, cursor) {
+                    if recursive && cursor.starts_with(&format!("{relative_path}/")) {
+                        next_dir = Some((relative_path, entry_path));
+                    }
+                    continue;
+                }
+            }
 
-    let initial_content = content.unwrap_or_default();
-    nb::create_file(&app_handle, &path, &initial_content).await?;
+            let metadata = fs::metadata(&entry_path)
+                .await
+                .map_err(|e| format!("failed to read metadata for '{relative_path}': {e}"))?;
+            let is_dir = metadata.is_dir();
+            // skip files with unmanaged extensions (only show notes and directories)
+            let name = relative_path.rsplit('/').next().unwrap_or(&relative_path);
+            if !is_dir && !vault_settings::is_managed_file(name) {
+                continue;
+            }
+            if vault_ignore::is_ignored(&entry_path, is_dir) {
+                continue;
+            }
+            if limit.is_some_and(|limit| listed >= limit) {
+                truncated = true;
+                break;
+            }
 
-    // get metadata from filesystem
-    let metadata = fs::metadata(&file_path)
-        .await
-        .map_err(|e| format!("failed to get metadata: {e}"))?;
+            if recursive && is_dir {
+                next_dir = Some((relative_path.clone(), entry_path));
+            }
+            last_listed = Some(relative_path.clone());
+            batch.push(fs_entry_from_metadata(relative_path, &metadata)?);
+            listed += 1;
+            if batch.len() >= batch_size {
+                send(std::mem::take(&mut batch))?;
+            }
+        }
+        if !batch.is_empty() {
+            send(batch)?;
+        }
+
+        log::info!("streamed {listed} entries in '{path}'");
+
+        Ok(ListDirPage {
+            listed,
+            next_cursor: if truncated {
+                last_listed.or(cursor)
+            } else {
+                None
+            },
+        })
+    })
+    .await
+}
 
+/// builds an FSEntry for a vault-relative path from its filesystem metadata
+pub(crate) fn fs_entry_from_metadata(
+    path: String,
+    metadata: &std::fs::Metadata,
+) -> Result<FSEntry, String> {
     let created = metadata
         .created()
-        .map_err(|e| format!("failed to get creation time: {e}"))?;
+        .map_err(|e| format!("failed to get creation time for '{path}': {e}"))?;
     let created_time_ms = created
         .duration_since(std::time::UNIX_EPOCH)
-        .map_err(|e| format!("failed to convert creation time: {e}"))?
+        .map_err(|e| format!("failed to convert creation time for '{path}': {e}"))?
         .as_millis() as u64;
 
     let modified = metadata
         .modified()
-        .map_err(|e| format!("failed to get modification time: {e}"))?;
+        .map_err(|e| format!("failed to get modification time for '{path}': {e}"))?;
     let modified_time_ms = modified
         .duration_since(std::time::UNIX_EPOCH)
-        .map_err(|e| format!("failed to convert modification time: {e}"))?
+        .map_err(|e| format!("failed to convert modification time for '{path}': {e}"))?
         .as_millis() as u64;
 
-    log::info!("created file: {path}");
-
     Ok(FSEntry {
         path,
-        is_dir: false,
-        size_bytes: 0,
+        is_dir: metadata.is_dir(),
+        size_bytes: metadata.len(),
         created_time_ms,
         modified_time_ms,
     })
 }
 
-#[tauri::command]
-pub async fn read_file(app_handle: AppHandle, path: String) -> Result<String, String> {
-    log::info!("reading file: {path}");
+/// internal recursive directory listing helper, served from `fs_cache`
+pub(crate) async fn list_dir_inner(
+    dir_path: &std::path::Path,
+    relative_prefix: &str,
+    recursive: bool,
+    files: &mut Vec<FSEntry>,
+) -> Result<(), String> {
+    let entries = fs_cache::read_dir(dir_path)
+        .await
+        .map_err(|e| format!("failed to read directory '{}': {e}", relative_prefix))?;
 
-    let content = nb::read_file(&app_handle, &path).await?;
+    for mut entry in entries {
+        let name = entry.path;
 
-    log::info!("read file: {path}");
+        // construct full relative path
+        entry.path = if relative_prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}/{}", relative_prefix, name)
+        };
+        let entry_relative_path = entry.path.clone();
+        let is_dir = entry.is_dir;
+        files.push(entry);
+
+        // recurse into subdirectories if recursive flag is set
+        if recursive && is_dir {
+            Box::pin(list_dir_inner(
+                &dir_path.join(&name),
+                &entry_relative_path,
+                true,
+                files,
+            ))
+            .await?;
+        }
+    }
 
-    Ok(content)
+    Ok(())
 }
 
 #[tauri::command]
-pub async fn update_file(
+pub async fn delete_dir(
     app_handle: AppHandle,
+    webview_window: WebviewWindow,
     path: String,
-    content: String,
-) -> Result<(), String> {
-    log::info!("updating file: {path}");
+) -> Result<(), FileOpError> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("deleting directory: {path}");
 
-    nb::update_file(&app_handle, &path, &content).await?;
+        locks::ensure_unlocked(&get_base_dir(&app_handle)?, &path)?;
 
-    log::info!("updated file: {path}");
+        nb::delete(&app_handle, &path).await?;
 
-    Ok(())
+        log::info!("deleted directory: {path}");
+
+        Ok(())
+    })
+    .await
 }
 
 #[tauri::command]
-pub async fn delete_file(app_handle: AppHandle, path: String) -> Result<(), String> {
-    log::info!("deleting file: {path}");
+pub async fn rename_dir(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    old_path: String,
+    new_path: String,
+) -> Result<(), FileOpError> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("renaming directory: {old_path} -> {new_path}");
 
-    nb::delete(&app_handle, &path).await?;
+        locks::ensure_unlocked(&get_base_dir(&app_handle)?, &old_path)?;
 
-    log::info!("deleted file: {path}");
+        validate_path_name(&new_path)?;
+        nb::rename(&app_handle, &old_path, &new_path).await?;
 
-    Ok(())
+        log::info!("renamed directory: {old_path} -> {new_path}");
+
+        Ok(())
+    })
+    .await
 }
 
+/// creates a note. with `withId` a markdown note gets a uuid `id:` in its
+/// frontmatter, which `resolve_note_id` maps to the note's current path.
 #[tauri::command]
-pub async fn rename_file(
+pub async fn create_file(
     app_handle: AppHandle,
-    old_path: String,
-    new_path: String,
-) -> Result<(), String> {
-    log::info!("renaming file: {old_path} -> {new_path}");
+    webview_window: WebviewWindow,
+    path: String,
+    content: Option<String>,
+    with_id: Option<bool>,
+) -> Result<FSEntry, String> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("creating file: {path}");
 
-    nb::rename(&app_handle, &old_path, &new_path).await?;
+        validate_path_name(&path)?;
+        let file_path = resolve_path(&app_handle, &path)?;
 
-    log::info!("renamed file: {old_path} -> {new_path}");
+        // check if file already exists
+        if file_path.exists() {
+            return Err(format!("file '{path}' already exists"));
+        }
 
-    Ok(())
-}
+        let mut initial_content = content.unwrap_or_default();
+        let mut note_id = None;
+        if with_id.unwrap_or(false) && vault_settings::is_managed_file(&path) {
+            let (content, id) = note_ids::inject_id(&initial_content)?;
+            initial_content = content;
+            note_id = Some(id);
+        }
+        nb::create_file(&app_handle, &path, &initial_content).await?;
+        if let Some(id) = note_id {
+            note_ids::insert(&app_handle, &path, &id);
+        }
 
-// -----------------------------------------
-// metadata-only file update (no git checkpoint)
-// -----------------------------------------
+        // get metadata from filesystem
+        let metadata = fs::metadata(&file_path)
+            .await
+            .map_err(|e| format!("failed to get metadata: {e}"))?;
+
+        let created = metadata
+            .created()
+            .map_err(|e| format!("failed to get creation time: {e}"))?;
+        let created_time_ms = created
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|e| format!("failed to convert creation time: {e}"))?
+            .as_millis() as u64;
+
+        let modified = metadata
+            .modified()
+            .map_err(|e| format!("failed to get modification time: {e}"))?;
+        let modified_time_ms = modified
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|e| format!("failed to convert modification time: {e}"))?
+            .as_millis() as u64;
+
+        log::info!("created file: {path}");
+
+        Ok(FSEntry {
+            path,
+            is_dir: false,
+            size_bytes: metadata.len(),
+            created_time_ms,
+            modified_time_ms,
+        })
+    })
+    .await
+}
 
-/// Writes only the YAML frontmatter section of an internal file.
-/// Reads the file, replaces (or prepends) the `---` delimited YAML header,
-/// and writes back without triggering a git checkpoint.
 #[tauri::command]
-pub async fn write_file_metadata(
+pub async fn read_file(
     app_handle: AppHandle,
+    webview_window: WebviewWindow,
     path: String,
-    yaml: String,
-) -> Result<(), String> {
-    let file_path = resolve_path(&app_handle, &path)?;
-    let content = fs::read_to_string(&file_path)
-        .await
-        .map_err(|e| format!("failed to read {path}: {e}"))?;
-
-    let trimmed_yaml = yaml.trim();
-
-    let new_content = if let Some(stripped) = content.strip_prefix("---\n") {
-        // find closing --- delimiter and replace everything between
-        if let Some(end) = stripped.find("\n---\n") {
-            format!("---\n{}\n---\n{}", trimmed_yaml, &stripped[end + 5..])
-        } else if let Some(end) = stripped.find("\n---") {
-            // closing --- at end of file (no trailing newline after ---)
-            let after = &stripped[end + 4..];
-            if after.is_empty() {
-                format!("---\n{}\n---\n", trimmed_yaml)
-            } else {
-                format!("---\n{}\n---\n{}", trimmed_yaml, after)
-            }
-        } else {
-            format!("---\n{}\n---\n{}", trimmed_yaml, stripped)
+) -> Result<String, String> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("reading file: {path}");
+
+        let content = nb::read_file(&app_handle, &path).await?;
+        if let Err(e) = recents::add_recent_file(&app_handle, &path) {
+            log::warn!("failed to record recent file {path}: {e}");
         }
-    } else {
-        format!("---\n{}\n---\n{}", trimmed_yaml, content)
-    };
 
-    fs::write(&file_path, new_content)
-        .await
-        .map_err(|e| format!("failed to write {path}: {e}"))?;
-    Ok(())
+        log::info!("read file: {path}");
+
+        Ok(content)
+    })
+    .await
 }
 
-// -----------------------------------------
-// external file commands (files outside ~/flowrite/)
-// -----------------------------------------
+/// a note together with what `update_file` needs to detect conflicting edits
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileContent {
+    pub path: String,
+    pub content: String,
+    pub size_bytes: u64,
+    pub modified_time_ms: u64,
+    /// sha-256 of the content, passed back as `update_file`'s `expected_hash`
+    pub hash: String,
+}
 
+/// `read_file` with size, modification time and content hash
 #[tauri::command]
-pub async fn create_external_file(path: String, content: Option<String>) -> Result<(), String> {
-    log::info!("creating external file: {path}");
-
-    let file_path = std::path::Path::new(&path);
+pub async fn read_file_v2(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    path: String,
+) -> Result<FileContent, String> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("reading file with metadata: {path}");
 
-    // create parent dirs if needed
-    if let Some(parent) = file_path.parent() {
-        fs::create_dir_all(parent)
+        let file_path = resolve_path(&app_handle, &path)?;
+        let bytes = fs::read(&file_path)
             .await
-            .map_err(|e| format!("failed to create parent directories for '{path}': {e}"))?;
-    }
-
-    let initial_content = content.unwrap_or_default();
-    fs::write(&path, initial_content)
-        .await
-        .map_err(|e| format!("failed to create external file '{path}': {e}"))?;
+            .map_err(|e| format!("failed to read file {path}: {e}"))?;
+        let bytes = encryption::decrypt_if_needed(&get_base_dir(&app_handle)?, bytes)?;
+        let metadata = fs::metadata(&file_path)
+            .await
+            .map_err(|e| format!("failed to get metadata for {path}: {e}"))?;
+        let entry = fs_entry_from_metadata(path.clone(), &metadata)?;
+        if let Err(e) = recents::add_recent_file(&app_handle, &path) {
+            log::warn!("failed to record recent file {path}: {e}");
+        }
 
-    log::info!("created external file: {path}");
+        log::info!("read file with metadata: {path}");
 
-    Ok(())
+        Ok(FileContent {
+            hash: content_hash(&bytes),
+            content: String::from_utf8(bytes)
+                .map_err(|e| format!("failed to read file {path}: {e}"))?,
+            path,
+            size_bytes: entry.size_bytes,
+            modified_time_ms: entry.modified_time_ms,
+        })
+    })
+    .await
 }
 
-#[tauri::command]
-pub async fn read_external_file(path: String) -> Result<String, String> {
-    log::info!("reading external file: {path}");
+/// bytes read per `read_file_stream` chunk
+const READ_CHUNK_BYTES: usize = 256 * 1024;
 
-    let content = fs::read_to_string(&path)
-        .await
+/// messages of `read_file_stream`: text chunks in order, then `done`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "event", content = "data")]
+pub enum FileChunk {
+    Chunk {
+        text: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Done {
+        size_bytes: u64,
+        modified_time_ms: u64,
+        /// sha-256 of the whole file, for `update_file`'s `expected_hash`
+        hash: String,
+    },
+}
+
+/// a slice of a file returned by `read_file_range`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileRange {
+    pub content: String,
+    pub offset: u64,
+    /// where the next range starts; before `offset + len` when the range
+    /// would have ended inside a multi-byte character
+    pub next_offset: u64,
+    pub total_bytes: u64,
+}
+
+/// splits `bytes` into the longest valid utf-8 prefix and an incomplete
+/// character at the end, which is kept for the next read. invalid bytes are
+/// replaced like `String::from_utf8_lossy`.
+fn split_utf8(bytes: &[u8]) -> (String, usize) {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => (text.to_string(), bytes.len()),
+        Err(e) if e.error_len().is_none() => {
+            let valid = e.valid_up_to();
+            (String::from_utf8_lossy(&bytes[..valid]).into_owned(), valid)
+        }
+        Err(_) => (String::from_utf8_lossy(bytes).into_owned(), bytes.len()),
+    }
+}
+
+/// sends everything `reader` yields over `on_chunk` as text, never splitting
+/// a character. returns the sha-256 of the bytes sent.
+async fn send_chunks(
+    mut reader: impl AsyncRead + Unpin,
+    path: &str,
+    on_chunk: &Channel<FileChunk>,
+) -> Result<String, String> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; READ_CHUNK_BYTES];
+    let mut pending: Vec<u8> = Vec::new();
+    loop {
+        let read = reader
+            .read(&mut buffer)
+            .await
+            .map_err(|e| format!("failed to read file {path}: {e}"))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        pending.extend_from_slice(&buffer[..read]);
+
+        let (text, used) = split_utf8(&pending);
+        pending.drain(..used);
+        on_chunk
+            .send(FileChunk::Chunk { text })
+            .map_err(|e| format!("failed to send chunk of {path}: {e}"))?;
+    }
+    if !pending.is_empty() {
+        let text = String::from_utf8_lossy(&pending).into_owned();
+        on_chunk
+            .send(FileChunk::Chunk { text })
+            .map_err(|e| format!("failed to send chunk of {path}: {e}"))?;
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// `read_file` for very large notes: sends the content over `on_chunk` in
+/// pieces, followed by the file's metadata
+#[tauri::command]
+pub async fn read_file_stream(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    path: String,
+    on_chunk: Channel<FileChunk>,
+) -> Result<(), String> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("streaming file: {path}");
+
+        let file_path = resolve_path(&app_handle, &path)?;
+        let metadata = fs::metadata(&file_path)
+            .await
+            .map_err(|e| format!("failed to get metadata for {path}: {e}"))?;
+        // encrypted notes cannot be decrypted piecewise, so they are decrypted
+        // whole and then sent in chunks
+        let hash = if encryption::is_encrypted_file(&file_path) {
+            let content = nb::read_file(&app_handle, &path).await?;
+            send_chunks(std::io::Cursor::new(content.into_bytes()), &path, &on_chunk).await?
+        } else {
+            let file = fs::File::open(&file_path)
+                .await
+                .map_err(|e| format!("failed to open file {path}: {e}"))?;
+            send_chunks(file, &path, &on_chunk).await?
+        };
+
+        let entry = fs_entry_from_metadata(path.clone(), &metadata)?;
+        on_chunk
+            .send(FileChunk::Done {
+                size_bytes: entry.size_bytes,
+                modified_time_ms: entry.modified_time_ms,
+                hash,
+            })
+            .map_err(|e| format!("failed to send metadata of {path}: {e}"))?;
+        if let Err(e) = recents::add_recent_file(&app_handle, &path) {
+            log::warn!("failed to record recent file {path}: {e}");
+        }
+
+        log::info!("streamed file: {path} ({} bytes)", entry.size_bytes);
+
+        Ok(())
+    })
+    .await
+}
+
+/// up to `len` bytes of a file starting at byte `offset`, for paging through
+/// notes too large to load at once
+#[tauri::command]
+pub async fn read_file_range(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    path: String,
+    offset: u64,
+    len: u64,
+) -> Result<FileRange, String> {
+    vaults::scoped(&webview_window, async move {
+        let file_path = resolve_path(&app_handle, &path)?;
+        // offsets of an encrypted note are into its plaintext
+        if encryption::is_encrypted_file(&file_path) {
+            let content = nb::read_file(&app_handle, &path).await?.into_bytes();
+            let total_bytes = content.len() as u64;
+            return read_range(
+                std::io::Cursor::new(content),
+                total_bytes,
+                &path,
+                offset,
+                len,
+            )
+            .await;
+        }
+
+        let file = fs::File::open(&file_path)
+            .await
+            .map_err(|e| format!("failed to open file {path}: {e}"))?;
+        let total_bytes = file
+            .metadata()
+            .await
+            .map_err(|e| format!("failed to get metadata for {path}: {e}"))?
+            .len();
+        read_range(file, total_bytes, &path, offset, len).await
+    })
+    .await
+}
+
+/// up to `len` bytes of `reader` starting at byte `offset`
+async fn read_range(
+    mut reader: impl AsyncRead + AsyncSeek + Unpin,
+    total_bytes: u64,
+    path: &str,
+    offset: u64,
+    len: u64,
+) -> Result<FileRange, String> {
+    let start = offset.min(total_bytes);
+    reader
+        .seek(std::io::SeekFrom::Start(start))
+        .await
+        .map_err(|e| format!("failed to seek in file {path}: {e}"))?;
+    let mut bytes = Vec::new();
+    reader
+        .take(len)
+        .read_to_end(&mut bytes)
+        .await
+        .map_err(|e| format!("failed to read file {path}: {e}"))?;
+
+    let at_end = start + bytes.len() as u64 >= total_bytes;
+    let (content, used) = if at_end {
+        (String::from_utf8_lossy(&bytes).into_owned(), bytes.len())
+    } else {
+        split_utf8(&bytes)
+    };
+    Ok(FileRange {
+        content,
+        offset: start,
+        next_offset: start + used as u64,
+        total_bytes,
+    })
+}
+
+/// reads any vault file as base64 with its detected mime type, for previewing
+/// images, pdfs and other attachments
+#[tauri::command]
+pub async fn read_file_bytes(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    path: String,
+) -> Result<Asset, String> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("reading file bytes: {path}");
+
+        assets::read_asset(app_handle, path).await
+    })
+    .await
+}
+
+/// writes a note. with `expected_hash` (sha-256 of the content the editor
+/// loaded) or `expected_mtime` (its modification time in ms), the write is
+/// refused with a conflict if the file changed on disk since.
+/// with `keep_backup` the previous content is kept next to it as `<name>.bak`.
+#[tauri::command]
+pub async fn update_file(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    path: String,
+    content: String,
+    expected_hash: Option<String>,
+    expected_mtime: Option<u64>,
+    keep_backup: Option<bool>,
+) -> Result<(), FileOpError> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("updating file: {path}");
+
+        if locks::find_locked(&get_base_dir(&app_handle)?, &path).is_some() {
+            log::warn!("refusing to overwrite {path}: locked");
+            return Err(FileOpError::Locked { path });
+        }
+
+        if expected_hash.is_some() || expected_mtime.is_some() {
+            let file_path = resolve_path(&app_handle, &path)?;
+            match fs::read(&file_path).await {
+                Ok(current) => {
+                    let current =
+                        encryption::decrypt_if_needed(&get_base_dir(&app_handle)?, current)?;
+                    let current_hash = content_hash(&current);
+                    let current_modified_time_ms = fs::metadata(&file_path)
+                        .await
+                        .ok()
+                        .and_then(|metadata| metadata.modified().ok())
+                        .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+                        .map(|duration| duration.as_millis() as u64)
+                        .unwrap_or_default();
+
+                    let hash_changed = expected_hash.is_some_and(|hash| hash != current_hash);
+                    let mtime_changed =
+                        expected_mtime.is_some_and(|mtime| mtime != current_modified_time_ms);
+                    if hash_changed || mtime_changed {
+                        log::warn!("refusing to overwrite {path}: changed on disk");
+                        return Err(FileOpError::Conflict {
+                            path,
+                            current_content: String::from_utf8_lossy(&current).into_owned(),
+                            current_hash,
+                            current_modified_time_ms,
+                        });
+                    }
+                }
+                // deleted behind the editor's back: writing recreates it
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(format!("failed to read {path}: {e}").into()),
+            }
+        }
+
+        nb::update_file(&app_handle, &path, &content, keep_backup.unwrap_or(false)).await?;
+
+        log::info!("updated file: {path}");
+
+        Ok(())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn delete_file(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    path: String,
+) -> Result<(), FileOpError> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("deleting file: {path}");
+
+        locks::ensure_unlocked(&get_base_dir(&app_handle)?, &path)?;
+
+        nb::delete(&app_handle, &path).await?;
+
+        log::info!("deleted file: {path}");
+
+        Ok(())
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn rename_file(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    old_path: String,
+    new_path: String,
+) -> Result<(), FileOpError> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("renaming file: {old_path} -> {new_path}");
+
+        locks::ensure_unlocked(&get_base_dir(&app_handle)?, &old_path)?;
+
+        validate_path_name(&new_path)?;
+        nb::rename(&app_handle, &old_path, &new_path).await?;
+
+        log::info!("renamed file: {old_path} -> {new_path}");
+
+        Ok(())
+    })
+    .await
+}
+
+/// checks a file or folder name before it is used, so the ui can show why
+/// it would be rejected
+#[tauri::command]
+pub fn validate_filename(name: String) -> Result<(), String> {
+    crate::utils::validate_filename(&name)
+}
+
+/// the first free path for `base_name` in `dir` ("" for the vault root):
+/// `Untitled.md`, then `Untitled 1.md`, `Untitled 2.md`, ...
+/// a name without an extension becomes a note.
+#[tauri::command]
+pub async fn generate_unique_path(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    dir: String,
+    base_name: String,
+) -> Result<String, String> {
+    vaults::scoped(&webview_window, async move {
+        crate::utils::validate_filename(&base_name)?;
+        let dir = dir.trim_matches('/');
+        resolve_path(&app_handle, dir)?;
+
+        let name = if base_name.contains('.') {
+            base_name
+        } else {
+            format!("{base_name}.md")
+        };
+        let base_dir = get_base_dir(&app_handle)?;
+        Ok(nb::available_path(&base_dir, dir, &name))
+    })
+    .await
+}
+
+/// moves a file or folder into `new_dir` ("" for the vault root).
+/// returns the new path, which gets a numeric suffix if the name was taken.
+#[tauri::command]
+pub async fn move_entry(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    old_path: String,
+    new_dir: String,
+) -> Result<String, FileOpError> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("moving entry: {old_path} -> {new_dir}/");
+
+        locks::ensure_unlocked(&get_base_dir(&app_handle)?, &old_path)?;
+
+        resolve_path(&app_handle, &old_path)?;
+        resolve_path(&app_handle, &new_dir)?;
+        let new_path = nb::move_entry(&app_handle, &old_path, &new_dir).await?;
+
+        log::info!("moved entry: {old_path} -> {new_path}");
+
+        Ok(new_path)
+    })
+    .await
+}
+
+/// copies a note, or a folder with `recursive`, to `dest_path`.
+/// returns the path of the copy, which gets a numeric suffix if the name was taken.
+#[tauri::command]
+pub async fn copy_entry(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    src_path: String,
+    dest_path: String,
+    recursive: Option<bool>,
+) -> Result<String, String> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("copying entry: {src_path} -> {dest_path}");
+
+        resolve_path(&app_handle, &src_path)?;
+        resolve_path(&app_handle, &dest_path)?;
+        let new_path = nb::copy_entry(
+            &app_handle,
+            &src_path,
+            &dest_path,
+            recursive.unwrap_or(false),
+        )
+        .await?;
+
+        log::info!("copied entry: {src_path} -> {new_path}");
+
+        Ok(new_path)
+    })
+    .await
+}
+
+/// applies create/update/rename/delete operations in order with a single
+/// checkpoint at the end. returns one result per operation.
+#[tauri::command]
+pub async fn batch_apply(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    operations: Vec<nb::BatchOp>,
+    message: Option<String>,
+) -> Result<Vec<nb::BatchOpResult>, String> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("applying batch of {} operations", operations.len());
+
+        let results = nb::apply_batch(&app_handle, &operations, message.as_deref()).await?;
+
+        log::info!(
+            "applied batch: {}/{} succeeded",
+            results.iter().filter(|result| result.ok).count(),
+            results.len()
+        );
+
+        Ok(results)
+    })
+    .await
+}
+
+// -----------------------------------------
+// vault repair
+// -----------------------------------------
+
+/// runs a remediation action reported with an `nb-failure` event
+#[tauri::command]
+pub async fn repair_vault(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    action: nb::NbRemediation,
+) -> Result<(), String> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("repairing vault: {action:?}");
+
+        nb::remediate(&app_handle, action).await?;
+
+        log::info!("repaired vault: {action:?}");
+
+        Ok(())
+    })
+    .await
+}
+
+/// cross-checks the vault's files, nb index and git history. with `repair`,
+/// problems are fixed by reconciling and checkpointing before reporting.
+#[tauri::command]
+pub async fn verify_vault(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    repair: Option<bool>,
+) -> Result<nb::VaultIntegrity, String> {
+    vaults::scoped(&webview_window, async move {
+        let repair = repair.unwrap_or(false);
+        log::info!("verifying vault (repair: {repair})");
+
+        let report = nb::verify_vault(&app_handle, repair).await?;
+
+        log::info!(
+            "verified vault: {} untracked, {} stale, {} uncommitted",
+            report.untracked_notes.len(),
+            report.stale_index_entries.len(),
+            report.uncommitted_changes.len()
+        );
+
+        Ok(report)
+    })
+    .await
+}
+
+/// how the window's vault authenticates to its sync remote
+#[tauri::command]
+pub async fn get_remote_credentials(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+) -> Result<nb::RemoteCredentials, String> {
+    vaults::scoped(&webview_window, async move {
+        nb::remote_credentials(&app_handle).await
+    })
+    .await
+}
+
+/// private keys in `~/.ssh` that can be picked for ssh remotes
+#[tauri::command]
+pub async fn list_ssh_keys(app_handle: AppHandle) -> Result<Vec<String>, String> {
+    nb::list_ssh_keys(&app_handle)
+}
+
+/// picks the private key used to reach the vault's ssh remote. `None`
+/// goes back to ssh's own key selection.
+#[tauri::command]
+pub async fn set_remote_ssh_key(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    key_path: Option<String>,
+) -> Result<(), String> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("setting remote ssh key: {key_path:?}");
+        nb::set_remote_ssh_key(&app_handle, key_path).await
+    })
+    .await
+}
+
+/// stores the token used to reach the vault's https remote in the keychain.
+/// an empty or missing token removes it.
+#[tauri::command]
+pub async fn set_remote_https_token(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    username: Option<String>,
+    token: Option<String>,
+) -> Result<(), String> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("setting remote https token (username: {username:?})");
+        nb::set_remote_https_token(&app_handle, username, token).await
+    })
+    .await
+}
+
+/// re-verifies the nb installation, bypassing the startup fast-path.
+/// returns the installed nb version.
+#[tauri::command]
+pub async fn verify_nb_installation(app_handle: AppHandle) -> Result<String, String> {
+    log::info!("verifying nb installation");
+
+    let version = nb::verify_installation(&app_handle).await?;
+
+    log::info!("verified nb installation: {version}");
+
+    Ok(version)
+}
+
+// -----------------------------------------
+// deleted note recovery
+// -----------------------------------------
+
+#[tauri::command]
+pub async fn list_deleted_notes(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    since: Option<i64>,
+) -> Result<Vec<nb::DeletedNote>, String> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("listing deleted notes since: {since:?}");
+
+        let deleted = nb::list_deleted_notes(&app_handle, since).await?;
+
+        log::info!("found {} deleted notes", deleted.len());
+
+        Ok(deleted)
+    })
+    .await
+}
+
+/// restores a deleted note from the git history.
+/// the note is written to `destination` (defaults to its original path) and
+/// the restored path is returned.
+#[tauri::command]
+pub async fn recover_deleted_note(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    path: String,
+    commit: String,
+    destination: Option<String>,
+) -> Result<String, String> {
+    vaults::scoped(&webview_window, async move {
+        let destination = destination.unwrap_or_else(|| path.clone());
+        log::info!("recovering deleted note {path} from {commit} to {destination}");
+
+        // validate both paths stay inside the vault
+        resolve_path(&app_handle, &path)?;
+        let destination_path = resolve_path(&app_handle, &destination)?;
+        if destination_path.exists() {
+            return Err(format!("file '{destination}' already exists"));
+        }
+
+        let content = nb::read_deleted_note(&app_handle, &path, &commit).await?;
+        nb::create_file(&app_handle, &destination, &content).await?;
+
+        log::info!("recovered deleted note to: {destination}");
+
+        Ok(destination)
+    })
+    .await
+}
+
+// -----------------------------------------
+// file history
+// -----------------------------------------
+
+/// lists the checkpoints of a note, newest first, across renames
+#[tauri::command]
+pub async fn list_file_versions(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    path: String,
+) -> Result<Vec<nb::FileVersion>, String> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("listing versions of: {path}");
+
+        resolve_path(&app_handle, &path)?;
+        let versions = nb::list_file_versions(&app_handle, &path).await?;
+
+        log::info!("found {} versions of {path}", versions.len());
+
+        Ok(versions)
+    })
+    .await
+}
+
+/// returns a note's content at a checkpoint, for read-only viewing
+#[tauri::command]
+pub async fn read_file_version(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    path: String,
+    commit: String,
+) -> Result<String, String> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("reading {path} at {commit}");
+
+        resolve_path(&app_handle, &path)?;
+        let content = nb::read_file_version(&app_handle, &path, &commit).await?;
+
+        Ok(content)
+    })
+    .await
+}
+
+/// lines of context around each change in version diffs
+const DIFF_CONTEXT_LINES: usize = 3;
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffLine {
+    /// "context" | "insert" | "delete"
+    pub kind: &'static str,
+    /// the line without its trailing newline
+    pub content: String,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffHunk {
+    /// 1-based first line and line count in the old version
+    pub old_start: usize,
+    pub old_lines: usize,
+    /// 1-based first line and line count in the new version
+    pub new_start: usize,
+    pub new_lines: usize,
+    pub lines: Vec<DiffLine>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileDiff {
+    /// `diff -u` style text
+    pub unified: String,
+    /// present only when requested with `hunks: true`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hunks: Option<Vec<DiffHunk>>,
+}
+
+fn diff_hunks(diff: &similar::TextDiff<'_, '_, '_, str>) -> Vec<DiffHunk> {
+    diff.grouped_ops(DIFF_CONTEXT_LINES)
+        .iter()
+        .filter_map(|group| {
+            let first = group.first()?;
+            let last = group.last()?;
+            let old_range = first.old_range().start..last.old_range().end;
+            let new_range = first.new_range().start..last.new_range().end;
+            let lines = group
+                .iter()
+                .flat_map(|op| diff.iter_changes(op))
+                .map(|change| DiffLine {
+                    kind: match change.tag() {
+                        similar::ChangeTag::Equal => "context",
+                        similar::ChangeTag::Insert => "insert",
+                        similar::ChangeTag::Delete => "delete",
+                    },
+                    content: change.value().trim_end_matches(['\n', '\r']).to_string(),
+                })
+                .collect();
+            Some(DiffHunk {
+                old_start: old_range.start + 1,
+                old_lines: old_range.len(),
+                new_start: new_range.start + 1,
+                new_lines: new_range.len(),
+                lines,
+            })
+        })
+        .collect()
+}
+
+/// diffs a note between two checkpoints. `path` is looked up in both commits,
+/// so pass the path from the matching `FileVersion` across renames.
+#[tauri::command]
+pub async fn diff_file_versions(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    path: String,
+    from_commit: String,
+    to_commit: String,
+    hunks: Option<bool>,
+) -> Result<FileDiff, String> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("diffing {path} between {from_commit} and {to_commit}");
+
+        resolve_path(&app_handle, &path)?;
+        let old = nb::read_file_version(&app_handle, &path, &from_commit).await?;
+        let new = nb::read_file_version(&app_handle, &path, &to_commit).await?;
+
+        let diff = similar::TextDiff::from_lines(old.as_str(), new.as_str());
+        let unified = diff
+            .unified_diff()
+            .context_radius(DIFF_CONTEXT_LINES)
+            .header(
+                &format!("{path}@{from_commit}"),
+                &format!("{path}@{to_commit}"),
+            )
+            .to_string();
+
+        Ok(FileDiff {
+            unified,
+            hunks: hunks.unwrap_or(false).then(|| diff_hunks(&diff)),
+        })
+    })
+    .await
+}
+
+// -----------------------------------------
+// frontmatter metadata (no git checkpoint)
+// -----------------------------------------
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileMetadata {
+    /// parsed frontmatter, `null` if the note has none
+    pub metadata: serde_json::Value,
+    /// where the body starts, in UTF-16 code units so the frontend can `slice` with it
+    pub body_offset: usize,
+}
+
+/// Reads the YAML frontmatter of an internal file, parsed into JSON.
+#[tauri::command]
+pub async fn read_file_metadata(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    path: String,
+) -> Result<FileMetadata, String> {
+    vaults::scoped(&webview_window, async move {
+        resolve_path(&app_handle, &path)?;
+        let content = nb::read_file(&app_handle, &path).await?;
+
+        let frontmatter = frontmatter::split(&content);
+        let metadata = match frontmatter.parse()? {
+            Some(value) => serde_json::to_value(value)
+                .map_err(|e| format!("failed to convert frontmatter of {path}: {e}"))?,
+            None => serde_json::Value::Null,
+        };
+        let header = &content[..content.len() - frontmatter.body.len()];
+
+        Ok(FileMetadata {
+            metadata,
+            body_offset: header.encode_utf16().count(),
+        })
+    })
+    .await
+}
+
+/// Writes only the YAML frontmatter section of an internal file.
+/// The new YAML is parsed and re-serialized, the existing header is located by
+/// its delimiter lines, and the body is written back untouched without
+/// triggering a git checkpoint. Empty YAML removes the header.
+#[tauri::command]
+pub async fn write_file_metadata(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    path: String,
+    yaml: String,
+) -> Result<(), FileOpError> {
+    vaults::scoped(&webview_window, async move {
+        locks::ensure_unlocked(&get_base_dir(&app_handle)?, &path)?;
+
+        resolve_path(&app_handle, &path)?;
+        let content = nb::read_file(&app_handle, &path).await?;
+
+        let value: Option<serde_yaml::Value> = if yaml.trim().is_empty() {
+            None
+        } else {
+            Some(serde_yaml::from_str(&yaml).map_err(|e| format!("invalid frontmatter: {e}"))?)
+        };
+        let new_content = frontmatter::replace(&content, value.as_ref())?;
+
+        // keeps notes in encrypted folders encrypted
+        nb::write_file(&app_handle, &path, &new_content, false).await?;
+        Ok(())
+    })
+    .await
+}
+
+/// a frontmatter key and the value types it has across the vault
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FrontmatterKey {
+    pub key: String,
+    /// `string`, `number`, `boolean`, `date`, `list`, `object` or `null`,
+    /// most used first
+    pub types: Vec<String>,
+    /// notes that have the key
+    pub note_count: usize,
+}
+
+fn yaml_type(value: &serde_yaml::Value) -> &'static str {
+    use serde_yaml::Value;
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(text) if chrono::NaiveDate::parse_from_str(text, "%Y-%m-%d").is_ok() => {
+            "date"
+        }
+        Value::String(_) => "string",
+        Value::Sequence(_) => "list",
+        Value::Mapping(_) => "object",
+        Value::Tagged(tagged) => yaml_type(&tagged.value),
+    }
+}
+
+/// every top-level frontmatter key used in the vault, sorted by key, for a
+/// properties editor to offer completions and pick an input type
+#[tauri::command]
+pub async fn list_frontmatter_keys(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+) -> Result<Vec<FrontmatterKey>, String> {
+    vaults::scoped(&webview_window, async move {
+        let root = resolve_path(&app_handle, "")?;
+        let mut entries: Vec<FSEntry> = Vec::new();
+        list_dir_inner(&root, "", true, &mut entries).await?;
+
+        // key -> (type -> notes)
+        let mut keys: BTreeMap<String, HashMap<&'static str, usize>> = BTreeMap::new();
+        for entry in entries {
+            if entry.is_dir || !vault_settings::is_managed_file(&entry.path) {
+                continue;
+            }
+            let Ok(content) = nb::read_file(&app_handle, &entry.path).await else {
+                continue;
+            };
+            // notes with invalid yaml are skipped rather than failing the listing
+            let Ok(Some(serde_yaml::Value::Mapping(map))) = frontmatter::split(&content).parse()
+            else {
+                continue;
+            };
+            for (key, value) in map {
+                let Some(key) = key.as_str() else {
+                    continue;
+                };
+                *keys
+                    .entry(key.to_string())
+                    .or_default()
+                    .entry(yaml_type(&value))
+                    .or_default() += 1;
+            }
+        }
+
+        Ok(keys
+            .into_iter()
+            .map(|(key, types)| {
+                let mut types: Vec<(&str, usize)> = types.into_iter().collect();
+                types.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+                FrontmatterKey {
+                    note_count: types.iter().map(|(_, count)| count).sum(),
+                    types: types
+                        .into_iter()
+                        .map(|(name, _)| name.to_string())
+                        .collect(),
+                    key,
+                }
+            })
+            .collect())
+    })
+    .await
+}
+
+/// sets one frontmatter field (or removes it when `value` is null), keeping
+/// the other fields and the body. like `write_file_metadata` this does not
+/// trigger a git checkpoint. returns the updated metadata.
+#[tauri::command]
+pub async fn update_frontmatter_field(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    path: String,
+    key: String,
+    value: serde_json::Value,
+) -> Result<FileMetadata, FileOpError> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("updating frontmatter field {key} of {path}");
+
+        locks::ensure_unlocked(&get_base_dir(&app_handle)?, &path)?;
+        let content = nb::read_file(&app_handle, &path).await?;
+
+        let mut map = match frontmatter::split(&content).parse()? {
+            Some(serde_yaml::Value::Mapping(map)) => map,
+            None | Some(serde_yaml::Value::Null) => serde_yaml::Mapping::new(),
+            Some(_) => return Err(format!("frontmatter of {path} is not a mapping").into()),
+        };
+        let yaml_key = serde_yaml::Value::String(key.clone());
+        if value.is_null() {
+            map.remove(&yaml_key);
+        } else {
+            let yaml_value = serde_yaml::to_value(&value)
+                .map_err(|e| format!("invalid value for {key}: {e}"))?;
+            map.insert(yaml_key, yaml_value);
+        }
+
+        let value = serde_yaml::Value::Mapping(map);
+        let new_content = frontmatter::replace(&content, Some(&value))?;
+        nb::write_file(&app_handle, &path, &new_content, false).await?;
+
+        let body = frontmatter::split(&new_content).body;
+        let header = &new_content[..new_content.len() - body.len()];
+        Ok(FileMetadata {
+            metadata: serde_json::to_value(&value)
+                .map_err(|e| format!("failed to convert frontmatter of {path}: {e}"))?,
+            body_offset: header.encode_utf16().count(),
+        })
+    })
+    .await
+}
+
+// -----------------------------------------
+// external file commands (files outside ~/flowrite/)
+// -----------------------------------------
+
+#[tauri::command]
+pub async fn create_external_file(path: String, content: Option<String>) -> Result<(), String> {
+    log::info!("creating external file: {path}");
+
+    let file_path = std::path::Path::new(&path);
+
+    // create parent dirs if needed
+    if let Some(parent) = file_path.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("failed to create parent directories for '{path}': {e}"))?;
+    }
+
+    let initial_content = content.unwrap_or_default();
+    fs::write(&path, initial_content)
+        .await
+        .map_err(|e| format!("failed to create external file '{path}': {e}"))?;
+
+    log::info!("created external file: {path}");
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn read_external_file(path: String) -> Result<String, String> {
+    log::info!("reading external file: {path}");
+
+    let content = fs::read_to_string(&path)
+        .await
         .map_err(|e| format!("failed to read external file '{path}': {e}"))?;
 
     log::info!("read external file: {path}");
@@ -459,13 +1847,22 @@ pub async fn read_external_file(path: String) -> Result<String, String> {
     Ok(content)
 }
 
+/// writes through a temp file like `update_file`, optionally keeping a `.bak`
 #[tauri::command]
-pub async fn update_external_file(path: String, content: String) -> Result<(), String> {
+pub async fn update_external_file(
+    path: String,
+    content: String,
+    keep_backup: Option<bool>,
+) -> Result<(), String> {
     log::info!("updating external file: {path}");
 
-    fs::write(&path, content)
-        .await
-        .map_err(|e| format!("failed to update external file '{path}': {e}"))?;
+    write_atomic(
+        std::path::Path::new(&path),
+        content.as_bytes(),
+        keep_backup.unwrap_or(false),
+    )
+    .await
+    .map_err(|e| format!("failed to update external file '{path}': {e}"))?;
 
     log::info!("updated external file: {path}");
 
@@ -505,6 +1902,54 @@ pub async fn rename_external_file(old_path: String, new_path: String) -> Result<
     Ok(())
 }
 
+/// metadata of a file or folder outside the vault; the entry's path is the
+/// absolute path given
+#[tauri::command]
+pub async fn stat_external_file(path: String) -> Result<FSEntry, String> {
+    let metadata = fs::metadata(&path)
+        .await
+        .map_err(|e| format!("failed to read metadata for external file '{path}': {e}"))?;
+    fs_entry_from_metadata(path, &metadata)
+}
+
+/// the entries of a folder outside the vault (not recursive), with absolute
+/// paths. hidden entries are skipped; folders come first, then by name.
+#[tauri::command]
+pub async fn list_external_dir(path: String) -> Result<Vec<FSEntry>, String> {
+    log::info!("listing external directory: {path}");
+
+    let mut entries = fs::read_dir(&path)
+        .await
+        .map_err(|e| format!("failed to read external directory '{path}': {e}"))?;
+    let mut listed = Vec::new();
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .map_err(|e| format!("failed to read directory entry: {e}"))?
+    {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') {
+            continue;
+        }
+        // entries can vanish or be unreadable (e.g. broken symlinks); skip them
+        let Ok(metadata) = fs::metadata(entry.path()).await else {
+            continue;
+        };
+        listed.push(fs_entry_from_metadata(
+            entry.path().to_string_lossy().to_string(),
+            &metadata,
+        )?);
+    }
+    listed.sort_by_cached_key(|entry| (!entry.is_dir, entry.path.to_lowercase()));
+
+    log::info!(
+        "listed {} entries in external directory {path}",
+        listed.len()
+    );
+
+    Ok(listed)
+}
+
 // -----------------------------------------
 // system prompt
 // -----------------------------------------
@@ -1,8 +1,28 @@
 // base directory
 pub const BASE_DIR_NAME: &str = "flowrite";
+pub const DEFAULT_VAULT_ID: &str = "default";
 pub const NB_DATA_DIR_NAME: &str = ".fwnb";
 pub const NB_RC_FILE_NAME: &str = ".fwnbrc";
 
+// per-vault config (lives inside the base directory)
+pub const VAULT_CONFIG_DIR_NAME: &str = ".flowrite";
+pub const AGENT_BINDINGS_FILE_NAME: &str = "agent-bindings.json";
+pub const JOURNAL_FILE_NAME: &str = "journal.ndjson";
+pub const TRASH_DIR_NAME: &str = "trash";
+pub const VAULT_SETTINGS_FILE_NAME: &str = "settings.json";
+pub const ENCRYPTION_FILE_NAME: &str = "encryption.json";
+pub const LOCKS_FILE_NAME: &str = "locks.json";
+pub const TRASH_ENTRY_FILE_NAME: &str = "entry.json";
+
+// gitignore-style patterns hidden from the tree, indexes and watcher (vault root)
+pub const VAULT_IGNORE_FILE_NAME: &str = ".flowriteignore";
+
+// note attachments (a folder next to the notes that embed them)
+pub const ASSETS_DIR_NAME: &str = "assets";
+
+// note templates (a folder at the vault root)
+pub const TEMPLATES_DIR_NAME: &str = "templates";
+
 // workspace window
 pub const WORKSPACE_WINDOW_LABEL_PREFIX: &str = "workspace";
 
@@ -12,8 +32,68 @@ pub const WORKSPACE_WINDOW_HEIGHT: f64 = 900.0;
 pub const WORKSPACE_WINDOW_MIN_WIDTH: f64 = 640.0;
 pub const WORKSPACE_WINDOW_MIN_HEIGHT: f64 = 480.0;
 
+// app settings store (must match frontend constants)
+pub const SETTINGS_STORE_FILE_NAME: &str = "settings.json";
+pub const FIRST_INSTALL_DONE_KEY: &str = "first-install-done";
+pub const PERMISSION_RULES_KEY: &str = "permission-rules";
+pub const PERMISSION_TIMEOUT_KEY: &str = "permission-timeout";
+pub const PROXY_SETTINGS_KEY: &str = "proxy-settings";
+pub const THINKING_LEVELS_KEY: &str = "thinking-levels";
+pub const VAULT_ONLY_AGENTS_KEY: &str = "vault-only-agents";
+pub const WATCHER_DIRECTORY_DELTAS_KEY: &str = "watcher-directory-deltas";
+pub const WATCHER_SETTINGS_KEY: &str = "watcher-settings";
+pub const AUTOSAVE_POLICY_KEY: &str = "autosave-policy";
+pub const CHECKPOINT_POLICY_KEY: &str = "checkpoint-policy";
+pub const SPOTLIGHT_INDEXING_KEY: &str = "spotlight-indexing";
+pub const PLUGINS_ENABLED_KEY: &str = "plugins-enabled";
+pub const DEFAULT_AGENTS_KEY: &str = "default-agents";
+pub const VAULT_SYNC_POLICY_KEY: &str = "vault-sync-policy";
+pub const VAULT_MAINTENANCE_POLICY_KEY: &str = "vault-maintenance-policy";
+pub const VAULTS_KEY: &str = "vaults";
+pub const RECENT_FILES_KEY: &str = "recent-files";
+pub const PINNED_NOTES_KEY: &str = "pinned-notes";
+
+// git remote used for vault sync
+pub const SYNC_REMOTE_NAME: &str = "origin";
+
 // global events
 pub const FILE_WATCHER_EVENT: &str = "file-watcher";
+pub const NB_FAILURE_EVENT: &str = "nb-failure";
+pub const NB_INSTALL_PROGRESS_EVENT: &str = "nb-install-progress";
+pub const AUTOSAVE_FLUSHED_EVENT: &str = "autosave-flushed";
+pub const VAULT_SYNC_STATUS_EVENT: &str = "vault-sync-status";
+pub const VAULT_CHANGED_EVENT: &str = "vault-changed";
+
+// window events
+pub const FOCUS_MODE_CHANGED_EVENT: &str = "focus-mode-changed";
+pub const VAULT_EXPORT_PROGRESS_EVENT: &str = "vault-export-progress";
+pub const EXTERNAL_FILE_EVENT: &str = "external-file-changed";
+pub const VAULT_MAINTENANCE_EVENT: &str = "vault-maintenance";
+
+// agent transcripts (inside the app data directory)
+pub const TRANSCRIPTS_DIR_NAME: &str = "transcripts";
+
+// automation plugins (inside the app data directory)
+pub const PLUGINS_DIR_NAME: &str = "plugins";
+
+// agent audit log (inside the app data directory)
+pub const AUDIT_LOG_FILE_NAME: &str = "agent-audit.jsonl";
+
+// full-text search index (inside the app data directory)
+pub const SEARCH_INDEX_FILE_NAME: &str = "search-index.db";
+
+// keychain secrets for agent env values
+pub const KEYCHAIN_SERVICE: &str = "com.flowrite.flowrite.secrets";
+// keychain entries the app keeps for itself (vault keys), out of reach of the
+// secrets commands and agent env values
+pub const INTERNAL_KEYCHAIN_SERVICE: &str = "com.flowrite.flowrite.internal";
+pub const KEYCHAIN_ENV_PREFIX: &str = "keychain:";
+
+// staged editor content not yet written (inside the app data directory)
+pub const AUTOSAVE_STAGED_FILE_NAME: &str = "autosave-staged.json";
+
+// nb install cache (inside the app data directory)
+pub const NB_INSTALL_CACHE_FILE_NAME: &str = "nb-install.json";
 
 // system prompt
 pub const SYSTEM_PROMPT_FILE_NAME: &str = "system-prompt.md";
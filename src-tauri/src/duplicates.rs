@@ -0,0 +1,238 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+use serde::Serialize;
+use tauri::{AppHandle, WebviewWindow};
+
+use crate::{
+    command::{list_dir_inner, FSEntry},
+    frontmatter, nb,
+    utils::{content_hash, resolve_path},
+    vault_settings, vaults,
+};
+
+/// words per shingle when comparing notes for near-duplicates
+const SHINGLE_WORDS: usize = 5;
+
+/// notes whose bodies are the same, or similar enough, to be merged
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateCluster {
+    /// sorted vault-relative paths
+    pub paths: Vec<String>,
+    /// every note has the same normalized body
+    pub exact: bool,
+    /// the lowest similarity (0.0 - 1.0) that joined a note to the cluster
+    pub similarity: f64,
+}
+
+struct Note {
+    path: String,
+    shingles: HashSet<u64>,
+}
+
+/// the body without frontmatter, lowercased, with whitespace collapsed, so
+/// reformatted copies and differing metadata still compare equal
+fn normalize(content: &str) -> String {
+    frontmatter::split(content)
+        .body
+        .split_whitespace()
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// hashed runs of `SHINGLE_WORDS` consecutive words; short notes are one shingle
+fn shingles(normalized: &str) -> HashSet<u64> {
+    let words: Vec<&str> = normalized.split(' ').collect();
+    words
+        .windows(SHINGLE_WORDS.min(words.len()))
+        .map(|window| {
+            let mut hasher = DefaultHasher::new();
+            window.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect()
+}
+
+fn jaccard(a: &HashSet<u64>, b: &HashSet<u64>) -> f64 {
+    let shared = a.intersection(b).count();
+    shared as f64 / (a.len() + b.len() - shared) as f64
+}
+
+/// union-find root of `index`, compressing the path on the way
+fn find(parents: &mut [usize], index: usize) -> usize {
+    let mut root = index;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    let mut current = index;
+    while parents[current] != root {
+        let next = parents[current];
+        parents[current] = root;
+        current = next;
+    }
+    root
+}
+
+/// groups notes whose shingle sets are at least `threshold` similar. notes
+/// are compared in order of size, so pairs too different in length to reach
+/// the threshold are never compared.
+fn near_duplicates(mut notes: Vec<Note>, threshold: f64) -> Vec<DuplicateCluster> {
+    notes.sort_by_key(|note| note.shingles.len());
+    let mut parents: Vec<usize> = (0..notes.len()).collect();
+    let mut lowest: HashMap<usize, f64> = HashMap::new();
+    let mut links: Vec<(usize, usize, f64)> = Vec::new();
+
+    for i in 0..notes.len() {
+        for j in i + 1..notes.len() {
+            let (small, large) = (notes[i].shingles.len(), notes[j].shingles.len());
+            if (small as f64) < threshold * large as f64 {
+                break;
+            }
+            let similarity = jaccard(&notes[i].shingles, &notes[j].shingles);
+            if similarity >= threshold {
+                links.push((i, j, similarity));
+            }
+        }
+    }
+    for (i, j, similarity) in links {
+        let (a, b) = (find(&mut parents, i), find(&mut parents, j));
+        if a == b {
+            continue;
+        }
+        let joined = lowest
+            .remove(&a)
+            .into_iter()
+            .chain(lowest.remove(&b))
+            .fold(similarity, f64::min);
+        parents[a] = b;
+        lowest.insert(b, joined);
+    }
+
+    let mut clusters: HashMap<usize, Vec<String>> = HashMap::new();
+    for index in 0..notes.len() {
+        let root = find(&mut parents, index);
+        clusters
+            .entry(root)
+            .or_default()
+            .push(std::mem::take(&mut notes[index].path));
+    }
+    clusters
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .map(|(root, mut paths)| {
+            paths.sort();
+            DuplicateCluster {
+                paths,
+                exact: false,
+                similarity: lowest.get(&root).copied().unwrap_or(1.0),
+            }
+        })
+        .collect()
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+/// clusters of duplicate notes in the vault, largest first. a `threshold`
+/// below 1.0 (e.g. 0.8) also groups near-duplicates whose bodies share at
+/// least that fraction of their word shingles; the default finds only notes
+/// with identical bodies.
+#[tauri::command]
+pub async fn find_duplicate_notes(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    threshold: Option<f64>,
+) -> Result<Vec<DuplicateCluster>, String> {
+    vaults::scoped(&webview_window, async move {
+        let threshold = threshold.unwrap_or(1.0);
+        log::info!("finding duplicate notes (threshold: {threshold})");
+        if !(0.0..=1.0).contains(&threshold) || threshold == 0.0 {
+            return Err(format!(
+                "threshold must be above 0 and at most 1, got {threshold}"
+            ));
+        }
+
+        let root = resolve_path(&app_handle, "")?;
+        let mut entries: Vec<FSEntry> = Vec::new();
+        list_dir_inner(&root, "", true, &mut entries).await?;
+
+        let mut by_hash: HashMap<String, Vec<String>> = HashMap::new();
+        let mut notes: Vec<Note> = Vec::new();
+        for entry in entries {
+            let name = entry.path.rsplit('/').next().unwrap_or(&entry.path);
+            if entry.is_dir || !vault_settings::is_managed_file(name) {
+                continue;
+            }
+            // decrypts notes in encrypted folders; unreadable notes are skipped
+            let Ok(content) = nb::read_file(&app_handle, &entry.path).await else {
+                continue;
+            };
+            let normalized = normalize(&content);
+            // empty notes are all alike; they are not worth reporting
+            if normalized.is_empty() {
+                continue;
+            }
+            let paths = by_hash
+                .entry(content_hash(normalized.as_bytes()))
+                .or_default();
+            // only the first copy of a body takes part in near-duplicate matching
+            if paths.is_empty() && threshold < 1.0 {
+                notes.push(Note {
+                    path: entry.path.clone(),
+                    shingles: shingles(&normalized),
+                });
+            }
+            paths.push(entry.path);
+        }
+
+        let mut clusters: Vec<DuplicateCluster> = Vec::new();
+        let mut exact_by_first: HashMap<String, Vec<String>> = HashMap::new();
+        for paths in by_hash.into_values() {
+            exact_by_first.insert(paths[0].clone(), paths);
+        }
+        if threshold < 1.0 {
+            // comparing every pair is quadratic, so keep it off the async runtime
+            let near =
+                tauri::async_runtime::spawn_blocking(move || near_duplicates(notes, threshold))
+                    .await
+                    .map_err(|e| format!("failed to compare notes: {e}"))?;
+            // widen each near-duplicate cluster with the exact copies of its notes
+            for mut cluster in near {
+                let mut paths = Vec::new();
+                for path in &cluster.paths {
+                    paths.extend(exact_by_first.remove(path).unwrap_or_default());
+                }
+                paths.sort();
+                cluster.paths = paths;
+                clusters.push(cluster);
+            }
+        }
+        clusters.extend(
+            exact_by_first
+                .into_values()
+                .filter(|paths| paths.len() > 1)
+                .map(|mut paths| {
+                    paths.sort();
+                    DuplicateCluster {
+                        paths,
+                        exact: true,
+                        similarity: 1.0,
+                    }
+                }),
+        );
+        clusters.sort_by(|a, b| {
+            b.paths
+                .len()
+                .cmp(&a.paths.len())
+                .then_with(|| a.paths.cmp(&b.paths))
+        });
+
+        log::info!("found {} duplicate note clusters", clusters.len());
+        Ok(clusters)
+    })
+    .await
+}
@@ -0,0 +1,436 @@
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Mutex, RwLock};
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, WebviewWindow};
+use tokio::fs;
+
+use crate::{
+    constants::{ENCRYPTION_FILE_NAME, VAULT_CONFIG_DIR_NAME},
+    fs_cache,
+    journal::{self, JournalOp, JournalOrigin},
+    nb, secrets,
+    utils::{get_base_dir, resolve_path, write_atomic},
+    vault_settings, vaults,
+};
+
+/// first bytes of every encrypted note, followed by the nonce and ciphertext
+const MAGIC: &[u8] = b"FLOWRITE-ENCRYPTED-1\n";
+const NONCE_LENGTH: usize = 24;
+const SALT_LENGTH: usize = 16;
+
+/// encrypted with the derived key to tell a wrong passphrase from a right one
+const CHECK_TEXT: &[u8] = b"flowrite";
+
+/// start and end of the lines flowrite manages in `.git/info/exclude`
+const EXCLUDE_BEGIN: &str = "# flowrite: unencrypted notes in encrypted folders";
+const EXCLUDE_END: &str = "# flowrite: end";
+
+/// encrypted folders of a vault, stored in `<vault>/.flowrite/encryption.json`.
+/// the passphrase itself lives in the keychain.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct EncryptionConfig {
+    /// base64 salt for deriving the key from the passphrase
+    salt: Option<String>,
+    /// base64 `CHECK_TEXT` encrypted with the key
+    check: Option<String>,
+    /// vault-relative folders whose notes are encrypted
+    folders: Vec<String>,
+}
+
+/// config of each vault, loaded on first use
+static CONFIGS: RwLock<Option<HashMap<PathBuf, EncryptionConfig>>> = RwLock::new(None);
+
+/// derived keys of each vault; argon2 is too slow to run on every read
+static KEYS: Mutex<Option<HashMap<PathBuf, [u8; 32]>>> = Mutex::new(None);
+
+fn get_config_path(vault_root: &Path) -> PathBuf {
+    vault_root
+        .join(VAULT_CONFIG_DIR_NAME)
+        .join(ENCRYPTION_FILE_NAME)
+}
+
+fn load_config(vault_root: &Path) -> EncryptionConfig {
+    if let Some(config) = CONFIGS
+        .read()
+        .unwrap()
+        .as_ref()
+        .and_then(|configs| configs.get(vault_root))
+    {
+        return config.clone();
+    }
+
+    let config = match std::fs::read_to_string(get_config_path(vault_root)) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            log::warn!("ignoring malformed encryption config: {e}");
+            EncryptionConfig::default()
+        }),
+        Err(_) => EncryptionConfig::default(),
+    };
+    CONFIGS
+        .write()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .insert(vault_root.to_path_buf(), config.clone());
+    config
+}
+
+async fn save_config(vault_root: &Path, config: &EncryptionConfig) -> Result<(), String> {
+    let path = get_config_path(vault_root);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("failed to create vault config directory: {e}"))?;
+    }
+    let content = serde_json::to_string_pretty(config)
+        .map_err(|e| format!("failed to serialize encryption config: {e}"))?;
+    fs::write(&path, content)
+        .await
+        .map_err(|e| format!("failed to write encryption config: {e}"))?;
+
+    CONFIGS
+        .write()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .insert(vault_root.to_path_buf(), config.clone());
+    Ok(())
+}
+
+/// keychain entry holding the passphrase of a vault
+fn keychain_name(vault_root: &Path) -> String {
+    format!("vault-encryption:{}", vault_root.display())
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32], String> {
+    let mut key = [0u8; 32];
+    argon2::Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| format!("failed to derive encryption key: {e}"))?;
+    Ok(key)
+}
+
+/// the vault's key, derived from the passphrase in the keychain
+fn get_key(vault_root: &Path) -> Result<[u8; 32], String> {
+    if let Some(key) = KEYS
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|keys| keys.get(vault_root))
+    {
+        return Ok(*key);
+    }
+
+    let config = load_config(vault_root);
+    let salt = config
+        .salt
+        .as_deref()
+        .ok_or("no encryption passphrase is set for this vault")?;
+    let salt = BASE64
+        .decode(salt)
+        .map_err(|e| format!("invalid encryption salt: {e}"))?;
+    let passphrase = secrets::read_internal_secret(&keychain_name(vault_root))?
+        .ok_or("the encryption passphrase is not in the keychain")?;
+    let key = derive_key(&passphrase, &salt)?;
+
+    KEYS.lock()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .insert(vault_root.to_path_buf(), key);
+    Ok(key)
+}
+
+fn encrypt(key: &[u8; 32], plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let cipher = XChaCha20Poly1305::new(Key::from_slice(key));
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext)
+        .map_err(|e| format!("failed to encrypt: {e}"))?;
+
+    let mut bytes = Vec::with_capacity(MAGIC.len() + NONCE_LENGTH + ciphertext.len());
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&nonce);
+    bytes.extend_from_slice(&ciphertext);
+    Ok(bytes)
+}
+
+/// `bytes` without the magic header
+fn decrypt(key: &[u8; 32], bytes: &[u8]) -> Result<Vec<u8>, String> {
+    if bytes.len() < NONCE_LENGTH {
+        return Err("encrypted note is truncated".to_string());
+    }
+    let (nonce, ciphertext) = bytes.split_at(NONCE_LENGTH);
+    XChaCha20Poly1305::new(Key::from_slice(key))
+        .decrypt(XNonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "failed to decrypt note: wrong passphrase or corrupted file".to_string())
+}
+
+fn is_in_encrypted_folder(vault_root: &Path, path: &str) -> bool {
+    load_config(vault_root)
+        .folders
+        .iter()
+        .any(|folder| path.starts_with(&format!("{folder}/")))
+}
+
+/// true if the file at `path` starts with the encrypted note header
+pub fn is_encrypted_file(path: &Path) -> bool {
+    std::fs::File::open(path).is_ok_and(|mut file| {
+        use std::io::Read;
+        let mut header = vec![0u8; MAGIC.len()];
+        file.read_exact(&mut header).is_ok() && header == MAGIC
+    })
+}
+
+/// plaintext of a note read from disk; unencrypted notes are returned as is
+pub fn decrypt_if_needed(vault_root: &Path, bytes: Vec<u8>) -> Result<Vec<u8>, String> {
+    match bytes.strip_prefix(MAGIC) {
+        Some(encrypted) => decrypt(&get_key(vault_root)?, encrypted),
+        None => Ok(bytes),
+    }
+}
+
+/// what to write to disk for a note: encrypted inside an encrypted folder,
+/// the content unchanged anywhere else
+pub fn encrypt_if_needed(vault_root: &Path, path: &str, content: &[u8]) -> Result<Vec<u8>, String> {
+    if is_in_encrypted_folder(vault_root, path) {
+        encrypt(&get_key(vault_root)?, content)
+    } else {
+        Ok(content.to_vec())
+    }
+}
+
+/// vault-relative paths of files in `folder` that are still plaintext
+fn plaintext_files(vault_root: &Path, folder: &str) -> Vec<String> {
+    let mut files = Vec::new();
+    let mut pending = vec![folder.to_string()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(vault_root.join(&dir)) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                continue;
+            }
+            let path = format!("{dir}/{name}");
+            match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() => pending.push(path),
+                Ok(file_type) if file_type.is_file() => {
+                    if !is_encrypted_file(&entry.path())
+                        && vault_settings::is_managed_file_in(vault_root, &name)
+                    {
+                        files.push(path);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+/// a path as a literal gitignore pattern
+fn escape_exclude_pattern(path: &str) -> String {
+    let mut escaped = String::with_capacity(path.len());
+    for c in path.chars() {
+        if matches!(c, '*' | '?' | '[' | '\\' | '!' | '#') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// keeps plaintext notes in encrypted folders (e.g. written by another app
+/// or while the passphrase was missing) out of git checkpoints by listing
+/// them in `.git/info/exclude`. returns them, so that ones already tracked
+/// can be untracked too; the exclude alone does not stop git from staging
+/// changes to tracked files.
+pub fn exclude_plaintext(vault_root: &Path) -> Vec<String> {
+    let config = load_config(vault_root);
+    let info_dir = vault_root.join(".git").join("info");
+    if !info_dir.parent().is_some_and(Path::exists) {
+        return Vec::new();
+    }
+    let exclude_path = info_dir.join("exclude");
+    let existing = std::fs::read_to_string(&exclude_path).unwrap_or_default();
+    if config.folders.is_empty() && !existing.contains(EXCLUDE_BEGIN) {
+        return Vec::new();
+    }
+
+    // everything outside the managed block is kept as the user wrote it
+    let mut content = String::new();
+    let mut in_block = false;
+    for line in existing.lines() {
+        match line {
+            EXCLUDE_BEGIN => in_block = true,
+            EXCLUDE_END => in_block = false,
+            _ if !in_block => {
+                content.push_str(line);
+                content.push('\n');
+            }
+            _ => {}
+        }
+    }
+    let plaintext: Vec<String> = config
+        .folders
+        .iter()
+        .flat_map(|folder| plaintext_files(vault_root, folder))
+        .collect();
+    if !plaintext.is_empty() {
+        content.push_str(EXCLUDE_BEGIN);
+        content.push('\n');
+        for path in &plaintext {
+            content.push_str(&format!("/{}\n", escape_exclude_pattern(path)));
+        }
+        content.push_str(EXCLUDE_END);
+        content.push('\n');
+    }
+
+    if content != existing {
+        if let Err(e) =
+            std::fs::create_dir_all(&info_dir).and_then(|_| std::fs::write(&exclude_path, content))
+        {
+            log::error!("failed to update git exclude for encrypted folders: {e}");
+        }
+    }
+    plaintext
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+/// sets the passphrase for the vault's encrypted folders and stores it in the
+/// keychain. once folders are encrypted, only the same passphrase is accepted.
+#[tauri::command]
+pub async fn encryption_set_passphrase(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    passphrase: String,
+) -> Result<(), String> {
+    vaults::scoped(&webview_window, async move {
+        if passphrase.is_empty() {
+            return Err("passphrase cannot be empty".to_string());
+        }
+        let vault_root = get_base_dir(&app_handle)?;
+        let mut config = load_config(&vault_root);
+
+        let key = match (&config.salt, &config.check) {
+            (Some(salt), Some(check)) => {
+                let salt = BASE64
+                    .decode(salt)
+                    .map_err(|e| format!("invalid encryption salt: {e}"))?;
+                let key = derive_key(&passphrase, &salt)?;
+                let check = BASE64
+                    .decode(check)
+                    .map_err(|e| format!("invalid encryption check: {e}"))?;
+                let matches = check
+                    .strip_prefix(MAGIC)
+                    .and_then(|check| decrypt(&key, check).ok())
+                    .is_some_and(|text| text == CHECK_TEXT);
+                if !matches {
+                    return Err(
+                        "passphrase does not match the one the folders were encrypted with"
+                            .to_string(),
+                    );
+                }
+                key
+            }
+            _ => {
+                let mut salt = [0u8; SALT_LENGTH];
+                OsRng.fill_bytes(&mut salt);
+                let key = derive_key(&passphrase, &salt)?;
+                config.salt = Some(BASE64.encode(salt));
+                config.check = Some(BASE64.encode(encrypt(&key, CHECK_TEXT)?));
+                save_config(&vault_root, &config).await?;
+                key
+            }
+        };
+
+        secrets::write_internal_secret(&keychain_name(&vault_root), &passphrase)?;
+        KEYS.lock()
+            .unwrap()
+            .get_or_insert_with(HashMap::new)
+            .insert(vault_root, key);
+        log::info!("encryption passphrase set");
+        Ok(())
+    })
+    .await
+}
+
+/// folders of the vault whose notes are encrypted at rest
+#[tauri::command]
+pub async fn list_encrypted_folders(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+) -> Result<Vec<String>, String> {
+    vaults::scoped(&webview_window, async move {
+        Ok(load_config(&get_base_dir(&app_handle)?).folders)
+    })
+    .await
+}
+
+/// encrypts the notes in `path` and every note saved there from now on.
+/// `read_file` decrypts them transparently. earlier versions already in the
+/// git history stay readable there. returns how many notes were encrypted.
+#[tauri::command]
+pub async fn encrypt_folder(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    path: String,
+) -> Result<usize, String> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("encrypting folder: {path}");
+
+        let folder = path.trim_matches('/').to_string();
+        let vault_root = get_base_dir(&app_handle)?;
+        let folder_path = resolve_path(&app_handle, &folder)?;
+        // `..` would still pass resolve_path's prefix check
+        let is_plain = Path::new(&folder)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+        if folder.is_empty() || !is_plain || !folder_path.is_dir() {
+            return Err(format!("folder '{path}' does not exist"));
+        }
+        let key = get_key(&vault_root)?;
+
+        let mut config = load_config(&vault_root);
+        if !config.folders.contains(&folder) {
+            config.folders.push(folder.clone());
+            config.folders.sort();
+            save_config(&vault_root, &config).await?;
+        }
+
+        let files = plaintext_files(&vault_root, &folder);
+        for file in &files {
+            let file_path = vault_root.join(file);
+            let content = fs::read(&file_path)
+                .await
+                .map_err(|e| format!("failed to read {file}: {e}"))?;
+            write_atomic(&file_path, &encrypt(&key, &content)?, false)
+                .await
+                .map_err(|e| format!("failed to write {file}: {e}"))?;
+            fs_cache::invalidate_file(&vault_root, file);
+            journal::record(
+                &vault_root,
+                JournalOp::Update,
+                file,
+                None,
+                JournalOrigin::User,
+            );
+        }
+
+        nb::reconcile_and_checkpoint(&app_handle, format!("[nb] Encrypt: {folder}"));
+
+        log::info!("encrypted {} notes in {folder}", files.len());
+        Ok(files.len())
+    })
+    .await
+}
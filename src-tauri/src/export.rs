@@ -0,0 +1,808 @@
+use std::io::{BufWriter, Cursor, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, WebviewWindow};
+use tokio::fs;
+use zip::{write::SimpleFileOptions, CompressionMethod, DateTime, ZipWriter};
+
+use crate::{
+    assets::detect_mime_type,
+    constants::{TRASH_DIR_NAME, VAULT_CONFIG_DIR_NAME, VAULT_EXPORT_PROGRESS_EVENT},
+    frontmatter, preflight,
+    utils::{get_base_dir, resolve_path},
+    vaults,
+};
+
+// -----------------------------------------
+// docx
+// -----------------------------------------
+
+/// EMUs (the unit of drawing sizes in office documents) per pixel at 96 dpi
+const EMU_PER_PIXEL: u64 = 9525;
+
+/// widest an embedded image may be: the text width of a letter page with 1in margins
+const MAX_IMAGE_WIDTH_EMU: u64 = 6 * 914_400;
+
+/// size used when an image's dimensions can't be read
+const DEFAULT_IMAGE_SIZE_PX: (u64, u64) = (640, 480);
+
+/// numbering definition shared by every bullet list (see `DocxWriter::numbering_xml`)
+const BULLET_NUM_ID: u32 = 1;
+
+const CONTENT_TYPES_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
+<Default Extension="xml" ContentType="application/xml"/>
+<Default Extension="png" ContentType="image/png"/>
+<Default Extension="jpeg" ContentType="image/jpeg"/>
+<Default Extension="gif" ContentType="image/gif"/>
+<Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/>
+<Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/>
+<Override PartName="/word/numbering.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.numbering+xml"/>
+</Types>"#;
+
+const PACKAGE_RELS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/>
+</Relationships>"#;
+
+const STYLES_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+<w:docDefaults><w:rPrDefault><w:rPr><w:rFonts w:ascii="Calibri" w:hAnsi="Calibri" w:eastAsia="Calibri" w:cs="Calibri"/><w:sz w:val="22"/></w:rPr></w:rPrDefault><w:pPrDefault><w:pPr><w:spacing w:after="160" w:line="276" w:lineRule="auto"/></w:pPr></w:pPrDefault></w:docDefaults>
+<w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/></w:style>
+<w:style w:type="paragraph" w:styleId="Heading1"><w:name w:val="heading 1"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:pPr><w:keepNext/><w:spacing w:before="360" w:after="120"/><w:outlineLvl w:val="0"/></w:pPr><w:rPr><w:b/><w:sz w:val="36"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading2"><w:name w:val="heading 2"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:pPr><w:keepNext/><w:spacing w:before="320" w:after="120"/><w:outlineLvl w:val="1"/></w:pPr><w:rPr><w:b/><w:sz w:val="30"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading3"><w:name w:val="heading 3"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:pPr><w:keepNext/><w:spacing w:before="280" w:after="80"/><w:outlineLvl w:val="2"/></w:pPr><w:rPr><w:b/><w:sz w:val="26"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading4"><w:name w:val="heading 4"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:pPr><w:keepNext/><w:spacing w:before="240" w:after="80"/><w:outlineLvl w:val="3"/></w:pPr><w:rPr><w:b/><w:i/><w:sz w:val="24"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading5"><w:name w:val="heading 5"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:pPr><w:keepNext/><w:outlineLvl w:val="4"/></w:pPr><w:rPr><w:b/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading6"><w:name w:val="heading 6"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:pPr><w:keepNext/><w:outlineLvl w:val="5"/></w:pPr><w:rPr><w:i/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Quote"><w:name w:val="Quote"/><w:basedOn w:val="Normal"/><w:pPr><w:ind w:left="720"/><w:pBdr><w:left w:val="single" w:sz="12" w:space="8" w:color="BFBFBF"/></w:pBdr></w:pPr><w:rPr><w:color w:val="595959"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Code"><w:name w:val="Code"/><w:basedOn w:val="Normal"/><w:pPr><w:spacing w:after="0" w:line="240" w:lineRule="auto"/><w:shd w:val="clear" w:color="auto" w:fill="F2F2F2"/></w:pPr><w:rPr><w:rFonts w:ascii="Menlo" w:hAnsi="Menlo" w:cs="Menlo"/><w:sz w:val="19"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="ListParagraph"><w:name w:val="List Paragraph"/><w:basedOn w:val="Normal"/><w:pPr><w:spacing w:after="60"/></w:pPr></w:style>
+<w:style w:type="character" w:styleId="InlineCode"><w:name w:val="Inline Code"/><w:rPr><w:rFonts w:ascii="Menlo" w:hAnsi="Menlo" w:cs="Menlo"/><w:sz w:val="19"/><w:shd w:val="clear" w:color="auto" w:fill="F2F2F2"/></w:rPr></w:style>
+<w:style w:type="character" w:styleId="Hyperlink"><w:name w:val="Hyperlink"/><w:rPr><w:color w:val="0563C1"/><w:u w:val="single"/></w:rPr></w:style>
+<w:style w:type="table" w:styleId="TableGrid"><w:name w:val="Table Grid"/><w:tblPr><w:tblBorders><w:top w:val="single" w:sz="4" w:color="BFBFBF"/><w:left w:val="single" w:sz="4" w:color="BFBFBF"/><w:bottom w:val="single" w:sz="4" w:color="BFBFBF"/><w:right w:val="single" w:sz="4" w:color="BFBFBF"/><w:insideH w:val="single" w:sz="4" w:color="BFBFBF"/><w:insideV w:val="single" w:sz="4" w:color="BFBFBF"/></w:tblBorders><w:tblCellMar><w:left w:w="100" w:type="dxa"/><w:right w:w="100" w:type="dxa"/></w:tblCellMar></w:tblPr></w:style>
+</w:styles>"#;
+
+/// xml escaping for text and attribute values
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// pixel size of a png, gif or jpeg, from its header
+fn image_size(bytes: &[u8]) -> Option<(u64, u64)> {
+    let be16 = |at: usize| -> Option<u64> {
+        Some(u16::from_be_bytes([*bytes.get(at)?, *bytes.get(at + 1)?]) as u64)
+    };
+    if bytes.starts_with(b"\x89PNG") {
+        let be32 = |at: usize| -> Option<u64> {
+            Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?) as u64)
+        };
+        return Some((be32(16)?, be32(20)?));
+    }
+    if bytes.starts_with(b"GIF8") {
+        let le16 = |at: usize| -> Option<u64> {
+            Some(u16::from_le_bytes([*bytes.get(at)?, *bytes.get(at + 1)?]) as u64)
+        };
+        return Some((le16(6)?, le16(8)?));
+    }
+    if bytes.starts_with(b"\xff\xd8") {
+        // walk the segments up to the first start-of-frame marker
+        let mut at = 2;
+        while at + 9 < bytes.len() {
+            if bytes[at] != 0xff {
+                return None;
+            }
+            let marker = bytes[at + 1];
+            let is_frame = matches!(marker, 0xc0..=0xcf) && !matches!(marker, 0xc4 | 0xc8 | 0xcc);
+            if is_frame {
+                return Some((be16(at + 7)?, be16(at + 5)?));
+            }
+            at += 2 + be16(at + 2)? as usize;
+        }
+    }
+    None
+}
+
+/// an image embedded in the document as `word/media/<name>`
+struct Media {
+    name: String,
+    bytes: Vec<u8>,
+}
+
+/// an open paragraph collecting runs
+struct Paragraph {
+    properties: String,
+    runs: String,
+}
+
+/// turns pulldown-cmark events into WordprocessingML
+struct DocxWriter<'a> {
+    vault_root: &'a Path,
+    /// vault-relative directory of the note, for resolving image links
+    note_dir: PathBuf,
+    body: String,
+    paragraph: Option<Paragraph>,
+    /// hyperlinks and images as (type, target, external), `rId<n + 1>` in order
+    relationships: Vec<(String, String, bool)>,
+    media: Vec<Media>,
+    /// numbering ids of the lists we are in, `None` for bullet lists
+    lists: Vec<Option<u32>>,
+    /// ordered lists each get their own numbering instance so they restart
+    ordered_lists: Vec<u64>,
+    /// whether the next paragraph is the first of a list item (and gets the marker)
+    item_start: bool,
+    quote_depth: usize,
+    heading: Option<HeadingLevel>,
+    code_block: Option<String>,
+    bold: usize,
+    italic: usize,
+    strike: usize,
+    link: Option<String>,
+    in_image: bool,
+    in_table_head: bool,
+}
+
+impl<'a> DocxWriter<'a> {
+    fn new(vault_root: &'a Path, note_path: &str) -> Self {
+        Self {
+            vault_root,
+            note_dir: Path::new(note_path)
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+            body: String::new(),
+            paragraph: None,
+            relationships: Vec::new(),
+            media: Vec::new(),
+            lists: Vec::new(),
+            ordered_lists: Vec::new(),
+            item_start: false,
+            quote_depth: 0,
+            heading: None,
+            code_block: None,
+            bold: 0,
+            italic: 0,
+            strike: 0,
+            link: None,
+            in_image: false,
+            in_table_head: false,
+        }
+    }
+
+    fn add_relationship(&mut self, kind: &str, target: String, external: bool) -> String {
+        self.relationships
+            .push((kind.to_string(), target, external));
+        format!("rId{}", self.relationships.len())
+    }
+
+    /// paragraph properties for the current block context
+    fn paragraph_properties(&mut self) -> String {
+        if let Some(level) = self.heading {
+            return format!(r#"<w:pStyle w:val="Heading{}"/>"#, level as usize);
+        }
+        if let Some(&list) = self.lists.last() {
+            let level = self.lists.len() - 1;
+            if std::mem::take(&mut self.item_start) {
+                return format!(
+                    r#"<w:pStyle w:val="ListParagraph"/><w:numPr><w:ilvl w:val="{level}"/><w:numId w:val="{}"/></w:numPr>"#,
+                    list.unwrap_or(BULLET_NUM_ID)
+                );
+            }
+            return format!(
+                r#"<w:pStyle w:val="ListParagraph"/><w:ind w:left="{}"/>"#,
+                720 * (level + 1)
+            );
+        }
+        if self.quote_depth > 0 {
+            return r#"<w:pStyle w:val="Quote"/>"#.to_string();
+        }
+        String::new()
+    }
+
+    fn open_paragraph(&mut self) {
+        if self.paragraph.is_none() {
+            let properties = self.paragraph_properties();
+            self.paragraph = Some(Paragraph {
+                properties,
+                runs: String::new(),
+            });
+        }
+    }
+
+    fn close_paragraph(&mut self) {
+        if let Some(paragraph) = self.paragraph.take() {
+            self.body.push_str("<w:p>");
+            if !paragraph.properties.is_empty() {
+                self.body.push_str("<w:pPr>");
+                self.body.push_str(&paragraph.properties);
+                self.body.push_str("</w:pPr>");
+            }
+            self.body.push_str(&paragraph.runs);
+            self.body.push_str("</w:p>");
+        }
+    }
+
+    fn push_run(&mut self, text: &str, code: bool) {
+        self.open_paragraph();
+        let mut properties = String::new();
+        if code {
+            properties.push_str(r#"<w:rStyle w:val="InlineCode"/>"#);
+        } else if self.link.is_some() {
+            properties.push_str(r#"<w:rStyle w:val="Hyperlink"/>"#);
+        }
+        if self.bold > 0 || self.in_table_head {
+            properties.push_str("<w:b/>");
+        }
+        if self.italic > 0 {
+            properties.push_str("<w:i/>");
+        }
+        if self.strike > 0 {
+            properties.push_str("<w:strike/>");
+        }
+
+        let mut run = String::from("<w:r>");
+        if !properties.is_empty() {
+            run.push_str(&format!("<w:rPr>{properties}</w:rPr>"));
+        }
+        run.push_str(&format!(
+            r#"<w:t xml:space="preserve">{}</w:t></w:r>"#,
+            escape_xml(text)
+        ));
+
+        let paragraph = self.paragraph.as_mut().expect("paragraph was opened");
+        match &self.link {
+            Some(id) => paragraph
+                .runs
+                .push_str(&format!(r#"<w:hyperlink r:id="{id}">{run}</w:hyperlink>"#)),
+            None => paragraph.runs.push_str(&run),
+        }
+    }
+
+    fn push_break(&mut self) {
+        self.open_paragraph();
+        if let Some(paragraph) = self.paragraph.as_mut() {
+            paragraph.runs.push_str("<w:r><w:br/></w:r>");
+        }
+    }
+
+    fn push_code_block(&mut self, code: &str) {
+        self.close_paragraph();
+        for line in code.strip_suffix('\n').unwrap_or(code).split('\n') {
+            self.body.push_str(&format!(
+                r#"<w:p><w:pPr><w:pStyle w:val="Code"/></w:pPr><w:r><w:t xml:space="preserve">{}</w:t></w:r></w:p>"#,
+                escape_xml(line)
+            ));
+        }
+    }
+
+    /// embeds an image from the vault. remote or missing images become their alt text.
+    fn push_image(&mut self, url: &str, title: &str) {
+        let decoded = url.replace("%20", " ");
+        let path = self.note_dir.join(decoded.trim_start_matches("./"));
+        let full_path = self.vault_root.join(&path);
+        let bytes = (!url.contains("://") && full_path.starts_with(self.vault_root))
+            .then(|| std::fs::read(&full_path).ok())
+            .flatten();
+        let Some(bytes) = bytes else {
+            self.push_run(&format!("[image: {url}]"), false);
+            return;
+        };
+        let extension = match detect_mime_type(&path.to_string_lossy(), &bytes) {
+            "image/png" => "png",
+            "image/jpeg" => "jpeg",
+            "image/gif" => "gif",
+            _ => {
+                self.push_run(&format!("[image: {url}]"), false);
+                return;
+            }
+        };
+
+        let (width_px, height_px) = image_size(&bytes).unwrap_or(DEFAULT_IMAGE_SIZE_PX);
+        let mut width = width_px.max(1) * EMU_PER_PIXEL;
+        let mut height = height_px.max(1) * EMU_PER_PIXEL;
+        if width > MAX_IMAGE_WIDTH_EMU {
+            height = height * MAX_IMAGE_WIDTH_EMU / width;
+            width = MAX_IMAGE_WIDTH_EMU;
+        }
+
+        let index = self.media.len() + 1;
+        let name = format!("image{index}.{extension}");
+        let id = self.add_relationship("image", format!("media/{name}"), false);
+        self.media.push(Media { name, bytes });
+
+        let description = escape_xml(title);
+        self.open_paragraph();
+        if let Some(paragraph) = self.paragraph.as_mut() {
+            paragraph.runs.push_str(&format!(
+                r#"<w:r><w:drawing><wp:inline><wp:extent cx="{width}" cy="{height}"/><wp:docPr id="{index}" name="Picture {index}" descr="{description}"/><a:graphic xmlns:a="http://schemas.openxmlformats.org/drawingml/2006/main"><a:graphicData uri="http://schemas.openxmlformats.org/drawingml/2006/picture"><pic:pic xmlns:pic="http://schemas.openxmlformats.org/drawingml/2006/picture"><pic:nvPicPr><pic:cNvPr id="{index}" name="Picture {index}"/><pic:cNvPicPr/></pic:nvPicPr><pic:blipFill><a:blip r:embed="{id}"/><a:stretch><a:fillRect/></a:stretch></pic:blipFill><pic:spPr><a:xfrm><a:off x="0" y="0"/><a:ext cx="{width}" cy="{height}"/></a:xfrm><a:prstGeom prst="rect"><a:avLst/></a:prstGeom></pic:spPr></pic:pic></a:graphicData></a:graphic></wp:inline></w:drawing></w:r>"#
+            ));
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph => self.open_paragraph(),
+            Tag::Heading { level, .. } => {
+                self.close_paragraph();
+                self.heading = Some(level);
+                self.open_paragraph();
+            }
+            Tag::BlockQuote(_) => {
+                self.close_paragraph();
+                self.quote_depth += 1;
+            }
+            Tag::CodeBlock(_) => {
+                self.close_paragraph();
+                self.code_block = Some(String::new());
+            }
+            Tag::List(start) => {
+                self.close_paragraph();
+                let list = start.map(|start| {
+                    self.ordered_lists.push(start);
+                    BULLET_NUM_ID + self.ordered_lists.len() as u32
+                });
+                self.lists.push(list);
+            }
+            Tag::Item => {
+                self.close_paragraph();
+                self.item_start = true;
+            }
+            Tag::Table(_) => {
+                self.close_paragraph();
+                self.body.push_str(
+                    r#"<w:tbl><w:tblPr><w:tblStyle w:val="TableGrid"/><w:tblW w:w="5000" w:type="pct"/></w:tblPr>"#,
+                );
+            }
+            Tag::TableHead => {
+                self.in_table_head = true;
+                self.body.push_str("<w:tr>");
+            }
+            Tag::TableRow => self.body.push_str("<w:tr>"),
+            Tag::TableCell => {
+                self.body.push_str("<w:tc>");
+                self.open_paragraph();
+            }
+            Tag::Emphasis => self.italic += 1,
+            Tag::Strong => self.bold += 1,
+            Tag::Strikethrough => self.strike += 1,
+            Tag::Link { dest_url, .. } => {
+                if dest_url.contains("://") || dest_url.starts_with("mailto:") {
+                    let id = self.add_relationship("hyperlink", dest_url.to_string(), true);
+                    self.link = Some(id);
+                }
+            }
+            Tag::Image {
+                dest_url, title, ..
+            } => {
+                self.push_image(&dest_url, &title);
+                self.in_image = true;
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph => self.close_paragraph(),
+            TagEnd::Heading(_) => {
+                self.close_paragraph();
+                self.heading = None;
+            }
+            TagEnd::BlockQuote(_) => {
+                self.close_paragraph();
+                self.quote_depth = self.quote_depth.saturating_sub(1);
+            }
+            TagEnd::CodeBlock => {
+                if let Some(code) = self.code_block.take() {
+                    self.push_code_block(&code);
+                }
+            }
+            TagEnd::List(_) => {
+                self.close_paragraph();
+                self.lists.pop();
+            }
+            TagEnd::Item => self.close_paragraph(),
+            TagEnd::Table => self.body.push_str("</w:tbl>"),
+            TagEnd::TableHead => {
+                self.in_table_head = false;
+                self.body.push_str("</w:tr>");
+            }
+            TagEnd::TableRow => self.body.push_str("</w:tr>"),
+            TagEnd::TableCell => {
+                // every cell needs a paragraph, even an empty one
+                self.open_paragraph();
+                self.close_paragraph();
+                self.body.push_str("</w:tc>");
+            }
+            TagEnd::Emphasis => self.italic = self.italic.saturating_sub(1),
+            TagEnd::Strong => self.bold = self.bold.saturating_sub(1),
+            TagEnd::Strikethrough => self.strike = self.strike.saturating_sub(1),
+            TagEnd::Link => self.link = None,
+            TagEnd::Image => self.in_image = false,
+            _ => {}
+        }
+    }
+
+    fn write_event(&mut self, event: Event) {
+        if let Some(code) = self.code_block.as_mut() {
+            if let Event::Text(text) = &event {
+                code.push_str(text);
+                return;
+            }
+        }
+        if self.in_image && !matches!(event, Event::End(TagEnd::Image)) {
+            // alt text is carried by the drawing
+            return;
+        }
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => self.push_run(&text, false),
+            Event::Code(code) => self.push_run(&code, true),
+            Event::InlineHtml(html) => self.push_run(&html, false),
+            Event::SoftBreak => self.push_run(" ", false),
+            Event::HardBreak => self.push_break(),
+            Event::Rule => {
+                self.close_paragraph();
+                self.body.push_str(
+                    r#"<w:p><w:pPr><w:pBdr><w:bottom w:val="single" w:sz="6" w:space="1" w:color="BFBFBF"/></w:pBdr></w:pPr></w:p>"#,
+                );
+            }
+            Event::TaskListMarker(checked) => {
+                self.push_run(if checked { "\u{2611} " } else { "\u{2610} " }, false)
+            }
+            _ => {}
+        }
+    }
+
+    fn document_xml(&self) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:document xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" xmlns:wp="http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing"><w:body>{}<w:sectPr><w:pgSz w:w="12240" w:h="15840"/><w:pgMar w:top="1440" w:right="1440" w:bottom="1440" w:left="1440" w:header="720" w:footer="720" w:gutter="0"/></w:sectPr></w:body></w:document>"#,
+            self.body
+        )
+    }
+
+    fn document_rels_xml(&self) -> String {
+        let mut xml = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">"#,
+        );
+        for (index, (kind, target, external)) in self.relationships.iter().enumerate() {
+            xml.push_str(&format!(
+                r#"<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/{kind}" Target="{}"{}/>"#,
+                index + 1,
+                escape_xml(target),
+                if *external { r#" TargetMode="External""# } else { "" }
+            ));
+        }
+        let next = self.relationships.len();
+        xml.push_str(&format!(
+            r#"<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/><Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/numbering" Target="numbering.xml"/></Relationships>"#,
+            next + 1,
+            next + 2
+        ));
+        xml
+    }
+
+    /// a bullet definition plus one numbering instance per ordered list
+    fn numbering_xml(&self) -> String {
+        let levels = |format: &str, text: fn(usize) -> String| -> String {
+            (0..9)
+                .map(|level| {
+                    format!(
+                        r#"<w:lvl w:ilvl="{level}"><w:start w:val="1"/><w:numFmt w:val="{format}"/><w:lvlText w:val="{}"/><w:lvlJc w:val="left"/><w:pPr><w:ind w:left="{}" w:hanging="360"/></w:pPr></w:lvl>"#,
+                        text(level),
+                        720 * (level + 1)
+                    )
+                })
+                .collect()
+        };
+        let bullets = levels("bullet", |level| {
+            ["\u{2022}", "\u{25e6}", "\u{25aa}"][level % 3].to_string()
+        });
+        let decimals = levels("decimal", |level| format!("%{}.", level + 1));
+
+        let mut xml = format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:numbering xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main"><w:abstractNum w:abstractNumId="0">{bullets}</w:abstractNum><w:abstractNum w:abstractNumId="1">{decimals}</w:abstractNum><w:num w:numId="{BULLET_NUM_ID}"><w:abstractNumId w:val="0"/></w:num>"#
+        );
+        for (index, start) in self.ordered_lists.iter().enumerate() {
+            xml.push_str(&format!(
+                r#"<w:num w:numId="{}"><w:abstractNumId w:val="1"/><w:lvlOverride w:ilvl="0"><w:startOverride w:val="{start}"/></w:lvlOverride></w:num>"#,
+                BULLET_NUM_ID + index as u32 + 1
+            ));
+        }
+        xml.push_str("</w:numbering>");
+        xml
+    }
+}
+
+/// converts a note to a .docx file's bytes. images are embedded from the vault.
+fn markdown_to_docx(vault_root: &Path, note_path: &str, content: &str) -> Result<Vec<u8>, String> {
+    let body = frontmatter::split(content).body;
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+
+    let mut writer = DocxWriter::new(vault_root, note_path);
+    for event in Parser::new_ext(body, options) {
+        writer.write_event(event);
+    }
+    writer.close_paragraph();
+
+    let to_error = |e: zip::result::ZipError| format!("failed to write docx: {e}");
+    let io_error = |e: std::io::Error| format!("failed to write docx: {e}");
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+
+    let parts = [
+        ("[Content_Types].xml", CONTENT_TYPES_XML.to_string()),
+        ("_rels/.rels", PACKAGE_RELS_XML.to_string()),
+        ("word/document.xml", writer.document_xml()),
+        ("word/_rels/document.xml.rels", writer.document_rels_xml()),
+        ("word/styles.xml", STYLES_XML.to_string()),
+        ("word/numbering.xml", writer.numbering_xml()),
+    ];
+    for (name, xml) in parts {
+        zip.start_file(name, options).map_err(to_error)?;
+        zip.write_all(xml.as_bytes()).map_err(io_error)?;
+    }
+    for media in &writer.media {
+        zip.start_file(format!("word/media/{}", media.name), options)
+            .map_err(to_error)?;
+        zip.write_all(&media.bytes).map_err(io_error)?;
+    }
+
+    Ok(zip.finish().map_err(to_error)?.into_inner())
+}
+
+// -----------------------------------------
+// vault zip
+// -----------------------------------------
+
+/// minimum time between two progress events of a vault export
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// emitted to the exporting window while `export_vault_zip` runs
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportProgress {
+    pub files_written: usize,
+    pub total_files: usize,
+    pub bytes_written: u64,
+    /// the file being written, `None` once the archive is complete
+    pub current_path: Option<String>,
+}
+
+/// vault-relative paths of every file and folder to archive, folders first.
+/// the vault trash is never included, the git history only when asked for.
+fn collect_vault_entries(
+    vault_root: &Path,
+    include_history: bool,
+) -> Result<(Vec<String>, Vec<String>), String> {
+    let mut dirs = Vec::new();
+    let mut files = Vec::new();
+    let mut pending = vec![String::new()];
+    while let Some(dir) = pending.pop() {
+        let entries = std::fs::read_dir(vault_root.join(&dir))
+            .map_err(|e| format!("failed to read '{dir}': {e}"))?;
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let path = if dir.is_empty() {
+                name.clone()
+            } else {
+                format!("{dir}/{name}")
+            };
+            if path == ".git" && !include_history {
+                continue;
+            }
+            if path == format!("{VAULT_CONFIG_DIR_NAME}/{TRASH_DIR_NAME}") {
+                continue;
+            }
+            // symlinks are skipped so the archive can't pull in files outside the vault
+            match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() => {
+                    dirs.push(path.clone());
+                    pending.push(path);
+                }
+                Ok(file_type) if file_type.is_file() => files.push(path),
+                _ => {}
+            }
+        }
+    }
+    dirs.sort();
+    files.sort();
+    Ok((dirs, files))
+}
+
+/// zip timestamp of a file's modification time (zip can't store dates before 1980)
+fn zip_time(metadata: &std::fs::Metadata) -> Option<DateTime> {
+    use chrono::{Datelike, Timelike};
+
+    let modified: chrono::DateTime<chrono::Local> = metadata.modified().ok()?.into();
+    DateTime::from_date_and_time(
+        modified.year().try_into().ok()?,
+        modified.month() as u8,
+        modified.day() as u8,
+        modified.hour() as u8,
+        modified.minute() as u8,
+        modified.second() as u8,
+    )
+    .ok()
+}
+
+/// total size of the files `write_vault_zip` would pack, an upper bound for
+/// the archive since they are only ever compressed
+fn vault_zip_size(vault_root: &Path, include_history: bool) -> Result<u64, String> {
+    let (_, files) = collect_vault_entries(vault_root, include_history)?;
+    Ok(files
+        .iter()
+        .filter_map(|path| std::fs::metadata(vault_root.join(path)).ok())
+        .map(|metadata| metadata.len())
+        .sum())
+}
+
+/// streams the vault into a zip file at `output`, one file at a time
+fn write_vault_zip(
+    vault_root: &Path,
+    output: &Path,
+    include_history: bool,
+    on_progress: impl Fn(ExportProgress),
+) -> Result<ExportProgress, String> {
+    let (dirs, files) = collect_vault_entries(vault_root, include_history)?;
+    let to_error = |e: zip::result::ZipError| format!("failed to write archive: {e}");
+
+    let file = std::fs::File::create(output)
+        .map_err(|e| format!("failed to create {}: {e}", output.display()))?;
+    let mut zip = ZipWriter::new(BufWriter::new(file));
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    for dir in &dirs {
+        zip.add_directory(dir.as_str(), options).map_err(to_error)?;
+    }
+
+    let mut progress = ExportProgress {
+        files_written: 0,
+        total_files: files.len(),
+        bytes_written: 0,
+        current_path: None,
+    };
+    let mut last_progress = Instant::now();
+    for path in files {
+        let full_path = vault_root.join(&path);
+        let mut source = match std::fs::File::open(&full_path) {
+            Ok(source) => source,
+            Err(e) => {
+                // files can disappear while the vault is in use
+                log::warn!("skipping {path} in vault export: {e}");
+                continue;
+            }
+        };
+        let metadata = source
+            .metadata()
+            .map_err(|e| format!("failed to read '{path}': {e}"))?;
+        let mut file_options = options.large_file(metadata.len() > u32::MAX as u64);
+        if let Some(time) = zip_time(&metadata) {
+            file_options = file_options.last_modified_time(time);
+        }
+
+        zip.start_file(path.as_str(), file_options)
+            .map_err(to_error)?;
+        progress.bytes_written += std::io::copy(&mut source, &mut zip)
+            .map_err(|e| format!("failed to write '{path}' to archive: {e}"))?;
+        progress.files_written += 1;
+
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            last_progress = Instant::now();
+            on_progress(ExportProgress {
+                current_path: Some(path),
+                ..progress.clone()
+            });
+        }
+    }
+
+    zip.finish()
+        .map_err(to_error)?
+        .flush()
+        .map_err(|e| format!("failed to write archive: {e}"))?;
+    on_progress(progress.clone());
+    Ok(progress)
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+/// exports a note as a Word document to `output_path` (an absolute path,
+/// usually picked in a save dialog)
+#[tauri::command]
+pub async fn export_note_docx(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    path: String,
+    output_path: String,
+) -> Result<(), String> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("exporting {path} to docx: {output_path}");
+
+        let output = PathBuf::from(&output_path);
+        if !output.is_absolute() {
+            return Err(format!("output path '{output_path}' must be absolute"));
+        }
+        let content = fs::read_to_string(resolve_path(&app_handle, &path)?)
+            .await
+            .map_err(|e| format!("failed to read file {path}: {e}"))?;
+
+        let vault_root = get_base_dir(&app_handle)?;
+        let bytes =
+            tokio::task::spawn_blocking(move || markdown_to_docx(&vault_root, &path, &content))
+                .await
+                .map_err(|e| format!("failed to export docx: {e}"))??;
+        preflight::check(&output, bytes.len() as u64).await?;
+
+        fs::write(&output, bytes)
+            .await
+            .map_err(|e| format!("failed to write {output_path}: {e}"))?;
+
+        log::info!("exported docx: {output_path}");
+        Ok(())
+    })
+    .await
+}
+
+/// packages the whole vault into a zip at `output_path` (absolute), optionally
+/// with its git history. progress is emitted to the calling window.
+#[tauri::command]
+pub async fn export_vault_zip(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    output_path: String,
+    include_history: Option<bool>,
+) -> Result<ExportProgress, String> {
+    let include_history = include_history.unwrap_or(false);
+    let window = webview_window.clone();
+    vaults::scoped(&webview_window, async move {
+        log::info!("exporting vault to {output_path} (history: {include_history})");
+
+        let output = PathBuf::from(&output_path);
+        if !output.is_absolute() {
+            return Err(format!("output path '{output_path}' must be absolute"));
+        }
+        let vault_root = get_base_dir(&app_handle)?;
+        if output.starts_with(&vault_root) {
+            return Err("the archive can't be written inside the vault".to_string());
+        }
+
+        let root = vault_root.clone();
+        let estimated_bytes =
+            tokio::task::spawn_blocking(move || vault_zip_size(&root, include_history))
+                .await
+                .map_err(|e| format!("failed to export vault: {e}"))??;
+        preflight::check(&output, estimated_bytes).await?;
+
+        let progress = tokio::task::spawn_blocking(move || {
+            write_vault_zip(&vault_root, &output, include_history, |progress| {
+                if let Err(e) = window.emit(VAULT_EXPORT_PROGRESS_EVENT, progress) {
+                    log::error!("failed to emit export progress: {e}");
+                }
+            })
+        })
+        .await
+        .map_err(|e| format!("failed to export vault: {e}"))??;
+
+        log::info!(
+            "exported {} files ({} bytes) to {output_path}",
+            progress.files_written,
+            progress.bytes_written
+        );
+        Ok(progress)
+    })
+    .await
+}
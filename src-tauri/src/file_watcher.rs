@@ -1,15 +1,153 @@
-use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
-use serde::Serialize;
+use notify::{Config, Event, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
+use std::os::unix::fs::MetadataExt;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::mpsc::{self, RecvTimeoutError};
-use std::time::Duration;
-use tauri::{AppHandle, Emitter};
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, EventTarget, WebviewWindow};
 
-use crate::constants::FILE_WATCHER_EVENT;
-use crate::utils::get_base_dir;
+use crate::command::{fs_entry_from_metadata, FSEntry};
+use crate::constants::{
+    EXTERNAL_FILE_EVENT, FILE_WATCHER_EVENT, VAULT_IGNORE_FILE_NAME, WATCHER_DIRECTORY_DELTAS_KEY,
+    WATCHER_SETTINGS_KEY,
+};
+use crate::utils::{content_hash, get_base_dir, get_setting, set_setting};
+use crate::{
+    fs_cache, links, note_ids, note_index, plugins, search_index, spotlight, tags, vault_ignore,
+    vault_settings, vault_sync, vaults,
+};
 
-const DEBOUNCE_DURATION: Duration = Duration::from_millis(500);
+/// shortest debounce accepted, so a typo cannot turn every event into a flush
+const MIN_DEBOUNCE_MS: u64 = 50;
+
+/// shortest poll interval accepted in polling mode
+const MIN_POLL_INTERVAL_MS: u64 = 500;
+
+/// how the vault watchers batch and detect changes. stored in settings under
+/// `watcher-settings`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WatcherSettings {
+    /// events are batched until none arrive for this long
+    pub debounce_ms: u64,
+    /// scan for changes instead of relying on filesystem notifications, for
+    /// network filesystems that do not deliver them
+    pub use_polling: bool,
+    /// how often to scan in polling mode
+    pub poll_interval_ms: u64,
+    /// flush a batch once it holds this many paths, even if events keep coming
+    pub max_batch_size: usize,
+}
+
+impl Default for WatcherSettings {
+    fn default() -> Self {
+        Self {
+            debounce_ms: 500,
+            use_polling: false,
+            poll_interval_ms: 5_000,
+            max_batch_size: 1_000,
+        }
+    }
+}
+
+impl WatcherSettings {
+    fn normalized(self) -> Self {
+        Self {
+            debounce_ms: self.debounce_ms.max(MIN_DEBOUNCE_MS),
+            poll_interval_ms: self.poll_interval_ms.max(MIN_POLL_INTERVAL_MS),
+            max_batch_size: self.max_batch_size.max(1),
+            ..self
+        }
+    }
+
+    fn debounce(&self) -> Duration {
+        Duration::from_millis(self.debounce_ms)
+    }
+}
+
+/// the settings in effect, read by the watcher threads on every event
+static SETTINGS: RwLock<Option<WatcherSettings>> = RwLock::new(None);
+
+fn settings() -> WatcherSettings {
+    SETTINGS.read().unwrap().clone().unwrap_or_default()
+}
+
+fn load_settings(app_handle: &AppHandle) -> WatcherSettings {
+    let settings = get_setting::<WatcherSettings>(app_handle, WATCHER_SETTINGS_KEY)
+        .unwrap_or_default()
+        .normalized();
+    *SETTINGS.write().unwrap() = Some(settings.clone());
+    settings
+}
+
+/// how often an idle watcher checks whether it has been stopped
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// how long the app's own write is recognized when its events arrive
+const OWN_WRITE_TTL: Duration = Duration::from_secs(5);
+
+/// id of the watcher thread running for each open vault. a thread exits once
+/// its vault is gone from the map or mapped to a newer thread.
+static WATCHERS: Mutex<Option<HashMap<PathBuf, u64>>> = Mutex::new(None);
+static NEXT_WATCHER_ID: AtomicU64 = AtomicU64::new(0);
+
+/// a file the app just wrote, so its watcher events are not sent back to
+/// the frontend that made the change
+struct OwnWrite {
+    hash: String,
+    written_at: Instant,
+}
+
+/// recent writes by the app, keyed by absolute path
+static OWN_WRITES: Mutex<Option<HashMap<PathBuf, OwnWrite>>> = Mutex::new(None);
+
+/// remembers that the app wrote `content` to `path`. watcher events for the
+/// path still update the indexes but are not emitted while the file holds
+/// that content.
+pub fn record_own_write(path: &Path, content: &[u8]) {
+    let now = Instant::now();
+    let mut own_writes = OWN_WRITES.lock().unwrap();
+    let own_writes = own_writes.get_or_insert_with(HashMap::new);
+    own_writes.retain(|_, write| now.duration_since(write.written_at) < OWN_WRITE_TTL);
+    own_writes.insert(
+        path.to_path_buf(),
+        OwnWrite {
+            hash: content_hash(content),
+            written_at: now,
+        },
+    );
+}
+
+/// the vault-relative path of `path` if it still holds what the app wrote there
+fn own_write_path(base_path: &Path, path: &Path) -> Option<String> {
+    let hash = OWN_WRITES
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|own_writes| own_writes.get(path))
+        .filter(|write| write.written_at.elapsed() < OWN_WRITE_TTL)
+        .map(|write| write.hash.clone())?;
+    // a different hash means someone else changed the file since
+    let content = std::fs::read(path).ok()?;
+    if content_hash(&content) != hash {
+        return None;
+    }
+    path.strip_prefix(base_path)
+        .ok()
+        .map(|relative| relative.to_string_lossy().to_string())
+}
+
+fn is_current_watcher(watch_path: &Path, id: u64) -> bool {
+    WATCHERS
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|watchers| watchers.get(watch_path))
+        == Some(&id)
+}
 
 // --- public event structures ---
 
@@ -17,14 +155,71 @@ const DEBOUNCE_DURATION: Duration = Duration::from_millis(500);
 #[serde(rename_all = "camelCase")]
 pub struct FileChange {
     pub path: String,
-    pub kind: String, // "modify" | "delete"
+    pub kind: String, // "create" | "modify" | "delete"
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RenamedEntry {
+    pub from: String,
+    pub to: FSEntry,
+}
+
+/// computed changes to a directory's direct children (and the full contents of
+/// newly added subdirectories), so the frontend can patch its tree without re-listing
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectoryDelta {
+    pub path: String,
+    pub added: Vec<FSEntry>,
+    pub removed: Vec<String>,
+    pub renamed: Vec<RenamedEntry>,
+    pub updated: Vec<FSEntry>,
+}
+
+impl DirectoryDelta {
+    fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.renamed.is_empty()
+            && self.updated.is_empty()
+    }
 }
 
 #[derive(Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FileWatcherEvent {
     pub file_changes: Vec<FileChange>,
+    /// directories to re-list. with delta mode on, only directories whose
+    /// delta could not be computed are listed here.
     pub directory_changes: Vec<String>,
+    /// present only when delta mode is enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub directory_deltas: Option<Vec<DirectoryDelta>>,
+}
+
+impl FileWatcherEvent {
+    fn is_empty(&self) -> bool {
+        self.file_changes.is_empty()
+            && self.directory_changes.is_empty()
+            && self
+                .directory_deltas
+                .as_ref()
+                .is_none_or(|deltas| deltas.is_empty())
+    }
+
+    /// the event without modifications of `paths`
+    fn without_modified(mut self, paths: &HashSet<String>) -> Self {
+        self.file_changes
+            .retain(|change| change.kind != "modify" || !paths.contains(&change.path));
+        if let Some(deltas) = self.directory_deltas.as_mut() {
+            for delta in deltas.iter_mut() {
+                delta.updated.retain(|entry| !paths.contains(&entry.path));
+            }
+            deltas.retain(|delta| !delta.is_empty());
+        }
+        self
+    }
 }
 
 // --- internal state structures ---
@@ -42,6 +237,8 @@ struct EventAccumulator {
     files: HashMap<String, FileEventState>,
     /// directories from directory-level events (not file events)
     dir_events: HashSet<String>,
+    /// files whose events come from the app's own writes
+    own_writes: HashSet<String>,
 }
 
 impl EventAccumulator {
@@ -49,6 +246,10 @@ impl EventAccumulator {
         self.files.is_empty() && self.dir_events.is_empty()
     }
 
+    fn len(&self) -> usize {
+        self.files.len() + self.dir_events.len()
+    }
+
     fn add_file_event(&mut self, path: String, kind: &str) {
         let state = self.files.entry(path).or_default();
 
@@ -65,18 +266,28 @@ impl EventAccumulator {
         self.dir_events.insert(dir);
     }
 
-    fn collate(self) -> FileWatcherEvent {
+    fn collate(self, snapshots: Option<&mut DirectorySnapshots>) -> FileWatcherEvent {
         let (file_changes, file_dirs) = self.collate_file_events();
 
         // combine directories from file events and directory events
         let mut all_dirs: HashSet<String> = self.dir_events;
         all_dirs.extend(file_dirs);
 
-        let directory_changes = Self::dedupe_directories(all_dirs);
+        // in delta mode, only directories without a usable snapshot need a refresh
+        let (directory_deltas, refresh_dirs) = match snapshots {
+            Some(snapshots) => {
+                let (deltas, unresolved) = snapshots.compute_deltas(all_dirs);
+                (Some(deltas), unresolved)
+            }
+            None => (None, all_dirs),
+        };
+
+        let directory_changes = Self::dedupe_directories(refresh_dirs);
 
         FileWatcherEvent {
             file_changes,
             directory_changes,
+            directory_deltas,
         }
     }
 
@@ -95,7 +306,12 @@ impl EventAccumulator {
                     // born and died - no net effect
                 }
                 (false, true) => {
-                    // new file appeared - directory refresh only
+                    // new file appeared - file change (so it can be opened or
+                    // highlighted) + directory refresh
+                    file_changes.push(FileChange {
+                        path: path.clone(),
+                        kind: "create".to_string(),
+                    });
                     directories.insert(parent);
                 }
                 (true, false) => {
@@ -148,27 +364,222 @@ impl EventAccumulator {
     }
 }
 
+// --- directory snapshots (delta mode) ---
+
+struct SnapshotEntry {
+    entry: FSEntry,
+    inode: u64,
+}
+
+/// last known listing of every watched directory, keyed by relative dir path
+struct DirectorySnapshots {
+    base_path: PathBuf,
+    dirs: HashMap<String, HashMap<String, SnapshotEntry>>,
+}
+
+impl DirectorySnapshots {
+    /// snapshots the whole vault so the first change in any directory has a baseline
+    fn new(base_path: PathBuf) -> Self {
+        let mut snapshots = Self {
+            base_path,
+            dirs: HashMap::new(),
+        };
+        snapshots.snapshot_tree("");
+        log::info!(
+            "directory snapshots primed: {} directories",
+            snapshots.dirs.len()
+        );
+        snapshots
+    }
+
+    /// reads the direct children of a directory, with the same filters as list_dir
+    fn read_dir(&self, dir: &str) -> Option<HashMap<String, SnapshotEntry>> {
+        let entries = std::fs::read_dir(self.base_path.join(dir)).ok()?;
+        let mut children = HashMap::new();
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                continue;
+            }
+            let Ok(metadata) = std::fs::metadata(entry.path()) else {
+                continue;
+            };
+            if !metadata.is_dir() && !vault_settings::is_managed_file_in(&self.base_path, &name) {
+                continue;
+            }
+            let path = if dir.is_empty() {
+                name
+            } else {
+                format!("{dir}/{name}")
+            };
+            if vault_ignore::is_ignored_in(&self.base_path, &path, metadata.is_dir()) {
+                continue;
+            }
+            let inode = metadata.ino();
+            if let Ok(entry) = fs_entry_from_metadata(path.clone(), &metadata) {
+                children.insert(path, SnapshotEntry { entry, inode });
+            }
+        }
+        Some(children)
+    }
+
+    /// snapshots `dir` and all of its subdirectories, returning every entry found
+    fn snapshot_tree(&mut self, dir: &str) -> Vec<FSEntry> {
+        let Some(children) = self.read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut entries: Vec<FSEntry> = children.values().map(|c| c.entry.clone()).collect();
+        let subdirs: Vec<String> = children
+            .values()
+            .filter(|c| c.entry.is_dir)
+            .map(|c| c.entry.path.clone())
+            .collect();
+        self.dirs.insert(dir.to_string(), children);
+        for subdir in subdirs {
+            entries.extend(self.snapshot_tree(&subdir));
+        }
+        entries
+    }
+
+    fn forget_tree(&mut self, dir: &str) {
+        let prefix = format!("{dir}/");
+        self.dirs
+            .retain(|path, _| path != dir && !path.starts_with(&prefix));
+    }
+
+    /// returns (deltas, directories that still need a full refresh)
+    fn compute_deltas(&mut self, dirs: HashSet<String>) -> (Vec<DirectoryDelta>, HashSet<String>) {
+        let mut sorted: Vec<String> = dirs.into_iter().collect();
+        sorted.sort_by_key(|d| d.len());
+
+        let mut deltas = Vec::new();
+        let mut unresolved = HashSet::new();
+
+        for dir in sorted {
+            let Some(current) = self.read_dir(&dir) else {
+                // directory is gone; its parent's delta reports the removal
+                self.forget_tree(&dir);
+                continue;
+            };
+            let Some(previous) = self.dirs.remove(&dir) else {
+                // no baseline to diff against
+                self.dirs.insert(dir.clone(), current);
+                unresolved.insert(dir);
+                continue;
+            };
+
+            let mut delta = DirectoryDelta {
+                path: dir.clone(),
+                added: Vec::new(),
+                removed: Vec::new(),
+                renamed: Vec::new(),
+                updated: Vec::new(),
+            };
+
+            let mut removed: Vec<&SnapshotEntry> = previous
+                .iter()
+                .filter(|(path, _)| !current.contains_key(*path))
+                .map(|(_, entry)| entry)
+                .collect();
+
+            for (path, entry) in &current {
+                match previous.get(path) {
+                    Some(old) => {
+                        if old.entry.size_bytes != entry.entry.size_bytes
+                            || old.entry.modified_time_ms != entry.entry.modified_time_ms
+                        {
+                            delta.updated.push(entry.entry.clone());
+                        }
+                    }
+                    None => {
+                        // same inode under a new name means the entry was renamed in place
+                        let renamed_from = removed.iter().position(|old| {
+                            old.inode == entry.inode && old.entry.is_dir == entry.entry.is_dir
+                        });
+                        if let Some(index) = renamed_from {
+                            let old = removed.swap_remove(index);
+                            delta.renamed.push(RenamedEntry {
+                                from: old.entry.path.clone(),
+                                to: entry.entry.clone(),
+                            });
+                            if entry.entry.is_dir {
+                                self.forget_tree(&old.entry.path);
+                                self.snapshot_tree(&entry.entry.path);
+                            }
+                        } else {
+                            delta.added.push(entry.entry.clone());
+                            if entry.entry.is_dir {
+                                delta.added.extend(self.snapshot_tree(&entry.entry.path));
+                            }
+                        }
+                    }
+                }
+            }
+
+            for old in removed {
+                delta.removed.push(old.entry.path.clone());
+                if old.entry.is_dir {
+                    self.forget_tree(&old.entry.path);
+                }
+            }
+
+            self.dirs.insert(dir, current);
+            if !delta.is_empty() {
+                deltas.push(delta);
+            }
+        }
+
+        (deltas, unresolved)
+    }
+}
+
 // --- watcher implementation ---
 
+/// watches every open vault (the active one and those pinned to a window),
+/// starting watchers for newly opened vaults and stopping the rest
 pub fn init_file_watcher(app_handle: AppHandle) {
-    let watch_path = match get_base_dir(&app_handle) {
-        Ok(path) => path,
-        Err(e) => {
-            log::error!("failed to get base directory for file watcher: {e}");
-            return;
+    load_settings(&app_handle);
+    let open_vaults = vaults::open_vault_dirs();
+    let open_vaults = if open_vaults.is_empty() {
+        match get_base_dir(&app_handle) {
+            Ok(path) => vec![path],
+            Err(e) => {
+                log::error!("failed to get base directory for file watcher: {e}");
+                return;
+            }
         }
+    } else {
+        open_vaults
     };
 
-    if !watch_path.exists() {
-        log::warn!("watch path does not exist: {:?}", watch_path);
-        return;
-    }
+    let mut started = Vec::new();
+    {
+        let mut watchers = WATCHERS.lock().unwrap();
+        let watchers = watchers.get_or_insert_with(HashMap::new);
+        watchers.retain(|path, _| open_vaults.contains(path));
 
-    std::thread::spawn(move || {
-        if let Err(e) = run_watcher(app_handle, watch_path) {
-            log::error!("file watcher error: {e}");
+        for watch_path in open_vaults {
+            if watchers.contains_key(&watch_path) {
+                continue;
+            }
+            if !watch_path.exists() {
+                log::warn!("watch path does not exist: {:?}", watch_path);
+                continue;
+            }
+            let id = NEXT_WATCHER_ID.fetch_add(1, Ordering::SeqCst);
+            watchers.insert(watch_path.clone(), id);
+            started.push((watch_path, id));
         }
-    });
+    }
+
+    for (watch_path, id) in started {
+        let app_handle = app_handle.clone();
+        std::thread::spawn(move || {
+            if let Err(e) = run_watcher(app_handle, watch_path, id) {
+                log::error!("file watcher error: {e}");
+            }
+        });
+    }
 
     log::info!("file watcher initialized");
 }
@@ -176,39 +587,79 @@ pub fn init_file_watcher(app_handle: AppHandle) {
 fn run_watcher(
     app_handle: AppHandle,
     watch_path: PathBuf,
+    id: u64,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let (tx, rx) = mpsc::channel();
 
+    // polling settings apply when the watcher starts; see `set_watcher_settings`
+    let settings = settings();
     let config = Config::default()
-        .with_poll_interval(Duration::from_secs(5))
+        .with_poll_interval(Duration::from_millis(settings.poll_interval_ms))
         .with_compare_contents(false);
 
-    let mut watcher: RecommendedWatcher = Watcher::new(tx, config)?;
+    let mut watcher: Box<dyn Watcher> = if settings.use_polling {
+        Box::new(PollWatcher::new(tx, config)?)
+    } else {
+        Box::new(RecommendedWatcher::new(tx, config)?)
+    };
     watcher.watch(&watch_path, RecursiveMode::Recursive)?;
 
-    log::info!("watching for file changes in: {:?}", watch_path);
+    log::info!(
+        "watching for file changes in: {:?} (polling: {})",
+        watch_path,
+        settings.use_polling
+    );
+
+    // delta mode is read once at startup; toggling it takes effect on next launch
+    let mut snapshots = get_setting::<bool>(&app_handle, WATCHER_DIRECTORY_DELTAS_KEY)
+        .unwrap_or(false)
+        .then(|| DirectorySnapshots::new(watch_path.clone()));
 
     let mut accumulator = EventAccumulator::default();
 
     loop {
+        if !is_current_watcher(&watch_path, id) {
+            // the vault was closed or switched away from. pending events are
+            // dropped rather than applied to the indexes of the new vault
+            log::info!("stopped watching {:?}", watch_path);
+            break;
+        }
+
         let recv_result = if accumulator.is_empty() {
-            // no pending events - wait indefinitely
-            rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+            // no pending events - wait, waking up to check whether to stop
+            match rx.recv_timeout(IDLE_CHECK_INTERVAL) {
+                Err(RecvTimeoutError::Timeout) => continue,
+                result => result,
+            }
         } else {
             // pending events - wait with timeout for debounce
-            rx.recv_timeout(DEBOUNCE_DURATION)
+            rx.recv_timeout(settings().debounce())
         };
 
         match recv_result {
             Ok(Ok(event)) => {
                 process_event(&watch_path, event, &mut accumulator);
+                // a long burst (e.g. a bulk copy) is flushed in batches
+                if accumulator.len() >= settings().max_batch_size {
+                    flush_events(
+                        &app_handle,
+                        &watch_path,
+                        &mut accumulator,
+                        snapshots.as_mut(),
+                    );
+                }
             }
             Ok(Err(e)) => {
                 log::error!("watch error: {e}");
             }
             Err(RecvTimeoutError::Timeout) => {
                 // debounce period elapsed - flush accumulated events
-                flush_events(&app_handle, &mut accumulator);
+                flush_events(
+                    &app_handle,
+                    &watch_path,
+                    &mut accumulator,
+                    snapshots.as_mut(),
+                );
             }
             Err(RecvTimeoutError::Disconnected) => {
                 log::error!("watcher channel disconnected");
@@ -224,6 +675,14 @@ fn process_event(base_path: &Path, event: Event, accumulator: &mut EventAccumula
     use notify::event::{ModifyKind, RenameMode};
     use notify::EventKind;
 
+    if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+        for path in &event.paths {
+            if let Some(relative_path) = own_write_path(base_path, path) {
+                accumulator.own_writes.insert(relative_path);
+            }
+        }
+    }
+
     match event.kind {
         EventKind::Create(_) => {
             for path in &event.paths {
@@ -282,6 +741,13 @@ fn process_path(base_path: &Path, path: &Path, kind: &str, accumulator: &mut Eve
         Err(_) => return,
     };
 
+    // new ignore rules change what the whole tree shows
+    if relative_path == VAULT_IGNORE_FILE_NAME {
+        vault_ignore::reload(base_path);
+        accumulator.add_dir_event(String::new());
+        return;
+    }
+
     // skip hidden files and folders (any path component starting with .)
     if relative_path
         .split('/')
@@ -290,13 +756,17 @@ fn process_path(base_path: &Path, path: &Path, kind: &str, accumulator: &mut Eve
         return;
     }
 
+    if vault_ignore::is_ignored_in(base_path, &relative_path, path.is_dir()) {
+        return;
+    }
+
     if path.is_dir() {
         // directory event - add parent to directory_changes
         let parent = get_parent_dir(&relative_path);
         accumulator.add_dir_event(parent);
         log::debug!("directory {kind}: {relative_path}");
-    } else if path.extension().is_some_and(|ext| ext == "md") {
-        // .md file event - track for collation (directory changes determined after)
+    } else if vault_settings::is_managed_file_in(base_path, &relative_path) {
+        // managed file event - track for collation (directory changes determined after)
         accumulator.add_file_event(relative_path.clone(), kind);
         log::debug!("file {kind}: {relative_path}");
     } else if !path.exists() && path.extension().is_none() {
@@ -308,12 +778,18 @@ fn process_path(base_path: &Path, path: &Path, kind: &str, accumulator: &mut Eve
     }
 }
 
-fn flush_events(app_handle: &AppHandle, accumulator: &mut EventAccumulator) {
-    let acc = std::mem::take(accumulator);
-    let event = acc.collate();
+fn flush_events(
+    app_handle: &AppHandle,
+    watch_path: &Path,
+    accumulator: &mut EventAccumulator,
+    snapshots: Option<&mut DirectorySnapshots>,
+) {
+    let mut acc = std::mem::take(accumulator);
+    let own_writes = std::mem::take(&mut acc.own_writes);
+    let event = acc.collate(snapshots);
 
     // skip if nothing to emit
-    if event.file_changes.is_empty() && event.directory_changes.is_empty() {
+    if event.is_empty() {
         return;
     }
 
@@ -332,15 +808,321 @@ fn flush_events(app_handle: &AppHandle, accumulator: &mut EventAccumulator) {
             if dir.is_empty() { "(root)" } else { dir }
         );
     }
+    for delta in event.directory_deltas.iter().flatten() {
+        log::info!(
+            "dir delta: {} (+{} -{} ~{} renamed {})",
+            if delta.path.is_empty() {
+                "(root)"
+            } else {
+                &delta.path
+            },
+            delta.added.len(),
+            delta.removed.len(),
+            delta.updated.len(),
+            delta.renamed.len()
+        );
+    }
+
+    fs_cache::sync_watcher_event(watch_path, &event);
+
+    // indexes, sync and plugins follow the active vault only
+    if vaults::global_vault_dir().as_deref() == Some(watch_path) {
+        spotlight::sync_watcher_event(app_handle, &event);
+        search_index::sync_watcher_event(app_handle, &event);
+        note_index::sync_watcher_event::<tags::TagIndex>(app_handle, &event);
+        note_index::sync_watcher_event::<links::LinkIndex>(app_handle, &event);
+        note_index::sync_watcher_event::<note_ids::NoteIdIndex>(app_handle, &event);
+        vault_sync::schedule_idle_sync(app_handle);
+        plugins::dispatch_watcher_event(app_handle, &event);
+    }
+
+    // the frontend already has the content it saved; echoing the save back
+    // would make it reload the note
+    let event = event.without_modified(&own_writes);
+    if event.is_empty() {
+        log::debug!("skipping file watcher event: only the app's own writes");
+        return;
+    }
+    if let Err(e) = vaults::emit_to_vault(app_handle, watch_path, FILE_WATCHER_EVENT, event) {
+        log::error!("failed to emit file watcher event: {e}");
+    }
+}
+
+/// emits file changes that are known ahead of the watcher (e.g. agent edits),
+/// with a directory refresh for every deleted file's parent
+pub fn emit_file_changes(app_handle: &AppHandle, file_changes: Vec<FileChange>) {
+    let mut directory_changes: Vec<String> = file_changes
+        .iter()
+        .filter(|change| change.kind == "delete")
+        .map(|change| get_parent_dir(&change.path))
+        .collect();
+    directory_changes.sort();
+    directory_changes.dedup();
+
+    for change in &file_changes {
+        log::info!("file {} (hint): {}", change.kind, change.path);
+    }
 
-    if let Err(e) = app_handle.emit(FILE_WATCHER_EVENT, event) {
+    let event = FileWatcherEvent {
+        file_changes,
+        directory_changes,
+        directory_deltas: None,
+    };
+    let result = match get_base_dir(app_handle) {
+        Ok(vault_dir) => vaults::emit_to_vault(app_handle, &vault_dir, FILE_WATCHER_EVENT, event),
+        Err(_) => app_handle.emit(FILE_WATCHER_EVENT, event),
+    };
+    if let Err(e) = result {
         log::error!("failed to emit file watcher event: {e}");
     }
 }
 
+// --- external files ---
+
+/// an external file and the windows that asked to watch it
+struct ExternalFile {
+    /// the path as the frontend passed it, used in events
+    path: String,
+    windows: HashSet<String>,
+}
+
+/// watched external files, keyed by canonical path (what notify reports)
+static EXTERNAL_FILES: Mutex<Option<HashMap<PathBuf, ExternalFile>>> = Mutex::new(None);
+
+/// one watcher for all external files, created on first use. it watches each
+/// file's parent directory so saves that replace the file are still seen.
+static EXTERNAL_WATCHER: Mutex<Option<RecommendedWatcher>> = Mutex::new(None);
+
+fn start_external_watcher(app_handle: &AppHandle) -> Result<(), String> {
+    let mut external_watcher = EXTERNAL_WATCHER.lock().unwrap();
+    if external_watcher.is_some() {
+        return Ok(());
+    }
+
+    let (tx, rx) = mpsc::channel();
+    let watcher: RecommendedWatcher = Watcher::new(tx, Config::default())
+        .map_err(|e| format!("failed to create external file watcher: {e}"))?;
+    *external_watcher = Some(watcher);
+
+    let app_handle = app_handle.clone();
+    std::thread::spawn(move || run_external_watcher(app_handle, rx));
+    Ok(())
+}
+
+fn run_external_watcher(app_handle: AppHandle, rx: mpsc::Receiver<notify::Result<Event>>) {
+    use notify::EventKind;
+
+    // latest kind per changed file, flushed once events settle
+    let mut pending: HashMap<PathBuf, &'static str> = HashMap::new();
+    loop {
+        let recv_result = if pending.is_empty() {
+            rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+        } else {
+            rx.recv_timeout(settings().debounce())
+        };
+
+        match recv_result {
+            Ok(Ok(event)) => {
+                if !matches!(
+                    event.kind,
+                    EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                ) {
+                    continue;
+                }
+                let files = EXTERNAL_FILES.lock().unwrap();
+                let Some(files) = files.as_ref() else {
+                    continue;
+                };
+                for path in event.paths {
+                    if files.contains_key(&path) {
+                        let kind = if path.exists() { "modify" } else { "delete" };
+                        pending.insert(path, kind);
+                    }
+                }
+            }
+            Ok(Err(e)) => log::error!("external watch error: {e}"),
+            Err(RecvTimeoutError::Timeout) => {
+                flush_external_events(&app_handle, std::mem::take(&mut pending));
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                log::info!("external file watcher stopped");
+                break;
+            }
+        }
+    }
+}
+
+/// sends each change only to the windows watching that file
+fn flush_external_events(app_handle: &AppHandle, pending: HashMap<PathBuf, &'static str>) {
+    let files = EXTERNAL_FILES.lock().unwrap();
+    let Some(files) = files.as_ref() else {
+        return;
+    };
+    for (canonical_path, kind) in pending {
+        let Some(file) = files.get(&canonical_path) else {
+            continue;
+        };
+        log::info!("external file {kind}: {}", file.path);
+        let change = FileChange {
+            path: file.path.clone(),
+            kind: kind.to_string(),
+        };
+        for label in &file.windows {
+            let target = EventTarget::webview_window(label.as_str());
+            if let Err(e) = app_handle.emit_to(target, EXTERNAL_FILE_EVENT, change.clone()) {
+                log::error!("failed to emit external file event: {e}");
+            }
+        }
+    }
+}
+
+/// true if another watched file lives in `dir`
+fn is_dir_watched(files: &HashMap<PathBuf, ExternalFile>, dir: &Path) -> bool {
+    files.keys().any(|path| path.parent() == Some(dir))
+}
+
+/// removes `label` from a watched file, unwatching its directory when no
+/// watched file is left in it
+fn unwatch_external_inner(canonical_path: &Path, label: &str) {
+    let mut files = EXTERNAL_FILES.lock().unwrap();
+    let Some(files) = files.as_mut() else {
+        return;
+    };
+    let Some(file) = files.get_mut(canonical_path) else {
+        return;
+    };
+    file.windows.remove(label);
+    if !file.windows.is_empty() {
+        return;
+    }
+    files.remove(canonical_path);
+
+    let Some(dir) = canonical_path.parent() else {
+        return;
+    };
+    if is_dir_watched(files, dir) {
+        return;
+    }
+    if let Some(watcher) = EXTERNAL_WATCHER.lock().unwrap().as_mut() {
+        if let Err(e) = watcher.unwatch(dir) {
+            log::warn!("failed to unwatch {}: {e}", dir.display());
+        }
+    }
+}
+
+/// stops watching external files for a closed window
+pub fn clear_window_external_files(label: &str) {
+    let watched: Vec<PathBuf> = EXTERNAL_FILES
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|files| {
+            files
+                .iter()
+                .filter(|(_, file)| file.windows.contains(label))
+                .map(|(path, _)| path.clone())
+                .collect()
+        })
+        .unwrap_or_default();
+    for path in watched {
+        unwatch_external_inner(&path, label);
+    }
+}
+
+/// notifies the calling window with `external-file-changed` events when a
+/// file outside the vault is modified or deleted
+#[tauri::command]
+pub fn watch_external_file(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    path: String,
+) -> Result<(), String> {
+    log::info!("watching external file: {path}");
+
+    let canonical_path = std::fs::canonicalize(&path)
+        .map_err(|e| format!("failed to watch external file '{path}': {e}"))?;
+    let dir = canonical_path
+        .parent()
+        .ok_or_else(|| format!("failed to watch external file '{path}': no parent directory"))?
+        .to_path_buf();
+    start_external_watcher(&app_handle)?;
+
+    let mut files = EXTERNAL_FILES.lock().unwrap();
+    let files = files.get_or_insert_with(HashMap::new);
+    if !is_dir_watched(files, &dir) {
+        if let Some(watcher) = EXTERNAL_WATCHER.lock().unwrap().as_mut() {
+            watcher
+                .watch(&dir, RecursiveMode::NonRecursive)
+                .map_err(|e| format!("failed to watch external file '{path}': {e}"))?;
+        }
+    }
+    files
+        .entry(canonical_path)
+        .or_insert_with(|| ExternalFile {
+            path,
+            windows: HashSet::new(),
+        })
+        .windows
+        .insert(webview_window.label().to_string());
+    Ok(())
+}
+
+/// stops `watch_external_file` for the calling window
+#[tauri::command]
+pub fn unwatch_external_file(webview_window: WebviewWindow, path: String) -> Result<(), String> {
+    log::info!("unwatching external file: {path}");
+
+    // a deleted file can no longer be canonicalized; fall back to a lookup by
+    // the path the frontend watched it with
+    let canonical_path = std::fs::canonicalize(&path).ok().or_else(|| {
+        EXTERNAL_FILES.lock().unwrap().as_ref().and_then(|files| {
+            files
+                .iter()
+                .find(|(_, file)| file.path == path)
+                .map(|(canonical_path, _)| canonical_path.clone())
+        })
+    });
+    if let Some(canonical_path) = canonical_path {
+        unwatch_external_inner(&canonical_path, webview_window.label());
+    }
+    Ok(())
+}
+
 fn get_parent_dir(path: &str) -> String {
     Path::new(path)
         .parent()
         .map(|p| p.to_string_lossy().to_string())
         .unwrap_or_default()
 }
+
+// --- settings commands ---
+
+#[tauri::command]
+pub fn get_watcher_settings(app_handle: AppHandle) -> WatcherSettings {
+    load_settings(&app_handle)
+}
+
+/// stores new watcher settings. debounce and batch size apply right away;
+/// switching polling on or off, or changing its interval, restarts the
+/// vault watchers. returns the settings in effect.
+#[tauri::command]
+pub fn set_watcher_settings(
+    app_handle: AppHandle,
+    settings: WatcherSettings,
+) -> Result<WatcherSettings, String> {
+    let settings = settings.normalized();
+    log::info!("setting watcher settings: {settings:?}");
+    set_setting(&app_handle, WATCHER_SETTINGS_KEY, &settings)?;
+
+    let previous = SETTINGS.write().unwrap().replace(settings.clone());
+    let restart = previous.is_none_or(|previous| {
+        previous.use_polling != settings.use_polling
+            || previous.poll_interval_ms != settings.poll_interval_ms
+    });
+    if restart {
+        // running watchers see they are no longer current and stop
+        WATCHERS.lock().unwrap().take();
+        init_file_watcher(app_handle);
+    }
+    Ok(settings)
+}
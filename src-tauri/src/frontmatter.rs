@@ -0,0 +1,69 @@
+/// yaml frontmatter of a note: the text between an opening `---` line at the very
+/// start of the file and the next line that is exactly `---` (or `...`)
+pub struct Frontmatter<'a> {
+    /// raw yaml between the delimiters, `None` if the note has no frontmatter
+    pub yaml: Option<&'a str>,
+    /// the note content after the closing delimiter line
+    pub body: &'a str,
+}
+
+impl Frontmatter<'_> {
+    /// parsed yaml. an empty header parses to `Null`.
+    pub fn parse(&self) -> Result<Option<serde_yaml::Value>, String> {
+        match self.yaml {
+            Some(yaml) if yaml.trim().is_empty() => Ok(Some(serde_yaml::Value::Null)),
+            Some(yaml) => serde_yaml::from_str(yaml)
+                .map(Some)
+                .map_err(|e| format!("invalid frontmatter: {e}")),
+            None => Ok(None),
+        }
+    }
+}
+
+/// splits a note into its frontmatter and body. delimiters must be whole lines,
+/// so `----` or `--- text` inside the yaml (e.g. in a block scalar) never end it.
+pub fn split(content: &str) -> Frontmatter<'_> {
+    let no_frontmatter = Frontmatter {
+        yaml: None,
+        body: content,
+    };
+    let Some(rest) = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))
+    else {
+        return no_frontmatter;
+    };
+    let yaml_start = content.len() - rest.len();
+
+    let mut offset = yaml_start;
+    for line in rest.split_inclusive('\n') {
+        let trimmed = line.trim_end_matches(['\n', '\r']);
+        if trimmed == "---" || trimmed == "..." {
+            return Frontmatter {
+                yaml: Some(&content[yaml_start..offset]),
+                body: &content[offset + line.len()..],
+            };
+        }
+        offset += line.len();
+    }
+    // unterminated header: treat the whole note as body
+    no_frontmatter
+}
+
+/// serializes `value` as a frontmatter header, or nothing for `None`/`Null`/an empty map
+pub fn render(value: Option<&serde_yaml::Value>) -> Result<String, String> {
+    let value = match value {
+        None | Some(serde_yaml::Value::Null) => return Ok(String::new()),
+        Some(serde_yaml::Value::Mapping(map)) if map.is_empty() => return Ok(String::new()),
+        Some(value) => value,
+    };
+    let yaml = serde_yaml::to_string(value)
+        .map_err(|e| format!("failed to serialize frontmatter: {e}"))?;
+    Ok(format!("---\n{}\n---\n", yaml.trim_end()))
+}
+
+/// replaces (or prepends, or removes) the frontmatter of a note, keeping the body as is
+pub fn replace(content: &str, value: Option<&serde_yaml::Value>) -> Result<String, String> {
+    let body = split(content).body;
+    Ok(format!("{}{}", render(value)?, body))
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use tokio::fs;
+
+use crate::command::{fs_entry_from_metadata, FSEntry};
+use crate::file_watcher::FileWatcherEvent;
+use crate::{vault_ignore, vault_settings};
+
+/// the visible entries of one directory, as listed when its modification time
+/// was `modified`. entry paths are bare names.
+struct CachedDir {
+    modified: Option<SystemTime>,
+    entries: Vec<FSEntry>,
+}
+
+/// listings keyed by absolute directory path. a directory's own modification
+/// time catches added, removed and renamed entries right away; watcher events
+/// catch changes to the files inside it.
+static CACHE: Mutex<Option<HashMap<PathBuf, CachedDir>>> = Mutex::new(None);
+
+fn with_cache<T>(f: impl FnOnce(&mut HashMap<PathBuf, CachedDir>) -> T) -> T {
+    let mut cache = CACHE.lock().unwrap();
+    f(cache.get_or_insert_with(HashMap::new))
+}
+
+/// reads a directory from disk: hidden entries, files with unmanaged
+/// extensions and entries matching `.flowriteignore` are left out
+async fn read_dir_uncached(dir_path: &Path) -> std::io::Result<Vec<FSEntry>> {
+    let mut entries = fs::read_dir(dir_path).await?;
+    let mut listed = Vec::new();
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().to_string();
+        // skip hidden files/directories (starting with .)
+        if name.starts_with('.') {
+            continue;
+        }
+
+        let metadata = fs::metadata(entry.path()).await?;
+        // skip files with unmanaged extensions (only show notes and directories)
+        if !metadata.is_dir() && !vault_settings::is_managed_file(&name) {
+            continue;
+        }
+        if vault_ignore::is_ignored(&entry.path(), metadata.is_dir()) {
+            continue;
+        }
+        listed.push(fs_entry_from_metadata(name, &metadata).map_err(std::io::Error::other)?);
+    }
+    Ok(listed)
+}
+
+/// the visible entries of `dir_path` with bare names as paths, from the
+/// cache when the directory has not changed since it was last read
+pub async fn read_dir(dir_path: &Path) -> std::io::Result<Vec<FSEntry>> {
+    let modified = fs::metadata(dir_path).await?.modified().ok();
+    let cached = with_cache(|cache| {
+        cache
+            .get(dir_path)
+            .filter(|cached| modified.is_some() && cached.modified == modified)
+            .map(|cached| cached.entries.clone())
+    });
+    if let Some(entries) = cached {
+        return Ok(entries);
+    }
+
+    let entries = read_dir_uncached(dir_path).await?;
+    with_cache(|cache| {
+        cache.insert(
+            dir_path.to_path_buf(),
+            CachedDir {
+                modified,
+                entries: entries.clone(),
+            },
+        )
+    });
+    Ok(entries)
+}
+
+/// drops the listing of `dir_path` and of every directory below it
+pub fn invalidate_dir(dir_path: &Path) {
+    with_cache(|cache| cache.retain(|cached_path, _| !cached_path.starts_with(dir_path)));
+}
+
+/// drops the listing holding a vault-relative file whose content changed
+/// (size and modification time do not touch the directory's own mtime)
+pub fn invalidate_file(vault_root: &Path, path: &str) {
+    if let Some(parent) = vault_root.join(path).parent() {
+        with_cache(|cache| cache.remove(parent));
+    }
+}
+
+/// forgets every listing, e.g. when the managed extensions change
+pub fn clear() {
+    with_cache(|cache| cache.clear());
+}
+
+/// invalidates what a batch of watcher events touched in the vault at `vault_root`
+pub fn sync_watcher_event(vault_root: &Path, event: &FileWatcherEvent) {
+    for change in &event.file_changes {
+        invalidate_file(vault_root, &change.path);
+    }
+    for dir in &event.directory_changes {
+        invalidate_dir(&vault_root.join(dir));
+    }
+    for delta in event.directory_deltas.iter().flatten() {
+        invalidate_dir(&vault_root.join(&delta.path));
+    }
+}
@@ -0,0 +1,566 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tauri::{ipc::Channel, AppHandle, WebviewWindow};
+use tokio::fs;
+
+use crate::{
+    assets::encode_link,
+    constants::ASSETS_DIR_NAME,
+    journal::{self, JournalOp, JournalOrigin},
+    links::percent_decode,
+    nb, preflight,
+    utils::get_base_dir,
+    vault_settings, vaults,
+};
+
+// -----------------------------------------
+// notion
+// -----------------------------------------
+
+/// length of the hex id notion appends to page and folder names
+const NOTION_ID_LENGTH: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportKind {
+    Note,
+    Asset,
+}
+
+/// one file of an import and where it lands in the vault
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlannedFile {
+    /// path inside the export
+    pub source: String,
+    /// vault-relative destination
+    pub destination: String,
+    pub kind: ImportKind,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotionImport {
+    pub files: Vec<PlannedFile>,
+    pub notes: usize,
+    pub assets: usize,
+    /// true if nothing was written
+    pub dry_run: bool,
+}
+
+/// `Page Title 0123…cdef` → `Page Title`
+fn strip_notion_id(name: &str) -> &str {
+    match name.rsplit_once(' ') {
+        Some((title, id))
+            if !title.trim().is_empty()
+                && id.len() == NOTION_ID_LENGTH
+                && id.chars().all(|c| c.is_ascii_hexdigit()) =>
+        {
+            title.trim_end()
+        }
+        _ => name,
+    }
+}
+
+/// a file or folder name without its notion id, keeping the extension
+fn clean_name(name: &str) -> String {
+    match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => {
+            format!("{}.{extension}", strip_notion_id(stem))
+        }
+        _ => strip_notion_id(name).to_string(),
+    }
+}
+
+fn is_page(path: &str) -> bool {
+    let lower = path.to_lowercase();
+    lower.ends_with(".md") || lower.ends_with(".html")
+}
+
+fn join(dir: &str, name: &str) -> String {
+    match (dir.is_empty(), name.is_empty()) {
+        (true, _) => name.to_string(),
+        (false, true) => dir.to_string(),
+        (false, false) => format!("{dir}/{name}"),
+    }
+}
+
+fn parent_of(path: &str) -> &str {
+    path.rsplit_once('/')
+        .map(|(parent, _)| parent)
+        .unwrap_or("")
+}
+
+/// resolves `.` and `..` in a `/`-separated relative path
+fn normalize(path: &str) -> Option<String> {
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                parts.pop()?;
+            }
+            part => parts.push(part),
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// relative link from a file in `from_dir` to `to` (both vault-relative)
+fn relative_link(from_dir: &str, to: &str) -> String {
+    let from: Vec<&str> = from_dir
+        .split('/')
+        .filter(|part| !part.is_empty())
+        .collect();
+    let to_parts: Vec<&str> = to.split('/').collect();
+    let common = from
+        .iter()
+        .zip(&to_parts)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut parts: Vec<&str> = vec![".."; from.len() - common];
+    parts.extend(&to_parts[common..]);
+    parts.join("/")
+}
+
+/// every file of the export, relative to its root
+fn walk_files(root: &Path) -> Result<Vec<String>, String> {
+    let mut files = Vec::new();
+    let mut pending = vec![String::new()];
+    while let Some(dir) = pending.pop() {
+        let entries = std::fs::read_dir(root.join(&dir))
+            .map_err(|e| format!("failed to read '{dir}': {e}"))?;
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') || name == "__MACOSX" {
+                continue;
+            }
+            let path = join(&dir, &name);
+            match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() => pending.push(path),
+                Ok(file_type) if file_type.is_file() => files.push(path),
+                _ => {}
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// extracts a notion zip (and the `Part-N.zip` archives large exports nest
+/// inside it) into `dest`
+/// size of a zip's entries once extracted (nested zips count as stored)
+fn zip_uncompressed_size(zip_path: &Path) -> Result<u64, String> {
+    let file = std::fs::File::open(zip_path)
+        .map_err(|e| format!("failed to open {}: {e}", zip_path.display()))?;
+    let mut archive = zip::ZipArchive::new(file)
+        .map_err(|e| format!("failed to read {}: {e}", zip_path.display()))?;
+    let mut total = 0u64;
+    for index in 0..archive.len() {
+        let entry = archive
+            .by_index_raw(index)
+            .map_err(|e| format!("failed to read {}: {e}", zip_path.display()))?;
+        total = total.saturating_add(entry.size());
+    }
+    Ok(total)
+}
+
+/// total size of the files at `paths` (relative to `root`)
+fn files_size<'a>(root: &Path, paths: impl IntoIterator<Item = &'a str>) -> u64 {
+    paths
+        .into_iter()
+        .filter_map(|path| std::fs::metadata(root.join(path)).ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+fn extract_zip(zip_path: &Path, dest: &Path) -> Result<(), String> {
+    let file = std::fs::File::open(zip_path)
+        .map_err(|e| format!("failed to open {}: {e}", zip_path.display()))?;
+    zip::ZipArchive::new(file)
+        .and_then(|mut archive| archive.extract(dest))
+        .map_err(|e| format!("failed to extract {}: {e}", zip_path.display()))?;
+
+    for nested in walk_files(dest)? {
+        if nested.to_lowercase().ends_with(".zip") {
+            let nested_path = dest.join(&nested);
+            let nested_dest = dest.join(parent_of(&nested));
+            extract_zip(&nested_path, &nested_dest)?;
+            let _ = std::fs::remove_file(&nested_path);
+        }
+    }
+    Ok(())
+}
+
+/// picks `path`, or `path` with a numeric suffix if it is taken on disk or
+/// by another planned file
+fn unique_path(vault_root: &Path, taken: &mut HashSet<String>, path: String) -> String {
+    let available = |candidate: &String, taken: &HashSet<String>| {
+        !taken.contains(candidate) && !vault_root.join(candidate).exists()
+    };
+    let mut candidate = path.clone();
+    let (stem, extension) = match path.rsplit_once('.') {
+        Some((stem, extension))
+            if !stem.is_empty() && !stem.ends_with('/') && !extension.contains('/') =>
+        {
+            (stem.to_string(), format!(".{extension}"))
+        }
+        _ => (path.clone(), String::new()),
+    };
+    let mut n = 1;
+    while !available(&candidate, taken) {
+        candidate = format!("{stem} {n}{extension}");
+        n += 1;
+    }
+    taken.insert(candidate.clone());
+    candidate
+}
+
+/// maps every export file to its vault destination. pages keep their folder
+/// structure with ids stripped; other files go to the `assets` folder next to
+/// the page that owns them (`Page <id>/image.png` → `assets/image.png`).
+fn plan_notion_import(vault_root: &Path, target_dir: &str, files: &[String]) -> Vec<PlannedFile> {
+    let clean_dir = |dir: &str| -> String {
+        dir.split('/')
+            .filter(|part| !part.is_empty())
+            .map(clean_name)
+            .collect::<Vec<_>>()
+            .join("/")
+    };
+
+    let mut taken = HashSet::new();
+    let mut planned = Vec::new();
+    for source in files {
+        let name = source.rsplit('/').next().unwrap_or(source);
+        let dir = parent_of(source);
+        let (destination, kind) = if is_page(source) {
+            (
+                join(&join(target_dir, &clean_dir(dir)), &clean_name(name)),
+                ImportKind::Note,
+            )
+        } else {
+            // notion keeps a page's files in a folder named like the page
+            let owner_dir = clean_dir(parent_of(dir));
+            let assets_dir = join(&join(target_dir, &owner_dir), ASSETS_DIR_NAME);
+            (join(&assets_dir, name), ImportKind::Asset)
+        };
+        planned.push(PlannedFile {
+            source: source.clone(),
+            destination: unique_path(vault_root, &mut taken, destination),
+            kind,
+        });
+    }
+    planned
+}
+
+/// rewrites markdown link targets and html `href`/`src` attributes that point
+/// at other files of the export
+fn rewrite_links(
+    content: &str,
+    source: &str,
+    destination: &str,
+    destinations: &HashMap<&str, &str>,
+) -> String {
+    let rewrite = |target: &str| -> Option<String> {
+        if target.contains("://") || target.starts_with('#') || target.starts_with("mailto:") {
+            return None;
+        }
+        let (path, anchor) = match target.split_once('#') {
+            Some((path, anchor)) => (path, format!("#{anchor}")),
+            None => (target, String::new()),
+        };
+        let resolved = normalize(&join(parent_of(source), &percent_decode(path)))?;
+        let new_path = destinations.get(resolved.as_str())?;
+        Some(format!(
+            "{}{anchor}",
+            encode_link(&relative_link(parent_of(destination), new_path))
+        ))
+    };
+
+    let mut output = String::with_capacity(content.len());
+    let mut rest = content;
+    loop {
+        let markdown = rest.find("](").map(|at| (at + 2, ')'));
+        let href = rest.find("href=\"").map(|at| (at + 6, '"'));
+        let src = rest.find("src=\"").map(|at| (at + 5, '"'));
+        let Some((start, close)) = [markdown, href, src]
+            .into_iter()
+            .flatten()
+            .min_by_key(|(start, _)| *start)
+        else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find(close) else {
+            break;
+        };
+        let target = &rest[..end];
+        match rewrite(target) {
+            Some(new_target) => output.push_str(&new_target),
+            None => output.push_str(target),
+        }
+        rest = &rest[end..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// copies the planned files into the vault, fixing links inside pages
+async fn write_notion_import(
+    source_root: &Path,
+    vault_root: &Path,
+    planned: &[PlannedFile],
+) -> Result<(), String> {
+    let destinations: HashMap<&str, &str> = planned
+        .iter()
+        .map(|file| (file.source.as_str(), file.destination.as_str()))
+        .collect();
+
+    for file in planned {
+        let source_path = source_root.join(&file.source);
+        let destination_path = vault_root.join(&file.destination);
+        if let Some(parent) = destination_path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .map_err(|e| format!("failed to create directory for {}: {e}", file.destination))?;
+        }
+
+        match file.kind {
+            ImportKind::Note => {
+                let content = fs::read_to_string(&source_path)
+                    .await
+                    .map_err(|e| format!("failed to read {}: {e}", file.source))?;
+                let content =
+                    rewrite_links(&content, &file.source, &file.destination, &destinations);
+                fs::write(&destination_path, content)
+                    .await
+                    .map_err(|e| format!("failed to write {}: {e}", file.destination))?;
+            }
+            ImportKind::Asset => {
+                fs::copy(&source_path, &destination_path)
+                    .await
+                    .map_err(|e| format!("failed to copy {}: {e}", file.source))?;
+            }
+        }
+        journal::record(
+            vault_root,
+            JournalOp::Create,
+            &file.destination,
+            None,
+            JournalOrigin::User,
+        );
+    }
+    Ok(())
+}
+
+// -----------------------------------------
+// folder
+// -----------------------------------------
+
+/// progress of `import_folder`, sent after every copied file
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportProgress {
+    pub files_copied: usize,
+    pub total_files: usize,
+    /// vault-relative destination of the last copied file
+    pub current_path: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderImport {
+    /// vault-relative destinations of the copied files
+    pub files: Vec<String>,
+    /// files left out because the vault does not manage their extension
+    pub skipped: usize,
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+/// imports a notion markdown/html export (the downloaded zip or its unpacked
+/// folder) into `target_dir`. with `dry_run` only the planned files are returned.
+#[tauri::command]
+pub async fn import_notion(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    zip_or_dir: String,
+    target_dir: String,
+    dry_run: Option<bool>,
+) -> Result<NotionImport, String> {
+    vaults::scoped(&webview_window, async move {
+        let dry_run = dry_run.unwrap_or(false);
+        log::info!("importing notion export {zip_or_dir} into '{target_dir}' (dry run: {dry_run})");
+
+        let source = PathBuf::from(&zip_or_dir);
+        let vault_root = get_base_dir(&app_handle)?;
+        let target_dir = target_dir.trim_matches('/').to_string();
+        if normalize(&target_dir).as_deref() != Some(target_dir.as_str()) {
+            return Err(format!("invalid target directory '{target_dir}'"));
+        }
+
+        // zips are unpacked to a temporary folder that is removed afterwards
+        let extracted = if source.is_file() {
+            let temp_dir = std::env::temp_dir().join(format!(
+                "flowrite-notion-{}",
+                chrono::Utc::now().timestamp_millis()
+            ));
+            let zip_path = source.clone();
+            let extracted_bytes =
+                tokio::task::spawn_blocking(move || zip_uncompressed_size(&zip_path))
+                    .await
+                    .map_err(|e| format!("failed to read notion export: {e}"))??;
+            preflight::check(&temp_dir, extracted_bytes).await?;
+
+            let (zip_path, dest) = (source.clone(), temp_dir.clone());
+            tokio::task::spawn_blocking(move || extract_zip(&zip_path, &dest))
+                .await
+                .map_err(|e| format!("failed to extract notion export: {e}"))??;
+            Some(temp_dir)
+        } else if source.is_dir() {
+            None
+        } else {
+            return Err(format!("'{zip_or_dir}' does not exist"));
+        };
+        let source_root = extracted.clone().unwrap_or(source);
+
+        let result = async {
+            let files = walk_files(&source_root)?;
+            let planned = plan_notion_import(&vault_root, &target_dir, &files);
+            if !dry_run {
+                let bytes = files_size(
+                    &source_root,
+                    planned.iter().map(|file| file.source.as_str()),
+                );
+                preflight::check(&vault_root, bytes).await?;
+                write_notion_import(&source_root, &vault_root, &planned).await?;
+            }
+            Ok::<_, String>(planned)
+        }
+        .await;
+        if let Some(temp_dir) = extracted {
+            let _ = fs::remove_dir_all(temp_dir).await;
+        }
+        let planned = result?;
+
+        let notes = planned
+            .iter()
+            .filter(|file| file.kind == ImportKind::Note)
+            .count();
+        if !dry_run && !planned.is_empty() {
+            let label = if target_dir.is_empty() {
+                "(root)"
+            } else {
+                target_dir.as_str()
+            };
+            nb::reconcile_and_checkpoint(&app_handle, format!("[nb] Import: Notion into {label}"));
+        }
+
+        log::info!("notion import: {notes} notes, {} files", planned.len());
+        Ok(NotionImport {
+            assets: planned.len() - notes,
+            notes,
+            files: planned,
+            dry_run,
+        })
+    })
+    .await
+}
+
+/// copies the files of `source_dir` the vault manages into `target_dir`,
+/// keeping the folder structure and never overwriting existing files
+#[tauri::command]
+pub async fn import_folder(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    source_dir: String,
+    target_dir: String,
+    on_progress: Channel<ImportProgress>,
+) -> Result<FolderImport, String> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("importing folder {source_dir} into '{target_dir}'");
+
+        let source_root = PathBuf::from(&source_dir);
+        if !source_root.is_dir() {
+            return Err(format!("'{source_dir}' is not a directory"));
+        }
+        let vault_root = get_base_dir(&app_handle)?;
+        if source_root.starts_with(&vault_root) || vault_root.starts_with(&source_root) {
+            return Err("cannot import a folder that overlaps the vault".to_string());
+        }
+        let target_dir = target_dir.trim_matches('/').to_string();
+        if normalize(&target_dir).as_deref() != Some(target_dir.as_str()) {
+            return Err(format!("invalid target directory '{target_dir}'"));
+        }
+
+        let files = walk_files(&source_root)?;
+        let total = files.len();
+        let eligible: Vec<String> = files
+            .into_iter()
+            .filter(|file| {
+                let name = file.rsplit('/').next().unwrap_or(file);
+                vault_settings::is_managed_file_in(&vault_root, name)
+            })
+            .collect();
+        let skipped = total - eligible.len();
+
+        let bytes = files_size(&source_root, eligible.iter().map(String::as_str));
+        preflight::check(&vault_root, bytes).await?;
+
+        let mut taken = HashSet::new();
+        let mut copied = Vec::with_capacity(eligible.len());
+        for source in &eligible {
+            let destination = unique_path(&vault_root, &mut taken, join(&target_dir, source));
+            let destination_path = vault_root.join(&destination);
+            if let Some(parent) = destination_path.parent() {
+                fs::create_dir_all(parent)
+                    .await
+                    .map_err(|e| format!("failed to create directory for {destination}: {e}"))?;
+            }
+            fs::copy(source_root.join(source), &destination_path)
+                .await
+                .map_err(|e| format!("failed to copy {source}: {e}"))?;
+            journal::record(
+                &vault_root,
+                JournalOp::Create,
+                &destination,
+                None,
+                JournalOrigin::User,
+            );
+
+            copied.push(destination.clone());
+            let _ = on_progress.send(ImportProgress {
+                files_copied: copied.len(),
+                total_files: eligible.len(),
+                current_path: destination,
+            });
+        }
+
+        if !copied.is_empty() {
+            let label = if target_dir.is_empty() {
+                "(root)"
+            } else {
+                target_dir.as_str()
+            };
+            nb::reconcile_and_checkpoint(
+                &app_handle,
+                format!("[nb] Import: {} files into {label}", copied.len()),
+            );
+        }
+
+        log::info!(
+            "folder import: {} files copied, {skipped} skipped",
+            copied.len()
+        );
+        Ok(FolderImport {
+            files: copied,
+            skipped,
+        })
+    })
+    .await
+}
@@ -0,0 +1,140 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, WebviewWindow};
+use tokio::{fs, io::AsyncWriteExt, sync::Mutex};
+
+use crate::{
+    constants::{JOURNAL_FILE_NAME, VAULT_CONFIG_DIR_NAME},
+    utils::get_base_dir,
+    vaults,
+};
+
+/// serializes appends so concurrent operations never interleave lines
+static JOURNAL_LOCK: Mutex<()> = Mutex::const_new(());
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalOp {
+    Create,
+    Update,
+    Rename,
+    Delete,
+}
+
+/// who made the change
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalOrigin {
+    /// the user, through the editor or file tree
+    User,
+    /// an agent tool call that edited files in the vault
+    Agent,
+    /// a sync with a git remote
+    Sync,
+}
+
+/// one line of `<vault>/.flowrite/journal.ndjson`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntry {
+    pub time_ms: i64,
+    pub op: JournalOp,
+    /// vault-relative path (the new path for renames)
+    pub path: String,
+    /// previous path, for renames only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    pub origin: JournalOrigin,
+}
+
+fn get_journal_path(vault_root: &Path) -> PathBuf {
+    vault_root
+        .join(VAULT_CONFIG_DIR_NAME)
+        .join(JOURNAL_FILE_NAME)
+}
+
+async fn append(vault_root: &Path, entry: &JournalEntry) -> Result<(), String> {
+    let path = get_journal_path(vault_root);
+    let mut line =
+        serde_json::to_string(entry).map_err(|e| format!("failed to serialize entry: {e}"))?;
+    line.push('\n');
+
+    let _guard = JOURNAL_LOCK.lock().await;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("failed to create vault config directory: {e}"))?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .await
+        .map_err(|e| format!("failed to open journal: {e}"))?;
+    file.write_all(line.as_bytes())
+        .await
+        .map_err(|e| format!("failed to write journal: {e}"))
+}
+
+/// appends an entry to the vault's change journal in the background.
+/// journal failures are logged and never fail the operation itself.
+pub fn record(
+    vault_root: &Path,
+    op: JournalOp,
+    path: &str,
+    from: Option<&str>,
+    origin: JournalOrigin,
+) {
+    let vault_root = vault_root.to_path_buf();
+    let entry = JournalEntry {
+        time_ms: chrono::Utc::now().timestamp_millis(),
+        op,
+        path: path.to_string(),
+        from: from.map(|from| from.to_string()),
+        origin,
+    };
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = append(&vault_root, &entry).await {
+            log::warn!("failed to record journal entry for {}: {e}", entry.path);
+        }
+    });
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+/// returns journal entries recorded strictly after `since` (unix ms), oldest first.
+/// without `since` the whole journal is returned.
+#[tauri::command]
+pub async fn read_journal(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    since: Option<i64>,
+) -> Result<Vec<JournalEntry>, String> {
+    vaults::scoped(&webview_window, async move {
+        let path = get_journal_path(&get_base_dir(&app_handle)?);
+        let content = match fs::read_to_string(&path).await {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(format!("failed to read journal: {e}")),
+        };
+
+        let entries = content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str::<JournalEntry>(line) {
+                Ok(entry) => Some(entry),
+                Err(e) => {
+                    log::warn!("skipping malformed journal line: {e}");
+                    None
+                }
+            })
+            .filter(|entry| since.is_none_or(|since| entry.time_ms > since))
+            .collect();
+
+        Ok(entries)
+    })
+    .await
+}
@@ -1,10 +1,11 @@
+use std::collections::{HashMap, HashSet};
 use std::sync::{
     atomic::{AtomicBool, Ordering},
-    Mutex,
+    Mutex, OnceLock,
 };
 
 use tauri::menu::{Menu, MenuId, MenuItem, PredefinedMenuItem, Submenu};
-use tauri::{Emitter, Listener, Manager, RunEvent};
+use tauri::{Emitter, Listener, Manager, RunEvent, WindowEvent};
 
 /// Flag to break the quit → ExitRequested → emit loop.
 /// Set to `true` once the frontend confirms quit, so the second
@@ -20,12 +21,51 @@ static INITIAL_WINDOW_CREATED: AtomicBool = AtomicBool::new(false);
 /// before the frontend is ready to handle them (cold launch).
 pub(crate) struct PendingFiles(pub Mutex<Vec<String>>);
 
+/// Labels of workspace windows currently in focus mode.
+/// The app menu is shared, so it is re-synced whenever window focus changes.
+pub(crate) struct FocusModeWindows(pub Mutex<HashSet<String>>);
+
 mod acp;
+mod agent_bindings;
+mod assets;
+mod audit;
+mod autosave;
+mod checkpoints;
 mod command;
 mod constants;
+mod duplicates;
+mod encryption;
+mod export;
 mod file_watcher;
+mod frontmatter;
+mod fs_cache;
+mod import;
+mod journal;
+mod links;
+mod locks;
 mod nb;
+mod note_ids;
+mod note_index;
+mod plugins;
+mod preflight;
+mod recents;
+mod replace;
+mod search_index;
+mod secrets;
+mod spotlight;
+mod stats;
+mod tags;
+mod templates;
+mod transcripts;
 mod utils;
+mod vault_commands;
+mod vault_ignore;
+mod vault_maintenance;
+mod vault_settings;
+mod vault_sync;
+mod vault_trash;
+mod vaults;
+mod vcs;
 
 pub fn run() {
     tauri::Builder::default()
@@ -39,35 +79,184 @@ pub fn run() {
                 .build(),
         )
         .manage(acp::AcpState::default())
+        .manage(autosave::AutosaveState::default())
+        .manage(plugins::PluginHost::default())
+        .manage(search_index::SearchIndex::default())
+        .manage(tags::TagIndex::default())
+        .manage(links::LinkIndex::default())
+        .manage(note_ids::NoteIdIndex::default())
         .manage(PendingFiles(Mutex::new(Vec::new())))
+        .manage(FocusModeWindows(Mutex::new(HashSet::new())))
         .setup(setup_app)
+        .on_window_event(|window, event| match event {
+            WindowEvent::Focused(true) => {
+                command::sync_focus_mode_menu(window.app_handle(), window.label());
+                recents::sync_recent_menu(window.app_handle(), window.label());
+            }
+            WindowEvent::Focused(false) => {
+                // write staged content first so its checkpoint includes it
+                let handle = window.app_handle().clone();
+                tauri::async_runtime::spawn(async move {
+                    autosave::flush_on_focus_loss(&handle).await;
+                    checkpoints::flush_on_focus_loss(&handle).await;
+                });
+            }
+            WindowEvent::Destroyed => {
+                command::clear_focus_mode(window.app_handle(), window.label());
+                agent_bindings::clear_window_default_agent(window.app_handle(), window.label());
+                vaults::clear_window_vault(window.app_handle(), window.label());
+                file_watcher::clear_window_external_files(window.label());
+            }
+            _ => {}
+        })
         .invoke_handler(tauri::generate_handler![
             command::set_traffic_lights_visible,
+            command::set_focus_mode,
             command::create_workspace_window,
             command::take_pending_files,
+            command::read_dropped_paths,
             command::create_dir,
             command::list_dir,
+            command::list_dir_stream,
             command::delete_dir,
             command::rename_dir,
             command::create_file,
             command::read_file,
+            command::read_file_bytes,
+            command::read_file_v2,
+            command::read_file_stream,
+            command::read_file_range,
             command::update_file,
             command::delete_file,
             command::rename_file,
+            command::validate_filename,
+            command::generate_unique_path,
+            command::move_entry,
+            command::copy_entry,
+            command::batch_apply,
+            assets::save_asset,
+            assets::read_asset,
+            assets::save_clipboard_image,
+            vault_settings::get_managed_extensions,
+            vault_settings::set_managed_extensions,
+            command::read_file_metadata,
             command::write_file_metadata,
+            command::list_frontmatter_keys,
+            command::update_frontmatter_field,
+            command::repair_vault,
+            command::verify_vault,
+            command::verify_nb_installation,
+            command::get_remote_credentials,
+            command::list_ssh_keys,
+            command::set_remote_ssh_key,
+            command::set_remote_https_token,
+            command::list_deleted_notes,
+            command::recover_deleted_note,
+            command::list_file_versions,
+            command::read_file_version,
+            command::diff_file_versions,
+            vault_sync::vault_sync_configure,
+            vault_sync::vault_push,
+            vault_sync::vault_pull,
+            vault_sync::vault_status,
+            vault_maintenance::vault_maintenance,
+            vault_trash::list_trashed_notes,
+            vault_trash::restore_note,
+            vault_trash::empty_trash,
+            vaults::list_vaults,
+            vaults::create_vault,
+            vaults::open_vault,
+            vaults::switch_vault,
+            vaults::remove_vault,
+            vaults::set_window_vault,
+            vaults::get_window_vault,
+            recents::recent_files_get,
+            recents::recent_files_add,
+            recents::pins_get,
+            recents::pins_set,
+            stats::note_stats,
+            stats::vault_stats,
+            export::export_note_docx,
+            export::export_vault_zip,
+            import::import_notion,
+            import::import_folder,
+            templates::list_templates,
+            templates::create_file_from_template,
             command::create_external_file,
             command::read_external_file,
             command::update_external_file,
             command::delete_external_file,
             command::rename_external_file,
+            command::stat_external_file,
+            command::list_external_dir,
+            file_watcher::watch_external_file,
+            file_watcher::unwatch_external_file,
+            file_watcher::get_watcher_settings,
+            file_watcher::set_watcher_settings,
+            encryption::encryption_set_passphrase,
+            encryption::list_encrypted_folders,
+            encryption::encrypt_folder,
+            locks::set_note_locked,
+            locks::list_locked_notes,
             command::read_system_prompt,
             acp::acp_connect,
             acp::acp_new_session,
             acp::acp_prompt,
+            acp::acp_prompt_many,
+            acp::acp_resume_stream,
+            acp::acp_subscribe_session,
+            acp::acp_unsubscribe_session,
+            acp::acp_list_sessions,
+            acp::acp_list_commands,
             acp::acp_respond_permission,
             acp::acp_cancel,
             acp::acp_set_mode,
             acp::acp_set_model,
+            acp::acp_set_thinking,
+            acp::acp_set_config_option,
+            acp::acp_get_agent_diagnostics,
+            acp::acp_get_stats,
+            acp::acp_get_proxy_settings,
+            acp::acp_set_proxy_settings,
+            acp::acp_get_vault_only,
+            acp::acp_set_vault_only,
+            acp::acp_get_permission_timeout,
+            acp::acp_set_permission_timeout,
+            acp::acp_list_permission_rules,
+            acp::acp_clear_permission_rules,
+            agent_bindings::get_agent_bindings,
+            agent_bindings::set_agent_bindings,
+            agent_bindings::resolve_agent_for_path,
+            agent_bindings::get_default_agent,
+            agent_bindings::set_default_agent,
+            journal::read_journal,
+            audit::read_audit_log,
+            secrets::secrets_set,
+            secrets::secrets_get,
+            secrets::secrets_delete,
+            spotlight::set_spotlight_indexing,
+            search_index::search_index_query,
+            search_index::search_index_rebuild,
+            tags::list_tags,
+            tags::list_notes_by_tag,
+            links::get_backlinks,
+            links::get_outgoing_links,
+            links::check_vault_links,
+            note_ids::resolve_note_id,
+            replace::replace_in_vault,
+            duplicates::find_duplicate_notes,
+            plugins::list_plugins,
+            plugins::install_plugin,
+            plugins::set_plugin_enabled,
+            plugins::uninstall_plugin,
+            plugins::run_plugin,
+            transcripts::save_transcript,
+            autosave::stage_content,
+            autosave::flush_staged,
+            autosave::discard_staged,
+            checkpoints::flush_checkpoints,
+            checkpoints::create_snapshot,
+            transcripts::export_transcripts,
         ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
@@ -89,12 +278,21 @@ pub fn run() {
                     let _ = app_handle.emit("request-quit", ());
                 } else if menu_id == &MenuId::new(NEW_WINDOW_MENU_ID) {
                     log::info!("new window menu clicked");
-                    let _ = command::create_workspace_window(app_handle.clone());
+                    let _ = command::create_workspace_window(app_handle.clone(), None);
                 } else if menu_id == &MenuId::new(CLOSE_WINDOW_MENU_ID) {
                     log::info!("close window menu clicked");
                     if let Some(window) = app_handle.get_focused_window() {
                         let _ = window.close();
                     }
+                } else if let Some(path) = menu_id
+                    .0
+                    .strip_prefix(OPEN_RECENT_MENU_ID)
+                    .and_then(|rest| rest.strip_prefix(':'))
+                {
+                    log::info!("open recent menu clicked: {path}");
+                    if let Some(window) = app_handle.get_focused_window() {
+                        let _ = window.emit("menu-open-recent", path.to_string());
+                    }
                 } else if let Some(window) = app_handle.get_focused_window() {
                     // forward remaining menu clicks to the frontend
                     let event_name = format!("menu-{}", menu_id.0);
@@ -113,9 +311,6 @@ pub fn run() {
                 // mark that we received an open event (prevents default window in MainEventsCleared)
                 INITIAL_WINDOW_CREATED.store(true, Ordering::SeqCst);
 
-                // ensure a workspace window exists
-                command::show_or_create_workspace_window(app_handle);
-
                 // collect file paths from URLs
                 let mut paths = Vec::new();
                 for url in urls {
@@ -127,22 +322,7 @@ pub fn run() {
                     }
                 }
 
-                // always buffer for frontend pickup on mount (cold launch safety net —
-                // the event below may fire before the frontend listener is registered)
-                if let Some(state) = app_handle.try_state::<PendingFiles>() {
-                    log::info!("buffering {} file(s) for frontend pickup", paths.len());
-                    state.0.lock().unwrap().extend(paths.clone());
-                }
-
-                // also try emitting directly to the focused window for immediate
-                // handling when the frontend is already loaded (warm case).
-                // uses emit_to so only the targeted window opens the file.
-                if let Some(window) = app_handle.get_focused_window() {
-                    let target = window.label().to_string();
-                    for path in &paths {
-                        let _ = app_handle.emit_to(&target, "open-file-from-os", path.clone());
-                    }
-                }
+                open_paths_from_os(app_handle, paths);
             }
             RunEvent::MainEventsCleared => {
                 // on the first event loop iteration, create a default workspace window
@@ -159,43 +339,102 @@ pub fn run() {
         });
 }
 
+/// Opens files requested by the OS (file association, dock drop, Spotlight)
+/// in a workspace window, creating one if needed.
+pub(crate) fn open_paths_from_os(app_handle: &tauri::AppHandle, paths: Vec<String>) {
+    // ensure a workspace window exists
+    command::show_or_create_workspace_window(app_handle);
+
+    // always buffer for frontend pickup on mount (cold launch safety net —
+    // the event below may fire before the frontend listener is registered)
+    if let Some(state) = app_handle.try_state::<PendingFiles>() {
+        log::info!("buffering {} file(s) for frontend pickup", paths.len());
+        state.0.lock().unwrap().extend(paths.clone());
+    }
+
+    // also try emitting directly to the focused window for immediate
+    // handling when the frontend is already loaded (warm case).
+    // uses emit_to so only the targeted window opens the file.
+    if let Some(window) = app_handle.get_focused_window() {
+        let target = window.label().to_string();
+        for path in &paths {
+            let _ = app_handle.emit_to(&target, "open-file-from-os", path.clone());
+        }
+    }
+}
+
 const QUIT_MENU_ID: &str = "quit";
-const NEW_WINDOW_MENU_ID: &str = "new-window";
+pub(crate) const NEW_WINDOW_MENU_ID: &str = "new-window";
 const CLOSE_WINDOW_MENU_ID: &str = "close-window";
 const CLOSE_EDITOR_MENU_ID: &str = "close-editor";
 const SAVE_MENU_ID: &str = "save";
 const SAVE_ALL_MENU_ID: &str = "save-all";
-const NEW_FILE_MENU_ID: &str = "new-file";
-const OPEN_FILE_MENU_ID: &str = "open-file";
+pub(crate) const NEW_FILE_MENU_ID: &str = "new-file";
+pub(crate) const OPEN_FILE_MENU_ID: &str = "open-file";
+pub(crate) const OPEN_RECENT_MENU_ID: &str = "open-recent";
+
+/// Environment variables that describe the login shell process itself
+/// rather than the user's configuration, so they are not passed on to agents.
+const SHELL_ENV_IGNORED_VARS: &[&str] = &["_", "PWD", "OLDPWD", "SHLVL"];
 
-/// Resolve the user's shell PATH so that child processes spawned from the
-/// production .app bundle can find commands like `npx`, `node`, `opencode`, etc.
+/// The user's login-shell environment, resolved once at startup.
+static LOGIN_SHELL_ENV: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+/// Returns the login-shell environment (empty if it could not be resolved).
+pub(crate) fn login_shell_env() -> &'static HashMap<String, String> {
+    LOGIN_SHELL_ENV.get_or_init(HashMap::new)
+}
+
+/// Resolve the user's login-shell environment so that child processes spawned
+/// from the production .app bundle can find commands like `npx`, `node`, `opencode`, etc.
 /// When launched from Finder/Dock, macOS does not load the user's shell profile,
-/// leaving PATH as a minimal `/usr/bin:/bin:/usr/sbin:/sbin`.
+/// leaving PATH as a minimal `/usr/bin:/bin:/usr/sbin:/sbin` and missing
+/// variables set up by tools like nvm. PATH is applied to this process; the full
+/// environment is kept for agent processes (see `login_shell_env`).
 #[allow(deprecated)] // std::env::set_var is safe here during single-threaded setup
-fn load_shell_path() {
+fn load_shell_env() {
     let shell = std::env::var("SHELL").unwrap_or_else(|_| "/bin/zsh".to_string());
-    match std::process::Command::new(&shell)
-        .args(["-ilc", "printf '%s' \"$PATH\""])
+    let env = match std::process::Command::new(&shell)
+        .args(["-ilc", "env -0"])
         .output()
     {
-        Ok(output) if output.status.success() => {
-            let path = String::from_utf8_lossy(&output.stdout);
-            if !path.is_empty() {
-                log::info!("resolved shell PATH from {shell}");
-                std::env::set_var("PATH", path.as_ref());
-            }
-        }
+        Ok(output) if output.status.success() => parse_env_output(&output.stdout),
         Ok(output) => {
             log::warn!(
-                "failed to resolve PATH from {shell}: exit {}",
+                "failed to resolve environment from {shell}: exit {}",
                 output.status
             );
+            HashMap::new()
         }
         Err(err) => {
-            log::warn!("failed to run {shell} for PATH resolution: {err}");
+            log::warn!("failed to run {shell} for environment resolution: {err}");
+            HashMap::new()
         }
+    };
+
+    if let Some(path) = env.get("PATH").filter(|path| !path.is_empty()) {
+        log::info!("resolved shell PATH from {shell}");
+        std::env::set_var("PATH", path);
     }
+    log::info!("resolved {} environment variables from {shell}", env.len());
+    let _ = LOGIN_SHELL_ENV.set(env);
+}
+
+/// Parses NUL-separated `KEY=value` pairs as printed by `env -0`.
+/// Shell profiles may print to stdout, so anything before the first
+/// well-formed variable is dropped along with malformed entries.
+fn parse_env_output(stdout: &[u8]) -> HashMap<String, String> {
+    String::from_utf8_lossy(stdout)
+        .split('\0')
+        .filter_map(|entry| {
+            let (key, value) = entry.split_once('=')?;
+            let key = key.rsplit('\n').next().unwrap_or(key);
+            let valid = !key.is_empty()
+                && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                && !SHELL_ENV_IGNORED_VARS.contains(&key);
+            valid.then(|| (key.to_string(), value.to_string()))
+        })
+        .collect()
 }
 
 /// Recursively copies all files and directories from `src` to `dst`.
@@ -247,12 +486,16 @@ async fn copy_bundled_docs(
 }
 
 fn setup_app(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
-    // resolve shell PATH for production builds (no-op when launched from terminal)
-    load_shell_path();
+    // resolve the login-shell environment for production builds
+    // (PATH is unchanged when launched from a terminal)
+    load_shell_env();
 
     // create custom menu
     setup_app_menu(app)?;
 
+    // select the open vault (everything below resolves paths against it)
+    vaults::init(app.handle());
+
     // initialize default directories (blocking - must succeed before app starts)
     let init_handle = app.handle().clone();
     tauri::async_runtime::block_on(async move {
@@ -265,10 +508,10 @@ fn setup_app(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
         use tauri_plugin_store::StoreExt;
         let store = app
             .handle()
-            .store("settings.json")
+            .store(constants::SETTINGS_STORE_FILE_NAME)
             .map_err(|e| format!("failed to open settings store: {e}"))?;
         let done = store
-            .get("first-install-done")
+            .get(constants::FIRST_INSTALL_DONE_KEY)
             .and_then(|v| v.as_bool())
             .unwrap_or(false);
 
@@ -278,15 +521,51 @@ fn setup_app(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>> {
         }
     }
 
+    // load vault settings (managed extensions are needed by the watcher)
+    vault_settings::init(app.handle());
+
     // initialize file watcher
     file_watcher::init_file_watcher(app.handle().clone());
 
+    // handle opened spotlight results and refresh the index if enabled
+    spotlight::init(app.handle());
+
+    // open the full-text search index and catch up with changes made while closed
+    search_index::init(app.handle());
+
+    // build the in-memory tag index
+    note_index::init::<tags::TagIndex>(app.handle());
+
+    // build the backlink graph
+    note_index::init::<links::LinkIndex>(app.handle());
+
+    // map note ids to their current paths
+    note_index::init::<note_ids::NoteIdIndex>(app.handle());
+
+    // write staged editor content a previous run left behind
+    autosave::restore_staged(app.handle());
+
+    // periodically flush staged editor content
+    autosave::start_interval_flush(app.handle().clone());
+
+    // push/pull the vault in the background if a remote is configured
+    vault_sync::start_background_sync(app.handle().clone());
+
+    // keep vault repos packed as history grows
+    vault_maintenance::start_background_maintenance(app.handle().clone());
+
     // listen for quit confirmation from frontend
     let quit_handle = app.handle().clone();
     app.listen("confirm-quit", move |_event| {
         log::info!("quit confirmed by frontend, exiting");
         QUIT_CONFIRMED.store(true, Ordering::SeqCst);
-        quit_handle.exit(0);
+        // write any staged editor content before exiting
+        let handle = quit_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            autosave::flush_all(&handle, "quit").await;
+            checkpoints::flush_on_quit(&handle).await;
+            handle.exit(0);
+        });
     });
 
     // NOTE: Window creation is deferred to the run event loop (MainEventsCleared)
@@ -348,6 +627,8 @@ fn setup_app_menu(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>
         true,
         None::<&str>,
     )?;
+    // filled with the focused window's vault files by `recents::sync_recent_menu`
+    let open_recent_submenu = Submenu::with_id(handle, OPEN_RECENT_MENU_ID, "Open Recent", false)?;
     let save_item = MenuItem::with_id(handle, SAVE_MENU_ID, "Save", true, None::<&str>)?;
     let save_all_item =
         MenuItem::with_id(handle, SAVE_ALL_MENU_ID, "Save All", true, None::<&str>)?;
@@ -375,6 +656,7 @@ fn setup_app_menu(app: &mut tauri::App) -> Result<(), Box<dyn std::error::Error>
             &new_window_item,
             &PredefinedMenuItem::separator(handle)?,
             &open_file_item,
+            &open_recent_submenu,
             &PredefinedMenuItem::separator(handle)?,
             &save_item,
             &save_all_item,
@@ -0,0 +1,395 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path};
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{AppHandle, State, WebviewWindow};
+use tokio::fs;
+
+use crate::{
+    command::{list_dir_inner, FSEntry},
+    note_index::NoteIndex,
+    utils::resolve_path,
+    vault_settings, vaults,
+};
+
+/// longest line excerpt kept as link context
+const CONTEXT_CHARS: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkKind {
+    /// `[[note]]`, `[[folder/note|alias]]`, `[[note#heading]]`
+    Wiki,
+    /// `[text](relative/note.md)`
+    Markdown,
+}
+
+/// a link as written in a note
+#[derive(Debug, Clone)]
+pub struct RawLink {
+    kind: LinkKind,
+    /// wiki links: the name as written. markdown links: vault-relative path.
+    target: String,
+    /// 1-based line number
+    line: usize,
+    context: String,
+}
+
+/// outgoing links of every note in the vault, keyed by vault-relative path.
+/// targets are resolved at query time so links to notes created later still match.
+#[derive(Default)]
+pub struct LinkIndex(Mutex<HashMap<String, Vec<RawLink>>>);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Backlink {
+    /// the note containing the link
+    pub source: String,
+    pub kind: LinkKind,
+    pub line: usize,
+    /// the line the link appears on
+    pub context: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutgoingLink {
+    /// the target as written in the note
+    pub target: String,
+    /// the linked note, `None` if it does not exist
+    pub path: Option<String>,
+    pub kind: LinkKind,
+    pub line: usize,
+}
+
+/// a link whose target note does not exist
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BrokenLink {
+    /// the note containing the link
+    pub source: String,
+    /// the target as written in the note
+    pub target: String,
+    pub kind: LinkKind,
+    pub line: usize,
+    pub context: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultLinkReport {
+    pub note_count: usize,
+    /// sorted by source note and line
+    pub broken_links: Vec<BrokenLink>,
+    /// notes that link to no other note and that no other note links to
+    pub orphans: Vec<String>,
+}
+
+// -----------------------------------------
+// parsing
+// -----------------------------------------
+
+/// joins `target` onto the note's directory and resolves `.`/`..` lexically.
+/// returns `None` for targets escaping the vault.
+fn join_relative(note_path: &str, target: &str) -> Option<String> {
+    let base = Path::new(note_path).parent().unwrap_or(Path::new(""));
+    let mut parts: Vec<String> = Vec::new();
+    for component in base.join(target).components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().to_string()),
+            Component::ParentDir => {
+                parts.pop()?;
+            }
+            Component::CurDir => {}
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(parts.join("/"))
+}
+
+/// decodes `%xx` escapes (e.g. `%20`) in markdown link targets
+pub(crate) fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or("");
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// vault-relative note path of a markdown link target, or `None` for urls,
+/// anchors and links to non-notes
+fn markdown_target(note_path: &str, target: &str) -> Option<String> {
+    let target = target.split_whitespace().next()?.trim_matches(['<', '>']);
+    if target.starts_with('#') || target.starts_with('/') || target.contains(':') {
+        return None;
+    }
+    let target = target.split('#').next()?;
+    let target = percent_decode(target);
+    if !target.ends_with(".md") {
+        return None;
+    }
+    join_relative(note_path, &target)
+}
+
+fn context_of(line: &str) -> String {
+    line.trim().chars().take(CONTEXT_CHARS).collect()
+}
+
+fn parse_links(note_path: &str, content: &str) -> Vec<RawLink> {
+    let mut links = Vec::new();
+    let mut in_fence = false;
+    for (index, line) in content.lines().enumerate() {
+        if line.trim_start().starts_with("```") || line.trim_start().starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let mut rest = line;
+        while let Some(start) = rest.find("[[") {
+            let after = &rest[start + 2..];
+            let Some(end) = after.find("]]") else {
+                break;
+            };
+            let name = after[..end].split(['|', '#']).next().unwrap_or("").trim();
+            if !name.is_empty() {
+                links.push(RawLink {
+                    kind: LinkKind::Wiki,
+                    target: name.to_string(),
+                    line: index + 1,
+                    context: context_of(line),
+                });
+            }
+            rest = &after[end + 2..];
+        }
+
+        let mut rest = line;
+        while let Some(start) = rest.find("](") {
+            let after = &rest[start + 2..];
+            let Some(end) = after.find(')') else {
+                break;
+            };
+            if let Some(target) = markdown_target(note_path, &after[..end]) {
+                links.push(RawLink {
+                    kind: LinkKind::Markdown,
+                    target,
+                    line: index + 1,
+                    context: context_of(line),
+                });
+            }
+            rest = &after[end + 1..];
+        }
+    }
+    links
+}
+
+// -----------------------------------------
+// resolution
+// -----------------------------------------
+
+/// maps wiki link targets to note paths. `[[folder/note]]` matches by path,
+/// `[[note]]` matches by file name (the shortest path wins on ambiguity).
+struct Resolver<'a> {
+    paths: HashSet<&'a str>,
+    by_name: HashMap<String, &'a str>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new(index: &'a HashMap<String, Vec<RawLink>>) -> Self {
+        let mut notes: Vec<&str> = index.keys().map(|path| path.as_str()).collect();
+        notes.sort_by_key(|path| (path.len(), *path));
+
+        let mut by_name = HashMap::new();
+        for path in &notes {
+            if let Some(stem) = Path::new(path).file_stem() {
+                by_name
+                    .entry(stem.to_string_lossy().to_lowercase())
+                    .or_insert(*path);
+            }
+        }
+        Self {
+            paths: notes.into_iter().collect(),
+            by_name,
+        }
+    }
+
+    fn resolve(&self, link: &RawLink) -> Option<&'a str> {
+        match link.kind {
+            LinkKind::Markdown => self.paths.get(link.target.as_str()).copied(),
+            LinkKind::Wiki => {
+                let target = link.target.trim_end_matches(".md");
+                if target.contains('/') {
+                    let path = format!("{}.md", target.trim_start_matches('/'));
+                    self.paths.get(path.as_str()).copied()
+                } else {
+                    self.by_name.get(&target.to_lowercase()).copied()
+                }
+            }
+        }
+    }
+}
+
+// -----------------------------------------
+// indexing
+// -----------------------------------------
+
+impl NoteIndex for LinkIndex {
+    type Entry = Vec<RawLink>;
+
+    const NAME: &'static str = "links";
+
+    fn entries(&self) -> &Mutex<HashMap<String, Vec<RawLink>>> {
+        &self.0
+    }
+
+    fn parse(path: &str, content: &str) -> Option<Vec<RawLink>> {
+        Some(parse_links(path, content))
+    }
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+/// notes linking to `path`, sorted by source note and line
+#[tauri::command]
+pub fn get_backlinks(
+    webview_window: WebviewWindow,
+    state: State<'_, LinkIndex>,
+    path: String,
+) -> Result<Vec<Backlink>, String> {
+    vaults::ensure_active_vault(&webview_window)?;
+    let index = state.0.lock().unwrap();
+    let resolver = Resolver::new(&index);
+
+    let mut backlinks: Vec<Backlink> = index
+        .iter()
+        .filter(|(source, _)| **source != path)
+        .flat_map(|(source, links)| {
+            links
+                .iter()
+                .filter(|link| resolver.resolve(link) == Some(path.as_str()))
+                .map(|link| Backlink {
+                    source: source.clone(),
+                    kind: link.kind,
+                    line: link.line,
+                    context: link.context.clone(),
+                })
+        })
+        .collect();
+    backlinks.sort_by(|a, b| a.source.cmp(&b.source).then(a.line.cmp(&b.line)));
+    Ok(backlinks)
+}
+
+/// links in `path`, in document order. unresolved links have no `path`.
+#[tauri::command]
+pub fn get_outgoing_links(
+    webview_window: WebviewWindow,
+    state: State<'_, LinkIndex>,
+    path: String,
+) -> Result<Vec<OutgoingLink>, String> {
+    vaults::ensure_active_vault(&webview_window)?;
+    let index = state.0.lock().unwrap();
+    let resolver = Resolver::new(&index);
+
+    let Some(links) = index.get(&path) else {
+        return Ok(Vec::new());
+    };
+    let mut outgoing: Vec<OutgoingLink> = links
+        .iter()
+        .map(|link| OutgoingLink {
+            target: link.target.clone(),
+            path: resolver.resolve(link).map(|path| path.to_string()),
+            kind: link.kind,
+            line: link.line,
+        })
+        .collect();
+    outgoing.sort_by_key(|link| link.line);
+    Ok(outgoing)
+}
+
+/// broken internal links and orphaned notes of the whole vault. notes are
+/// read fresh rather than from the link index, which only follows the
+/// active vault.
+#[tauri::command]
+pub async fn check_vault_links(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+) -> Result<VaultLinkReport, String> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("checking vault links");
+
+        let root = resolve_path(&app_handle, "")?;
+        let mut entries: Vec<FSEntry> = Vec::new();
+        list_dir_inner(&root, "", true, &mut entries).await?;
+
+        let mut index: HashMap<String, Vec<RawLink>> = HashMap::new();
+        for entry in entries {
+            if entry.is_dir || !vault_settings::is_managed_file(&entry.path) {
+                continue;
+            }
+            let Ok(content) = fs::read_to_string(root.join(&entry.path)).await else {
+                continue;
+            };
+            let links = parse_links(&entry.path, &content);
+            index.insert(entry.path, links);
+        }
+
+        let resolver = Resolver::new(&index);
+        let mut broken_links = Vec::new();
+        let mut linked: HashSet<&str> = HashSet::new();
+        for (source, links) in &index {
+            for link in links {
+                match resolver.resolve(link) {
+                    Some(target) if target != source.as_str() => {
+                        linked.insert(source.as_str());
+                        linked.insert(target);
+                    }
+                    Some(_) => {}
+                    None => broken_links.push(BrokenLink {
+                        source: source.clone(),
+                        target: link.target.clone(),
+                        kind: link.kind,
+                        line: link.line,
+                        context: link.context.clone(),
+                    }),
+                }
+            }
+        }
+        broken_links.sort_by(|a, b| a.source.cmp(&b.source).then(a.line.cmp(&b.line)));
+
+        let mut orphans: Vec<String> = index
+            .keys()
+            .filter(|path| !linked.contains(path.as_str()))
+            .cloned()
+            .collect();
+        orphans.sort();
+
+        log::info!(
+            "vault links: {} broken, {} orphans in {} notes",
+            broken_links.len(),
+            orphans.len(),
+            index.len()
+        );
+        Ok(VaultLinkReport {
+            note_count: index.len(),
+            broken_links,
+            orphans,
+        })
+    })
+    .await
+}
@@ -0,0 +1,135 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, WebviewWindow};
+use tokio::fs;
+
+use crate::{
+    constants::{LOCKS_FILE_NAME, VAULT_CONFIG_DIR_NAME},
+    utils::{get_base_dir, resolve_path},
+    vaults,
+};
+
+/// read-only notes of a vault, stored in `<vault>/.flowrite/locks.json`
+/// so the lock travels with the vault and works for notes of any format
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct Locks {
+    /// vault-relative paths
+    paths: Vec<String>,
+}
+
+/// why a change to a file was refused
+#[derive(Debug, Clone, Serialize, thiserror::Error)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FileOpError {
+    /// the note (or one inside the folder) is locked; unlock it first
+    #[error("'{path}' is locked")]
+    Locked { path: String },
+    /// the file on disk no longer matches what the editor loaded. carries the
+    /// current content so the frontend can merge and retry.
+    #[error("'{path}' was changed on disk")]
+    #[serde(rename_all = "camelCase")]
+    Conflict {
+        path: String,
+        current_content: String,
+        current_hash: String,
+        current_modified_time_ms: u64,
+    },
+    #[error("{message}")]
+    Failed { message: String },
+}
+
+impl From<String> for FileOpError {
+    fn from(message: String) -> Self {
+        Self::Failed { message }
+    }
+}
+
+fn get_locks_path(vault_root: &Path) -> std::path::PathBuf {
+    vault_root.join(VAULT_CONFIG_DIR_NAME).join(LOCKS_FILE_NAME)
+}
+
+fn load(vault_root: &Path) -> Locks {
+    match std::fs::read_to_string(get_locks_path(vault_root)) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            log::warn!("ignoring malformed note locks: {e}");
+            Locks::default()
+        }),
+        Err(_) => Locks::default(),
+    }
+}
+
+/// the first locked note at `path` or inside it, if any
+pub fn find_locked(vault_root: &Path, path: &str) -> Option<String> {
+    let path = path.trim_matches('/');
+    load(vault_root)
+        .paths
+        .into_iter()
+        .find(|locked| locked == path || locked.starts_with(&format!("{path}/")))
+}
+
+/// refuses changes to a locked note, or to a folder holding one
+pub fn ensure_unlocked(vault_root: &Path, path: &str) -> Result<(), FileOpError> {
+    match find_locked(vault_root, path) {
+        Some(path) => Err(FileOpError::Locked { path }),
+        None => Ok(()),
+    }
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+/// locks a note against edits, deletes and renames, or unlocks it
+#[tauri::command]
+pub async fn set_note_locked(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    path: String,
+    locked: bool,
+) -> Result<(), String> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("setting note locked: {path} ({locked})");
+
+        if locked && !resolve_path(&app_handle, &path)?.is_file() {
+            return Err(format!("file '{path}' does not exist"));
+        }
+        // stored the way find_locked compares them
+        let path = path.trim_matches('/').to_string();
+        let vault_root = get_base_dir(&app_handle)?;
+        let mut locks = load(&vault_root);
+        locks
+            .paths
+            .retain(|locked_path| locked_path.trim_matches('/') != path);
+        if locked {
+            locks.paths.push(path);
+            locks.paths.sort();
+        }
+
+        let locks_path = get_locks_path(&vault_root);
+        if let Some(parent) = locks_path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .map_err(|e| format!("failed to create vault config directory: {e}"))?;
+        }
+        let content = serde_json::to_string_pretty(&locks)
+            .map_err(|e| format!("failed to serialize note locks: {e}"))?;
+        fs::write(&locks_path, content)
+            .await
+            .map_err(|e| format!("failed to write note locks: {e}"))
+    })
+    .await
+}
+
+/// locked notes of the window's vault
+#[tauri::command]
+pub async fn list_locked_notes(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+) -> Result<Vec<String>, String> {
+    vaults::scoped(&webview_window, async move {
+        Ok(load(&get_base_dir(&app_handle)?).paths)
+    })
+    .await
+}
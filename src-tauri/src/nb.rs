@@ -1,20 +1,53 @@
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-use tauri::{AppHandle, Manager};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tauri::{AppHandle, Emitter, Manager};
 use tauri_plugin_shell::ShellExt;
 use tokio::fs;
-use trash::TrashContext;
+use tokio::sync::{Mutex, OwnedMutexGuard};
 
-use crate::constants::{NB_DATA_DIR_NAME, NB_RC_FILE_NAME};
-use crate::utils::get_base_dir;
+use crate::checkpoints;
+use crate::constants::{
+    NB_DATA_DIR_NAME, NB_FAILURE_EVENT, NB_INSTALL_CACHE_FILE_NAME, NB_INSTALL_PROGRESS_EVENT,
+    NB_RC_FILE_NAME, SYNC_REMOTE_NAME,
+};
+use crate::encryption;
+use crate::file_watcher;
+use crate::fs_cache;
+use crate::journal::{self, JournalOp, JournalOrigin};
+use crate::locks;
+use crate::preflight;
+use crate::secrets;
+use crate::utils::{get_base_dir, resolve_path, write_atomic};
+use crate::vault_trash;
+use crate::vaults;
+use crate::vcs;
 
-/// version of nb to download and use
+/// version of nb to bundle and use
 const NB_VERSION: &str = "7.14.4";
 
+/// sha256 of the nb script at `NB_VERSION`; update both together
+/// (`curl -fsSL https://raw.githubusercontent.com/xwmx/nb/<version>/nb | shasum -a 256`).
+/// no script is installed while this is `None`.
+const NB_SHA256: Option<&str> = None;
+
+/// where the nb script is shipped in the bundle's resources. the folder is
+/// always bundled so plain cargo builds work; app builds fetch the script
+/// into it with `scripts/fetch-nb.sh`.
+const NB_RESOURCE_DIR: &str = "bin";
+const NB_RESOURCE_NAME: &str = "nb";
+
 /// binary name for the nb executable
 const NB_BINARY_NAME: &str = "fwnb";
 
+/// disk space needed to install the nb script
+const NB_INSTALL_REQUIRED_BYTES: u64 = 2 * 1024 * 1024;
+
 // -----------------------------------------
 // nb binary management
 // -----------------------------------------
@@ -92,94 +125,269 @@ async fn get_installed_version(app_handle: &AppHandle) -> Option<String> {
         .ok()?;
 
     if output.status.success() {
-        let version_output = String::from_utf8_lossy(&output.stdout);
-        // nb version output format: "nb version X.X.X"
-        version_output
-            .split_whitespace()
-            .last()
-            .map(|s| s.to_string())
+        parse_version(&String::from_utf8_lossy(&output.stdout))
     } else {
         None
     }
 }
 
-/// ensure nb is installed with correct version, downloading if necessary
-pub async fn ensure_nb_installed(app_handle: &AppHandle) -> Result<(), String> {
-    let binary_path = get_nb_binary_path(app_handle)?;
+/// the version in `nb version` output ("nb version X.X.X")
+fn parse_version(output: &str) -> Option<String> {
+    output.split_whitespace().last().map(|s| s.to_string())
+}
 
-    // check if binary exists and has correct version
-    if binary_path.exists() {
-        if let Some(installed_version) = get_installed_version(app_handle).await {
-            if installed_version == NB_VERSION {
-                log::info!("fwnb {} already installed", NB_VERSION);
-                return Ok(());
-            }
-            log::info!(
-                "fwnb version mismatch: installed={}, required={}. reinstalling...",
-                installed_version,
-                NB_VERSION
-            );
-            // delete existing binary
-            fs::remove_file(&binary_path)
-                .await
-                .map_err(|e| format!("failed to remove old fwnb binary: {e}"))?;
-        } else {
-            log::warn!("could not determine installed fwnb version, reinstalling...");
-            fs::remove_file(&binary_path)
-                .await
-                .map_err(|e| format!("failed to remove fwnb binary: {e}"))?;
-        }
+/// version + binary hash recorded after a successful version check, so later
+/// launches can skip spawning nb (a slow bash startup) when nothing changed
+#[derive(Debug, Serialize, Deserialize)]
+struct NbInstallCache {
+    version: String,
+    sha256: String,
+}
+
+fn get_nb_install_cache_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let app_data = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("failed to get app data directory: {e}"))?;
+    Ok(app_data.join(NB_INSTALL_CACHE_FILE_NAME))
+}
+
+async fn hash_nb_binary(binary_path: &Path) -> Option<String> {
+    let bytes = fs::read(binary_path).await.ok()?;
+    Some(format!("{:x}", Sha256::digest(&bytes)))
+}
+
+/// true if the cached verification still matches the binary on disk
+async fn is_install_cache_valid(app_handle: &AppHandle, binary_path: &Path) -> bool {
+    let Ok(cache_path) = get_nb_install_cache_path(app_handle) else {
+        return false;
+    };
+    let Some(cache) = fs::read_to_string(&cache_path)
+        .await
+        .ok()
+        .and_then(|content| serde_json::from_str::<NbInstallCache>(&content).ok())
+    else {
+        return false;
+    };
+    if cache.version != NB_VERSION {
+        return false;
     }
+    hash_nb_binary(binary_path)
+        .await
+        .is_some_and(|sha256| sha256 == cache.sha256)
+}
 
-    log::info!("downloading fwnb {}...", NB_VERSION);
+async fn write_install_cache(app_handle: &AppHandle, binary_path: &Path) {
+    let Some(sha256) = hash_nb_binary(binary_path).await else {
+        return;
+    };
+    let cache = NbInstallCache {
+        version: NB_VERSION.to_string(),
+        sha256,
+    };
+    let result = match (
+        get_nb_install_cache_path(app_handle),
+        serde_json::to_string(&cache),
+    ) {
+        (Ok(path), Ok(content)) => fs::write(path, content).await.map_err(|e| e.to_string()),
+        (Err(e), _) => Err(e),
+        (_, Err(e)) => Err(e.to_string()),
+    };
+    if let Err(e) = result {
+        log::warn!("failed to write fwnb install cache: {e}");
+    }
+}
 
-    // ensure parent directory exists
-    if let Some(parent) = binary_path.parent() {
-        fs::create_dir_all(parent)
-            .await
-            .map_err(|e| format!("failed to create bin directory: {e}"))?;
+/// forgets the cached verification so the next check spawns nb again
+async fn clear_install_cache(app_handle: &AppHandle) {
+    if let Ok(path) = get_nb_install_cache_path(app_handle) {
+        let _ = fs::remove_file(path).await;
     }
+}
 
-    // download nb using curl
-    let download_url = format!(
-        "https://raw.githubusercontent.com/xwmx/nb/{}/nb",
-        NB_VERSION
-    );
+/// steps of `ensure_nb_installed`, reported to the splash screen
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NbInstallStage {
+    /// asking the installed binary for its version
+    Checking,
+    /// installing the bundled script
+    Installing,
+    /// the bundled script was unusable, fetching it instead
+    Downloading,
+    /// running the new binary once
+    Verifying,
+    Ready,
+}
 
-    let binary_path_str = binary_path
-        .to_str()
-        .ok_or("binary path contains invalid UTF-8")?;
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NbInstallProgress {
+    stage: NbInstallStage,
+    version: &'static str,
+    /// present while downloading
+    #[serde(skip_serializing_if = "Option::is_none")]
+    downloaded_bytes: Option<u64>,
+    /// the download size, if the server sent it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_bytes: Option<u64>,
+}
 
-    let output = app_handle
-        .shell()
-        .command("curl")
-        .args(["-fsSL", "-o", binary_path_str, &download_url])
-        .output()
-        .await
-        .map_err(|e| format!("failed to download fwnb: {e}"))?;
+fn emit_install_progress(
+    app_handle: &AppHandle,
+    stage: NbInstallStage,
+    download: Option<(u64, Option<u64>)>,
+) {
+    let payload = NbInstallProgress {
+        stage,
+        version: NB_VERSION,
+        downloaded_bytes: download.map(|(downloaded, _)| downloaded),
+        total_bytes: download.and_then(|(_, total)| total),
+    };
+    if let Err(e) = app_handle.emit(NB_INSTALL_PROGRESS_EVENT, payload) {
+        log::error!("failed to emit nb install progress: {e}");
+    }
+}
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("failed to download fwnb: {stderr}"));
+/// checks `script` against `NB_SHA256` and installs it as an executable at
+/// `binary_path`. the script is written next to the target and renamed into
+/// place only once verified, so a failed install never leaves a partial binary.
+async fn install_nb_script(binary_path: &Path, script: &[u8], source: &str) -> Result<(), String> {
+    let sha256 = format!("{:x}", Sha256::digest(script));
+    let Some(expected) = NB_SHA256 else {
+        return Err(format!(
+            "{source} fwnb cannot be verified: no sha256 is pinned for nb {NB_VERSION} (got {sha256})"
+        ));
+    };
+    if sha256 != expected {
+        return Err(format!(
+            "{source} fwnb failed verification: expected sha256 {expected}, got {sha256}"
+        ));
+    }
+
+    let staging_path = binary_path.with_extension("download");
+    fs::write(&staging_path, script)
+        .await
+        .map_err(|e| format!("failed to write fwnb binary: {e}"))?;
+    if let Err(e) = fs::set_permissions(&staging_path, std::fs::Permissions::from_mode(0o755)).await
+    {
+        let _ = fs::remove_file(&staging_path).await;
+        return Err(format!("failed to set executable permission: {e}"));
     }
+    fs::rename(&staging_path, binary_path)
+        .await
+        .map_err(|e| format!("failed to install fwnb binary: {e}"))?;
+    log::info!("installed {source} fwnb ({} bytes)", script.len());
+    Ok(())
+}
 
-    log::info!("fwnb downloaded successfully");
+/// installs the nb script shipped in the app bundle (`resources/bin/nb`), so
+/// first launch works offline. it is checked against `NB_SHA256` like a
+/// downloaded script.
+async fn install_bundled_nb(app_handle: &AppHandle, binary_path: &Path) -> Result<(), String> {
+    let resource_path = app_handle
+        .path()
+        .resource_dir()
+        .map_err(|e| format!("failed to resolve resource dir: {e}"))?
+        .join("resources")
+        .join(NB_RESOURCE_DIR)
+        .join(NB_RESOURCE_NAME);
+    let script = fs::read(&resource_path)
+        .await
+        .map_err(|e| format!("failed to read bundled fwnb: {e}"))?;
+    install_nb_script(binary_path, &script, "bundled").await
+}
 
-    // make executable
-    let output = app_handle
-        .shell()
-        .command("chmod")
-        .args(["+x", binary_path_str])
-        .output()
+/// downloads the pinned nb script over https; only used when the bundled
+/// copy is missing or does not match `NB_VERSION`
+async fn download_nb(app_handle: &AppHandle, binary_path: &Path) -> Result<(), String> {
+    let download_url = format!("https://raw.githubusercontent.com/xwmx/nb/{NB_VERSION}/nb");
+    log::info!("downloading fwnb {NB_VERSION}...");
+
+    emit_install_progress(app_handle, NbInstallStage::Downloading, Some((0, None)));
+    let mut response = reqwest::get(&download_url).await.map_err(|e| {
+        if e.is_connect() || e.is_timeout() {
+            format!("failed to download fwnb, check your internet connection: {e}")
+        } else {
+            format!("failed to download fwnb: {e}")
+        }
+    })?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!(
+            "failed to download fwnb: {download_url} returned {status}"
+        ));
+    }
+    let total_bytes = response.content_length();
+    let mut bytes = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
         .await
-        .map_err(|e| format!("failed to set executable permission: {e}"))?;
+        .map_err(|e| format!("failed to download fwnb: connection lost ({e})"))?
+    {
+        bytes.extend_from_slice(&chunk);
+        emit_install_progress(
+            app_handle,
+            NbInstallStage::Downloading,
+            Some((bytes.len() as u64, total_bytes)),
+        );
+    }
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("failed to set executable permission: {stderr}"));
+    install_nb_script(binary_path, &bytes, "downloaded").await
+}
+
+/// ensure nb is installed with correct version, installing it from the bundle
+/// (or downloading it) if necessary. progress is emitted as
+/// `nb-install-progress` events for the splash screen.
+/// skips the version check when the binary hash matches the cached verification.
+pub async fn ensure_nb_installed(app_handle: &AppHandle) -> Result<(), String> {
+    let binary_path = get_nb_binary_path(app_handle)?;
+
+    if binary_path.exists() && is_install_cache_valid(app_handle, &binary_path).await {
+        log::info!("fwnb {} already installed (cached)", NB_VERSION);
+        emit_install_progress(app_handle, NbInstallStage::Ready, None);
+        return Ok(());
+    }
+
+    // check if binary exists and has correct version. a working binary of
+    // another version is kept until its replacement is installed, so startup
+    // still succeeds when the upgrade cannot be fetched (e.g. offline).
+    emit_install_progress(app_handle, NbInstallStage::Checking, None);
+    let installed_version = if binary_path.exists() {
+        get_installed_version(app_handle).await
+    } else {
+        None
+    };
+    match &installed_version {
+        Some(version) if version == NB_VERSION => {
+            log::info!("fwnb {} already installed", NB_VERSION);
+            write_install_cache(app_handle, &binary_path).await;
+            emit_install_progress(app_handle, NbInstallStage::Ready, None);
+            return Ok(());
+        }
+        Some(version) => log::info!(
+            "fwnb version mismatch: installed={}, required={}. reinstalling...",
+            version,
+            NB_VERSION
+        ),
+        None if binary_path.exists() => {
+            log::warn!("could not determine installed fwnb version, reinstalling...")
+        }
+        None => {}
+    }
+
+    log::info!("installing fwnb {}...", NB_VERSION);
+    if let Err(e) = install_nb(app_handle, &binary_path).await {
+        if let Some(version) = installed_version {
+            log::warn!("failed to upgrade fwnb, keeping installed {version}: {e}");
+            emit_install_progress(app_handle, NbInstallStage::Ready, None);
+            return Ok(());
+        }
+        return Err(e);
     }
 
     // verify installation
+    emit_install_progress(app_handle, NbInstallStage::Verifying, None);
     let env = get_nb_env(app_handle)?;
     let output = app_handle
         .shell()
@@ -192,7 +400,15 @@ pub async fn ensure_nb_installed(app_handle: &AppHandle) -> Result<(), String> {
 
     if output.status.success() {
         let version = String::from_utf8_lossy(&output.stdout);
+        if parse_version(&version).as_deref() != Some(NB_VERSION) {
+            return Err(format!(
+                "fwnb verification failed: expected version {NB_VERSION}, got '{}'",
+                version.trim()
+            ));
+        }
         log::info!("fwnb installed successfully: {}", version.trim());
+        write_install_cache(app_handle, &binary_path).await;
+        emit_install_progress(app_handle, NbInstallStage::Ready, None);
         Ok(())
     } else {
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -200,17 +416,320 @@ pub async fn ensure_nb_installed(app_handle: &AppHandle) -> Result<(), String> {
     }
 }
 
+/// installs the pinned nb script from the bundle, or downloads it
+async fn install_nb(app_handle: &AppHandle, binary_path: &Path) -> Result<(), String> {
+    // ensure parent directory exists and can hold the script
+    if let Some(parent) = binary_path.parent() {
+        preflight::check(parent, NB_INSTALL_REQUIRED_BYTES).await?;
+        fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("failed to create bin directory: {e}"))?;
+    }
+
+    emit_install_progress(app_handle, NbInstallStage::Installing, None);
+    if let Err(e) = install_bundled_nb(app_handle, binary_path).await {
+        log::warn!("{e}, falling back to download");
+        download_nb(app_handle, binary_path).await?;
+    }
+    Ok(())
+}
+
+/// re-checks the nb installation from scratch (ignoring the cache),
+/// reinstalling if needed. returns the verified version.
+pub async fn verify_installation(app_handle: &AppHandle) -> Result<String, String> {
+    clear_install_cache(app_handle).await;
+    ensure_nb_installed(app_handle).await?;
+    get_installed_version(app_handle)
+        .await
+        .ok_or_else(|| "could not determine installed fwnb version".to_string())
+}
+
+// -----------------------------------------
+// failure classification
+// -----------------------------------------
+
+/// common nb/git failure modes, classified from stderr
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NbFailureKind {
+    /// the fwnb script (or the git it runs) is missing or not executable
+    BinaryMissing,
+    /// nb's .index no longer matches the files on disk
+    IndexOutOfSync,
+    /// a stale .git/index.lock left behind by a crashed git process
+    LockFilePresent,
+    /// the notebook or its git directory is not writable
+    PermissionDenied,
+    /// ~/flowrite is missing its git repo or nb index
+    RepoMissing,
+    Unknown,
+}
+
+/// actions the frontend can trigger to fix a classified failure
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NbRemediation {
+    RepairIndex,
+    RemoveLock,
+    Reinit,
+    Reinstall,
+}
+
+#[derive(Clone, Debug, Serialize, thiserror::Error)]
+#[serde(rename_all = "camelCase")]
+#[error("{message}")]
+pub struct NbFailure {
+    pub kind: NbFailureKind,
+    /// what went wrong, fit to show to the user
+    pub message: String,
+    /// trimmed stderr from nb/git, for logs and a "details" disclosure
+    pub details: Option<String>,
+    /// what the user can do about it
+    pub suggestion: Option<String>,
+    pub remediation: Option<NbRemediation>,
+}
+
+/// the last line of nb/git output that says something, without bash's
+/// "<script>: line <n>:" and git's "fatal:"/"error:" prefixes
+fn summarize_stderr(stderr: &str) -> String {
+    let line = stderr
+        .lines()
+        .map(str::trim)
+        .rfind(|line| !line.is_empty())
+        .unwrap_or("unknown error");
+    let line = match line.find(": line ") {
+        Some(at) => line[at + ": line ".len()..]
+            .split_once(": ")
+            .map_or(line, |(_, rest)| rest),
+        None => line,
+    };
+    ["fatal: ", "error: ", "Error: "]
+        .iter()
+        .fold(line, |line, prefix| {
+            line.strip_prefix(prefix).unwrap_or(line)
+        })
+        .to_string()
+}
+
+/// stderr from nb when its `.index` no longer matches the files on disk.
+/// "index" alone is too broad: git's `index.lock` and reindex progress
+/// messages mention it too.
+const NB_INDEX_ERRORS: &[&str] = &[
+    "/.index:",
+    "not found in index",
+    "index is out of sync",
+    "index out of sync",
+    "nb index reconcile",
+];
+
+impl NbFailure {
+    fn classify(stderr: &str) -> Self {
+        let details = stderr.trim().to_string();
+        let lower = details.to_lowercase();
+
+        let (kind, remediation) = if lower.contains("command not found")
+            || (lower.contains("no such file or directory") && lower.contains("fwnb"))
+        {
+            let remediation =
+                (!lower.contains("git: command not found")).then_some(NbRemediation::Reinstall);
+            (NbFailureKind::BinaryMissing, remediation)
+        } else if lower.contains("index.lock") || lower.contains(".lock': file exists") {
+            (
+                NbFailureKind::LockFilePresent,
+                Some(NbRemediation::RemoveLock),
+            )
+        } else if lower.contains("permission denied") || lower.contains("operation not permitted") {
+            (NbFailureKind::PermissionDenied, None)
+        } else if lower.contains("not a git repository")
+            || lower.contains("could not find repository")
+            || lower.contains("not a valid notebook")
+        {
+            (NbFailureKind::RepoMissing, Some(NbRemediation::Reinit))
+        } else if NB_INDEX_ERRORS.iter().any(|needle| lower.contains(needle)) {
+            (
+                NbFailureKind::IndexOutOfSync,
+                Some(NbRemediation::RepairIndex),
+            )
+        } else {
+            (NbFailureKind::Unknown, None)
+        };
+
+        let (message, suggestion) = match kind {
+            NbFailureKind::BinaryMissing if remediation.is_none() => (
+                "git is not installed".to_string(),
+                Some("install the command line developer tools (xcode-select --install)"),
+            ),
+            NbFailureKind::BinaryMissing => (
+                "the nb helper is missing or broken".to_string(),
+                Some("reinstall nb"),
+            ),
+            NbFailureKind::LockFilePresent => (
+                "another git process left the vault locked".to_string(),
+                Some("remove the stale lock file if no other git program is running"),
+            ),
+            NbFailureKind::PermissionDenied => (
+                "flowrite is not allowed to write to the vault".to_string(),
+                Some("check the vault folder's permissions, or grant flowrite access in System Settings > Privacy & Security"),
+            ),
+            NbFailureKind::RepoMissing => (
+                "the vault is not an nb notebook".to_string(),
+                Some("re-initialize the vault; your notes are kept"),
+            ),
+            NbFailureKind::IndexOutOfSync => (
+                "the vault index is out of sync with the files on disk".to_string(),
+                Some("rebuild the index"),
+            ),
+            NbFailureKind::Unknown => (summarize_stderr(&details), None),
+        };
+
+        Self {
+            kind,
+            message,
+            details: (!details.is_empty()).then_some(details),
+            suggestion: suggestion.map(str::to_string),
+            remediation,
+        }
+    }
+
+    fn unclassified(message: String) -> Self {
+        Self {
+            kind: NbFailureKind::Unknown,
+            message,
+            details: None,
+            suggestion: None,
+            remediation: None,
+        }
+    }
+
+    /// the fwnb script could not be started at all
+    fn binary_missing(details: String) -> Self {
+        Self {
+            kind: NbFailureKind::BinaryMissing,
+            message: "the nb helper is missing or broken".to_string(),
+            details: Some(details),
+            suggestion: Some("reinstall nb".to_string()),
+            remediation: Some(NbRemediation::Reinstall),
+        }
+    }
+}
+
+impl From<NbFailure> for String {
+    fn from(failure: NbFailure) -> Self {
+        failure.message
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct NbFailurePayload {
+    operation: String,
+    #[serde(flatten)]
+    failure: NbFailure,
+}
+
+/// logs a background nb failure and notifies the frontend so it can offer the remediation
+pub fn report_failure(app_handle: &AppHandle, operation: &str, failure: NbFailure) {
+    log::warn!(
+        "nb {operation} failed [{:?}, remediation: {:?}]: {}",
+        failure.kind,
+        failure.remediation,
+        failure.details.as_deref().unwrap_or(&failure.message)
+    );
+    let payload = NbFailurePayload {
+        operation: operation.to_string(),
+        failure,
+    };
+    if let Err(e) = app_handle.emit(NB_FAILURE_EVENT, payload) {
+        log::error!("failed to emit nb failure event: {e}");
+    }
+}
+
+/// runs a remediation action suggested by a classified failure
+pub async fn remediate(app_handle: &AppHandle, action: NbRemediation) -> Result<(), NbFailure> {
+    log::info!("running nb remediation: {:?}", action);
+    match action {
+        NbRemediation::RepairIndex => {
+            run_nb_command(app_handle, &["index", "rebuild"]).await?;
+            git_checkpoint(app_handle, "[nb] Rebuild index").await?;
+        }
+        NbRemediation::RemoveLock => {
+            let base_dir = get_base_dir(app_handle).map_err(NbFailure::unclassified)?;
+            let lock_path = base_dir.join(".git").join("index.lock");
+            if lock_path.exists() {
+                fs::remove_file(&lock_path).await.map_err(|e| {
+                    NbFailure::classify(&format!("failed to remove {}: {e}", lock_path.display()))
+                })?;
+                log::info!("removed stale git lock: {}", lock_path.display());
+            }
+        }
+        NbRemediation::Reinstall => {
+            verify_installation(app_handle)
+                .await
+                .map_err(NbFailure::binary_missing)?;
+        }
+        NbRemediation::Reinit => {
+            let base_dir = get_base_dir(app_handle).map_err(NbFailure::unclassified)?;
+            fs::create_dir_all(&base_dir)
+                .await
+                .map_err(|e| NbFailure::classify(&e.to_string()))?;
+            init_notebook(app_handle).await?;
+            reconcile_index(app_handle).await?;
+        }
+    }
+    Ok(())
+}
+
 // -----------------------------------------
 // internal command execution
 // -----------------------------------------
 
+/// one queue per vault: nb and git operations on the same repo run one at a
+/// time, so overlapping saves never race on `.git/index.lock`
+static VAULT_QUEUES: std::sync::Mutex<Option<HashMap<PathBuf, VaultQueue>>> =
+    std::sync::Mutex::new(None);
+
+type VaultQueue = Arc<Mutex<()>>;
+
+/// waits for the vault's earlier nb/git operations to finish. hold the guard
+/// for the duration of the operation.
+async fn queue(base_dir: &Path) -> OwnedMutexGuard<()> {
+    let queue = VAULT_QUEUES
+        .lock()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .entry(base_dir.to_path_buf())
+        .or_default()
+        .clone();
+    match queue.clone().try_lock_owned() {
+        Ok(guard) => guard,
+        Err(_) => {
+            log::debug!(
+                "waiting for running nb/git operation in {}",
+                base_dir.display()
+            );
+            queue.lock_owned().await
+        }
+    }
+}
+
 /// run an nb command with the given arguments (internal use only)
 /// commands are run from within the local notebook directory (~/flowrite)
-async fn run_nb_command(app_handle: &AppHandle, args: &[&str]) -> Result<String, String> {
-    let fwnb = get_nb_binary_path(app_handle)?;
-    let env = get_nb_env(app_handle)?;
-    let base_dir = get_base_dir(app_handle)?;
+async fn run_nb_command(app_handle: &AppHandle, args: &[&str]) -> Result<String, NbFailure> {
+    run_nb_command_with_env(app_handle, args, HashMap::new()).await
+}
 
+/// `run_nb_command` with extra environment variables on top of nb's own
+async fn run_nb_command_with_env(
+    app_handle: &AppHandle,
+    args: &[&str],
+    extra_env: HashMap<String, String>,
+) -> Result<String, NbFailure> {
+    let fwnb = get_nb_binary_path(app_handle).map_err(NbFailure::unclassified)?;
+    let mut env = get_nb_env(app_handle).map_err(NbFailure::unclassified)?;
+    env.extend(extra_env);
+    let base_dir = get_base_dir(app_handle).map_err(NbFailure::unclassified)?;
+
+    let _queued = queue(&base_dir).await;
     log::debug!("running fwnb command: {:?}", args);
 
     let output = app_handle
@@ -221,16 +740,21 @@ async fn run_nb_command(app_handle: &AppHandle, args: &[&str]) -> Result<String,
         .current_dir(&base_dir)
         .output()
         .await
-        .map_err(|e| format!("fwnb not available: {e}"))?;
+        .map_err(|e| NbFailure::binary_missing(format!("failed to run fwnb: {e}")))?;
 
     if output.status.success() {
         let stdout = String::from_utf8_lossy(&output.stdout).to_string();
         log::debug!("fwnb command succeeded");
         Ok(stdout)
     } else {
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-        log::error!("fwnb command failed: {}", stderr.trim());
-        Err(stderr)
+        let failure = NbFailure::classify(&String::from_utf8_lossy(&output.stderr));
+        log::error!(
+            "fwnb command {:?} failed [{:?}]: {}",
+            args,
+            failure.kind,
+            failure.details.as_deref().unwrap_or(&failure.message)
+        );
+        Err(failure)
     }
 }
 
@@ -239,33 +763,111 @@ async fn run_nb_command(app_handle: &AppHandle, args: &[&str]) -> Result<String,
 // -----------------------------------------
 
 /// initialize the current directory as a local nb notebook
-pub async fn init_notebook(app_handle: &AppHandle) -> Result<(), String> {
+pub async fn init_notebook(app_handle: &AppHandle) -> Result<(), NbFailure> {
     run_nb_command(app_handle, &["notebooks", "init", "-y"]).await?;
     Ok(())
 }
 
 /// reconcile nb index to catch external file changes (adds/removes entries in .index)
-pub async fn reconcile_index(app_handle: &AppHandle) -> Result<(), String> {
+pub async fn reconcile_index(app_handle: &AppHandle) -> Result<(), NbFailure> {
     run_nb_command(app_handle, &["index", "reconcile", "-y"]).await?;
     Ok(())
 }
 
+/// runs `f` on the vault's git repo in a blocking task, so libgit2 never
+/// stalls the async runtime
+pub async fn with_repo<T: Send + 'static>(
+    app_handle: &AppHandle,
+    f: impl FnOnce(&git2::Repository) -> Result<T, git2::Error> + Send + 'static,
+) -> Result<T, NbFailure> {
+    let base_dir = get_base_dir(app_handle).map_err(NbFailure::unclassified)?;
+    let _queued = queue(&base_dir).await;
+    tauri::async_runtime::spawn_blocking(move || f(&vcs::open(&base_dir)?))
+        .await
+        .map_err(|e| NbFailure::unclassified(format!("git task failed: {e}")))?
+        .map_err(|e| NbFailure::classify(e.message()))
+}
+
 /// git checkpoint: stage all changes and commit with message
 /// message format follows nb convention: "[nb] Action: path"
-pub async fn git_checkpoint(app_handle: &AppHandle, message: &str) -> Result<(), String> {
-    run_nb_command(app_handle, &["git", "checkpoint", message]).await?;
+pub async fn git_checkpoint(app_handle: &AppHandle, message: &str) -> Result<(), NbFailure> {
+    let plaintext = get_base_dir(app_handle)
+        .map(|base_dir| encryption::exclude_plaintext(&base_dir))
+        .unwrap_or_default();
+    let message = message.to_string();
+    with_repo(app_handle, move |repo| {
+        vcs::untrack(repo, &plaintext)?;
+        vcs::checkpoint(repo, &message)
+    })
+    .await?;
+    Ok(())
+}
+
+/// git checkpoint of only `paths` (and the nb `.index` files listing them),
+/// so unrelated unsaved work stays out of the commit
+pub async fn git_checkpoint_paths(
+    app_handle: &AppHandle,
+    paths: &[String],
+    message: &str,
+) -> Result<(), NbFailure> {
+    let plaintext = get_base_dir(app_handle)
+        .map(|base_dir| encryption::exclude_plaintext(&base_dir))
+        .unwrap_or_default();
+    let mut paths: Vec<String> = paths
+        .iter()
+        .map(|path| path.trim_matches('/').to_string())
+        .filter(|path| !path.is_empty())
+        .collect();
+    let index_files: Vec<String> = paths
+        .iter()
+        .map(|path| match path.rsplit_once('/') {
+            Some((parent, _)) => format!("{parent}/.index"),
+            None => ".index".to_string(),
+        })
+        .collect();
+    paths.extend(index_files);
+    paths.sort();
+    paths.dedup();
+
+    let message = message.to_string();
+    with_repo(app_handle, move |repo| {
+        vcs::untrack(repo, &plaintext)?;
+        vcs::checkpoint_paths(repo, &paths, &message)
+    })
+    .await?;
     Ok(())
 }
 
-/// run nb index reconcile + git checkpoint in a background task
-fn reconcile_and_checkpoint(app_handle: &AppHandle, message: String) {
+/// runs a git command inside the notebook repo (via `nb git`)
+pub async fn git(app_handle: &AppHandle, args: &[&str]) -> Result<String, NbFailure> {
+    let mut nb_args = vec!["git"];
+    nb_args.extend_from_slice(args);
+    run_nb_command(app_handle, &nb_args).await
+}
+
+/// run nb index reconcile + git checkpoint of the whole vault in a background
+/// task (in the caller's vault, see `vaults::spawn`)
+pub fn reconcile_and_checkpoint(app_handle: &AppHandle, message: String) {
+    let handle = app_handle.clone();
+    vaults::spawn(async move {
+        if let Err(failure) = reconcile_index(&handle).await {
+            report_failure(&handle, "index reconciliation", failure);
+        }
+        if let Err(failure) = git_checkpoint(&handle, &message).await {
+            report_failure(&handle, "git checkpoint", failure);
+        }
+    });
+}
+
+/// like `reconcile_and_checkpoint`, but commits only `paths`
+pub fn reconcile_and_checkpoint_paths(app_handle: &AppHandle, paths: Vec<String>, message: String) {
     let handle = app_handle.clone();
-    tauri::async_runtime::spawn(async move {
-        if let Err(e) = reconcile_index(&handle).await {
-            log::warn!("nb index reconciliation failed: {}", e);
+    vaults::spawn(async move {
+        if let Err(failure) = reconcile_index(&handle).await {
+            report_failure(&handle, "index reconciliation", failure);
         }
-        if let Err(e) = git_checkpoint(&handle, &message).await {
-            log::warn!("nb git checkpoint failed: {}", e);
+        if let Err(failure) = git_checkpoint_paths(&handle, &paths, &message).await {
+            report_failure(&handle, "git checkpoint", failure);
         }
     });
 }
@@ -282,13 +884,25 @@ pub async fn create_file(app_handle: &AppHandle, path: &str, content: &str) -> R
             .map_err(|e| format!("failed to create parent directory: {e}"))?;
     }
 
-    // write file with initial content
-    fs::write(&file_path, content)
+    // write file with initial content (encrypted inside encrypted folders)
+    let bytes = encryption::encrypt_if_needed(&base_dir, path, content.as_bytes())?;
+    fs::write(&file_path, bytes)
         .await
         .map_err(|e| format!("failed to create file {}: {e}", path))?;
+    journal::record(
+        &base_dir,
+        JournalOp::Create,
+        path,
+        None,
+        JournalOrigin::User,
+    );
 
     // reconcile + checkpoint in background
-    reconcile_and_checkpoint(app_handle, format!("[nb] Add: {}", path));
+    reconcile_and_checkpoint_paths(
+        app_handle,
+        vec![path.to_string()],
+        format!("[nb] Add: {}", path),
+    );
 
     Ok(())
 }
@@ -297,51 +911,76 @@ pub async fn create_file(app_handle: &AppHandle, path: &str, content: &str) -> R
 pub async fn read_file(app_handle: &AppHandle, path: &str) -> Result<String, String> {
     let base_dir = get_base_dir(app_handle)?;
     let file_path = base_dir.join(path);
-    fs::read_to_string(&file_path)
+    let bytes = fs::read(&file_path)
         .await
-        .map_err(|e| format!("failed to read file {}: {}", path, e))
+        .map_err(|e| format!("failed to read file {}: {}", path, e))?;
+    let bytes = encryption::decrypt_if_needed(&base_dir, bytes)?;
+    String::from_utf8(bytes).map_err(|e| format!("failed to read file {}: {}", path, e))
 }
 
 /// update a note file with new content
-pub async fn update_file(app_handle: &AppHandle, path: &str, content: &str) -> Result<(), String> {
+pub async fn update_file(
+    app_handle: &AppHandle,
+    path: &str,
+    content: &str,
+    keep_backup: bool,
+) -> Result<(), String> {
+    write_file(app_handle, path, content, keep_backup).await?;
+
+    // checkpoint with the other edits of this batch (no index change needed
+    // for existing files)
+    checkpoints::schedule(app_handle, path);
+
+    Ok(())
+}
+
+/// `update_file` without the checkpoint, for changes to many notes that are
+/// checkpointed together
+pub async fn write_file(
+    app_handle: &AppHandle,
+    path: &str,
+    content: &str,
+    keep_backup: bool,
+) -> Result<(), String> {
     let base_dir = get_base_dir(app_handle)?;
     let file_path = base_dir.join(path);
 
-    // write content directly to file
-    fs::write(&file_path, content)
+    // write through a temp file so a crash never truncates the note
+    let bytes = encryption::encrypt_if_needed(&base_dir, path, content.as_bytes())?;
+    write_atomic(&file_path, &bytes, keep_backup)
         .await
         .map_err(|e| format!("failed to update file {}: {e}", path))?;
-
-    // checkpoint in background (no index change needed for existing files)
-    let handle = app_handle.clone();
-    let msg = format!("[nb] Edit: {}", path);
-    tauri::async_runtime::spawn(async move {
-        if let Err(e) = git_checkpoint(&handle, &msg).await {
-            log::warn!("nb git checkpoint failed: {}", e);
-        }
-    });
-
+    file_watcher::record_own_write(&file_path, &bytes);
+    fs_cache::invalidate_file(&base_dir, path);
+    journal::record(
+        &base_dir,
+        JournalOp::Update,
+        path,
+        None,
+        JournalOrigin::User,
+    );
     Ok(())
 }
 
-/// delete a file or directory (moves to Trash)
+/// delete a file or directory (moves to the vault trash, see `vault_trash`)
 pub async fn delete(app_handle: &AppHandle, path: &str) -> Result<(), String> {
     let base_dir = get_base_dir(app_handle)?;
-    let full_path = base_dir.join(path);
-
-    let path_clone = full_path.clone();
-    tokio::task::spawn_blocking(move || {
-        use trash::macos::{DeleteMethod, TrashContextExtMacos};
-        let mut ctx = TrashContext::default();
-        ctx.set_delete_method(DeleteMethod::NsFileManager);
-        ctx.delete(&path_clone)
-    })
-    .await
-    .map_err(|e| format!("failed to trash '{}': {e}", path))?
-    .map_err(|e| format!("failed to trash '{}': {e}", path))?;
+
+    vault_trash::move_to_trash(&base_dir, path).await?;
+    journal::record(
+        &base_dir,
+        JournalOp::Delete,
+        path,
+        None,
+        JournalOrigin::User,
+    );
 
     // reconcile + checkpoint in background
-    reconcile_and_checkpoint(app_handle, format!("[nb] Delete: {}", path));
+    reconcile_and_checkpoint_paths(
+        app_handle,
+        vec![path.to_string()],
+        format!("[nb] Delete: {}", path),
+    );
 
     Ok(())
 }
@@ -355,16 +994,785 @@ pub async fn rename(app_handle: &AppHandle, old_path: &str, new_path: &str) -> R
     fs::rename(&old_resolved, &new_resolved)
         .await
         .map_err(|e| format!("failed to rename '{}' to '{}': {e}", old_path, new_path))?;
+    journal::record(
+        &base_dir,
+        JournalOp::Rename,
+        new_path,
+        Some(old_path),
+        JournalOrigin::User,
+    );
 
     // reconcile + checkpoint in background
-    reconcile_and_checkpoint(
+    reconcile_and_checkpoint_paths(
         app_handle,
+        vec![old_path.to_string(), new_path.to_string()],
         format!("[nb] Rename: {} -> {}", old_path, new_path),
     );
 
     Ok(())
 }
 
+/// `dir/name`, or `dir/name 1.md`, `dir/name 2.md`, ... if that is taken
+pub fn available_path(base_dir: &Path, dir: &str, name: &str) -> String {
+    let join = |name: &str| {
+        if dir.is_empty() {
+            name.to_string()
+        } else {
+            format!("{dir}/{name}")
+        }
+    };
+    let candidate = join(name);
+    if !base_dir.join(&candidate).exists() {
+        return candidate;
+    }
+
+    let (stem, extension) = match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, format!(".{extension}")),
+        _ => (name, String::new()),
+    };
+    (1..)
+        .map(|n| join(&format!("{stem} {n}{extension}")))
+        .find(|candidate| !base_dir.join(candidate).exists())
+        .unwrap_or(candidate)
+}
+
+/// move a file or directory into another directory, keeping its git history.
+/// a name taken in the destination gets a numeric suffix. returns the new path.
+pub async fn move_entry(
+    app_handle: &AppHandle,
+    old_path: &str,
+    new_dir: &str,
+) -> Result<String, String> {
+    let base_dir = get_base_dir(app_handle)?;
+    let name = Path::new(old_path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| format!("invalid path '{old_path}'"))?;
+    let new_dir = new_dir.trim_matches('/');
+    if new_dir == old_path || new_dir.starts_with(&format!("{old_path}/")) {
+        return Err(format!("cannot move '{old_path}' into itself"));
+    }
+
+    fs::create_dir_all(base_dir.join(new_dir))
+        .await
+        .map_err(|e| format!("failed to create directory '{new_dir}': {e}"))?;
+    let new_path = available_path(&base_dir, new_dir, name);
+
+    // git mv keeps the move a rename in history; files that were never
+    // checkpointed are unknown to git and are moved directly
+    if let Err(failure) = git(app_handle, &["mv", "--", old_path, &new_path]).await {
+        log::debug!("git mv failed, moving directly: {failure}");
+        fs::rename(base_dir.join(old_path), base_dir.join(&new_path))
+            .await
+            .map_err(|e| format!("failed to move '{old_path}' to '{new_path}': {e}"))?;
+    }
+    journal::record(
+        &base_dir,
+        JournalOp::Rename,
+        &new_path,
+        Some(old_path),
+        JournalOrigin::User,
+    );
+
+    // reconcile + checkpoint in background
+    reconcile_and_checkpoint_paths(
+        app_handle,
+        vec![old_path.to_string(), new_path.clone()],
+        format!("[nb] Move: {old_path} -> {new_path}"),
+    );
+
+    Ok(new_path)
+}
+
+/// copies a directory tree, file by file
+async fn copy_dir(source: &Path, destination: &Path) -> Result<(), String> {
+    fs::create_dir_all(destination)
+        .await
+        .map_err(|e| format!("failed to create '{}': {e}", destination.display()))?;
+    let mut entries = fs::read_dir(source)
+        .await
+        .map_err(|e| format!("failed to read '{}': {e}", source.display()))?;
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .map_err(|e| format!("failed to read directory entry: {e}"))?
+    {
+        let target = destination.join(entry.file_name());
+        if entry.path().is_dir() {
+            Box::pin(copy_dir(&entry.path(), &target)).await?;
+        } else {
+            fs::copy(entry.path(), &target)
+                .await
+                .map_err(|e| format!("failed to copy '{}': {e}", entry.path().display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// copy a file, or a directory when `recursive` is set. a taken destination
+/// gets a numeric suffix. returns the path of the copy.
+pub async fn copy_entry(
+    app_handle: &AppHandle,
+    src_path: &str,
+    dest_path: &str,
+    recursive: bool,
+) -> Result<String, String> {
+    let base_dir = get_base_dir(app_handle)?;
+    let source = base_dir.join(src_path);
+    let dest_path = dest_path.trim_matches('/');
+    let is_dir = source.is_dir();
+    if is_dir && !recursive {
+        return Err(format!("'{src_path}' is a folder, copy it recursively"));
+    }
+    if is_dir && (dest_path == src_path || dest_path.starts_with(&format!("{src_path}/"))) {
+        return Err(format!("cannot copy '{src_path}' into itself"));
+    }
+
+    let (dest_dir, dest_name) = match dest_path.rsplit_once('/') {
+        Some((dir, name)) => (dir, name),
+        None => ("", dest_path),
+    };
+    if dest_name.is_empty() {
+        return Err(format!("invalid destination '{dest_path}'"));
+    }
+    fs::create_dir_all(base_dir.join(dest_dir))
+        .await
+        .map_err(|e| format!("failed to create directory '{dest_dir}': {e}"))?;
+    let new_path = available_path(&base_dir, dest_dir, dest_name);
+
+    if is_dir {
+        copy_dir(&source, &base_dir.join(&new_path)).await?;
+    } else {
+        fs::copy(&source, base_dir.join(&new_path))
+            .await
+            .map_err(|e| format!("failed to copy '{src_path}' to '{new_path}': {e}"))?;
+    }
+    journal::record(
+        &base_dir,
+        JournalOp::Create,
+        &new_path,
+        None,
+        JournalOrigin::User,
+    );
+
+    // reconcile + checkpoint in background
+    reconcile_and_checkpoint_paths(
+        app_handle,
+        vec![new_path.clone()],
+        format!("[nb] Copy: {src_path} -> {new_path}"),
+    );
+
+    Ok(new_path)
+}
+
+// -----------------------------------------
+// batch operations
+// -----------------------------------------
+
+/// one step of a `batch_apply` call
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum BatchOp {
+    Create {
+        path: String,
+        #[serde(default)]
+        content: String,
+    },
+    Update {
+        path: String,
+        content: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    Rename {
+        old_path: String,
+        new_path: String,
+    },
+    Delete {
+        path: String,
+    },
+}
+
+impl BatchOp {
+    /// the paths the operation changes
+    fn paths(&self) -> Vec<&str> {
+        match self {
+            Self::Create { path, .. } | Self::Update { path, .. } | Self::Delete { path } => {
+                vec![path]
+            }
+            Self::Rename { old_path, new_path } => vec![old_path, new_path],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchOpResult {
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+async fn apply_batch_op(
+    app_handle: &AppHandle,
+    base_dir: &Path,
+    op: &BatchOp,
+) -> Result<(), String> {
+    // locked notes refuse batch edits like any other change
+    match op {
+        BatchOp::Update { path, .. }
+        | BatchOp::Delete { path }
+        | BatchOp::Rename { old_path: path, .. } => {
+            locks::ensure_unlocked(base_dir, path).map_err(|e| e.to_string())?
+        }
+        BatchOp::Create { .. } => {}
+    }
+
+    match op {
+        BatchOp::Create { path, content } => {
+            let file_path = resolve_path(app_handle, path)?;
+            if file_path.exists() {
+                return Err(format!("file '{path}' already exists"));
+            }
+            if let Some(parent) = file_path.parent() {
+                fs::create_dir_all(parent)
+                    .await
+                    .map_err(|e| format!("failed to create parent directory: {e}"))?;
+            }
+            let bytes = encryption::encrypt_if_needed(base_dir, path, content.as_bytes())?;
+            fs::write(&file_path, bytes)
+                .await
+                .map_err(|e| format!("failed to create file {path}: {e}"))?;
+            journal::record(base_dir, JournalOp::Create, path, None, JournalOrigin::User);
+        }
+        BatchOp::Update { path, content } => {
+            let file_path = resolve_path(app_handle, path)?;
+            if !file_path.is_file() {
+                return Err(format!("file '{path}' does not exist"));
+            }
+            let bytes = encryption::encrypt_if_needed(base_dir, path, content.as_bytes())?;
+            write_atomic(&file_path, &bytes, false)
+                .await
+                .map_err(|e| format!("failed to update file {path}: {e}"))?;
+            fs_cache::invalidate_file(base_dir, path);
+            journal::record(base_dir, JournalOp::Update, path, None, JournalOrigin::User);
+        }
+        BatchOp::Rename { old_path, new_path } => {
+            let old_resolved = resolve_path(app_handle, old_path)?;
+            let new_resolved = resolve_path(app_handle, new_path)?;
+            if new_resolved.exists() {
+                return Err(format!("'{new_path}' already exists"));
+            }
+            if let Some(parent) = new_resolved.parent() {
+                fs::create_dir_all(parent)
+                    .await
+                    .map_err(|e| format!("failed to create parent directory: {e}"))?;
+            }
+            fs::rename(&old_resolved, &new_resolved)
+                .await
+                .map_err(|e| format!("failed to rename '{old_path}' to '{new_path}': {e}"))?;
+            journal::record(
+                base_dir,
+                JournalOp::Rename,
+                new_path,
+                Some(old_path),
+                JournalOrigin::User,
+            );
+        }
+        BatchOp::Delete { path } => {
+            resolve_path(app_handle, path)?;
+            vault_trash::move_to_trash(base_dir, path).await?;
+            journal::record(base_dir, JournalOp::Delete, path, None, JournalOrigin::User);
+        }
+    }
+    Ok(())
+}
+
+/// applies operations in order. a failed operation does not stop the batch;
+/// every result is reported. all successful changes share one index
+/// reconcile and one checkpoint.
+pub async fn apply_batch(
+    app_handle: &AppHandle,
+    ops: &[BatchOp],
+    message: Option<&str>,
+) -> Result<Vec<BatchOpResult>, String> {
+    let base_dir = get_base_dir(app_handle)?;
+
+    let mut results = Vec::with_capacity(ops.len());
+    let mut touched: Vec<String> = Vec::new();
+    for op in ops {
+        let result = match apply_batch_op(app_handle, &base_dir, op).await {
+            Ok(()) => {
+                touched.extend(op.paths().into_iter().map(str::to_string));
+                BatchOpResult {
+                    ok: true,
+                    error: None,
+                }
+            }
+            Err(e) => {
+                log::warn!("batch operation {op:?} failed: {e}");
+                BatchOpResult {
+                    ok: false,
+                    error: Some(e),
+                }
+            }
+        };
+        results.push(result);
+    }
+
+    let applied = results.iter().filter(|result| result.ok).count();
+    if applied > 0 {
+        let message = match message {
+            Some(message) => format!("[nb] {message}"),
+            None => format!("[nb] Batch: {applied} changes"),
+        };
+        reconcile_and_checkpoint_paths(app_handle, touched, message);
+    }
+    Ok(results)
+}
+
+// -----------------------------------------
+// deleted note history
+// -----------------------------------------
+
+/// a file removed from the vault, as recorded by an nb git checkpoint
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeletedNote {
+    pub path: String,
+    /// checkpoint commit that removed the file
+    pub commit: String,
+    pub deleted_time_ms: i64,
+    pub message: String,
+}
+
+/// lists files deleted from the vault according to the git history,
+/// most recent deletion first. files that exist again on disk are skipped.
+/// `since_ms` limits the results to deletions at or after that timestamp.
+pub async fn list_deleted_notes(
+    app_handle: &AppHandle,
+    since_ms: Option<i64>,
+) -> Result<Vec<DeletedNote>, NbFailure> {
+    let base_dir = get_base_dir(app_handle).map_err(NbFailure::unclassified)?;
+
+    let deletions = with_repo(app_handle, move |repo| vcs::deletions(repo, since_ms)).await?;
+
+    let mut seen = std::collections::HashSet::new();
+    let mut deleted = Vec::new();
+    for (commit, paths) in deletions {
+        for path in paths {
+            // skip nb internals (.index, etc.) and anything already reported
+            if path.split('/').any(|part| part.starts_with('.')) || !seen.insert(path.clone()) {
+                continue;
+            }
+            if base_dir.join(&path).exists() {
+                continue;
+            }
+            deleted.push(DeletedNote {
+                path,
+                commit: commit.id.clone(),
+                deleted_time_ms: commit.time_ms,
+                message: commit.message.clone(),
+            });
+        }
+    }
+
+    Ok(deleted)
+}
+
+/// rejects anything but a (possibly abbreviated) commit hash, so user input
+/// can never be interpreted as a git option or revision expression
+fn validate_commit(commit: &str) -> Result<(), NbFailure> {
+    if commit.is_empty() || !commit.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(NbFailure::unclassified(format!(
+            "invalid commit id '{commit}'"
+        )));
+    }
+    Ok(())
+}
+
+/// a file's content in `commit` (or in its parent with `before`), as text
+async fn show(
+    app_handle: &AppHandle,
+    path: &str,
+    commit: &str,
+    before: bool,
+) -> Result<String, NbFailure> {
+    validate_commit(commit)?;
+    let (path, commit) = (path.to_string(), commit.to_string());
+    let content = with_repo(app_handle, move |repo| {
+        vcs::show(repo, &commit, &path, before)
+    })
+    .await?;
+    Ok(String::from_utf8_lossy(&content).into_owned())
+}
+
+/// reads a deleted file's last content from the parent of the commit that removed it
+pub async fn read_deleted_note(
+    app_handle: &AppHandle,
+    path: &str,
+    commit: &str,
+) -> Result<String, NbFailure> {
+    show(app_handle, path, commit, true).await
+}
+
+// -----------------------------------------
+// file history
+// -----------------------------------------
+
+/// a checkpoint commit that touched a file
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileVersion {
+    pub commit: String,
+    pub time_ms: i64,
+    /// checkpoint message, e.g. "[nb] Edit: notes/todo.md"
+    pub message: String,
+    /// the file's path in this commit (differs from the current path across renames)
+    pub path: String,
+}
+
+/// lists the commits that changed a file, newest first, following renames
+pub async fn list_file_versions(
+    app_handle: &AppHandle,
+    path: &str,
+) -> Result<Vec<FileVersion>, NbFailure> {
+    let path = path.to_string();
+    let versions = with_repo(app_handle, move |repo| vcs::log(repo, &path))
+        .await?
+        .into_iter()
+        .map(|(commit, path)| FileVersion {
+            commit: commit.id,
+            time_ms: commit.time_ms,
+            message: commit.message,
+            path,
+        })
+        .collect();
+
+    Ok(versions)
+}
+
+/// reads a file as it was in `commit`. `path` is the file's path in that
+/// commit (see `FileVersion::path`).
+pub async fn read_file_version(
+    app_handle: &AppHandle,
+    path: &str,
+    commit: &str,
+) -> Result<String, NbFailure> {
+    show(app_handle, path, commit, false).await
+}
+
+// -----------------------------------------
+// remote credentials
+// -----------------------------------------
+
+/// repo config key remembering the ssh key picked for the remote
+const SSH_KEY_CONFIG: &str = "flowrite.sshKey";
+
+/// repo config key of the username sent with an https token
+const HTTPS_USERNAME_CONFIG: &str = "credential.username";
+
+/// username sent with an https token when none is set; github accepts any
+/// non-empty name with a personal access token
+const DEFAULT_HTTPS_USERNAME: &str = "x-access-token";
+
+/// how the vault authenticates to its sync remote. the token itself never
+/// leaves the keychain.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteCredentials {
+    /// private key used for ssh remotes, `None` for ssh's defaults
+    pub ssh_key: Option<String>,
+    pub https_username: Option<String>,
+    /// an https token is stored in the keychain for this vault
+    pub has_https_token: bool,
+}
+
+/// keychain entry of the vault's https token, one per vault
+fn https_token_name(base_dir: &Path) -> String {
+    format!("vault-remote-token:{}", base_dir.display())
+}
+
+/// quotes `value` for the shell that runs `core.sshCommand`
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// the remote credentials configured for the vault
+pub async fn remote_credentials(app_handle: &AppHandle) -> Result<RemoteCredentials, String> {
+    let base_dir = get_base_dir(app_handle)?;
+    let (ssh_key, https_username) = with_repo(app_handle, |repo| {
+        Ok((
+            vcs::config_get(repo, SSH_KEY_CONFIG)?,
+            vcs::config_get(repo, HTTPS_USERNAME_CONFIG)?,
+        ))
+    })
+    .await?;
+    Ok(RemoteCredentials {
+        ssh_key,
+        https_username,
+        has_https_token: secrets::read_internal_secret(&https_token_name(&base_dir))?.is_some(),
+    })
+}
+
+/// uses the private key at `key_path` for ssh remotes (`core.sshCommand`),
+/// or ssh's defaults again with `None`
+pub async fn set_remote_ssh_key(
+    app_handle: &AppHandle,
+    key_path: Option<String>,
+) -> Result<(), String> {
+    if let Some(key_path) = &key_path {
+        if !Path::new(key_path).is_file() {
+            return Err(format!("ssh key not found: {key_path}"));
+        }
+    }
+    let ssh_command = key_path
+        .as_deref()
+        .map(|key_path| format!("ssh -i {} -o IdentitiesOnly=yes", shell_quote(key_path)));
+    with_repo(app_handle, move |repo| {
+        vcs::config_set(repo, SSH_KEY_CONFIG, key_path.as_deref())?;
+        vcs::config_set(repo, "core.sshCommand", ssh_command.as_deref())
+    })
+    .await?;
+    Ok(())
+}
+
+/// stores an https token for the vault's remote in the keychain, or removes
+/// it with `None`. `username` defaults to one github accepts.
+pub async fn set_remote_https_token(
+    app_handle: &AppHandle,
+    username: Option<String>,
+    token: Option<String>,
+) -> Result<(), String> {
+    let base_dir = get_base_dir(app_handle)?;
+    let name = https_token_name(&base_dir);
+    match token
+        .as_deref()
+        .map(str::trim)
+        .filter(|token| !token.is_empty())
+    {
+        Some(token) => secrets::write_internal_secret(&name, token)?,
+        None => secrets::delete_internal_secret(&name)?,
+    }
+    let username = username
+        .map(|username| username.trim().to_string())
+        .filter(|username| !username.is_empty());
+    with_repo(app_handle, move |repo| {
+        vcs::config_set(repo, HTTPS_USERNAME_CONFIG, username.as_deref())
+    })
+    .await?;
+    Ok(())
+}
+
+/// private keys in `~/.ssh` that have a matching `.pub` file
+pub fn list_ssh_keys(app_handle: &AppHandle) -> Result<Vec<String>, String> {
+    let ssh_dir = app_handle
+        .path()
+        .home_dir()
+        .map_err(|e| format!("failed to get home directory: {e}"))?
+        .join(".ssh");
+    let Ok(entries) = std::fs::read_dir(&ssh_dir) else {
+        return Ok(Vec::new());
+    };
+    let mut keys: Vec<String> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_none_or(|extension| extension != "pub"))
+        .filter(|path| {
+            let mut public_key = path.clone().into_os_string();
+            public_key.push(".pub");
+            path.is_file() && Path::new(&public_key).is_file()
+        })
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    keys.sort();
+    Ok(keys)
+}
+
+/// environment for git commands that talk to the remote: never prompt for a
+/// password (there is no terminal), and send the vault's https token if one
+/// is stored. the token goes through the environment so it never shows up
+/// in process arguments or `.git/config`, and is scoped to the remote's url so
+/// git never sends it to another host (a redirect, a submodule).
+async fn remote_env(app_handle: &AppHandle) -> Result<HashMap<String, String>, String> {
+    let base_dir = get_base_dir(app_handle)?;
+    let mut env = HashMap::new();
+    env.insert("GIT_TERMINAL_PROMPT".to_string(), "0".to_string());
+
+    let Some(token) = secrets::read_internal_secret(&https_token_name(&base_dir))? else {
+        return Ok(env);
+    };
+    let (username, remote_url) = with_repo(app_handle, |repo| {
+        Ok((
+            vcs::config_get(repo, HTTPS_USERNAME_CONFIG)?,
+            vcs::remote_url(repo, SYNC_REMOTE_NAME)?,
+        ))
+    })
+    .await?;
+    // the token is only for an https remote
+    let Some(remote_url) = remote_url.filter(|url| url.starts_with("https://")) else {
+        return Ok(env);
+    };
+    let username = username.unwrap_or_else(|| DEFAULT_HTTPS_USERNAME.to_string());
+    let basic = BASE64.encode(format!("{username}:{token}"));
+    env.insert("GIT_CONFIG_COUNT".to_string(), "1".to_string());
+    env.insert(
+        "GIT_CONFIG_KEY_0".to_string(),
+        format!("http.{remote_url}.extraHeader"),
+    );
+    env.insert(
+        "GIT_CONFIG_VALUE_0".to_string(),
+        format!("Authorization: Basic {basic}"),
+    );
+    Ok(env)
+}
+
+/// `git` for commands that reach the remote (fetch, pull, push), with the
+/// vault's remote credentials
+pub async fn git_remote(app_handle: &AppHandle, args: &[&str]) -> Result<String, NbFailure> {
+    let env = remote_env(app_handle)
+        .await
+        .map_err(NbFailure::unclassified)?;
+    let mut nb_args = vec!["git"];
+    nb_args.extend_from_slice(args);
+    run_nb_command_with_env(app_handle, &nb_args, env).await
+}
+
+// -----------------------------------------
+// integrity check
+// -----------------------------------------
+
+/// how the files on disk, nb's `.index` files and git agree with each other
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultIntegrity {
+    /// the vault has both its git repo and its root `.index`
+    pub repo_present: bool,
+    /// files on disk missing from their folder's `.index`
+    pub untracked_notes: Vec<String>,
+    /// `.index` entries whose file no longer exists
+    pub stale_index_entries: Vec<String>,
+    /// changed, added or deleted files not yet checkpointed
+    pub uncommitted_changes: Vec<String>,
+    /// a repair ran before this report was taken
+    pub repaired: bool,
+}
+
+impl VaultIntegrity {
+    pub fn is_healthy(&self) -> bool {
+        self.repo_present
+            && self.untracked_notes.is_empty()
+            && self.stale_index_entries.is_empty()
+            && self.uncommitted_changes.is_empty()
+    }
+}
+
+/// compares each visible folder's `.index` with its entries, depth first.
+/// nb indexes folders too, so only files count as untracked, but an entry
+/// is stale only when neither a file nor a folder has its name.
+async fn check_index_files(
+    base_dir: &Path,
+    report: &mut VaultIntegrity,
+) -> Result<(), std::io::Error> {
+    let mut pending = vec![String::new()];
+    while let Some(dir) = pending.pop() {
+        let dir_path = base_dir.join(&dir);
+        let indexed: Vec<String> = match fs::read_to_string(dir_path.join(".index")).await {
+            Ok(content) => content
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+
+        let mut present = std::collections::HashSet::new();
+        let mut entries = fs::read_dir(&dir_path).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                continue;
+            }
+            let path = if dir.is_empty() {
+                name.clone()
+            } else {
+                format!("{dir}/{name}")
+            };
+            if entry.file_type().await?.is_dir() {
+                pending.push(path);
+            } else if !indexed.contains(&name) {
+                report.untracked_notes.push(path);
+            }
+            present.insert(name);
+        }
+
+        for name in indexed.into_iter().filter(|name| !present.contains(name)) {
+            report.stale_index_entries.push(if dir.is_empty() {
+                name
+            } else {
+                format!("{dir}/{name}")
+            });
+        }
+    }
+    report.untracked_notes.sort();
+    report.stale_index_entries.sort();
+    Ok(())
+}
+
+/// visible paths with uncommitted changes
+async fn uncommitted_changes(app_handle: &AppHandle) -> Result<Vec<String>, NbFailure> {
+    let mut changes: Vec<String> = with_repo(app_handle, vcs::status)
+        .await?
+        .into_iter()
+        .map(|entry| entry.path)
+        // skip nb internals (.index, etc.) and vault config
+        .filter(|path| !path.split('/').any(|part| part.starts_with('.')))
+        .collect();
+    changes.sort();
+    changes.dedup();
+    Ok(changes)
+}
+
+async fn check_vault(app_handle: &AppHandle) -> Result<VaultIntegrity, NbFailure> {
+    let base_dir = get_base_dir(app_handle).map_err(NbFailure::unclassified)?;
+    let mut report = VaultIntegrity {
+        repo_present: base_dir.join(".git").exists() && base_dir.join(".index").exists(),
+        ..Default::default()
+    };
+    check_index_files(&base_dir, &mut report)
+        .await
+        .map_err(|e| NbFailure::unclassified(format!("failed to scan vault: {e}")))?;
+    if base_dir.join(".git").exists() {
+        report.uncommitted_changes = uncommitted_changes(app_handle).await?;
+    }
+    Ok(report)
+}
+
+/// checks the vault for drift between disk, nb's index and git. with
+/// `repair`, a vault with problems is reconciled and checkpointed first
+/// (re-initialized if its repo is missing) and the report is taken after.
+pub async fn verify_vault(
+    app_handle: &AppHandle,
+    repair: bool,
+) -> Result<VaultIntegrity, NbFailure> {
+    let mut report = check_vault(app_handle).await?;
+    if !repair || report.is_healthy() {
+        return Ok(report);
+    }
+
+    log::info!("repairing vault integrity");
+    if !report.repo_present {
+        remediate(app_handle, NbRemediation::Reinit).await?;
+    } else {
+        reconcile_index(app_handle).await?;
+    }
+    git_checkpoint(app_handle, "[nb] Repair vault").await?;
+
+    report = check_vault(app_handle).await?;
+    report.repaired = true;
+    Ok(report)
+}
+
 // -----------------------------------------
 // initialization
 // -----------------------------------------
@@ -382,6 +1790,11 @@ pub async fn init_nb(app_handle: &AppHandle) -> Result<(), Box<dyn std::error::E
     if has_git && has_index {
         // already a local notebook
         log::info!("nb notebook already initialized at {:?}", base_dir);
+    } else if has_index {
+        // only the git repo is gone: recreate it around the existing notes
+        vcs::init(&base_dir)?;
+        git_checkpoint(app_handle, "[nb] Initialize").await?;
+        log::info!("recreated git repo for nb notebook at {:?}", base_dir);
     } else {
         // ensure base directory exists before running nb notebooks init
         fs::create_dir_all(&base_dir).await?;
@@ -392,10 +1805,10 @@ pub async fn init_nb(app_handle: &AppHandle) -> Result<(), Box<dyn std::error::E
 
     // reconcile indexes in background (catch any external file changes)
     let app_handle_clone = app_handle.clone();
-    tauri::async_runtime::spawn(async move {
+    vaults::spawn(async move {
         match reconcile_index(&app_handle_clone).await {
             Ok(_) => log::info!("nb index reconciliation complete"),
-            Err(e) => log::warn!("nb index reconciliation failed: {}", e),
+            Err(failure) => report_failure(&app_handle_clone, "index reconciliation", failure),
         }
     });
 
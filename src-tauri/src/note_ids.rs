@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use tauri::{AppHandle, State, WebviewWindow};
+
+use crate::{
+    frontmatter,
+    note_index::{self, NoteIndex},
+    vaults,
+};
+
+/// frontmatter key holding a note's durable id
+const ID_KEY: &str = "id";
+
+/// frontmatter ids of every note in the vault, keyed by vault-relative path.
+/// a rename shows up as a removed and an added path, so the id follows the
+/// note to its new path.
+#[derive(Default)]
+pub struct NoteIdIndex(Mutex<HashMap<String, String>>);
+
+// -----------------------------------------
+// parsing
+// -----------------------------------------
+
+/// the `id:` of a note's frontmatter, if it has a non-empty one
+fn parse_id(path: &str, content: &str) -> Option<String> {
+    let value = match frontmatter::split(content).parse() {
+        Ok(Some(value)) => value,
+        Ok(None) => return None,
+        Err(e) => {
+            log::warn!("note ids: ignoring frontmatter of {path}: {e}");
+            return None;
+        }
+    };
+    let id = match value.get(ID_KEY)? {
+        serde_yaml::Value::String(id) => id.trim().to_string(),
+        serde_yaml::Value::Number(id) => id.to_string(),
+        _ => return None,
+    };
+    (!id.is_empty()).then_some(id)
+}
+
+/// adds a fresh uuid `id:` to the frontmatter of `content`, keeping an id it
+/// already has. returns the new content and the note's id.
+pub fn inject_id(content: &str) -> Result<(String, String), String> {
+    if let Some(id) = parse_id("new note", content) {
+        return Ok((content.to_string(), id));
+    }
+    let mut map = match frontmatter::split(content).parse()? {
+        Some(serde_yaml::Value::Mapping(map)) => map,
+        None | Some(serde_yaml::Value::Null) => serde_yaml::Mapping::new(),
+        Some(_) => return Err("frontmatter is not a mapping".to_string()),
+    };
+    // an empty `id:` is replaced; the id goes first so it stays visible at
+    // the top of the header
+    map.remove(ID_KEY);
+    let id = uuid::Uuid::new_v4().to_string();
+    let mut with_id = serde_yaml::Mapping::new();
+    with_id.insert(ID_KEY.into(), id.clone().into());
+    with_id.extend(map);
+    let content = frontmatter::replace(content, Some(&serde_yaml::Value::Mapping(with_id)))?;
+    Ok((content, id))
+}
+
+// -----------------------------------------
+// indexing
+// -----------------------------------------
+
+impl NoteIndex for NoteIdIndex {
+    type Entry = String;
+
+    const NAME: &'static str = "note ids";
+
+    fn entries(&self) -> &Mutex<HashMap<String, String>> {
+        &self.0
+    }
+
+    fn parse(path: &str, content: &str) -> Option<String> {
+        parse_id(path, content)
+    }
+}
+
+/// records the id of a note that was just written
+pub fn insert(app_handle: &AppHandle, path: &str, id: &str) {
+    note_index::with_index::<NoteIdIndex, _>(app_handle, |index| {
+        index.insert(path.to_string(), id.to_string())
+    });
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+/// the current path of the note with frontmatter `id: <id>`, so links by id
+/// keep working after the note is renamed or moved
+#[tauri::command]
+pub fn resolve_note_id(
+    webview_window: WebviewWindow,
+    state: State<'_, NoteIdIndex>,
+    id: String,
+) -> Result<Option<String>, String> {
+    vaults::ensure_active_vault(&webview_window)?;
+    let id = id.trim();
+    let index = state.0.lock().unwrap();
+    // the smallest path wins if a copied note left the same id in two places
+    Ok(index
+        .iter()
+        .filter(|(_, note_id)| note_id.as_str() == id)
+        .map(|(path, _)| path)
+        .min()
+        .cloned())
+}
@@ -0,0 +1,158 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+
+use tauri::{AppHandle, Manager};
+use tokio::fs;
+
+use crate::{
+    command::{list_dir_inner, FSEntry},
+    file_watcher::FileWatcherEvent,
+    utils::resolve_path,
+    vault_settings, vaults,
+};
+
+/// an in-memory index holding one entry per note, keyed by vault-relative
+/// path (tags, links, note ids). the functions below build it and keep it in
+/// sync with the vault.
+pub trait NoteIndex: Send + Sync + 'static {
+    type Entry: Send + 'static;
+
+    /// prefix of the index's log messages
+    const NAME: &'static str;
+
+    fn entries(&self) -> &Mutex<HashMap<String, Self::Entry>>;
+
+    /// what to store for a note, `None` to leave it out of the index
+    fn parse(path: &str, content: &str) -> Option<Self::Entry>;
+}
+
+/// runs `f` against the index. the index follows the active vault only, so
+/// this does nothing for work scoped to a window showing another vault.
+pub fn with_index<I: NoteIndex, T>(
+    app_handle: &AppHandle,
+    f: impl FnOnce(&mut HashMap<String, I::Entry>) -> T,
+) -> Option<T> {
+    if vaults::active_vault_dir() != vaults::global_vault_dir() {
+        return None;
+    }
+    let state = app_handle.try_state::<I>()?;
+    let mut index = state.entries().lock().unwrap();
+    Some(f(&mut index))
+}
+
+/// true for files with one of the vault's note extensions
+fn is_note(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    vault_settings::is_managed_file(name)
+}
+
+/// re-reads notes and replaces their entries. unreadable notes are dropped.
+async fn index_files<I: NoteIndex>(app_handle: &AppHandle, paths: &[String]) {
+    let mut parsed = Vec::new();
+    for path in paths {
+        let Ok(full_path) = resolve_path(app_handle, path) else {
+            continue;
+        };
+        let entry = match fs::read_to_string(&full_path).await {
+            Ok(content) => I::parse(path, &content),
+            Err(_) => None,
+        };
+        parsed.push((path.clone(), entry));
+    }
+    with_index::<I, _>(app_handle, |index| {
+        for (path, entry) in parsed {
+            match entry {
+                Some(entry) => index.insert(path, entry),
+                None => index.remove(&path),
+            };
+        }
+    });
+}
+
+/// drops notes (or every note under a removed directory) from the index
+fn remove_paths<I: NoteIndex>(app_handle: &AppHandle, paths: &[String]) {
+    if paths.is_empty() {
+        return;
+    }
+    with_index::<I, _>(app_handle, |index| {
+        index.retain(|path, _| {
+            !paths
+                .iter()
+                .any(|removed| path == removed || path.starts_with(&format!("{removed}/")))
+        });
+    });
+}
+
+/// rescans every note under `dir` ("" for the whole vault), dropping notes
+/// that no longer exist
+async fn reindex_dir<I: NoteIndex>(app_handle: &AppHandle, dir: &str) -> Result<usize, String> {
+    let dir_path = resolve_path(app_handle, dir)?;
+    let mut entries: Vec<FSEntry> = Vec::new();
+    if dir_path.is_dir() {
+        list_dir_inner(&dir_path, dir, true, &mut entries).await?;
+    }
+    let paths: Vec<String> = entries
+        .into_iter()
+        .filter(|entry| !entry.is_dir && is_note(&entry.path))
+        .map(|entry| entry.path)
+        .collect();
+    let present: HashSet<&String> = paths.iter().collect();
+
+    let prefix = format!("{dir}/");
+    with_index::<I, _>(app_handle, |index| {
+        index.retain(|path, _| {
+            !(dir.is_empty() || path.starts_with(&prefix)) || present.contains(path)
+        });
+    });
+    index_files::<I>(app_handle, &paths).await;
+    Ok(paths.len())
+}
+
+/// builds the index in the background
+pub fn init<I: NoteIndex>(app_handle: &AppHandle) {
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        match reindex_dir::<I>(&handle, "").await {
+            Ok(count) => log::info!("{}: indexed {count} notes", I::NAME),
+            Err(e) => log::error!("{}: failed to index vault: {e}", I::NAME),
+        }
+    });
+}
+
+/// keeps the index in sync with a flushed file watcher event
+pub fn sync_watcher_event<I: NoteIndex>(app_handle: &AppHandle, event: &FileWatcherEvent) {
+    let mut modified = Vec::new();
+    let mut removed = Vec::new();
+    let mut directories = event.directory_changes.clone();
+
+    for change in &event.file_changes {
+        if change.kind == "delete" {
+            removed.push(change.path.clone());
+        } else if is_note(&change.path) {
+            modified.push(change.path.clone());
+        }
+    }
+    for delta in event.directory_deltas.iter().flatten() {
+        removed.extend(delta.removed.iter().cloned());
+        removed.extend(delta.renamed.iter().map(|renamed| renamed.from.clone()));
+        let renamed_to = delta.renamed.iter().map(|renamed| &renamed.to);
+        for entry in delta.added.iter().chain(&delta.updated).chain(renamed_to) {
+            if entry.is_dir {
+                directories.push(entry.path.clone());
+            } else if is_note(&entry.path) {
+                modified.push(entry.path.clone());
+            }
+        }
+    }
+
+    remove_paths::<I>(app_handle, &removed);
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        index_files::<I>(&handle, &modified).await;
+        for dir in directories {
+            if let Err(e) = reindex_dir::<I>(&handle, &dir).await {
+                log::error!("{}: failed to update {dir}: {e}", I::NAME);
+            }
+        }
+    });
+}
@@ -0,0 +1,647 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, State, WebviewWindow};
+use tokio::fs;
+use wasmtime::{Caller, Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use crate::{
+    acp,
+    command::{list_dir_inner, FSEntry},
+    constants::{PLUGINS_DIR_NAME, PLUGINS_ENABLED_KEY},
+    encryption,
+    file_watcher::FileWatcherEvent,
+    nb,
+    utils::{get_base_dir, get_setting, set_setting},
+    vaults,
+};
+
+/// manifest file expected in every plugin directory
+const PLUGIN_MANIFEST_FILE_NAME: &str = "plugin.json";
+
+/// compiled plugin module expected next to the manifest
+const PLUGIN_MODULE_FILE_NAME: &str = "plugin.wasm";
+
+/// import module name for the host api
+const HOST_MODULE: &str = "flowrite";
+
+/// execution budget per plugin call, so a runaway script can't hang the backend
+const PLUGIN_FUEL: u64 = 500_000_000;
+
+/// linear memory a plugin instance may grow to, so a leaking or hostile
+/// plugin can't exhaust the app's memory
+const PLUGIN_MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+
+/// maximum number of search results returned to a plugin
+const SEARCH_RESULT_LIMIT: usize = 50;
+
+/// what a plugin is allowed to do through the host api
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginCapability {
+    ReadNotes,
+    SearchNotes,
+    CreateNotes,
+    WatchEvents,
+}
+
+/// `plugin.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginManifest {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub capabilities: Vec<PluginCapability>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginInfo {
+    #[serde(flatten)]
+    pub manifest: PluginManifest,
+    pub enabled: bool,
+}
+
+/// shared wasm engine plus compiled modules, keyed by plugin id
+pub struct PluginHost {
+    /// the error instead when the engine could not be created; plugins are
+    /// then unavailable but the rest of the app keeps working
+    engine: Result<Engine, String>,
+    modules: Mutex<HashMap<String, Module>>,
+}
+
+impl Default for PluginHost {
+    fn default() -> Self {
+        let mut config = Config::new();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config).map_err(|e| {
+            log::error!("failed to create wasm engine, plugins are disabled: {e}");
+            format!("plugins are unavailable: failed to create wasm engine: {e}")
+        });
+        Self {
+            engine,
+            modules: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl PluginHost {
+    fn engine(&self) -> Result<&Engine, String> {
+        self.engine.as_ref().map_err(Clone::clone)
+    }
+}
+
+/// per-call store data: who is calling and what they may do
+struct HostContext {
+    app_handle: AppHandle,
+    /// vault the plugin was run against. host calls run on a blocking
+    /// thread, outside the caller's vault scope.
+    vault_dir: PathBuf,
+    plugin_id: String,
+    capabilities: Vec<PluginCapability>,
+    limits: StoreLimits,
+}
+
+impl HostContext {
+    fn require(&self, capability: PluginCapability) -> Result<(), String> {
+        if self.capabilities.contains(&capability) {
+            Ok(())
+        } else {
+            Err(format!(
+                "plugin '{}' lacks the {capability:?} capability",
+                self.plugin_id
+            ))
+        }
+    }
+}
+
+// -----------------------------------------
+// plugin storage
+// -----------------------------------------
+
+fn get_plugins_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let app_data = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("failed to get app data directory: {e}"))?;
+    Ok(app_data.join(PLUGINS_DIR_NAME))
+}
+
+fn is_valid_plugin_id(id: &str) -> bool {
+    !id.is_empty()
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+        && !id.starts_with('.')
+}
+
+async fn read_manifest(dir: &Path) -> Result<PluginManifest, String> {
+    let content = fs::read_to_string(dir.join(PLUGIN_MANIFEST_FILE_NAME))
+        .await
+        .map_err(|e| format!("failed to read plugin manifest: {e}"))?;
+    let manifest: PluginManifest = serde_json::from_str(&content)
+        .map_err(|e| format!("failed to parse plugin manifest: {e}"))?;
+    if !is_valid_plugin_id(&manifest.id) {
+        return Err(format!("invalid plugin id '{}'", manifest.id));
+    }
+    Ok(manifest)
+}
+
+async fn load_manifests(app_handle: &AppHandle) -> Result<Vec<PluginManifest>, String> {
+    let dir = get_plugins_dir(app_handle)?;
+    let mut manifests = Vec::new();
+    let mut entries = match fs::read_dir(&dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(manifests),
+        Err(e) => return Err(format!("failed to read plugins directory: {e}")),
+    };
+
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .map_err(|e| format!("failed to read plugins directory: {e}"))?
+    {
+        if !entry.path().is_dir() {
+            continue;
+        }
+        match read_manifest(&entry.path()).await {
+            Ok(manifest) => manifests.push(manifest),
+            Err(e) => log::warn!("skipping plugin {}: {e}", entry.path().display()),
+        }
+    }
+
+    manifests.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(manifests)
+}
+
+fn get_enabled_ids(app_handle: &AppHandle) -> Vec<String> {
+    get_setting(app_handle, PLUGINS_ENABLED_KEY).unwrap_or_default()
+}
+
+async fn find_manifest(app_handle: &AppHandle, id: &str) -> Result<PluginManifest, String> {
+    if !is_valid_plugin_id(id) {
+        return Err(format!("invalid plugin id '{id}'"));
+    }
+    read_manifest(&get_plugins_dir(app_handle)?.join(id)).await
+}
+
+// -----------------------------------------
+// wasm runtime
+// -----------------------------------------
+
+/// returns the compiled module for a plugin, compiling it on first use
+fn get_module(app_handle: &AppHandle, host: &PluginHost, id: &str) -> Result<Module, String> {
+    if let Some(module) = host.modules.lock().unwrap().get(id) {
+        return Ok(module.clone());
+    }
+    let path = get_plugins_dir(app_handle)?
+        .join(id)
+        .join(PLUGIN_MODULE_FILE_NAME);
+    let module = Module::from_file(host.engine()?, &path)
+        .map_err(|e| format!("failed to compile plugin '{id}': {e}"))?;
+    host.modules
+        .lock()
+        .unwrap()
+        .insert(id.to_string(), module.clone());
+    Ok(module)
+}
+
+fn read_guest_string(
+    caller: &mut Caller<'_, HostContext>,
+    ptr: i32,
+    len: i32,
+) -> Result<String, String> {
+    let memory = caller
+        .get_export("memory")
+        .and_then(|export| export.into_memory())
+        .ok_or("plugin does not export memory")?;
+    let start = usize::try_from(ptr).map_err(|_| "invalid pointer")?;
+    let end = start + usize::try_from(len).map_err(|_| "invalid length")?;
+    let bytes = memory
+        .data(&*caller)
+        .get(start..end)
+        .ok_or("pointer out of bounds")?;
+    String::from_utf8(bytes.to_vec()).map_err(|e| format!("invalid utf-8 from plugin: {e}"))
+}
+
+/// copies `value` into guest memory via the plugin's `alloc` export and
+/// returns `(ptr << 32) | len`
+fn write_guest_string(caller: &mut Caller<'_, HostContext>, value: &str) -> Result<i64, String> {
+    let alloc = caller
+        .get_export("alloc")
+        .and_then(|export| export.into_func())
+        .ok_or("plugin does not export alloc")?
+        .typed::<i32, i32>(&*caller)
+        .map_err(|e| format!("invalid alloc export: {e}"))?;
+    let len = i32::try_from(value.len()).map_err(|_| "result too large")?;
+    let ptr = alloc
+        .call(&mut *caller, len)
+        .map_err(|e| format!("plugin alloc failed: {e}"))?;
+    let memory = caller
+        .get_export("memory")
+        .and_then(|export| export.into_memory())
+        .ok_or("plugin does not export memory")?;
+    memory
+        .write(&mut *caller, ptr as usize, value.as_bytes())
+        .map_err(|e| format!("failed to write plugin memory: {e}"))?;
+    Ok(((ptr as i64) << 32) | len as i64)
+}
+
+/// logs a host call failure and maps it to the `-1` error return value
+fn host_result(caller: &Caller<'_, HostContext>, result: Result<i64, String>) -> i64 {
+    result.unwrap_or_else(|e| {
+        log::warn!("plugin '{}': {e}", caller.data().plugin_id);
+        -1
+    })
+}
+
+/// resolves a note path passed by a plugin. `..` is normalized away first, so
+/// paths that would leave the vault are rejected rather than followed.
+fn resolve_note_path(vault_root: &Path, path: &str) -> Result<(PathBuf, String), String> {
+    let full_path = acp::normalize_absolute(&vault_root.join(path))
+        .filter(|full_path| acp::is_inside_vault(full_path, vault_root))
+        .ok_or_else(|| format!("note path '{path}' is outside the vault"))?;
+    let relative_path = full_path
+        .strip_prefix(vault_root)
+        .map_err(|_| format!("note path '{path}' is outside the vault"))?
+        .to_string_lossy()
+        .to_string();
+    if relative_path.is_empty() {
+        return Err(format!("invalid note path '{path}'"));
+    }
+    Ok((full_path, relative_path))
+}
+
+/// reads a note as text, decrypting it if it lives in an encrypted folder
+fn read_note(vault_root: &Path, full_path: &Path) -> Result<String, String> {
+    let bytes = std::fs::read(full_path).map_err(|e| e.to_string())?;
+    let bytes = encryption::decrypt_if_needed(vault_root, bytes)?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+fn search_notes(vault_root: &Path, query: &str) -> Result<Vec<String>, String> {
+    let mut entries: Vec<FSEntry> = Vec::new();
+    tauri::async_runtime::block_on(vaults::within(
+        vault_root.to_path_buf(),
+        list_dir_inner(vault_root, "", true, &mut entries),
+    ))?;
+
+    let query = query.to_lowercase();
+    let matches = entries
+        .into_iter()
+        .filter(|entry| !entry.is_dir)
+        .filter(|entry| {
+            entry.path.to_lowercase().contains(&query)
+                || read_note(vault_root, &vault_root.join(&entry.path))
+                    .is_ok_and(|content| content.to_lowercase().contains(&query))
+        })
+        .map(|entry| entry.path)
+        .take(SEARCH_RESULT_LIMIT)
+        .collect();
+    Ok(matches)
+}
+
+/// host api imported by plugins from the `flowrite` module. strings are passed
+/// as (ptr, len) pairs in guest memory; string results come back packed as
+/// `(ptr << 32) | len` and every call returns -1 on failure.
+fn build_linker(engine: &Engine) -> Result<Linker<HostContext>, String> {
+    let mut linker = Linker::new(engine);
+
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "log",
+            |mut caller: Caller<'_, HostContext>, ptr: i32, len: i32| {
+                if let Ok(message) = read_guest_string(&mut caller, ptr, len) {
+                    log::info!("plugin '{}': {message}", caller.data().plugin_id);
+                }
+            },
+        )
+        .map_err(|e| e.to_string())?;
+
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "read_note",
+            |mut caller: Caller<'_, HostContext>, ptr: i32, len: i32| -> i64 {
+                let result = (|| -> Result<i64, String> {
+                    caller.data().require(PluginCapability::ReadNotes)?;
+                    let path = read_guest_string(&mut caller, ptr, len)?;
+                    let vault_dir = caller.data().vault_dir.clone();
+                    let (full_path, _) = resolve_note_path(&vault_dir, &path)?;
+                    let content = read_note(&vault_dir, &full_path)
+                        .map_err(|e| format!("failed to read note {path}: {e}"))?;
+                    write_guest_string(&mut caller, &content)
+                })();
+                host_result(&caller, result)
+            },
+        )
+        .map_err(|e| e.to_string())?;
+
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "search_notes",
+            |mut caller: Caller<'_, HostContext>, ptr: i32, len: i32| -> i64 {
+                let result = (|| -> Result<i64, String> {
+                    caller.data().require(PluginCapability::SearchNotes)?;
+                    let query = read_guest_string(&mut caller, ptr, len)?;
+                    let matches = search_notes(&caller.data().vault_dir, &query)?;
+                    let json = serde_json::to_string(&matches).map_err(|e| e.to_string())?;
+                    write_guest_string(&mut caller, &json)
+                })();
+                host_result(&caller, result)
+            },
+        )
+        .map_err(|e| e.to_string())?;
+
+    linker
+        .func_wrap(
+            HOST_MODULE,
+            "create_note",
+            |mut caller: Caller<'_, HostContext>,
+             path_ptr: i32,
+             path_len: i32,
+             content_ptr: i32,
+             content_len: i32|
+             -> i64 {
+                let result = (|| -> Result<i64, String> {
+                    caller.data().require(PluginCapability::CreateNotes)?;
+                    let path = read_guest_string(&mut caller, path_ptr, path_len)?;
+                    let content = read_guest_string(&mut caller, content_ptr, content_len)?;
+                    let app_handle = caller.data().app_handle.clone();
+                    let vault_dir = caller.data().vault_dir.clone();
+                    let (full_path, path) = resolve_note_path(&vault_dir, &path)?;
+                    if full_path.exists() {
+                        return Err(format!("note '{path}' already exists"));
+                    }
+                    tauri::async_runtime::block_on(vaults::within(
+                        vault_dir,
+                        nb::create_file(&app_handle, &path, &content),
+                    ))?;
+                    log::info!("plugin '{}' created note {path}", caller.data().plugin_id);
+                    Ok(0)
+                })();
+                host_result(&caller, result)
+            },
+        )
+        .map_err(|e| e.to_string())?;
+
+    Ok(linker)
+}
+
+/// instantiates a plugin and calls one of its exports with a string argument.
+/// `export` takes `(ptr, len)` and returns a packed string (or 0 for none).
+/// missing optional exports are treated as a no-op. must run on a blocking thread.
+fn call_plugin(
+    app_handle: &AppHandle,
+    vault_dir: PathBuf,
+    manifest: &PluginManifest,
+    export: &str,
+    input: &str,
+) -> Result<Option<String>, String> {
+    let host = app_handle.state::<PluginHost>();
+    let module = get_module(app_handle, &host, &manifest.id)?;
+    let engine = host.engine()?;
+    let linker = build_linker(engine)?;
+
+    let mut store = Store::new(
+        engine,
+        HostContext {
+            app_handle: app_handle.clone(),
+            vault_dir,
+            plugin_id: manifest.id.clone(),
+            capabilities: manifest.capabilities.clone(),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(PLUGIN_MEMORY_LIMIT)
+                .build(),
+        },
+    );
+    store.limiter(|context| &mut context.limits);
+    store
+        .set_fuel(PLUGIN_FUEL)
+        .map_err(|e| format!("failed to set plugin fuel: {e}"))?;
+
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .map_err(|e| format!("failed to instantiate plugin '{}': {e}", manifest.id))?;
+    let Ok(func) = instance.get_typed_func::<(i32, i32), i64>(&mut store, export) else {
+        return Ok(None);
+    };
+
+    let memory = instance
+        .get_memory(&mut store, "memory")
+        .ok_or("plugin does not export memory")?;
+    let alloc = instance
+        .get_typed_func::<i32, i32>(&mut store, "alloc")
+        .map_err(|e| format!("plugin does not export alloc: {e}"))?;
+    let len = i32::try_from(input.len()).map_err(|_| "plugin input too large")?;
+    let ptr = alloc
+        .call(&mut store, len)
+        .map_err(|e| format!("plugin alloc failed: {e}"))?;
+    memory
+        .write(&mut store, ptr as usize, input.as_bytes())
+        .map_err(|e| format!("failed to write plugin memory: {e}"))?;
+
+    let packed = func
+        .call(&mut store, (ptr, len))
+        .map_err(|e| format!("plugin '{}' {export} failed: {e}", manifest.id))?;
+    if packed <= 0 {
+        return Ok(None);
+    }
+
+    let (out_ptr, out_len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+    let bytes = memory
+        .data(&store)
+        .get(out_ptr..out_ptr + out_len)
+        .ok_or("plugin returned an out-of-bounds result")?;
+    Ok(Some(String::from_utf8_lossy(bytes).to_string()))
+}
+
+/// forwards a flushed watcher event to enabled plugins with the
+/// `watch_events` capability (their `on_event` export receives it as json)
+pub fn dispatch_watcher_event(app_handle: &AppHandle, event: &FileWatcherEvent) {
+    let enabled = get_enabled_ids(app_handle);
+    if enabled.is_empty() {
+        return;
+    }
+    let Ok(payload) = serde_json::to_string(event) else {
+        return;
+    };
+    let Ok(vault_dir) = get_base_dir(app_handle) else {
+        return;
+    };
+
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        for id in enabled {
+            let manifest = match find_manifest(&handle, &id).await {
+                Ok(manifest) => manifest,
+                Err(e) => {
+                    log::warn!("skipping plugin {id}: {e}");
+                    continue;
+                }
+            };
+            if !manifest
+                .capabilities
+                .contains(&PluginCapability::WatchEvents)
+            {
+                continue;
+            }
+
+            let task_handle = handle.clone();
+            let task_vault_dir = vault_dir.clone();
+            let task_payload = payload.clone();
+            let result = tokio::task::spawn_blocking(move || {
+                call_plugin(
+                    &task_handle,
+                    task_vault_dir,
+                    &manifest,
+                    "on_event",
+                    &task_payload,
+                )
+            })
+            .await;
+            match result {
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => log::warn!("plugin {id} failed to handle watcher event: {e}"),
+                Err(e) => log::warn!("plugin {id} panicked handling watcher event: {e}"),
+            }
+        }
+    });
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+#[tauri::command]
+pub async fn list_plugins(app_handle: AppHandle) -> Result<Vec<PluginInfo>, String> {
+    let enabled = get_enabled_ids(&app_handle);
+    Ok(load_manifests(&app_handle)
+        .await?
+        .into_iter()
+        .map(|manifest| PluginInfo {
+            enabled: enabled.contains(&manifest.id),
+            manifest,
+        })
+        .collect())
+}
+
+/// installs (or updates) a plugin from a directory containing `plugin.json`
+/// and `plugin.wasm`. new plugins start disabled.
+#[tauri::command]
+pub async fn install_plugin(
+    app_handle: AppHandle,
+    host: State<'_, PluginHost>,
+    source: String,
+) -> Result<PluginInfo, String> {
+    log::info!("installing plugin from {source}");
+
+    let source_dir = PathBuf::from(&source);
+    if !source_dir.is_absolute() {
+        return Err(format!("plugin source '{source}' must be an absolute path"));
+    }
+    let manifest = read_manifest(&source_dir).await?;
+    let wasm = fs::read(source_dir.join(PLUGIN_MODULE_FILE_NAME))
+        .await
+        .map_err(|e| format!("failed to read plugin module: {e}"))?;
+    Module::validate(host.engine()?, &wasm).map_err(|e| format!("invalid plugin module: {e}"))?;
+
+    let dest_dir = get_plugins_dir(&app_handle)?.join(&manifest.id);
+    fs::create_dir_all(&dest_dir)
+        .await
+        .map_err(|e| format!("failed to create plugin directory: {e}"))?;
+    fs::copy(
+        source_dir.join(PLUGIN_MANIFEST_FILE_NAME),
+        dest_dir.join(PLUGIN_MANIFEST_FILE_NAME),
+    )
+    .await
+    .map_err(|e| format!("failed to copy plugin manifest: {e}"))?;
+    fs::write(dest_dir.join(PLUGIN_MODULE_FILE_NAME), wasm)
+        .await
+        .map_err(|e| format!("failed to copy plugin module: {e}"))?;
+
+    // drop any stale compiled module from a previous version
+    host.modules.lock().unwrap().remove(&manifest.id);
+
+    log::info!("installed plugin {} {}", manifest.id, manifest.version);
+
+    Ok(PluginInfo {
+        enabled: get_enabled_ids(&app_handle).contains(&manifest.id),
+        manifest,
+    })
+}
+
+#[tauri::command]
+pub async fn set_plugin_enabled(
+    app_handle: AppHandle,
+    id: String,
+    enabled: bool,
+) -> Result<(), String> {
+    log::info!("setting plugin {id} enabled: {enabled}");
+    find_manifest(&app_handle, &id).await?;
+
+    let mut ids = get_enabled_ids(&app_handle);
+    ids.retain(|existing| *existing != id);
+    if enabled {
+        ids.push(id);
+    }
+    set_setting(&app_handle, PLUGINS_ENABLED_KEY, &ids)
+}
+
+#[tauri::command]
+pub async fn uninstall_plugin(
+    app_handle: AppHandle,
+    host: State<'_, PluginHost>,
+    id: String,
+) -> Result<(), String> {
+    log::info!("uninstalling plugin {id}");
+    find_manifest(&app_handle, &id).await?;
+
+    set_plugin_enabled(app_handle.clone(), id.clone(), false).await?;
+    host.modules.lock().unwrap().remove(&id);
+    fs::remove_dir_all(get_plugins_dir(&app_handle)?.join(&id))
+        .await
+        .map_err(|e| format!("failed to remove plugin '{id}': {e}"))
+}
+
+/// runs an enabled plugin's `run` export against the window's vault with an
+/// optional input string and returns its output, if any
+#[tauri::command]
+pub async fn run_plugin(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    id: String,
+    input: Option<String>,
+) -> Result<Option<String>, String> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("running plugin {id}");
+        if !get_enabled_ids(&app_handle).contains(&id) {
+            return Err(format!("plugin '{id}' is not enabled"));
+        }
+        let manifest = find_manifest(&app_handle, &id).await?;
+
+        let vault_dir = get_base_dir(&app_handle)?;
+        let handle = app_handle.clone();
+        tokio::task::spawn_blocking(move || {
+            call_plugin(
+                &handle,
+                vault_dir,
+                &manifest,
+                "run",
+                &input.unwrap_or_default(),
+            )
+        })
+        .await
+        .map_err(|e| format!("plugin '{id}' panicked: {e}"))?
+    })
+    .await
+}
@@ -0,0 +1,81 @@
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tokio::fs;
+
+/// headroom kept free on the target volume beyond an operation's own estimate
+const SAFETY_MARGIN_BYTES: u64 = 16 * 1024 * 1024;
+
+/// name of the probe file used to verify write access
+const WRITE_PROBE_FILE_NAME: &str = ".flowrite-preflight";
+
+#[derive(Clone, Debug, Serialize, thiserror::Error)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PreflightError {
+    #[error(
+        "not enough disk space at '{path}': {required_bytes} bytes required, {available_bytes} available"
+    )]
+    #[serde(rename_all = "camelCase")]
+    InsufficientSpace {
+        path: String,
+        required_bytes: u64,
+        available_bytes: u64,
+    },
+    #[error("'{path}' is not writable: {message}")]
+    #[serde(rename_all = "camelCase")]
+    NotWritable { path: String, message: String },
+}
+
+/// serialized as json so the frontend can branch on `kind`
+impl From<PreflightError> for String {
+    fn from(error: PreflightError) -> Self {
+        serde_json::to_string(&error).unwrap_or_else(|_| error.to_string())
+    }
+}
+
+/// verifies that `target` (or its nearest existing ancestor, for paths that
+/// will be created) is writable and has room for `required_bytes` plus a margin.
+/// run this before operations that would otherwise fail halfway through a batch.
+pub async fn check(target: &Path, required_bytes: u64) -> Result<(), PreflightError> {
+    let dir = nearest_existing_dir(target);
+    let path = dir.to_string_lossy().to_string();
+
+    let available_bytes = fs4::available_space(&dir).map_err(|e| PreflightError::NotWritable {
+        path: path.clone(),
+        message: format!("failed to query free space: {e}"),
+    })?;
+
+    let required_with_margin = required_bytes.saturating_add(SAFETY_MARGIN_BYTES);
+    if available_bytes < required_with_margin {
+        log::warn!(
+            "preflight failed for '{path}': {required_with_margin} bytes required, {available_bytes} available"
+        );
+        return Err(PreflightError::InsufficientSpace {
+            path,
+            required_bytes: required_with_margin,
+            available_bytes,
+        });
+    }
+
+    let probe = dir.join(WRITE_PROBE_FILE_NAME);
+    fs::write(&probe, b"")
+        .await
+        .map_err(|e| PreflightError::NotWritable {
+            path: path.clone(),
+            message: e.to_string(),
+        })?;
+    let _ = fs::remove_file(&probe).await;
+
+    log::debug!("preflight ok for '{path}' ({required_bytes} bytes)");
+
+    Ok(())
+}
+
+/// walks up from `target` to the first directory that exists
+fn nearest_existing_dir(target: &Path) -> PathBuf {
+    target
+        .ancestors()
+        .find(|ancestor| ancestor.is_dir())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("/"))
+}
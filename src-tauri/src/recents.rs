@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+
+use tauri::menu::{MenuItem, MenuItemKind};
+use tauri::{AppHandle, WebviewWindow};
+
+use crate::{
+    constants::{PINNED_NOTES_KEY, RECENT_FILES_KEY},
+    utils::{get_base_dir, get_setting, resolve_path, set_setting},
+    vaults, OPEN_RECENT_MENU_ID,
+};
+
+/// how many recently opened files are kept per vault
+const RECENT_FILES_LIMIT: usize = 20;
+
+/// paths per vault, keyed by the vault's absolute path
+type VaultLists = HashMap<String, Vec<String>>;
+
+fn vault_key(app_handle: &AppHandle) -> Result<String, String> {
+    Ok(get_base_dir(app_handle)?.to_string_lossy().to_string())
+}
+
+fn get_list(app_handle: &AppHandle, key: &str, vault: &str) -> Vec<String> {
+    get_setting::<VaultLists>(app_handle, key)
+        .and_then(|mut lists| lists.remove(vault))
+        .unwrap_or_default()
+}
+
+fn set_list(
+    app_handle: &AppHandle,
+    key: &str,
+    vault: &str,
+    paths: Vec<String>,
+) -> Result<(), String> {
+    let mut lists: VaultLists = get_setting(app_handle, key).unwrap_or_default();
+    lists.insert(vault.to_string(), paths);
+    set_setting(app_handle, key, &lists)
+}
+
+/// keeps paths that still exist in the vault, without duplicates
+fn existing_paths(app_handle: &AppHandle, paths: Vec<String>) -> Vec<String> {
+    let mut existing: Vec<String> = Vec::new();
+    for path in paths {
+        let exists = resolve_path(app_handle, &path).is_ok_and(|full_path| full_path.is_file());
+        if exists && !existing.contains(&path) {
+            existing.push(path);
+        }
+    }
+    existing
+}
+
+/// moves `path` to the front of the current vault's recent files
+pub fn add_recent_file(app_handle: &AppHandle, path: &str) -> Result<Vec<String>, String> {
+    let vault = vault_key(app_handle)?;
+    let mut recent = get_list(app_handle, RECENT_FILES_KEY, &vault);
+    recent.retain(|recent_path| recent_path != path);
+    recent.insert(0, path.to_string());
+    recent.truncate(RECENT_FILES_LIMIT);
+    set_list(app_handle, RECENT_FILES_KEY, &vault, recent.clone())?;
+
+    update_recent_menu(app_handle, &recent);
+    Ok(recent)
+}
+
+// -----------------------------------------
+// open recent menu
+// -----------------------------------------
+
+/// menu item id for a recent file, handled in `lib.rs`
+fn recent_menu_item_id(path: &str) -> String {
+    format!("{OPEN_RECENT_MENU_ID}:{path}")
+}
+
+/// replaces the items of the native "Open Recent" submenu
+fn update_recent_menu(app_handle: &AppHandle, recent: &[String]) {
+    let Some(menu) = app_handle.menu() else {
+        return;
+    };
+    for item in menu.items().unwrap_or_default() {
+        let MenuItemKind::Submenu(submenu) = item else {
+            continue;
+        };
+        let Some(MenuItemKind::Submenu(recent_menu)) = submenu.get(OPEN_RECENT_MENU_ID) else {
+            continue;
+        };
+
+        for item in recent_menu.items().unwrap_or_default() {
+            let _ = recent_menu.remove(&item);
+        }
+        for path in recent {
+            match MenuItem::with_id(
+                app_handle,
+                recent_menu_item_id(path),
+                path,
+                true,
+                None::<&str>,
+            ) {
+                Ok(menu_item) => {
+                    let _ = recent_menu.append(&menu_item);
+                }
+                Err(e) => log::error!("failed to create recent file menu item: {e}"),
+            }
+        }
+        let _ = recent_menu.set_enabled(!recent.is_empty());
+    }
+}
+
+/// shows the recent files of the vault in the given (focused) window.
+/// the app menu is shared, so it is re-synced whenever window focus changes.
+pub fn sync_recent_menu(app_handle: &AppHandle, label: &str) {
+    let Some(vault_dir) = vaults::window_vault_dir(label) else {
+        return;
+    };
+    let vault = vault_dir.to_string_lossy().to_string();
+    let recent = get_list(app_handle, RECENT_FILES_KEY, &vault);
+    update_recent_menu(app_handle, &recent);
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+/// recently opened files of the window's vault, most recent first.
+/// files that no longer exist are dropped.
+#[tauri::command]
+pub async fn recent_files_get(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+) -> Result<Vec<String>, String> {
+    vaults::scoped(&webview_window, async move {
+        let vault = vault_key(&app_handle)?;
+        let recent = get_list(&app_handle, RECENT_FILES_KEY, &vault);
+        let existing = existing_paths(&app_handle, recent.clone());
+        if existing.len() != recent.len() {
+            set_list(&app_handle, RECENT_FILES_KEY, &vault, existing.clone())?;
+            update_recent_menu(&app_handle, &existing);
+        }
+        Ok(existing)
+    })
+    .await
+}
+
+/// records a file as opened (`read_file` does this automatically)
+#[tauri::command]
+pub async fn recent_files_add(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    path: String,
+) -> Result<Vec<String>, String> {
+    vaults::scoped(&webview_window, async move {
+        add_recent_file(&app_handle, &path)
+    })
+    .await
+}
+
+/// pinned notes of the window's vault, in the user's order
+#[tauri::command]
+pub async fn pins_get(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+) -> Result<Vec<String>, String> {
+    vaults::scoped(&webview_window, async move {
+        let vault = vault_key(&app_handle)?;
+        Ok(existing_paths(
+            &app_handle,
+            get_list(&app_handle, PINNED_NOTES_KEY, &vault),
+        ))
+    })
+    .await
+}
+
+/// replaces the pinned notes of the window's vault
+#[tauri::command]
+pub async fn pins_set(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    paths: Vec<String>,
+) -> Result<Vec<String>, String> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("setting {} pinned notes", paths.len());
+        let vault = vault_key(&app_handle)?;
+        let pins = existing_paths(&app_handle, paths);
+        set_list(&app_handle, PINNED_NOTES_KEY, &vault, pins.clone())?;
+        Ok(pins)
+    })
+    .await
+}
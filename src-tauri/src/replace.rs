@@ -0,0 +1,203 @@
+use regex::{NoExpand, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, WebviewWindow};
+
+use crate::{
+    command::{list_dir_inner, FSEntry},
+    locks, nb,
+    utils::{get_base_dir, resolve_path},
+    vault_settings, vaults,
+};
+
+/// lines previewed per note; the match count is always exact
+const MAX_PREVIEWS_PER_FILE: usize = 50;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReplaceOptions {
+    /// treat `query` as a regular expression; `$1`, `${name}` in the
+    /// replacement then refer to its groups
+    pub regex: bool,
+    pub case_insensitive: bool,
+    pub whole_word: bool,
+    /// only return what would change
+    pub dry_run: bool,
+}
+
+/// a line with a match, before and after the replacement
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchPreview {
+    /// 1-based line number
+    pub line: usize,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileReplacement {
+    pub path: String,
+    pub matches: usize,
+    pub previews: Vec<MatchPreview>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplaceResult {
+    pub files: Vec<FileReplacement>,
+    pub total_matches: usize,
+    /// notes with matches that were left alone because they are locked
+    pub skipped_locked: Vec<String>,
+    pub dry_run: bool,
+}
+
+fn build_regex(query: &str, options: &ReplaceOptions) -> Result<Regex, String> {
+    let pattern = if options.regex {
+        query.to_string()
+    } else {
+        regex::escape(query)
+    };
+    let pattern = if options.whole_word {
+        format!(r"\b(?:{pattern})\b")
+    } else {
+        pattern
+    };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(options.case_insensitive)
+        .multi_line(true)
+        .build()
+        .map_err(|e| format!("invalid search pattern: {e}"))
+}
+
+fn replace_all(re: &Regex, text: &str, replacement: &str, expand: bool) -> String {
+    if expand {
+        re.replace_all(text, replacement).into_owned()
+    } else {
+        re.replace_all(text, NoExpand(replacement)).into_owned()
+    }
+}
+
+/// the lines where matches start, each with its replaced version
+fn previews(re: &Regex, content: &str, replacement: &str, expand: bool) -> Vec<MatchPreview> {
+    let mut line_starts: Vec<usize> = Vec::new();
+    for found in re.find_iter(content) {
+        let start = content[..found.start()].rfind('\n').map_or(0, |at| at + 1);
+        if line_starts.last() != Some(&start) {
+            line_starts.push(start);
+        }
+        if line_starts.len() == MAX_PREVIEWS_PER_FILE {
+            break;
+        }
+    }
+    line_starts
+        .into_iter()
+        .map(|start| {
+            let line = content[start..].lines().next().unwrap_or("");
+            MatchPreview {
+                line: content[..start].matches('\n').count() + 1,
+                before: line.to_string(),
+                after: replace_all(re, line, replacement, expand),
+            }
+        })
+        .collect()
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+/// replaces `query` in every note of the vault. with `dryRun` only the
+/// matches are returned; otherwise the notes are written and checkpointed
+/// together as one "Replace" commit.
+#[tauri::command]
+pub async fn replace_in_vault(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    query: String,
+    replacement: String,
+    options: Option<ReplaceOptions>,
+) -> Result<ReplaceResult, String> {
+    vaults::scoped(&webview_window, async move {
+        let options = options.unwrap_or_default();
+        log::info!(
+            "replacing '{query}' in vault (regex: {}, dry run: {})",
+            options.regex,
+            options.dry_run
+        );
+        if query.is_empty() {
+            return Err("search query cannot be empty".to_string());
+        }
+        let re = build_regex(&query, &options)?;
+
+        let vault_root = get_base_dir(&app_handle)?;
+        let root = resolve_path(&app_handle, "")?;
+        let mut entries: Vec<FSEntry> = Vec::new();
+        list_dir_inner(&root, "", true, &mut entries).await?;
+
+        let mut result = ReplaceResult {
+            files: Vec::new(),
+            total_matches: 0,
+            skipped_locked: Vec::new(),
+            dry_run: options.dry_run,
+        };
+        let mut errors = Vec::new();
+        for entry in entries {
+            if entry.is_dir || !vault_settings::is_managed_file(&entry.path) {
+                continue;
+            }
+            // decrypts notes in encrypted folders; unreadable notes are skipped
+            let Ok(content) = nb::read_file(&app_handle, &entry.path).await else {
+                continue;
+            };
+            let matches = re.find_iter(&content).count();
+            if matches == 0 {
+                continue;
+            }
+            if locks::find_locked(&vault_root, &entry.path).is_some() {
+                result.skipped_locked.push(entry.path);
+                continue;
+            }
+
+            if !options.dry_run {
+                let new_content = replace_all(&re, &content, &replacement, options.regex);
+                // keep going so the notes already rewritten get checkpointed
+                if let Err(e) = nb::write_file(&app_handle, &entry.path, &new_content, false).await
+                {
+                    errors.push(e);
+                    continue;
+                }
+            }
+            result.total_matches += matches;
+            result.files.push(FileReplacement {
+                previews: previews(&re, &content, &replacement, options.regex),
+                path: entry.path,
+                matches,
+            });
+        }
+
+        if !options.dry_run && !result.files.is_empty() {
+            nb::reconcile_and_checkpoint_paths(
+                &app_handle,
+                result.files.iter().map(|file| file.path.clone()).collect(),
+                format!("[nb] Replace: '{query}' in {} notes", result.files.len()),
+            );
+        }
+        if !errors.is_empty() {
+            return Err(format!(
+                "replaced in {} notes, but {} could not be written: {}",
+                result.files.len(),
+                errors.len(),
+                errors.join("; ")
+            ));
+        }
+
+        log::info!(
+            "replace in vault: {} matches in {} notes",
+            result.total_matches,
+            result.files.len()
+        );
+        Ok(result)
+    })
+    .await
+}
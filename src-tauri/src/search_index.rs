@@ -0,0 +1,359 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Mutex;
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use tauri::{AppHandle, Manager, WebviewWindow};
+use tokio::fs;
+
+use crate::{
+    command::{list_dir_inner, FSEntry},
+    constants::SEARCH_INDEX_FILE_NAME,
+    file_watcher::FileWatcherEvent,
+    utils::{get_base_dir, resolve_path},
+    vault_settings, vaults,
+};
+
+/// default and maximum number of hits returned by `search_index_query`
+const DEFAULT_QUERY_LIMIT: usize = 50;
+const MAX_QUERY_LIMIT: usize = 500;
+
+/// full-text index of the active vault's notes (sqlite fts5 in the app data directory).
+/// `None` until `init` opened the database.
+#[derive(Default)]
+pub struct SearchIndex(Mutex<Option<Connection>>);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub path: String,
+    pub title: String,
+    /// matching excerpt with hits wrapped in `<mark>` tags
+    pub snippet: String,
+    /// bm25 rank, lower is better
+    pub score: f64,
+}
+
+/// a note read from disk, ready to be written to the index
+struct IndexedNote {
+    path: String,
+    modified_time_ms: u64,
+    title: String,
+    body: String,
+}
+
+// -----------------------------------------
+// database
+// -----------------------------------------
+
+fn open_database(app_handle: &AppHandle) -> Result<Connection, String> {
+    let app_data = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("failed to get app data directory: {e}"))?;
+    std::fs::create_dir_all(&app_data)
+        .map_err(|e| format!("failed to create app data directory: {e}"))?;
+
+    let conn = Connection::open(app_data.join(SEARCH_INDEX_FILE_NAME))
+        .map_err(|e| format!("failed to open search index: {e}"))?;
+    conn.execute_batch(
+        "PRAGMA journal_mode = WAL;
+         CREATE TABLE IF NOT EXISTS files (
+             path TEXT PRIMARY KEY,
+             modified_time_ms INTEGER NOT NULL
+         );
+         CREATE VIRTUAL TABLE IF NOT EXISTS notes USING fts5(
+             path UNINDEXED,
+             title,
+             body,
+             tokenize = 'porter unicode61'
+         );",
+    )
+    .map_err(|e| format!("failed to create search index tables: {e}"))?;
+    Ok(conn)
+}
+
+/// runs `f` against the open index. fails if the index was not initialized.
+fn with_connection<T>(
+    app_handle: &AppHandle,
+    f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>,
+) -> Result<T, String> {
+    let state = app_handle
+        .try_state::<SearchIndex>()
+        .ok_or_else(|| "search index is not available".to_string())?;
+    let mut guard = state.0.lock().unwrap();
+    let conn = guard
+        .as_mut()
+        .ok_or_else(|| "search index is not initialized".to_string())?;
+    f(conn).map_err(|e| format!("search index error: {e}"))
+}
+
+fn write_changes(
+    app_handle: &AppHandle,
+    notes: &[IndexedNote],
+    removed: &[String],
+) -> Result<(), String> {
+    if notes.is_empty() && removed.is_empty() {
+        return Ok(());
+    }
+    with_connection(app_handle, |conn| {
+        let tx = conn.transaction()?;
+        for path in removed {
+            // removing a directory removes everything under it
+            let prefix = format!("{path}/%");
+            tx.execute(
+                "DELETE FROM notes WHERE path = ?1 OR path LIKE ?2",
+                params![path, prefix],
+            )?;
+            tx.execute(
+                "DELETE FROM files WHERE path = ?1 OR path LIKE ?2",
+                params![path, prefix],
+            )?;
+        }
+        for note in notes {
+            tx.execute("DELETE FROM notes WHERE path = ?1", params![note.path])?;
+            tx.execute(
+                "INSERT INTO notes (path, title, body) VALUES (?1, ?2, ?3)",
+                params![note.path, note.title, note.body],
+            )?;
+            tx.execute(
+                "INSERT OR REPLACE INTO files (path, modified_time_ms) VALUES (?1, ?2)",
+                params![note.path, note.modified_time_ms as i64],
+            )?;
+        }
+        tx.commit()
+    })
+}
+
+/// indexed paths (with their modification time) under `dir`, or the whole vault for ""
+fn indexed_files_under(app_handle: &AppHandle, dir: &str) -> Result<HashMap<String, u64>, String> {
+    with_connection(app_handle, |conn| {
+        let mut stmt = conn.prepare(
+            "SELECT path, modified_time_ms FROM files WHERE ?1 = '' OR path LIKE ?1 || '/%'",
+        )?;
+        let rows = stmt.query_map(params![dir], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)? as u64))
+        })?;
+        rows.collect()
+    })
+}
+
+// -----------------------------------------
+// indexing
+// -----------------------------------------
+
+async fn read_note(
+    app_handle: &AppHandle,
+    path: &str,
+    modified_time_ms: u64,
+) -> Option<IndexedNote> {
+    let full_path = resolve_path(app_handle, path).ok()?;
+    match fs::read_to_string(&full_path).await {
+        Ok(body) => Some(IndexedNote {
+            path: path.to_string(),
+            modified_time_ms,
+            title: Path::new(path)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            body,
+        }),
+        Err(e) => {
+            log::warn!("search index: skipping unreadable note {path}: {e}");
+            None
+        }
+    }
+}
+
+/// brings the index in line with the notes under `dir` ("" for the whole vault):
+/// new and modified notes are (re)indexed, notes that no longer exist are dropped.
+/// returns the number of notes written.
+async fn reconcile_dir(app_handle: &AppHandle, dir: &str) -> Result<usize, String> {
+    let dir_path = resolve_path(app_handle, dir)?;
+    let mut entries: Vec<FSEntry> = Vec::new();
+    if dir_path.is_dir() {
+        list_dir_inner(&dir_path, dir, true, &mut entries).await?;
+    }
+    let indexed = indexed_files_under(app_handle, dir)?;
+
+    let mut present = HashSet::new();
+    let mut notes = Vec::new();
+    for entry in entries.into_iter().filter(|entry| !entry.is_dir) {
+        present.insert(entry.path.clone());
+        if indexed.get(&entry.path) == Some(&entry.modified_time_ms) {
+            continue;
+        }
+        if let Some(note) = read_note(app_handle, &entry.path, entry.modified_time_ms).await {
+            notes.push(note);
+        }
+    }
+    let removed: Vec<String> = indexed
+        .into_keys()
+        .filter(|path| !present.contains(path))
+        .collect();
+
+    write_changes(app_handle, &notes, &removed)?;
+    Ok(notes.len())
+}
+
+async fn index_files(app_handle: &AppHandle, paths: &[String]) -> Result<(), String> {
+    let mut notes = Vec::new();
+    for path in paths {
+        let Ok(full_path) = resolve_path(app_handle, path) else {
+            continue;
+        };
+        let modified_time_ms = fs::metadata(&full_path)
+            .await
+            .ok()
+            .and_then(|metadata| metadata.modified().ok())
+            .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|duration| duration.as_millis() as u64)
+            .unwrap_or(0);
+        if let Some(note) = read_note(app_handle, path, modified_time_ms).await {
+            notes.push(note);
+        }
+    }
+    write_changes(app_handle, &notes, &[])
+}
+
+/// opens the index and catches it up with changes made while the app was closed
+pub fn init(app_handle: &AppHandle) {
+    let conn = match open_database(app_handle) {
+        Ok(conn) => conn,
+        Err(e) => {
+            log::error!("search index: {e}");
+            return;
+        }
+    };
+    if let Some(state) = app_handle.try_state::<SearchIndex>() {
+        *state.0.lock().unwrap() = Some(conn);
+    }
+
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        match reconcile_dir(&handle, "").await {
+            Ok(count) => log::info!("search index: indexed {count} changed notes"),
+            Err(e) => log::error!("search index: failed to sync vault: {e}"),
+        }
+    });
+}
+
+/// keeps the index in sync with a flushed file watcher event
+pub fn sync_watcher_event(app_handle: &AppHandle, event: &FileWatcherEvent) {
+    let mut modified = Vec::new();
+    let mut removed = Vec::new();
+    let mut directories = event.directory_changes.clone();
+
+    for change in &event.file_changes {
+        if change.kind == "delete" {
+            removed.push(change.path.clone());
+        } else if vault_settings::is_managed_file(&change.path) {
+            modified.push(change.path.clone());
+        }
+    }
+    for delta in event.directory_deltas.iter().flatten() {
+        removed.extend(delta.removed.iter().cloned());
+        let renamed_to = delta.renamed.iter().map(|renamed| &renamed.to);
+        removed.extend(delta.renamed.iter().map(|renamed| renamed.from.clone()));
+        for entry in delta.added.iter().chain(&delta.updated).chain(renamed_to) {
+            if entry.is_dir {
+                directories.push(entry.path.clone());
+            } else {
+                modified.push(entry.path.clone());
+            }
+        }
+    }
+
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = async {
+            write_changes(&handle, &[], &removed)?;
+            index_files(&handle, &modified).await?;
+            for dir in directories {
+                reconcile_dir(&handle, &dir).await?;
+            }
+            Ok::<_, String>(())
+        }
+        .await;
+        if let Err(e) = result {
+            log::error!("search index: failed to update: {e}");
+        }
+    });
+}
+
+/// turns free text into an fts5 query: every word must match, the last one as a prefix
+fn to_fts_query(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect();
+    if terms.is_empty() {
+        return None;
+    }
+    Some(format!("{}*", terms.join(" ")))
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+/// searches note titles and bodies. results are ordered by relevance.
+#[tauri::command]
+pub async fn search_index_query(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    query: String,
+    limit: Option<usize>,
+) -> Result<Vec<SearchHit>, String> {
+    vaults::ensure_active_vault(&webview_window)?;
+    vaults::scoped(&webview_window, async move {
+        let Some(fts_query) = to_fts_query(&query) else {
+            return Ok(Vec::new());
+        };
+        let limit = limit.unwrap_or(DEFAULT_QUERY_LIMIT).min(MAX_QUERY_LIMIT);
+
+        with_connection(&app_handle, |conn| {
+            let mut stmt = conn.prepare(
+                "SELECT path, title, snippet(notes, 2, '<mark>', '</mark>', '…', 16), bm25(notes, 0.0, 5.0, 1.0)
+                 FROM notes WHERE notes MATCH ?1
+                 ORDER BY bm25(notes, 0.0, 5.0, 1.0)
+                 LIMIT ?2",
+            )?;
+            let rows = stmt.query_map(params![fts_query, limit as i64], |row| {
+                Ok(SearchHit {
+                    path: row.get(0)?,
+                    title: row.get(1)?,
+                    snippet: row.get(2)?,
+                    score: row.get(3)?,
+                })
+            })?;
+            rows.collect()
+        })
+    })
+    .await
+}
+
+/// drops the index and rebuilds it from every note in the vault.
+/// returns the number of indexed notes.
+#[tauri::command]
+pub async fn search_index_rebuild(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+) -> Result<usize, String> {
+    vaults::ensure_active_vault(&webview_window)?;
+    vaults::scoped(&webview_window, async move {
+        log::info!("rebuilding search index");
+        let base_dir = get_base_dir(&app_handle)?;
+        if !base_dir.exists() {
+            return Ok(0);
+        }
+        with_connection(&app_handle, |conn| {
+            conn.execute_batch("DELETE FROM notes; DELETE FROM files;")
+        })?;
+        let count = reconcile_dir(&app_handle, "").await?;
+        log::info!("search index: rebuilt with {count} notes");
+        Ok(count)
+    })
+    .await
+}
@@ -0,0 +1,117 @@
+use security_framework::passwords::{
+    delete_generic_password, get_generic_password, set_generic_password,
+};
+
+use crate::constants::{INTERNAL_KEYCHAIN_SERVICE, KEYCHAIN_ENV_PREFIX, KEYCHAIN_SERVICE};
+
+/// `errSecItemNotFound`
+const ERR_SEC_ITEM_NOT_FOUND: i32 = -25300;
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("secret name cannot be empty".to_string());
+    }
+    Ok(())
+}
+
+fn read_from(service: &str, name: &str) -> Result<Option<String>, String> {
+    match get_generic_password(service, name) {
+        Ok(bytes) => String::from_utf8(bytes)
+            .map(Some)
+            .map_err(|_| format!("secret '{name}' is not valid utf-8")),
+        Err(e) if e.code() == ERR_SEC_ITEM_NOT_FOUND => Ok(None),
+        Err(e) => Err(format!(
+            "failed to read secret '{name}' from the keychain: {e}"
+        )),
+    }
+}
+
+fn write_to(service: &str, name: &str, value: &str) -> Result<(), String> {
+    set_generic_password(service, name, value.as_bytes())
+        .map_err(|e| format!("failed to store secret '{name}' in the keychain: {e}"))
+}
+
+/// removing a missing secret is not an error
+fn delete_from(service: &str, name: &str) -> Result<(), String> {
+    match delete_generic_password(service, name) {
+        Ok(()) => Ok(()),
+        Err(e) if e.code() == ERR_SEC_ITEM_NOT_FOUND => Ok(()),
+        Err(e) => Err(format!(
+            "failed to delete secret '{name}' from the keychain: {e}"
+        )),
+    }
+}
+
+fn read_secret(name: &str) -> Result<Option<String>, String> {
+    read_from(KEYCHAIN_SERVICE, name)
+}
+
+fn write_secret(name: &str, value: &str) -> Result<(), String> {
+    write_to(KEYCHAIN_SERVICE, name, value)
+}
+
+fn delete_secret(name: &str) -> Result<(), String> {
+    delete_from(KEYCHAIN_SERVICE, name)
+}
+
+/// reads a secret the app keeps for itself. entries stored by older versions
+/// next to the user's secrets are moved over on first read.
+pub(crate) fn read_internal_secret(name: &str) -> Result<Option<String>, String> {
+    if let Some(value) = read_from(INTERNAL_KEYCHAIN_SERVICE, name)? {
+        return Ok(Some(value));
+    }
+    let Some(value) = read_secret(name)? else {
+        return Ok(None);
+    };
+    write_internal_secret(name, &value)?;
+    delete_secret(name)?;
+    Ok(Some(value))
+}
+
+pub(crate) fn write_internal_secret(name: &str, value: &str) -> Result<(), String> {
+    write_to(INTERNAL_KEYCHAIN_SERVICE, name, value)
+}
+
+/// also removes an entry an older version stored next to the user's secrets
+pub(crate) fn delete_internal_secret(name: &str) -> Result<(), String> {
+    delete_from(INTERNAL_KEYCHAIN_SERVICE, name)?;
+    delete_secret(name)
+}
+
+/// resolves an agent env value. `keychain:<name>` is replaced by the secret stored
+/// under that name; any other value is returned unchanged.
+pub fn resolve_env_value(value: &str) -> Result<String, String> {
+    let Some(name) = value.strip_prefix(KEYCHAIN_ENV_PREFIX) else {
+        return Ok(value.to_string());
+    };
+    read_secret(name)?.ok_or_else(|| format!("secret '{name}' is not in the keychain"))
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+/// stores (or replaces) a secret in the macOS keychain.
+/// agents can then reference it in their env as `keychain:<name>`.
+#[tauri::command]
+pub async fn secrets_set(name: String, value: String) -> Result<(), String> {
+    validate_name(&name)?;
+    log::info!("storing secret: {name}");
+    write_secret(&name, &value)
+}
+
+/// whether a secret is stored under `name`. the value itself never leaves the
+/// backend; it is only read when resolving `keychain:<name>` env values.
+#[tauri::command]
+pub async fn secrets_get(name: String) -> Result<bool, String> {
+    validate_name(&name)?;
+    Ok(read_secret(&name)?.is_some())
+}
+
+/// removes a secret from the keychain. deleting a missing secret is not an error.
+#[tauri::command]
+pub async fn secrets_delete(name: String) -> Result<(), String> {
+    validate_name(&name)?;
+    log::info!("deleting secret: {name}");
+    delete_secret(&name)
+}
@@ -0,0 +1,362 @@
+#![allow(deprecated)]
+
+use std::path::Path;
+use std::sync::OnceLock;
+
+use cocoa::base::{id, nil, BOOL, NO, YES};
+use cocoa::foundation::{NSAutoreleasePool, NSString};
+use objc::runtime::{Class, Object, Sel};
+use objc::{class, msg_send, sel, sel_impl};
+use tauri::AppHandle;
+use tokio::fs;
+
+use crate::{
+    command::{list_dir_inner, FSEntry},
+    constants::SPOTLIGHT_INDEXING_KEY,
+    file_watcher::FileWatcherEvent,
+    frontmatter,
+    utils::{get_base_dir, get_setting, resolve_path, set_setting},
+};
+
+#[link(name = "CoreSpotlight", kind = "framework")]
+extern "C" {}
+
+/// `CSSearchableItemActionType`, the activity type used when a spotlight result is opened
+const SPOTLIGHT_ACTIVITY_TYPE: &str = "com.apple.corespotlightitem";
+
+/// `CSSearchableItemActivityIdentifier`, the user info key holding the item's unique id
+const SPOTLIGHT_ACTIVITY_IDENTIFIER_KEY: &str = "kCSSearchableItemActivityIdentifier";
+
+const NOTE_CONTENT_TYPE: &str = "net.daringfireball.markdown";
+
+/// length of the content snippet shown in spotlight results
+const SNIPPET_CHARS: usize = 300;
+
+/// used by the objc activity handler to route opened results into the app
+static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+
+fn is_enabled(app_handle: &AppHandle) -> bool {
+    get_setting(app_handle, SPOTLIGHT_INDEXING_KEY).unwrap_or(false)
+}
+
+/// installs the handler for opened spotlight results and, if indexing is
+/// enabled, re-indexes the vault in the background. must run on the main thread.
+pub fn init(app_handle: &AppHandle) {
+    let _ = APP_HANDLE.set(app_handle.clone());
+    install_activity_handler();
+
+    if is_enabled(app_handle) {
+        let handle = app_handle.clone();
+        tauri::async_runtime::spawn(async move {
+            match reindex_vault(&handle).await {
+                Ok(count) => log::info!("spotlight: indexed {count} notes"),
+                Err(e) => log::error!("spotlight: failed to index vault: {e}"),
+            }
+        });
+    }
+}
+
+// -----------------------------------------
+// indexing
+// -----------------------------------------
+
+/// the vault path, used as the spotlight domain so each vault can be dropped at once
+fn get_domain(app_handle: &AppHandle) -> Result<String, String> {
+    Ok(get_base_dir(app_handle)?.to_string_lossy().to_string())
+}
+
+/// note title (file name without extension) and a snippet of the body
+/// with any yaml frontmatter stripped
+fn note_summary(path: &Path, content: &str) -> (String, String) {
+    let title = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let snippet = frontmatter::split(content)
+        .body
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .take(SNIPPET_CHARS)
+        .collect();
+
+    (title, snippet)
+}
+
+/// indexes (or re-indexes) a note. the unique identifier is its absolute path.
+fn index_note(domain: &str, path: &Path, content: &str) {
+    let (title, snippet) = note_summary(path, content);
+    let path_str = path.to_string_lossy();
+
+    unsafe {
+        let pool = NSAutoreleasePool::new(nil);
+
+        let attributes: id = msg_send![class!(CSSearchableItemAttributeSet), alloc];
+        let attributes: id =
+            msg_send![attributes, initWithItemContentType: ns_string(NOTE_CONTENT_TYPE)];
+        let _: () = msg_send![attributes, setTitle: ns_string(&title)];
+        let _: () = msg_send![attributes, setContentDescription: ns_string(&snippet)];
+        let _: () = msg_send![attributes, setTextContent: ns_string(content)];
+        let url: id = msg_send![class!(NSURL), fileURLWithPath: ns_string(&path_str)];
+        let _: () = msg_send![attributes, setContentURL: url];
+
+        let item: id = msg_send![class!(CSSearchableItem), alloc];
+        let item: id = msg_send![item,
+            initWithUniqueIdentifier: ns_string(&path_str)
+            domainIdentifier: ns_string(domain)
+            attributeSet: attributes];
+        let items: id = msg_send![class!(NSArray), arrayWithObject: item];
+
+        let index: id = msg_send![class!(CSSearchableIndex), defaultSearchableIndex];
+        let _: () = msg_send![index, indexSearchableItems: items completionHandler: nil];
+
+        let _: () = msg_send![item, release];
+        let _: () = msg_send![attributes, release];
+        pool.drain();
+    }
+}
+
+/// removes notes from the index by absolute path
+fn remove_notes(paths: &[String]) {
+    if paths.is_empty() {
+        return;
+    }
+    unsafe {
+        let pool = NSAutoreleasePool::new(nil);
+
+        let identifiers: id = msg_send![class!(NSMutableArray), array];
+        for path in paths {
+            let _: () = msg_send![identifiers, addObject: ns_string(path)];
+        }
+        let index: id = msg_send![class!(CSSearchableIndex), defaultSearchableIndex];
+        let _: () = msg_send![index,
+            deleteSearchableItemsWithIdentifiers: identifiers
+            completionHandler: nil];
+
+        pool.drain();
+    }
+}
+
+/// removes every note of a vault from the index
+fn remove_domain(domain: &str) {
+    unsafe {
+        let pool = NSAutoreleasePool::new(nil);
+
+        let domains: id = msg_send![class!(NSArray), arrayWithObject: ns_string(domain)];
+        let index: id = msg_send![class!(CSSearchableIndex), defaultSearchableIndex];
+        let _: () = msg_send![index,
+            deleteSearchableItemsWithDomainIdentifiers: domains
+            completionHandler: nil];
+
+        pool.drain();
+    }
+}
+
+/// autoreleased NSString
+unsafe fn ns_string(value: &str) -> id {
+    let string = NSString::alloc(nil).init_str(value);
+    msg_send![string, autorelease]
+}
+
+async fn index_relative_paths(app_handle: &AppHandle, paths: &[String]) -> Result<usize, String> {
+    let domain = get_domain(app_handle)?;
+    let mut count = 0;
+    for path in paths {
+        let full_path = resolve_path(app_handle, path)?;
+        match fs::read_to_string(&full_path).await {
+            Ok(content) => {
+                index_note(&domain, &full_path, &content);
+                count += 1;
+            }
+            Err(e) => log::warn!("spotlight: skipping unreadable note {path}: {e}"),
+        }
+    }
+    Ok(count)
+}
+
+/// indexes every note in the vault. returns the number of indexed notes.
+async fn reindex_vault(app_handle: &AppHandle) -> Result<usize, String> {
+    let base_dir = get_base_dir(app_handle)?;
+    let mut entries: Vec<FSEntry> = Vec::new();
+    list_dir_inner(&base_dir, "", true, &mut entries).await?;
+
+    let paths: Vec<String> = entries
+        .into_iter()
+        .filter(|entry| !entry.is_dir)
+        .map(|entry| entry.path)
+        .collect();
+    index_relative_paths(app_handle, &paths).await
+}
+
+/// keeps the index in sync with a flushed file watcher event
+pub fn sync_watcher_event(app_handle: &AppHandle, event: &FileWatcherEvent) {
+    if !is_enabled(app_handle) {
+        return;
+    }
+
+    let mut modified = Vec::new();
+    let mut deleted = Vec::new();
+    for change in &event.file_changes {
+        if !change.path.ends_with(".md") {
+            continue;
+        }
+        if change.kind == "delete" {
+            deleted.push(change.path.clone());
+        } else {
+            modified.push(change.path.clone());
+        }
+    }
+    for delta in event.directory_deltas.iter().flatten() {
+        deleted.extend(delta.removed.iter().cloned());
+        for renamed in &delta.renamed {
+            deleted.push(renamed.from.clone());
+            modified.push(renamed.to.path.clone());
+        }
+        modified.extend(delta.added.iter().map(|entry| entry.path.clone()));
+    }
+    modified.retain(|path| path.ends_with(".md"));
+    let directories = event.directory_changes.clone();
+
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Ok(base_dir) = get_base_dir(&handle) {
+            let deleted: Vec<String> = deleted
+                .iter()
+                .map(|path| base_dir.join(path).to_string_lossy().to_string())
+                .collect();
+            remove_notes(&deleted);
+        }
+
+        // new notes only show up as directory changes
+        for dir in directories {
+            let Ok(dir_path) = resolve_path(&handle, &dir) else {
+                continue;
+            };
+            let mut entries: Vec<FSEntry> = Vec::new();
+            if list_dir_inner(&dir_path, &dir, false, &mut entries)
+                .await
+                .is_ok()
+            {
+                modified.extend(
+                    entries
+                        .into_iter()
+                        .filter(|entry| !entry.is_dir)
+                        .map(|entry| entry.path),
+                );
+            }
+        }
+        modified.sort();
+        modified.dedup();
+
+        if let Err(e) = index_relative_paths(&handle, &modified).await {
+            log::error!("spotlight: failed to update index: {e}");
+        }
+    });
+}
+
+// -----------------------------------------
+// opening results
+// -----------------------------------------
+
+/// adds `application:continueUserActivity:restorationHandler:` to the app
+/// delegate so clicking a spotlight result opens the note in flowrite
+fn install_activity_handler() {
+    unsafe {
+        let app: id = msg_send![class!(NSApplication), sharedApplication];
+        let delegate: id = msg_send![app, delegate];
+        if delegate.is_null() {
+            log::warn!("spotlight: no app delegate, results will not open notes");
+            return;
+        }
+        let delegate_class: *const Class = msg_send![delegate, class];
+
+        extern "C" fn continue_user_activity(
+            _this: &Object,
+            _cmd: Sel,
+            _application: id,
+            activity: id,
+            _restoration_handler: id,
+        ) -> BOOL {
+            match unsafe { activity_note_path(activity) } {
+                Some(path) => {
+                    if let Some(app_handle) = APP_HANDLE.get() {
+                        log::info!("spotlight: opening {path}");
+                        crate::open_paths_from_os(app_handle, vec![path]);
+                    }
+                    YES
+                }
+                None => NO,
+            }
+        }
+
+        let imp: extern "C" fn(&Object, Sel, id, id, id) -> BOOL = continue_user_activity;
+        let added = objc::runtime::class_addMethod(
+            delegate_class as *mut Class,
+            sel!(application:continueUserActivity:restorationHandler:),
+            std::mem::transmute::<
+                extern "C" fn(&Object, Sel, id, id, id) -> BOOL,
+                objc::runtime::Imp,
+            >(imp),
+            c"c@:@@@".as_ptr(),
+        );
+        if added == NO {
+            log::warn!("spotlight: app delegate already handles user activities");
+        }
+    }
+}
+
+/// extracts the note path from a spotlight user activity
+unsafe fn activity_note_path(activity: id) -> Option<String> {
+    if activity.is_null() {
+        return None;
+    }
+    let activity_type: id = msg_send![activity, activityType];
+    if ns_string_to_string(activity_type)? != SPOTLIGHT_ACTIVITY_TYPE {
+        return None;
+    }
+    let user_info: id = msg_send![activity, userInfo];
+    if user_info.is_null() {
+        return None;
+    }
+    let identifier: id =
+        msg_send![user_info, objectForKey: ns_string(SPOTLIGHT_ACTIVITY_IDENTIFIER_KEY)];
+    ns_string_to_string(identifier)
+}
+
+unsafe fn ns_string_to_string(string: id) -> Option<String> {
+    if string.is_null() {
+        return None;
+    }
+    let utf8 = NSString::UTF8String(string);
+    if utf8.is_null() {
+        return None;
+    }
+    Some(
+        std::ffi::CStr::from_ptr(utf8)
+            .to_string_lossy()
+            .into_owned(),
+    )
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+/// turns spotlight indexing on (indexing the whole vault) or off (dropping the
+/// vault from the index). returns the number of indexed notes.
+#[tauri::command]
+pub async fn set_spotlight_indexing(app_handle: AppHandle, enabled: bool) -> Result<usize, String> {
+    log::info!("setting spotlight indexing: {enabled}");
+    set_setting(&app_handle, SPOTLIGHT_INDEXING_KEY, &enabled)?;
+
+    if enabled {
+        let count = reindex_vault(&app_handle).await?;
+        log::info!("spotlight: indexed {count} notes");
+        Ok(count)
+    } else {
+        remove_domain(&get_domain(&app_handle)?);
+        Ok(0)
+    }
+}
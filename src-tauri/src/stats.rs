@@ -0,0 +1,197 @@
+use serde::Serialize;
+use tauri::{AppHandle, WebviewWindow};
+use tokio::fs;
+
+use crate::{
+    command::{list_dir_inner, FSEntry},
+    frontmatter,
+    utils::resolve_path,
+    vaults,
+};
+
+/// average silent reading speed used for reading time
+const WORDS_PER_MINUTE: usize = 200;
+
+/// how many notes `vault_stats` lists as largest
+const LARGEST_NOTES_LIMIT: usize = 10;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteStats {
+    pub path: String,
+    /// words in the body (frontmatter excluded)
+    pub words: usize,
+    /// characters in the body (frontmatter excluded)
+    pub characters: usize,
+    pub headings: usize,
+    /// wiki links and markdown links, internal and external
+    pub links: usize,
+    /// rounded up, 0 for an empty note
+    pub reading_time_minutes: usize,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteSize {
+    pub path: String,
+    pub words: usize,
+    pub size_bytes: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultStats {
+    pub note_count: usize,
+    pub total_words: usize,
+    pub total_characters: usize,
+    pub total_size_bytes: u64,
+    pub reading_time_minutes: usize,
+    /// notes with the most words, largest first
+    pub largest_notes: Vec<NoteSize>,
+}
+
+// -----------------------------------------
+// counting
+// -----------------------------------------
+
+/// counts of a note body; code blocks count towards words but not headings or links
+#[derive(Default)]
+struct Counts {
+    words: usize,
+    characters: usize,
+    headings: usize,
+    links: usize,
+}
+
+fn is_heading(line: &str) -> bool {
+    let level = line.chars().take_while(|&c| c == '#').count();
+    let rest = &line[level..];
+    (1..=6).contains(&level) && (rest.is_empty() || rest.starts_with(char::is_whitespace))
+}
+
+fn count_links(line: &str) -> usize {
+    let wiki = line.matches("[[").count();
+    let markdown = line
+        .match_indices("](")
+        .filter(|(start, _)| line[start + 2..].contains(')'))
+        .count();
+    wiki + markdown
+}
+
+fn count(content: &str) -> Counts {
+    let body = frontmatter::split(content).body;
+    let mut counts = Counts {
+        words: body.split_whitespace().count(),
+        characters: body.chars().count(),
+        ..Counts::default()
+    };
+
+    let mut in_fence = false;
+    for line in body.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+        if is_heading(trimmed) {
+            counts.headings += 1;
+        }
+        counts.links += count_links(line);
+    }
+    counts
+}
+
+fn reading_time_minutes(words: usize) -> usize {
+    words.div_ceil(WORDS_PER_MINUTE)
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+/// word count, reading time and structure of one note
+#[tauri::command]
+pub async fn note_stats(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    path: String,
+) -> Result<NoteStats, String> {
+    vaults::scoped(&webview_window, async move {
+        let file_path = resolve_path(&app_handle, &path)?;
+        let content = fs::read_to_string(&file_path)
+            .await
+            .map_err(|e| format!("failed to read file {path}: {e}"))?;
+
+        let counts = count(&content);
+        Ok(NoteStats {
+            path,
+            words: counts.words,
+            characters: counts.characters,
+            headings: counts.headings,
+            links: counts.links,
+            reading_time_minutes: reading_time_minutes(counts.words),
+            size_bytes: content.len() as u64,
+        })
+    })
+    .await
+}
+
+/// totals over every note in the vault, for the statistics panel
+#[tauri::command]
+pub async fn vault_stats(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+) -> Result<VaultStats, String> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("computing vault stats");
+
+        let root = resolve_path(&app_handle, "")?;
+        let mut entries: Vec<FSEntry> = Vec::new();
+        list_dir_inner(&root, "", true, &mut entries).await?;
+
+        let mut stats = VaultStats {
+            note_count: 0,
+            total_words: 0,
+            total_characters: 0,
+            total_size_bytes: 0,
+            reading_time_minutes: 0,
+            largest_notes: Vec::new(),
+        };
+        let mut notes: Vec<NoteSize> = Vec::new();
+        for entry in entries {
+            if entry.is_dir || !entry.path.ends_with(".md") {
+                continue;
+            }
+            let Ok(content) = fs::read_to_string(root.join(&entry.path)).await else {
+                continue;
+            };
+            let counts = count(&content);
+            stats.note_count += 1;
+            stats.total_words += counts.words;
+            stats.total_characters += counts.characters;
+            stats.total_size_bytes += entry.size_bytes;
+            notes.push(NoteSize {
+                path: entry.path,
+                words: counts.words,
+                size_bytes: entry.size_bytes,
+            });
+        }
+
+        notes.sort_by(|a, b| b.words.cmp(&a.words).then_with(|| a.path.cmp(&b.path)));
+        notes.truncate(LARGEST_NOTES_LIMIT);
+        stats.largest_notes = notes;
+        stats.reading_time_minutes = reading_time_minutes(stats.total_words);
+
+        log::info!(
+            "vault stats: {} notes, {} words",
+            stats.note_count,
+            stats.total_words
+        );
+        Ok(stats)
+    })
+    .await
+}
@@ -0,0 +1,169 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Mutex;
+
+use serde::Serialize;
+use tauri::{State, WebviewWindow};
+
+use crate::{frontmatter, note_index::NoteIndex, vaults};
+
+/// tags of every note in the vault, keyed by vault-relative path.
+/// tags are stored lowercased and without the leading `#`.
+#[derive(Default)]
+pub struct TagIndex(Mutex<HashMap<String, BTreeSet<String>>>);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+// -----------------------------------------
+// parsing
+// -----------------------------------------
+
+fn normalize_tag(tag: &str) -> Option<String> {
+    let tag = tag.trim().trim_start_matches('#').trim_end_matches('/');
+    if tag.is_empty() || tag.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    Some(tag.to_lowercase())
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '/')
+}
+
+/// `tags:` (or `tag:`) from the frontmatter, as a list or a comma/space separated string
+fn frontmatter_tags(value: &serde_yaml::Value, tags: &mut BTreeSet<String>) {
+    for key in ["tags", "tag"] {
+        match value.get(key) {
+            Some(serde_yaml::Value::Sequence(items)) => {
+                tags.extend(
+                    items
+                        .iter()
+                        .filter_map(|item| item.as_str())
+                        .filter_map(normalize_tag),
+                );
+            }
+            Some(serde_yaml::Value::String(list)) => {
+                tags.extend(
+                    list.split(|c: char| c == ',' || c.is_whitespace())
+                        .filter_map(normalize_tag),
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+/// inline `#tag` tokens in the body. headings (`# title`), fenced code blocks,
+/// inline code and `#` in the middle of a word (urls, anchors) are ignored.
+fn inline_tags(body: &str, tags: &mut BTreeSet<String>) {
+    let mut in_fence = false;
+    for line in body.lines() {
+        if line.trim_start().starts_with("```") || line.trim_start().starts_with("~~~") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let mut in_code = false;
+        let mut previous = ' ';
+        for (index, c) in line.char_indices() {
+            if c == '`' {
+                in_code = !in_code;
+            } else if c == '#' && !in_code && previous.is_whitespace() {
+                let rest = &line[index + 1..];
+                let end = rest.find(|c: char| !is_tag_char(c)).unwrap_or(rest.len());
+                if let Some(tag) = normalize_tag(&rest[..end]) {
+                    tags.insert(tag);
+                }
+            }
+            previous = c;
+        }
+    }
+}
+
+fn parse_tags(path: &str, content: &str) -> BTreeSet<String> {
+    let mut tags = BTreeSet::new();
+    let frontmatter = frontmatter::split(content);
+    match frontmatter.parse() {
+        Ok(Some(value)) => frontmatter_tags(&value, &mut tags),
+        Ok(None) => {}
+        Err(e) => log::warn!("tags: ignoring frontmatter of {path}: {e}"),
+    }
+    inline_tags(frontmatter.body, &mut tags);
+    tags
+}
+
+// -----------------------------------------
+// indexing
+// -----------------------------------------
+
+impl NoteIndex for TagIndex {
+    type Entry = BTreeSet<String>;
+
+    const NAME: &'static str = "tags";
+
+    fn entries(&self) -> &Mutex<HashMap<String, BTreeSet<String>>> {
+        &self.0
+    }
+
+    fn parse(path: &str, content: &str) -> Option<BTreeSet<String>> {
+        Some(parse_tags(path, content))
+    }
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+/// every tag in the vault with the number of notes using it, sorted by tag
+#[tauri::command]
+pub fn list_tags(
+    webview_window: WebviewWindow,
+    state: State<'_, TagIndex>,
+) -> Result<Vec<TagCount>, String> {
+    vaults::ensure_active_vault(&webview_window)?;
+    let index = state.0.lock().unwrap();
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for tag in index.values().flatten() {
+        *counts.entry(tag).or_default() += 1;
+    }
+    Ok(counts
+        .into_iter()
+        .map(|(tag, count)| TagCount {
+            tag: tag.to_string(),
+            count,
+        })
+        .collect())
+}
+
+/// notes carrying `tag`, sorted by path. nested tags match too, so `project`
+/// also returns notes tagged `project/flowrite`.
+#[tauri::command]
+pub fn list_notes_by_tag(
+    webview_window: WebviewWindow,
+    state: State<'_, TagIndex>,
+    tag: String,
+) -> Result<Vec<String>, String> {
+    vaults::ensure_active_vault(&webview_window)?;
+    let Some(tag) = normalize_tag(&tag) else {
+        return Ok(Vec::new());
+    };
+    let nested_prefix = format!("{tag}/");
+    let index = state.0.lock().unwrap();
+    let mut paths: Vec<String> = index
+        .iter()
+        .filter(|(_, tags)| {
+            tags.iter()
+                .any(|note_tag| *note_tag == tag || note_tag.starts_with(&nested_prefix))
+        })
+        .map(|(path, _)| path.clone())
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use tauri::{AppHandle, WebviewWindow};
+use tokio::fs;
+
+use crate::{
+    command::{fs_entry_from_metadata, list_dir_inner, FSEntry},
+    constants::TEMPLATES_DIR_NAME,
+    nb,
+    utils::resolve_path,
+    vaults,
+};
+
+/// fills `{{name}}` placeholders. unknown placeholders are left as they are so
+/// a template can still contain literal braces.
+fn substitute(template: &str, vars: &HashMap<String, String>) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some(end) = rest.find("}}") else {
+            break;
+        };
+        let name = rest[2..end].trim();
+        match vars.get(name) {
+            Some(value) => output.push_str(value),
+            None => output.push_str(&rest[..end + 2]),
+        }
+        rest = &rest[end + 2..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// `{{date}}`, `{{time}}` and `{{title}}` (the new note's file name without
+/// extension), overridden by the caller's variables
+fn template_vars(target_path: &str, custom: HashMap<String, String>) -> HashMap<String, String> {
+    let now = chrono::Local::now();
+    let name = target_path.rsplit('/').next().unwrap_or(target_path);
+    let title = name.strip_suffix(".md").unwrap_or(name);
+
+    let mut vars = HashMap::from([
+        ("date".to_string(), now.format("%Y-%m-%d").to_string()),
+        ("time".to_string(), now.format("%H:%M").to_string()),
+        ("title".to_string(), title.to_string()),
+    ]);
+    vars.extend(custom);
+    vars
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+/// notes in the vault's `templates/` folder, as paths relative to it
+#[tauri::command]
+pub async fn list_templates(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+) -> Result<Vec<String>, String> {
+    vaults::scoped(&webview_window, async move {
+        let templates_dir = resolve_path(&app_handle, TEMPLATES_DIR_NAME)?;
+        if !templates_dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut entries: Vec<FSEntry> = Vec::new();
+        list_dir_inner(&templates_dir, "", true, &mut entries).await?;
+        let mut templates: Vec<String> = entries
+            .into_iter()
+            .filter(|entry| !entry.is_dir && entry.path.ends_with(".md"))
+            .map(|entry| entry.path)
+            .collect();
+        templates.sort();
+        Ok(templates)
+    })
+    .await
+}
+
+/// creates `target_path` from a template in `templates/`, substituting
+/// `{{date}}`, `{{time}}`, `{{title}}` and the given `vars`
+#[tauri::command]
+pub async fn create_file_from_template(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    template: String,
+    target_path: String,
+    vars: Option<HashMap<String, String>>,
+) -> Result<FSEntry, String> {
+    vaults::scoped(&webview_window, async move {
+        log::info!("creating file {target_path} from template {template}");
+
+        let template_path = resolve_path(&app_handle, &format!("{TEMPLATES_DIR_NAME}/{template}"))?;
+        let content = fs::read_to_string(&template_path)
+            .await
+            .map_err(|e| format!("failed to read template '{template}': {e}"))?;
+
+        let file_path = resolve_path(&app_handle, &target_path)?;
+        if file_path.exists() {
+            return Err(format!("file '{target_path}' already exists"));
+        }
+
+        let vars = template_vars(&target_path, vars.unwrap_or_default());
+        nb::create_file(&app_handle, &target_path, &substitute(&content, &vars)).await?;
+
+        let metadata = fs::metadata(&file_path)
+            .await
+            .map_err(|e| format!("failed to get metadata: {e}"))?;
+
+        log::info!("created file {target_path} from template {template}");
+        fs_entry_from_metadata(target_path, &metadata)
+    })
+    .await
+}
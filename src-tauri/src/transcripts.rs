@@ -0,0 +1,244 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use tokio::fs;
+
+use crate::constants::TRANSCRIPTS_DIR_NAME;
+
+/// a single chat turn as shown in the chat pane
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptMessage {
+    /// "user" or "assistant"
+    pub role: String,
+    pub content: String,
+}
+
+/// a saved agent conversation, one file per session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Transcript {
+    pub session_id: String,
+    /// agent profile id from the agent settings
+    pub agent_id: String,
+    pub agent_name: String,
+    #[serde(default)]
+    pub created_time_ms: i64,
+    #[serde(default)]
+    pub updated_time_ms: i64,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub messages: Vec<TranscriptMessage>,
+}
+
+/// all fields are optional; an empty filter matches every transcript
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptFilter {
+    pub agent_ids: Option<Vec<String>>,
+    /// only transcripts updated at or after this time
+    pub from_ms: Option<i64>,
+    /// only transcripts created at or before this time
+    pub to_ms: Option<i64>,
+    /// transcripts carrying any of these tags
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranscriptFormat {
+    Markdown,
+    Jsonl,
+}
+
+impl TranscriptFilter {
+    fn matches(&self, transcript: &Transcript) -> bool {
+        if let Some(agent_ids) = &self.agent_ids {
+            if !agent_ids.contains(&transcript.agent_id) {
+                return false;
+            }
+        }
+        if self
+            .from_ms
+            .is_some_and(|from| transcript.updated_time_ms < from)
+        {
+            return false;
+        }
+        if self.to_ms.is_some_and(|to| transcript.created_time_ms > to) {
+            return false;
+        }
+        if let Some(tags) = &self.tags {
+            if !tags.iter().any(|tag| transcript.tags.contains(tag)) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+// -----------------------------------------
+// storage
+// -----------------------------------------
+
+/// returns the transcripts directory inside the app data directory
+fn get_transcripts_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let app_data = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("failed to get app data directory: {e}"))?;
+    Ok(app_data.join(TRANSCRIPTS_DIR_NAME))
+}
+
+fn transcript_file_name(session_id: &str) -> String {
+    let safe_id: String = session_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{safe_id}.json")
+}
+
+async fn load_transcripts(dir: &Path) -> Result<Vec<Transcript>, String> {
+    let mut transcripts = Vec::new();
+    let mut entries = match fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(transcripts),
+        Err(e) => return Err(format!("failed to read transcripts directory: {e}")),
+    };
+
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .map_err(|e| format!("failed to read transcripts directory: {e}"))?
+    {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        let content = match fs::read_to_string(&path).await {
+            Ok(content) => content,
+            Err(e) => {
+                log::warn!("skipping unreadable transcript {}: {e}", path.display());
+                continue;
+            }
+        };
+        match serde_json::from_str::<Transcript>(&content) {
+            Ok(transcript) => transcripts.push(transcript),
+            Err(e) => log::warn!("skipping malformed transcript {}: {e}", path.display()),
+        }
+    }
+
+    transcripts.sort_by_key(|transcript| transcript.created_time_ms);
+    Ok(transcripts)
+}
+
+fn transcript_to_markdown(transcript: &Transcript) -> String {
+    let created = chrono::DateTime::from_timestamp_millis(transcript.created_time_ms)
+        .map(|time| time.to_rfc3339())
+        .unwrap_or_default();
+
+    let mut markdown = format!(
+        "## {} — {}\n\n- session: `{}`\n",
+        transcript.agent_name, created, transcript.session_id
+    );
+    if !transcript.tags.is_empty() {
+        markdown.push_str(&format!("- tags: {}\n", transcript.tags.join(", ")));
+    }
+    for message in &transcript.messages {
+        markdown.push_str(&format!("\n### {}\n\n{}\n", message.role, message.content));
+    }
+    markdown
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+/// saves (or replaces) the transcript of a chat session.
+/// the original creation time is kept when the session is saved again.
+#[tauri::command]
+pub async fn save_transcript(app_handle: AppHandle, transcript: Transcript) -> Result<(), String> {
+    let dir = get_transcripts_dir(&app_handle)?;
+    fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| format!("failed to create transcripts directory: {e}"))?;
+
+    let path = dir.join(transcript_file_name(&transcript.session_id));
+    let now = chrono::Utc::now().timestamp_millis();
+    let created_time_ms = match fs::read_to_string(&path).await {
+        Ok(existing) => serde_json::from_str::<Transcript>(&existing)
+            .map(|existing| existing.created_time_ms)
+            .unwrap_or(now),
+        Err(_) => now,
+    };
+
+    let transcript = Transcript {
+        created_time_ms,
+        updated_time_ms: now,
+        ..transcript
+    };
+    let content = serde_json::to_string(&transcript)
+        .map_err(|e| format!("failed to serialize transcript: {e}"))?;
+    fs::write(&path, content)
+        .await
+        .map_err(|e| format!("failed to write transcript: {e}"))
+}
+
+/// exports saved transcripts matching `filter` into a single file at `dest`
+/// (absolute path). returns the number of exported transcripts.
+#[tauri::command]
+pub async fn export_transcripts(
+    app_handle: AppHandle,
+    filter: Option<TranscriptFilter>,
+    dest: String,
+    format: TranscriptFormat,
+) -> Result<usize, String> {
+    log::info!("exporting transcripts to {dest} as {format:?}");
+
+    let dest_path = PathBuf::from(&dest);
+    if !dest_path.is_absolute() {
+        return Err(format!(
+            "export destination '{dest}' must be an absolute path"
+        ));
+    }
+
+    let filter = filter.unwrap_or_default();
+    let transcripts: Vec<Transcript> = load_transcripts(&get_transcripts_dir(&app_handle)?)
+        .await?
+        .into_iter()
+        .filter(|transcript| filter.matches(transcript))
+        .collect();
+
+    let content = match format {
+        TranscriptFormat::Markdown => transcripts
+            .iter()
+            .map(transcript_to_markdown)
+            .collect::<Vec<_>>()
+            .join("\n---\n\n"),
+        TranscriptFormat::Jsonl => transcripts
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("failed to serialize transcript: {e}"))?
+            .join("\n"),
+    };
+
+    if let Some(parent) = dest_path.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("failed to create export directory: {e}"))?;
+    }
+    fs::write(&dest_path, content)
+        .await
+        .map_err(|e| format!("failed to write export file: {e}"))?;
+
+    log::info!("exported {} transcripts to {dest}", transcripts.len());
+
+    Ok(transcripts.len())
+}
@@ -1,15 +1,22 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use serde::{de::DeserializeOwned, Serialize};
+use sha2::{Digest, Sha256};
 use tauri::{AppHandle, Manager};
+use tauri_plugin_store::StoreExt;
 
-use crate::constants::BASE_DIR_NAME;
+use crate::constants::{BASE_DIR_NAME, SETTINGS_STORE_FILE_NAME};
 
 // -----------------------------------------
 // directory helpers
 // -----------------------------------------
 
-/// returns the base flowrite directory path.
+/// returns the base flowrite directory path: the open vault, or `~/flowrite`
+/// before the vault registry is loaded.
 pub fn get_base_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    if let Some(vault_dir) = crate::vaults::active_vault_dir() {
+        return Ok(vault_dir);
+    }
     let home_dir = app_handle
         .path()
         .home_dir()
@@ -33,3 +40,156 @@ pub fn resolve_path(app_handle: &AppHandle, relative_path: &str) -> Result<PathB
 
     Ok(resolved)
 }
+
+// -----------------------------------------
+// atomic writes
+// -----------------------------------------
+
+/// extension of the previous content kept by `write_atomic`
+const BACKUP_EXTENSION: &str = "bak";
+
+/// replaces `path` without ever leaving it half written: the content goes to a
+/// hidden temp file in the same directory, is fsynced, then renamed over the
+/// target. with `keep_backup` the previous content is kept as `<name>.bak`.
+pub async fn write_atomic(path: &Path, content: &[u8], keep_backup: bool) -> std::io::Result<()> {
+    let path = path.to_path_buf();
+    let content = content.to_vec();
+    tokio::task::spawn_blocking(move || write_atomic_blocking(&path, &content, keep_backup))
+        .await
+        .map_err(std::io::Error::other)?
+}
+
+fn write_atomic_blocking(path: &Path, content: &[u8], keep_backup: bool) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| std::io::Error::other("path has no file name"))?;
+    // unique per call, so concurrent writes to the same file never share a
+    // temp file
+    let temp_path = dir.join(format!(".{name}.{}.tmp", uuid::Uuid::new_v4().simple()));
+
+    let result = (|| {
+        let mut file = std::fs::File::create(&temp_path)?;
+        file.write_all(content)?;
+        // keep the permissions of the file being replaced
+        if let Ok(metadata) = std::fs::metadata(path) {
+            file.set_permissions(metadata.permissions())?;
+        }
+        file.sync_all()?;
+        drop(file);
+
+        if keep_backup && path.is_file() {
+            std::fs::copy(path, dir.join(format!("{name}.{BACKUP_EXTENSION}")))?;
+        }
+        std::fs::rename(&temp_path, path)?;
+        // persist the rename itself; not every platform can open a directory
+        if let Ok(dir_file) = std::fs::File::open(dir) {
+            let _ = dir_file.sync_all();
+        }
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+// -----------------------------------------
+// file names
+// -----------------------------------------
+
+/// longest file name (in bytes) macOS, windows and linux all accept
+const MAX_FILENAME_BYTES: usize = 255;
+
+/// characters rejected by windows or used as separators
+const INVALID_FILENAME_CHARS: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+
+/// device names windows reserves, with or without an extension
+const RESERVED_FILENAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// checks that a single file or folder name is safe on every platform the
+/// vault may be synced to. the error is shown to the user as is.
+pub fn validate_filename(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("name cannot be empty".to_string());
+    }
+    if name == "." || name == ".." {
+        return Err(format!("'{name}' is not a valid name"));
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| INVALID_FILENAME_CHARS.contains(c) || c.is_control())
+    {
+        return Err(format!("name cannot contain '{}'", c.escape_default()));
+    }
+    if name.ends_with(' ') || name.ends_with('.') {
+        return Err("name cannot end with a space or a period".to_string());
+    }
+    if name.len() > MAX_FILENAME_BYTES {
+        return Err(format!(
+            "name is too long ({} of at most {MAX_FILENAME_BYTES} bytes)",
+            name.len()
+        ));
+    }
+    let stem = name.split('.').next().unwrap_or(name).trim_end();
+    if RESERVED_FILENAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+    {
+        return Err(format!("'{stem}' is a reserved name"));
+    }
+    Ok(())
+}
+
+/// `validate_filename` for the last component of a vault-relative path
+pub fn validate_path_name(path: &str) -> Result<(), String> {
+    validate_filename(path.rsplit('/').next().unwrap_or(path))
+}
+
+// -----------------------------------------
+// content hashing
+// -----------------------------------------
+
+/// hex sha-256 of file content, used to detect changes made behind an editor's back
+pub fn content_hash(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+// -----------------------------------------
+// settings store helpers
+// -----------------------------------------
+
+/// reads a value from the settings store.
+/// returns None if the key is missing or the stored value has an unexpected shape.
+pub fn get_setting<T: DeserializeOwned>(app_handle: &AppHandle, key: &str) -> Option<T> {
+    let store = app_handle.store(SETTINGS_STORE_FILE_NAME).ok()?;
+    let value = store.get(key)?;
+    match serde_json::from_value(value) {
+        Ok(parsed) => Some(parsed),
+        Err(e) => {
+            log::warn!("ignoring malformed setting '{key}': {e}");
+            None
+        }
+    }
+}
+
+/// writes a value to the settings store (persisted by the store's autosave).
+pub fn set_setting<T: Serialize>(
+    app_handle: &AppHandle,
+    key: &str,
+    value: &T,
+) -> Result<(), String> {
+    let store = app_handle
+        .store(SETTINGS_STORE_FILE_NAME)
+        .map_err(|e| format!("failed to open settings store: {e}"))?;
+    let value =
+        serde_json::to_value(value).map_err(|e| format!("failed to serialize '{key}': {e}"))?;
+    store.set(key, value);
+    Ok(())
+}
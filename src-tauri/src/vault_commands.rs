@@ -0,0 +1,236 @@
+use std::path::PathBuf;
+
+use sacp::schema::{EmbeddedResource, EmbeddedResourceResource, TextResourceContents};
+use tauri::{AppHandle, Url};
+
+use crate::{
+    acp::SlashCommandInfo,
+    command::{list_dir_inner, FSEntry},
+    nb,
+    utils::{get_base_dir, resolve_path},
+};
+
+/// slash commands implemented by flowrite rather than by the agent.
+/// (name, description, input hint)
+const VAULT_COMMANDS: &[(&str, &str, Option<&str>)] = &[
+    (
+        "file-tree",
+        "share the vault file tree with the agent",
+        Some("folder (optional)"),
+    ),
+    (
+        "read-note",
+        "share the content of a note with the agent",
+        Some("path to note"),
+    ),
+    (
+        "summarize-note",
+        "ask the agent to summarize a note",
+        Some("path to note"),
+    ),
+];
+
+const SYSTEM_PROMPT_CLOSE_TAG: &str = "</system_prompt>";
+
+// -----------------------------------------
+// command metadata
+// -----------------------------------------
+
+/// returns the flowrite-provided slash commands in the same shape as agent commands
+pub fn vault_slash_commands() -> Vec<SlashCommandInfo> {
+    VAULT_COMMANDS
+        .iter()
+        .map(|(name, description, input_hint)| SlashCommandInfo {
+            name: name.to_string(),
+            description: description.to_string(),
+            input_hint: input_hint.map(|hint| hint.to_string()),
+        })
+        .collect()
+}
+
+/// merges vault commands into an agent-provided command list.
+/// vault commands take precedence over agent commands with the same name,
+/// since prompts starting with them never reach the agent verbatim.
+pub fn merge_vault_commands(commands: &mut Vec<SlashCommandInfo>) {
+    commands.retain(|command| !is_vault_command(&command.name));
+    commands.extend(vault_slash_commands());
+}
+
+fn is_vault_command(name: &str) -> bool {
+    VAULT_COMMANDS
+        .iter()
+        .any(|(command, _, _)| *command == name)
+}
+
+// -----------------------------------------
+// prompt expansion
+// -----------------------------------------
+
+/// expands a leading vault slash command in the prompt into context for the agent.
+/// the system prompt block prepended by the frontend on the first message is preserved.
+/// prompts that don't start with a vault command are returned unchanged.
+pub async fn expand_prompt(app_handle: &AppHandle, text: String) -> Result<String, String> {
+    let (prefix, body) = split_system_prompt(&text);
+    let body = body.trim_start();
+
+    let Some(command_line) = body.strip_prefix('/') else {
+        return Ok(text);
+    };
+
+    let (line, rest) = command_line.split_once('\n').unwrap_or((command_line, ""));
+    let (name, argument) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    if !is_vault_command(name) {
+        return Ok(text);
+    }
+
+    let argument = argument.trim();
+    log::info!("expanding vault command /{name} {argument}");
+
+    let context = match name {
+        "file-tree" => file_tree_context(app_handle, argument).await?,
+        "read-note" => note_context(app_handle, argument).await?,
+        "summarize-note" => format!(
+            "{}\n\nsummarize the note above.",
+            note_context(app_handle, argument).await?
+        ),
+        _ => return Ok(text),
+    };
+
+    let rest = rest.trim();
+    let expanded = if rest.is_empty() {
+        context
+    } else {
+        format!("{context}\n\n{rest}")
+    };
+
+    Ok(format!("{prefix}{expanded}"))
+}
+
+// -----------------------------------------
+// mention expansion
+// -----------------------------------------
+
+/// resolves `@path` mentions sent alongside the prompt into resources embedded
+/// after the user text. notes are embedded with their content; folders
+/// (trailing `/`) as a recursive listing.
+pub async fn expand_mentions(
+    app_handle: &AppHandle,
+    mentions: &[String],
+) -> Result<Vec<EmbeddedResource>, String> {
+    let mut resources = Vec::with_capacity(mentions.len());
+    for mention in mentions {
+        let path = mention.trim().trim_start_matches('@');
+        if path.is_empty() {
+            continue;
+        }
+        log::info!("expanding mention @{path}");
+
+        let relative = to_vault_relative(app_handle, path)?;
+        let is_dir = path.ends_with('/') || resolve_path(app_handle, &relative)?.is_dir();
+        let resource = if is_dir {
+            file_tree_resource(app_handle, &relative).await?
+        } else {
+            note_resource(app_handle, &relative).await?
+        };
+        resources.push(EmbeddedResource::new(
+            EmbeddedResourceResource::TextResourceContents(resource),
+        ));
+    }
+    Ok(resources)
+}
+
+/// splits the optional `<system_prompt>...</system_prompt>` header from the user text
+fn split_system_prompt(text: &str) -> (&str, &str) {
+    if text.starts_with("<system_prompt>") {
+        if let Some(end) = text.find(SYSTEM_PROMPT_CLOSE_TAG) {
+            let split_at = end + SYSTEM_PROMPT_CLOSE_TAG.len();
+            let (prefix, body) = text.split_at(split_at);
+            return (prefix, body);
+        }
+    }
+    ("", text)
+}
+
+/// recursive listing of a vault folder, one vault-relative path per line.
+/// returns the normalized folder path, its absolute path and the listing.
+async fn file_tree(
+    app_handle: &AppHandle,
+    folder: &str,
+) -> Result<(String, PathBuf, String), String> {
+    let folder = to_vault_relative(app_handle, folder)?;
+    let dir_path = resolve_path(app_handle, &folder)?;
+    if !dir_path.is_dir() {
+        return Err(format!("folder '{folder}' does not exist"));
+    }
+
+    let mut entries: Vec<FSEntry> = Vec::new();
+    list_dir_inner(&dir_path, &folder, true, &mut entries).await?;
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let lines = entries
+        .iter()
+        .map(|entry| {
+            if entry.is_dir {
+                format!("{}/", entry.path)
+            } else {
+                entry.path.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    Ok((folder, dir_path, lines))
+}
+
+async fn file_tree_context(app_handle: &AppHandle, folder: &str) -> Result<String, String> {
+    let (folder, _, lines) = file_tree(app_handle, folder).await?;
+    Ok(format!(
+        "<vault_file_tree root=\"{folder}\">\n{lines}\n</vault_file_tree>"
+    ))
+}
+
+async fn file_tree_resource(
+    app_handle: &AppHandle,
+    folder: &str,
+) -> Result<TextResourceContents, String> {
+    let (_, dir_path, lines) = file_tree(app_handle, folder).await?;
+    let uri = Url::from_directory_path(&dir_path)
+        .map_err(|_| format!("'{}' is not a valid folder path", dir_path.display()))?;
+    Ok(TextResourceContents::new(lines, uri.to_string()).mime_type("text/plain"))
+}
+
+async fn note_context(app_handle: &AppHandle, path: &str) -> Result<String, String> {
+    if path.is_empty() {
+        return Err("a note path is required".to_string());
+    }
+    let relative = to_vault_relative(app_handle, path)?;
+    let full_path = resolve_path(app_handle, &relative)?;
+    let content = nb::read_file(app_handle, &relative).await?;
+
+    Ok(format!(
+        "<file path=\"{}\">\n{}\n</file>",
+        full_path.to_string_lossy(),
+        content
+    ))
+}
+
+async fn note_resource(app_handle: &AppHandle, path: &str) -> Result<TextResourceContents, String> {
+    let full_path = resolve_path(app_handle, path)?;
+    let content = nb::read_file(app_handle, path).await?;
+    let uri = Url::from_file_path(&full_path)
+        .map_err(|_| format!("'{}' is not a valid file path", full_path.display()))?;
+    Ok(TextResourceContents::new(content, uri.to_string()).mime_type("text/markdown"))
+}
+
+/// accepts either a vault-relative path or an absolute path inside the vault
+fn to_vault_relative(app_handle: &AppHandle, path: &str) -> Result<String, String> {
+    let base_dir = get_base_dir(app_handle)?;
+    let path = path.trim_matches('"');
+    match std::path::Path::new(path).strip_prefix(&base_dir) {
+        Ok(relative) => Ok(relative.to_string_lossy().to_string()),
+        Err(_) if std::path::Path::new(path).is_absolute() => {
+            Err(format!("'{path}' is outside the vault"))
+        }
+        Err(_) => Ok(path.trim_end_matches('/').to_string()),
+    }
+}
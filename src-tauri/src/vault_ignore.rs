@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+
+use crate::{constants::VAULT_IGNORE_FILE_NAME, fs_cache, vaults};
+
+/// ignore rules of each open vault, read on every listing and watcher event
+static RULES: RwLock<Option<HashMap<PathBuf, Arc<Gitignore>>>> = RwLock::new(None);
+
+/// parses `<vault>/.flowriteignore`. a missing file ignores nothing; bad
+/// lines are skipped.
+fn load(vault_root: &Path) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(vault_root);
+    let path = vault_root.join(VAULT_IGNORE_FILE_NAME);
+    if path.exists() {
+        if let Some(e) = builder.add(&path) {
+            log::warn!("ignoring invalid lines in {VAULT_IGNORE_FILE_NAME}: {e}");
+        }
+    }
+    builder.build().unwrap_or_else(|e| {
+        log::warn!("ignoring malformed {VAULT_IGNORE_FILE_NAME}: {e}");
+        Gitignore::empty()
+    })
+}
+
+fn rules(vault_root: &Path) -> Arc<Gitignore> {
+    let loaded = RULES
+        .read()
+        .unwrap()
+        .as_ref()
+        .and_then(|vaults| vaults.get(vault_root).cloned());
+    match loaded {
+        Some(rules) => rules,
+        None => {
+            let rules = Arc::new(load(vault_root));
+            RULES
+                .write()
+                .unwrap()
+                .get_or_insert_with(HashMap::new)
+                .insert(vault_root.to_path_buf(), rules.clone());
+            rules
+        }
+    }
+}
+
+/// re-reads the vault's ignore file after it changed
+pub fn reload(vault_root: &Path) {
+    log::info!("reloading {VAULT_IGNORE_FILE_NAME}");
+    RULES
+        .write()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .insert(vault_root.to_path_buf(), Arc::new(load(vault_root)));
+    // cached listings were filtered with the old rules
+    fs_cache::clear();
+}
+
+/// true if a vault-relative path, or one of its folders, matches the vault's
+/// `.flowriteignore`. ignored entries are left out of listings, indexes and
+/// watcher events.
+pub fn is_ignored_in(vault_root: &Path, relative_path: &str, is_dir: bool) -> bool {
+    let relative_path = relative_path.trim_matches('/');
+    if relative_path.is_empty() {
+        return false;
+    }
+    rules(vault_root)
+        .matched_path_or_any_parents(relative_path, is_dir)
+        .is_ignore()
+}
+
+/// `is_ignored_in` for an absolute path in the vault of the running command.
+/// paths outside the vault are never ignored.
+pub fn is_ignored(path: &Path, is_dir: bool) -> bool {
+    let Some(vault_root) = vaults::active_vault_dir() else {
+        return false;
+    };
+    match path.strip_prefix(&vault_root) {
+        Ok(relative_path) => is_ignored_in(&vault_root, &relative_path.to_string_lossy(), is_dir),
+        Err(_) => false,
+    }
+}
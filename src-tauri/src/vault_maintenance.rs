@@ -0,0 +1,165 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, WebviewWindow};
+
+use crate::{
+    constants::{VAULT_MAINTENANCE_EVENT, VAULT_MAINTENANCE_POLICY_KEY},
+    nb,
+    utils::{get_base_dir, get_setting},
+    vaults, vcs,
+};
+
+/// when vault repos are garbage collected in the background. stored in
+/// settings under `vault-maintenance-policy`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VaultMaintenancePolicy {
+    pub enabled: bool,
+    /// run `git gc --auto` on every open vault this often (0 disables)
+    pub interval_ms: u64,
+}
+
+impl Default for VaultMaintenancePolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_ms: 24 * 60 * 60_000,
+        }
+    }
+}
+
+/// repo size and history length before and after a maintenance run. also the
+/// payload of the `vault-maintenance` event.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceReport {
+    /// size of `.git` before the run, in bytes
+    pub size_before: u64,
+    /// size of `.git` after the run, in bytes
+    pub size_after: u64,
+    pub commit_count: usize,
+    /// true for a full repack, false for `git gc --auto`, which only packs
+    /// once git thinks there are enough loose objects
+    pub full: bool,
+}
+
+fn get_policy(app_handle: &AppHandle) -> VaultMaintenancePolicy {
+    get_setting(app_handle, VAULT_MAINTENANCE_POLICY_KEY).unwrap_or_default()
+}
+
+/// total size of the files under `dir`, in bytes
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => dir_size(&entry.path()),
+            Ok(_) => entry.metadata().map(|metadata| metadata.len()).unwrap_or(0),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+async fn repo_size(base_dir: &Path) -> u64 {
+    let git_dir = base_dir.join(".git");
+    tauri::async_runtime::spawn_blocking(move || dir_size(&git_dir))
+        .await
+        .unwrap_or(0)
+}
+
+/// garbage collects the vault repo and reports its size. `full` repacks
+/// everything (`git gc`); otherwise git decides whether packing is worth it
+/// (`git gc --auto`).
+async fn maintain(app_handle: &AppHandle, full: bool) -> Result<MaintenanceReport, String> {
+    let base_dir = get_base_dir(app_handle)?;
+    let size_before = repo_size(&base_dir).await;
+
+    let args: &[&str] = if full {
+        &["gc", "--quiet"]
+    } else {
+        &["gc", "--auto", "--quiet"]
+    };
+    nb::git(app_handle, args).await?;
+
+    let commit_count = nb::with_repo(app_handle, vcs::commit_count).await?;
+    let report = MaintenanceReport {
+        size_before,
+        size_after: repo_size(&base_dir).await,
+        commit_count,
+        full,
+    };
+    if let Err(e) = vaults::emit_to_vault(
+        app_handle,
+        &base_dir,
+        VAULT_MAINTENANCE_EVENT,
+        report.clone(),
+    ) {
+        log::error!("failed to emit vault maintenance: {e}");
+    }
+    Ok(report)
+}
+
+/// starts the periodic maintenance loop over every open vault. the policy is
+/// re-read on every tick.
+pub fn start_background_maintenance(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let policy = get_policy(&app_handle);
+            let interval = match policy.interval_ms {
+                0 => VaultMaintenancePolicy::default().interval_ms,
+                ms => ms,
+            };
+            tokio::time::sleep(Duration::from_millis(interval)).await;
+
+            if !policy.enabled || policy.interval_ms == 0 {
+                continue;
+            }
+            let vault_dirs: Vec<PathBuf> = vaults::open_vault_dirs();
+            for vault_dir in vault_dirs {
+                let result = vaults::within(vault_dir.clone(), maintain(&app_handle, false)).await;
+                match result {
+                    Ok(report) => log::info!(
+                        "vault maintenance of {}: {} -> {} bytes",
+                        vault_dir.display(),
+                        report.size_before,
+                        report.size_after
+                    ),
+                    Err(e) => {
+                        log::warn!("vault maintenance of {} failed: {e}", vault_dir.display())
+                    }
+                }
+            }
+        }
+    });
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+/// garbage collects the window's vault repo now and reports its size and
+/// commit count. `full` forces a repack even when git would skip it.
+#[tauri::command]
+pub async fn vault_maintenance(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    full: Option<bool>,
+) -> Result<MaintenanceReport, String> {
+    vaults::scoped(&webview_window, async move {
+        let full = full.unwrap_or(false);
+        log::info!("running vault maintenance (full: {full})");
+        let report = maintain(&app_handle, full).await?;
+        log::info!(
+            "vault maintenance done: {} -> {} bytes, {} commits",
+            report.size_before,
+            report.size_after,
+            report.commit_count
+        );
+        Ok(report)
+    })
+    .await
+}
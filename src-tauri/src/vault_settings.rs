@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tokio::fs;
+
+use crate::{
+    constants::{VAULT_CONFIG_DIR_NAME, VAULT_SETTINGS_FILE_NAME},
+    fs_cache,
+    utils::get_base_dir,
+    vaults,
+};
+
+/// extensions shown in the file tree and watched, used when the vault has no settings
+const DEFAULT_MANAGED_EXTENSIONS: &[&str] = &["md"];
+
+/// managed extensions of each open vault, read on every listing and watcher event
+static MANAGED_EXTENSIONS: RwLock<Option<HashMap<PathBuf, Vec<String>>>> = RwLock::new(None);
+
+/// settings stored with the vault in `<vault>/.flowrite/settings.json`,
+/// so they travel with it (unlike the app settings store)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VaultSettings {
+    /// file extensions (without the dot) managed by flowrite
+    pub managed_extensions: Vec<String>,
+}
+
+impl Default for VaultSettings {
+    fn default() -> Self {
+        Self {
+            managed_extensions: DEFAULT_MANAGED_EXTENSIONS
+                .iter()
+                .map(|extension| extension.to_string())
+                .collect(),
+        }
+    }
+}
+
+fn get_settings_path(vault_root: &Path) -> std::path::PathBuf {
+    vault_root
+        .join(VAULT_CONFIG_DIR_NAME)
+        .join(VAULT_SETTINGS_FILE_NAME)
+}
+
+/// lowercases, strips leading dots and drops duplicates. `md` is always managed.
+fn normalize_extensions(extensions: Vec<String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    let all = DEFAULT_MANAGED_EXTENSIONS
+        .iter()
+        .map(|extension| extension.to_string())
+        .chain(extensions);
+    for extension in all {
+        let extension = extension.trim().trim_start_matches('.').to_lowercase();
+        if !extension.is_empty() && !normalized.contains(&extension) {
+            normalized.push(extension);
+        }
+    }
+    normalized
+}
+
+fn load(vault_root: &Path) -> VaultSettings {
+    match std::fs::read_to_string(get_settings_path(vault_root)) {
+        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+            log::warn!("ignoring malformed vault settings: {e}");
+            VaultSettings::default()
+        }),
+        Err(_) => VaultSettings::default(),
+    }
+}
+
+fn apply(vault_root: &Path, settings: &VaultSettings) {
+    MANAGED_EXTENSIONS
+        .write()
+        .unwrap()
+        .get_or_insert_with(HashMap::new)
+        .insert(
+            vault_root.to_path_buf(),
+            normalize_extensions(settings.managed_extensions.clone()),
+        );
+}
+
+/// loads the vault settings. must run before the file watcher starts.
+pub fn init(app_handle: &AppHandle) {
+    match get_base_dir(app_handle) {
+        Ok(base_dir) => apply(&base_dir, &load(&base_dir)),
+        Err(e) => log::error!("failed to load vault settings: {e}"),
+    }
+}
+
+/// true if a file with this name is shown in the tree and watched, in the
+/// vault of the running command
+pub fn is_managed_file(name: &str) -> bool {
+    match vaults::active_vault_dir() {
+        Some(vault_root) => is_managed_file_in(&vault_root, name),
+        None => is_managed_extension(DEFAULT_MANAGED_EXTENSIONS, name),
+    }
+}
+
+/// `is_managed_file` for a given vault (e.g. from a watcher thread),
+/// loading its settings on first use
+pub fn is_managed_file_in(vault_root: &Path, name: &str) -> bool {
+    let loaded = MANAGED_EXTENSIONS
+        .read()
+        .unwrap()
+        .as_ref()
+        .and_then(|vaults| vaults.get(vault_root).cloned());
+    let managed = match loaded {
+        Some(managed) => managed,
+        None => {
+            apply(vault_root, &load(vault_root));
+            return is_managed_file_in(vault_root, name);
+        }
+    };
+    is_managed_extension(&managed, name)
+}
+
+fn is_managed_extension<S: AsRef<str>>(managed: &[S], name: &str) -> bool {
+    let Some((_, extension)) = name.rsplit_once('.') else {
+        return false;
+    };
+    let extension = extension.to_lowercase();
+    managed.iter().any(|managed| managed.as_ref() == extension)
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+#[tauri::command]
+pub fn get_managed_extensions(app_handle: AppHandle) -> Result<Vec<String>, String> {
+    let base_dir = get_base_dir(&app_handle)?;
+    Ok(normalize_extensions(load(&base_dir).managed_extensions))
+}
+
+/// updates the file extensions shown in the tree and watched. `md` is always
+/// included. the frontend should reload the tree afterwards.
+#[tauri::command]
+pub async fn set_managed_extensions(
+    app_handle: AppHandle,
+    extensions: Vec<String>,
+) -> Result<Vec<String>, String> {
+    let base_dir = get_base_dir(&app_handle)?;
+    let settings = VaultSettings {
+        managed_extensions: normalize_extensions(extensions),
+    };
+    log::info!(
+        "setting managed extensions: {:?}",
+        settings.managed_extensions
+    );
+
+    let path = get_settings_path(&base_dir);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .await
+            .map_err(|e| format!("failed to create vault config directory: {e}"))?;
+    }
+    let content = serde_json::to_string_pretty(&settings)
+        .map_err(|e| format!("failed to serialize vault settings: {e}"))?;
+    fs::write(&path, content)
+        .await
+        .map_err(|e| format!("failed to write vault settings: {e}"))?;
+
+    apply(&base_dir, &settings);
+    // cached listings were filtered with the old extensions
+    fs_cache::clear();
+    Ok(settings.managed_extensions)
+}
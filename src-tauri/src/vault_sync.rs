@@ -0,0 +1,558 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, WebviewWindow};
+use tokio::sync::Mutex;
+
+use crate::{
+    constants::{SYNC_REMOTE_NAME, VAULT_SYNC_POLICY_KEY, VAULT_SYNC_STATUS_EVENT},
+    journal::{self, JournalOp, JournalOrigin},
+    nb::{self, NbFailure},
+    preflight,
+    utils::{get_base_dir, get_setting},
+    vaults, vcs,
+};
+
+/// serializes syncs so manual and background push/pull never overlap
+static SYNC_LOCK: Mutex<()> = Mutex::const_new(());
+
+/// bumped on every vault change, used to debounce idle syncs
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// when the vault is synced in the background. stored in settings under `vault-sync-policy`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VaultSyncPolicy {
+    pub enabled: bool,
+    /// sync on this interval (0 disables)
+    pub interval_ms: u64,
+    /// sync once the vault has not changed for this long (0 disables)
+    pub idle_ms: u64,
+}
+
+impl Default for VaultSyncPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_ms: 5 * 60_000,
+            idle_ms: 60_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncStatus {
+    Syncing,
+    Clean,
+    Conflict,
+    Offline,
+    Error,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SyncStatusPayload {
+    status: SyncStatus,
+    ahead: usize,
+    behind: usize,
+    conflicts: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+}
+
+/// state of the vault relative to its remote after a sync operation
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncResult {
+    pub remote_url: Option<String>,
+    pub branch: String,
+    /// local commits not on the remote
+    pub ahead: usize,
+    /// remote commits not merged locally
+    pub behind: usize,
+    /// files left with conflict markers by a pull, empty when the merge succeeded
+    pub conflicts: Vec<String>,
+    /// files changed by a pull
+    pub changed_files: Vec<String>,
+}
+
+/// uncommitted work and position relative to the remote, for a "changes
+/// pending" indicator
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultStatus {
+    /// tracked files changed since the last checkpoint
+    pub dirty: Vec<String>,
+    /// files never checkpointed
+    pub untracked: Vec<String>,
+    pub last_checkpoint_ms: Option<i64>,
+    pub last_checkpoint_message: Option<String>,
+    /// `None` when sync is not configured
+    pub remote_url: Option<String>,
+    /// counts against the last fetched state of the remote, present only
+    /// with a remote
+    pub ahead: Option<usize>,
+    pub behind: Option<usize>,
+}
+
+fn get_policy(app_handle: &AppHandle) -> VaultSyncPolicy {
+    get_setting(app_handle, VAULT_SYNC_POLICY_KEY).unwrap_or_default()
+}
+
+async fn current_branch(app_handle: &AppHandle) -> Result<String, NbFailure> {
+    Ok(nb::git(app_handle, &["rev-parse", "--abbrev-ref", "HEAD"])
+        .await?
+        .trim()
+        .to_string())
+}
+
+async fn remote_url(app_handle: &AppHandle) -> Option<String> {
+    nb::git(app_handle, &["remote", "get-url", SYNC_REMOTE_NAME])
+        .await
+        .ok()
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+}
+
+async fn require_remote(app_handle: &AppHandle) -> Result<String, String> {
+    remote_url(app_handle)
+        .await
+        .ok_or_else(|| "vault sync is not configured, set a remote url first".to_string())
+}
+
+/// ahead/behind counts against the remote branch. a branch that was never
+/// pushed counts every local commit as ahead.
+async fn ahead_behind(app_handle: &AppHandle, branch: &str) -> Result<(usize, usize), NbFailure> {
+    let remote_ref = format!("{SYNC_REMOTE_NAME}/{branch}");
+    if nb::git(
+        app_handle,
+        &["rev-parse", "--verify", "--quiet", &remote_ref],
+    )
+    .await
+    .is_err()
+    {
+        let count = nb::git(app_handle, &["rev-list", "--count", "HEAD"]).await?;
+        return Ok((count.trim().parse().unwrap_or(0), 0));
+    }
+
+    let range = format!("HEAD...{remote_ref}");
+    let output = nb::git(app_handle, &["rev-list", "--left-right", "--count", &range]).await?;
+    let mut counts = output
+        .split_whitespace()
+        .map(|count| count.parse::<usize>().unwrap_or(0));
+    Ok((counts.next().unwrap_or(0), counts.next().unwrap_or(0)))
+}
+
+async fn conflicted_files(app_handle: &AppHandle) -> Vec<String> {
+    nb::git(
+        app_handle,
+        &[
+            "-c",
+            "core.quotePath=false",
+            "diff",
+            "--name-only",
+            "--diff-filter=U",
+        ],
+    )
+    .await
+    .map(|output| output.lines().map(str::to_string).collect())
+    .unwrap_or_default()
+}
+
+async fn status(app_handle: &AppHandle) -> Result<SyncResult, String> {
+    let branch = current_branch(app_handle).await?;
+    let (ahead, behind) = ahead_behind(app_handle, &branch).await?;
+    Ok(SyncResult {
+        remote_url: remote_url(app_handle).await,
+        branch,
+        ahead,
+        behind,
+        ..Default::default()
+    })
+}
+
+/// records the files a pull changed in the vault journal and returns their paths
+async fn journal_pulled_changes(app_handle: &AppHandle, before: &str) -> Vec<String> {
+    let Ok(base_dir) = get_base_dir(app_handle) else {
+        return Vec::new();
+    };
+    let range = format!("{before}..HEAD");
+    let Ok(output) = nb::git(
+        app_handle,
+        &[
+            "-c",
+            "core.quotePath=false",
+            "diff",
+            "--name-status",
+            "-M",
+            &range,
+        ],
+    )
+    .await
+    else {
+        return Vec::new();
+    };
+
+    let mut changed = Vec::new();
+    for line in output.lines() {
+        let fields: Vec<&str> = line.split('\t').collect();
+        let (op, path, from) = match fields.as_slice() {
+            [status, from, to] if status.starts_with('R') => (JournalOp::Rename, *to, Some(*from)),
+            [status, path] if status.starts_with('A') => (JournalOp::Create, *path, None),
+            [status, path] if status.starts_with('D') => (JournalOp::Delete, *path, None),
+            [_, path] => (JournalOp::Update, *path, None),
+            _ => continue,
+        };
+        // skip nb internals (.index, etc.)
+        if path.split('/').any(|part| part.starts_with('.')) {
+            continue;
+        }
+        journal::record(&base_dir, op, path, from, JournalOrigin::Sync);
+        changed.push(path.to_string());
+    }
+    changed
+}
+
+// -----------------------------------------
+// push / pull
+// -----------------------------------------
+
+/// checkpoints pending changes and pushes the vault to its remote.
+/// fails without pushing if the remote has commits that need to be pulled first.
+async fn push(app_handle: &AppHandle) -> Result<SyncResult, String> {
+    require_remote(app_handle).await?;
+    log::info!("pushing vault");
+
+    nb::git_checkpoint(app_handle, "[nb] Sync").await?;
+    nb::git_remote(app_handle, &["fetch", SYNC_REMOTE_NAME])
+        .await
+        .map_err(|e| format!("failed to fetch from remote: {e}"))?;
+
+    let branch = current_branch(app_handle).await?;
+    let (_, behind) = ahead_behind(app_handle, &branch).await?;
+    if behind > 0 {
+        return Err(format!(
+            "the remote has {behind} new commits, pull before pushing"
+        ));
+    }
+
+    nb::git_remote(
+        app_handle,
+        &["push", "--set-upstream", SYNC_REMOTE_NAME, &branch],
+    )
+    .await
+    .map_err(|e| format!("failed to push: {e}"))?;
+
+    log::info!("pushed vault to {SYNC_REMOTE_NAME}/{branch}");
+    status(app_handle).await
+}
+
+/// checkpoints pending changes and merges the remote into the vault.
+/// conflicts are not an error: they are returned so the user can resolve them.
+async fn pull(app_handle: &AppHandle) -> Result<SyncResult, String> {
+    require_remote(app_handle).await?;
+    log::info!("pulling vault");
+
+    // a merge that runs out of space halfway leaves the vault conflicted
+    preflight::check(&get_base_dir(app_handle)?, 0).await?;
+    nb::git_checkpoint(app_handle, "[nb] Sync").await?;
+    let before = nb::git(app_handle, &["rev-parse", "HEAD"])
+        .await?
+        .trim()
+        .to_string();
+    let branch = current_branch(app_handle).await?;
+
+    let pull = nb::git_remote(
+        app_handle,
+        &[
+            "pull",
+            "--no-rebase",
+            "--no-edit",
+            SYNC_REMOTE_NAME,
+            &branch,
+        ],
+    )
+    .await;
+
+    let conflicts = conflicted_files(app_handle).await;
+    if let Err(e) = pull {
+        if conflicts.is_empty() {
+            return Err(format!("failed to pull: {e}"));
+        }
+        log::warn!("pull left {} conflicted files", conflicts.len());
+    }
+
+    let changed_files = journal_pulled_changes(app_handle, &before).await;
+    if let Err(failure) = nb::reconcile_index(app_handle).await {
+        log::warn!("index reconciliation after pull failed: {failure}");
+    }
+
+    log::info!(
+        "pulled vault: {} files changed, {} conflicts",
+        changed_files.len(),
+        conflicts.len()
+    );
+    Ok(SyncResult {
+        conflicts,
+        changed_files,
+        ..status(app_handle).await?
+    })
+}
+
+// -----------------------------------------
+// background sync
+// -----------------------------------------
+
+/// true when a sync error means the remote could not be reached
+fn is_offline_error(message: &str) -> bool {
+    let lower = message.to_lowercase();
+    [
+        "could not resolve host",
+        "unable to access",
+        "could not read from remote",
+        "connection timed out",
+        "connection refused",
+        "network is unreachable",
+    ]
+    .iter()
+    .any(|needle| lower.contains(needle))
+}
+
+fn emit_status(app_handle: &AppHandle, status: SyncStatus, result: Option<&SyncResult>) {
+    let payload = SyncStatusPayload {
+        status,
+        ahead: result.map(|result| result.ahead).unwrap_or_default(),
+        behind: result.map(|result| result.behind).unwrap_or_default(),
+        conflicts: result
+            .map(|result| result.conflicts.clone())
+            .unwrap_or_default(),
+        message: None,
+    };
+    if let Err(e) = app_handle.emit(VAULT_SYNC_STATUS_EVENT, payload) {
+        log::error!("failed to emit vault sync status: {e}");
+    }
+}
+
+fn emit_error(app_handle: &AppHandle, message: String) {
+    let status = if is_offline_error(&message) {
+        SyncStatus::Offline
+    } else {
+        SyncStatus::Error
+    };
+    let payload = SyncStatusPayload {
+        status,
+        ahead: 0,
+        behind: 0,
+        conflicts: Vec::new(),
+        message: Some(message),
+    };
+    if let Err(e) = app_handle.emit(VAULT_SYNC_STATUS_EVENT, payload) {
+        log::error!("failed to emit vault sync status: {e}");
+    }
+}
+
+/// pulls, then pushes if there is anything to push. skipped when no remote is
+/// configured or another sync is already running.
+async fn sync_in_background(app_handle: &AppHandle, reason: &str) {
+    if remote_url(app_handle).await.is_none() {
+        return;
+    }
+    let Ok(_guard) = SYNC_LOCK.try_lock() else {
+        return;
+    };
+
+    // pulling again would fail and checkpointing would commit conflict
+    // markers, so keep reporting the conflict until the user resolves it
+    if nb::with_repo(app_handle, vcs::is_mid_merge)
+        .await
+        .unwrap_or(false)
+    {
+        log::info!("skipping background vault sync ({reason}): merge in progress");
+        let result = SyncResult {
+            conflicts: conflicted_files(app_handle).await,
+            ..status(app_handle).await.unwrap_or_default()
+        };
+        emit_status(app_handle, SyncStatus::Conflict, Some(&result));
+        return;
+    }
+
+    log::info!("background vault sync ({reason})");
+    emit_status(app_handle, SyncStatus::Syncing, None);
+
+    let pulled = match pull(app_handle).await {
+        Ok(pulled) => pulled,
+        Err(e) => {
+            log::warn!("background vault sync failed: {e}");
+            emit_error(app_handle, e);
+            return;
+        }
+    };
+    if !pulled.conflicts.is_empty() {
+        emit_status(app_handle, SyncStatus::Conflict, Some(&pulled));
+        return;
+    }
+    if pulled.ahead == 0 {
+        emit_status(app_handle, SyncStatus::Clean, Some(&pulled));
+        return;
+    }
+    match push(app_handle).await {
+        Ok(pushed) => emit_status(app_handle, SyncStatus::Clean, Some(&pushed)),
+        Err(e) => {
+            log::warn!("background vault sync failed: {e}");
+            emit_error(app_handle, e);
+        }
+    }
+}
+
+/// starts the periodic sync loop. the policy is re-read on every tick.
+pub fn start_background_sync(app_handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let policy = get_policy(&app_handle);
+            let interval = match policy.interval_ms {
+                0 => VaultSyncPolicy::default().interval_ms,
+                ms => ms,
+            };
+            tokio::time::sleep(Duration::from_millis(interval)).await;
+
+            if policy.enabled && policy.interval_ms > 0 {
+                sync_in_background(&app_handle, "interval").await;
+            }
+        }
+    });
+}
+
+/// schedules a sync once the vault has been idle for the policy's idle period.
+/// called for every flushed file watcher event.
+pub fn schedule_idle_sync(app_handle: &AppHandle) {
+    let policy = get_policy(app_handle);
+    if !policy.enabled || policy.idle_ms == 0 {
+        return;
+    }
+    let generation = NEXT_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(policy.idle_ms)).await;
+        // a newer change restarted the idle period
+        if NEXT_GENERATION.load(Ordering::SeqCst) == generation {
+            sync_in_background(&handle, "idle").await;
+        }
+    });
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+/// points the vault repo at a git remote (e.g. a private github repo).
+/// an empty url removes the remote.
+#[tauri::command]
+pub async fn vault_sync_configure(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    remote_url: String,
+) -> Result<SyncResult, String> {
+    vaults::scoped(&webview_window, async move {
+        let remote_url = remote_url.trim();
+        log::info!("configuring vault sync remote: {remote_url}");
+
+        let existing = self::remote_url(&app_handle).await;
+        match (existing, remote_url.is_empty()) {
+            (Some(_), true) => {
+                nb::git(&app_handle, &["remote", "remove", SYNC_REMOTE_NAME]).await?;
+            }
+            (None, true) => {}
+            (Some(_), false) => {
+                nb::git(
+                    &app_handle,
+                    &["remote", "set-url", SYNC_REMOTE_NAME, remote_url],
+                )
+                .await?;
+            }
+            (None, false) => {
+                nb::git(
+                    &app_handle,
+                    &["remote", "add", SYNC_REMOTE_NAME, remote_url],
+                )
+                .await?;
+            }
+        }
+
+        if !remote_url.is_empty() {
+            // a fresh or empty remote has nothing to fetch yet
+            if let Err(e) = nb::git_remote(&app_handle, &["fetch", SYNC_REMOTE_NAME]).await {
+                log::warn!("initial fetch from {remote_url} failed: {e}");
+            }
+        }
+        status(&app_handle).await
+    })
+    .await
+}
+
+/// uncommitted changes, the last checkpoint and, with a remote, the
+/// ahead/behind counts of the window's vault. does not fetch.
+#[tauri::command]
+pub async fn vault_status(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+) -> Result<VaultStatus, String> {
+    vaults::scoped(&webview_window, async move {
+        let mut status = VaultStatus::default();
+        for entry in nb::with_repo(&app_handle, vcs::status).await? {
+            // skip nb internals (.index, etc.) and vault config
+            if entry.path.split('/').any(|part| part.starts_with('.')) {
+                continue;
+            }
+            if entry.untracked {
+                status.untracked.push(entry.path);
+            } else {
+                status.dirty.push(entry.path);
+            }
+        }
+        status.dirty.sort();
+        status.untracked.sort();
+
+        if let Some(commit) = nb::with_repo(&app_handle, vcs::last_commit).await? {
+            status.last_checkpoint_ms = Some(commit.time_ms);
+            status.last_checkpoint_message = Some(commit.message);
+        }
+
+        status.remote_url = remote_url(&app_handle).await;
+        if status.remote_url.is_some() {
+            let branch = current_branch(&app_handle).await?;
+            let (ahead, behind) = ahead_behind(&app_handle, &branch).await?;
+            status.ahead = Some(ahead);
+            status.behind = Some(behind);
+        }
+        Ok(status)
+    })
+    .await
+}
+
+/// pushes the window's vault to its remote, see `push`
+#[tauri::command]
+pub async fn vault_push(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+) -> Result<SyncResult, String> {
+    vaults::scoped(&webview_window, async move {
+        let _guard = SYNC_LOCK.lock().await;
+        push(&app_handle).await
+    })
+    .await
+}
+
+/// merges the remote into the window's vault, see `pull`
+#[tauri::command]
+pub async fn vault_pull(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+) -> Result<SyncResult, String> {
+    vaults::scoped(&webview_window, async move {
+        let _guard = SYNC_LOCK.lock().await;
+        pull(&app_handle).await
+    })
+    .await
+}
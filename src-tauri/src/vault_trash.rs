@@ -0,0 +1,219 @@
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, WebviewWindow};
+use tokio::fs;
+
+use crate::{
+    constants::{TRASH_DIR_NAME, TRASH_ENTRY_FILE_NAME, VAULT_CONFIG_DIR_NAME},
+    journal::{self, JournalOp, JournalOrigin},
+    nb,
+    utils::{get_base_dir, resolve_path},
+    vaults,
+};
+
+/// disambiguates entries trashed within the same millisecond
+static NEXT_ENTRY: AtomicU64 = AtomicU64::new(0);
+
+/// a note or folder in the vault trash. stored as
+/// `<vault>/.flowrite/trash/<id>/entry.json` next to the trashed item itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashedNote {
+    pub id: String,
+    /// vault-relative path the item was deleted from
+    pub path: String,
+    pub is_dir: bool,
+    pub trashed_time_ms: i64,
+}
+
+fn get_trash_dir(vault_root: &Path) -> PathBuf {
+    vault_root.join(VAULT_CONFIG_DIR_NAME).join(TRASH_DIR_NAME)
+}
+
+/// the trash is never checkpointed: everything in it is git-ignored
+async fn ensure_trash_dir(trash_dir: &Path) -> Result<(), String> {
+    fs::create_dir_all(trash_dir)
+        .await
+        .map_err(|e| format!("failed to create trash directory: {e}"))?;
+    let gitignore = trash_dir.join(".gitignore");
+    if !gitignore.exists() {
+        fs::write(&gitignore, "*\n")
+            .await
+            .map_err(|e| format!("failed to write trash .gitignore: {e}"))?;
+    }
+    Ok(())
+}
+
+/// rejects ids that are not plain directory names inside the trash
+fn get_entry_dir(vault_root: &Path, id: &str) -> Result<PathBuf, String> {
+    if id.is_empty() || id.starts_with('.') || id.contains(['/', '\\']) {
+        return Err(format!("invalid trash entry '{id}'"));
+    }
+    Ok(get_trash_dir(vault_root).join(id))
+}
+
+async fn read_entry(entry_dir: &Path) -> Result<TrashedNote, String> {
+    let content = fs::read_to_string(entry_dir.join(TRASH_ENTRY_FILE_NAME))
+        .await
+        .map_err(|e| format!("failed to read trash entry: {e}"))?;
+    serde_json::from_str(&content).map_err(|e| format!("failed to parse trash entry: {e}"))
+}
+
+fn item_name(path: &str) -> Result<&str, String> {
+    Path::new(path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| format!("invalid path '{path}'"))
+}
+
+/// moves a vault file or folder into the vault trash
+pub async fn move_to_trash(vault_root: &Path, path: &str) -> Result<TrashedNote, String> {
+    let source = vault_root.join(path);
+    let metadata = fs::metadata(&source)
+        .await
+        .map_err(|e| format!("failed to read '{path}': {e}"))?;
+
+    let trash_dir = get_trash_dir(vault_root);
+    ensure_trash_dir(&trash_dir).await?;
+
+    let trashed_time_ms = chrono::Utc::now().timestamp_millis();
+    let entry = TrashedNote {
+        id: format!(
+            "{trashed_time_ms}-{}",
+            NEXT_ENTRY.fetch_add(1, Ordering::SeqCst)
+        ),
+        path: path.to_string(),
+        is_dir: metadata.is_dir(),
+        trashed_time_ms,
+    };
+    let entry_dir = trash_dir.join(&entry.id);
+    fs::create_dir_all(&entry_dir)
+        .await
+        .map_err(|e| format!("failed to create trash entry: {e}"))?;
+
+    let json = serde_json::to_string_pretty(&entry)
+        .map_err(|e| format!("failed to serialize trash entry: {e}"))?;
+    fs::write(entry_dir.join(TRASH_ENTRY_FILE_NAME), json)
+        .await
+        .map_err(|e| format!("failed to write trash entry: {e}"))?;
+    fs::rename(&source, entry_dir.join(item_name(path)?))
+        .await
+        .map_err(|e| format!("failed to move '{path}' to trash: {e}"))?;
+
+    Ok(entry)
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+/// lists the vault trash, most recently deleted first
+#[tauri::command]
+pub async fn list_trashed_notes(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+) -> Result<Vec<TrashedNote>, String> {
+    vaults::scoped(&webview_window, async move {
+        let trash_dir = get_trash_dir(&get_base_dir(&app_handle)?);
+        let mut entries = match fs::read_dir(&trash_dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(format!("failed to read trash: {e}")),
+        };
+
+        let mut trashed = Vec::new();
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| format!("failed to read trash: {e}"))?
+        {
+            if !entry.path().is_dir() {
+                continue;
+            }
+            match read_entry(&entry.path()).await {
+                Ok(note) => trashed.push(note),
+                Err(e) => log::warn!("skipping trash entry {}: {e}", entry.path().display()),
+            }
+        }
+        trashed.sort_by(|a, b| b.trashed_time_ms.cmp(&a.trashed_time_ms));
+        Ok(trashed)
+    })
+    .await
+}
+
+/// moves a trashed note back into the vault, to `destination` or its original
+/// path. returns the restored path.
+#[tauri::command]
+pub async fn restore_note(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    id: String,
+    destination: Option<String>,
+) -> Result<String, String> {
+    vaults::scoped(&webview_window, async move {
+        let base_dir = get_base_dir(&app_handle)?;
+        let entry_dir = get_entry_dir(&base_dir, &id)?;
+        let entry = read_entry(&entry_dir).await?;
+        let destination = destination.unwrap_or_else(|| entry.path.clone());
+        log::info!("restoring {} from trash to {destination}", entry.path);
+
+        let destination_path = resolve_path(&app_handle, &destination)?;
+        if destination_path.exists() {
+            return Err(format!("file '{destination}' already exists"));
+        }
+        if let Some(parent) = destination_path.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .map_err(|e| format!("failed to create parent directory: {e}"))?;
+        }
+        fs::rename(entry_dir.join(item_name(&entry.path)?), &destination_path)
+            .await
+            .map_err(|e| format!("failed to restore '{}': {e}", entry.path))?;
+        if let Err(e) = fs::remove_dir_all(&entry_dir).await {
+            log::warn!("failed to remove trash entry {id}: {e}");
+        }
+
+        journal::record(
+            &base_dir,
+            JournalOp::Create,
+            &destination,
+            None,
+            JournalOrigin::User,
+        );
+        nb::reconcile_and_checkpoint_paths(
+            &app_handle,
+            vec![destination.clone()],
+            format!("[nb] Restore: {destination}"),
+        );
+
+        log::info!("restored note to: {destination}");
+        Ok(destination)
+    })
+    .await
+}
+
+/// permanently deletes one trash entry, or the whole trash when `id` is omitted
+#[tauri::command]
+pub async fn empty_trash(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    id: Option<String>,
+) -> Result<(), String> {
+    vaults::scoped(&webview_window, async move {
+        let base_dir = get_base_dir(&app_handle)?;
+        let target = match &id {
+            Some(id) => get_entry_dir(&base_dir, id)?,
+            None => get_trash_dir(&base_dir),
+        };
+        log::info!("emptying trash: {}", id.as_deref().unwrap_or("(all)"));
+
+        match fs::remove_dir_all(&target).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(format!("failed to empty trash: {e}")),
+        }
+    })
+    .await
+}
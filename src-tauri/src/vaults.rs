@@ -0,0 +1,479 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, EventTarget, Manager, WebviewWindow};
+use tokio::fs;
+
+use crate::{
+    autosave,
+    constants::{BASE_DIR_NAME, DEFAULT_VAULT_ID, VAULTS_KEY, VAULT_CHANGED_EVENT},
+    file_watcher, links, nb, note_ids, note_index, search_index, tags,
+    utils::{get_setting, set_setting},
+    vault_settings,
+};
+
+/// the vault every `get_base_dir` call resolves to, set at startup and on switch
+static ACTIVE_VAULT: RwLock<Option<PathBuf>> = RwLock::new(None);
+
+/// windows pinned to a vault (instead of following the active one), by window label
+static WINDOW_VAULTS: RwLock<Option<HashMap<String, PathBuf>>> = RwLock::new(None);
+
+tokio::task_local! {
+    /// the vault of the window whose command is running, see `scoped`
+    static SCOPED_VAULT: PathBuf;
+}
+
+/// a notes folder flowrite knows about
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultInfo {
+    pub id: String,
+    pub name: String,
+    /// absolute path of the vault folder
+    pub path: String,
+}
+
+/// registered vaults and the one that is open, persisted in the settings store
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct VaultRegistry {
+    vaults: Vec<VaultInfo>,
+    active: Option<String>,
+}
+
+/// registered vaults with the active one marked, returned by the commands
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultList {
+    pub vaults: Vec<VaultInfo>,
+    pub active: String,
+}
+
+/// the vault path resolved by `utils::get_base_dir`: the vault of the window
+/// running the current command, else the active vault
+pub fn active_vault_dir() -> Option<PathBuf> {
+    SCOPED_VAULT
+        .try_with(|vault_dir| vault_dir.clone())
+        .ok()
+        .or_else(|| ACTIVE_VAULT.read().unwrap().clone())
+}
+
+/// the globally active vault, ignoring any window scope
+pub fn global_vault_dir() -> Option<PathBuf> {
+    ACTIVE_VAULT.read().unwrap().clone()
+}
+
+/// the vault a window shows: its pinned vault, else the active vault
+pub fn window_vault_dir(label: &str) -> Option<PathBuf> {
+    WINDOW_VAULTS
+        .read()
+        .unwrap()
+        .as_ref()
+        .and_then(|windows| windows.get(label).cloned())
+        .or_else(global_vault_dir)
+}
+
+/// fails unless `window` shows the active vault. the search, tag, link and
+/// note id indexes are only kept for the active vault, so querying them from
+/// a window pinned to another vault would answer for the wrong vault.
+pub fn ensure_active_vault(window: &WebviewWindow) -> Result<(), String> {
+    if window_vault_dir(window.label()) == global_vault_dir() {
+        Ok(())
+    } else {
+        Err("this window shows a vault other than the active one; search, tags and links only cover the active vault".to_string())
+    }
+}
+
+/// every vault shown by some window (the active vault first), for the file watcher
+pub fn open_vault_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = global_vault_dir().into_iter().collect();
+    if let Some(windows) = WINDOW_VAULTS.read().unwrap().as_ref() {
+        for dir in windows.values() {
+            if !dirs.contains(dir) {
+                dirs.push(dir.clone());
+            }
+        }
+    }
+    dirs
+}
+
+/// runs a command future against the vault of the window that invoked it, so
+/// `get_base_dir` (and with it `resolve_path` and nb) resolve inside that vault
+pub async fn scoped<T, E>(
+    window: &WebviewWindow,
+    future: impl Future<Output = Result<T, E>>,
+) -> Result<T, E> {
+    match window_vault_dir(window.label()) {
+        Some(vault_dir) => SCOPED_VAULT.scope(vault_dir, future).await,
+        None => future.await,
+    }
+}
+
+/// runs `future` scoped to `vault_dir`, for background work that outlives
+/// the command that queued it
+pub async fn within<F: Future>(vault_dir: PathBuf, future: F) -> F::Output {
+    SCOPED_VAULT.scope(vault_dir, future).await
+}
+
+/// `tauri::async_runtime::spawn` that keeps the caller's vault scope, for
+/// background work (checkpoints, reconciles) started by a window's command
+pub fn spawn<F>(future: F) -> tauri::async_runtime::JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    match SCOPED_VAULT.try_with(|vault_dir| vault_dir.clone()) {
+        Ok(vault_dir) => tauri::async_runtime::spawn(SCOPED_VAULT.scope(vault_dir, future)),
+        Err(_) => tauri::async_runtime::spawn(future),
+    }
+}
+
+/// emits to the windows showing `vault_dir` only. this filters listeners
+/// registered on a window (`getCurrentWebviewWindow().listen`); global listeners
+/// still receive every vault's events.
+pub fn emit_to_vault<S: Serialize + Clone>(
+    app_handle: &AppHandle,
+    vault_dir: &Path,
+    event: &str,
+    payload: S,
+) -> tauri::Result<()> {
+    app_handle.emit_filter(event, payload, |target| match target {
+        EventTarget::WebviewWindow { label } | EventTarget::Window { label } => {
+            window_vault_dir(label).as_deref() == Some(vault_dir)
+        }
+        _ => true,
+    })
+}
+
+/// pins a window to `vault_dir`, or back to the active vault with `None`
+pub fn assign_window(label: &str, vault_dir: Option<PathBuf>) {
+    let mut windows = WINDOW_VAULTS.write().unwrap();
+    let windows = windows.get_or_insert_with(HashMap::new);
+    match vault_dir {
+        Some(vault_dir) => {
+            windows.insert(label.to_string(), vault_dir);
+        }
+        None => {
+            windows.remove(label);
+        }
+    }
+}
+
+/// forgets a closed window and stops watching its vault if no other window shows it
+pub fn clear_window_vault(app_handle: &AppHandle, label: &str) {
+    let was_pinned = WINDOW_VAULTS
+        .write()
+        .unwrap()
+        .as_mut()
+        .and_then(|windows| windows.remove(label))
+        .is_some();
+    if was_pinned {
+        file_watcher::init_file_watcher(app_handle.clone());
+    }
+}
+
+/// looks up a registered vault by id
+pub fn find_vault(app_handle: &AppHandle, id: &str) -> Result<VaultInfo, String> {
+    load_registry(app_handle)?
+        .vaults
+        .into_iter()
+        .find(|vault| vault.id == id)
+        .ok_or_else(|| format!("unknown vault '{id}'"))
+}
+
+/// pins a window to a registered vault, initializing its notebook and watcher
+pub async fn open_in_window(
+    app_handle: &AppHandle,
+    label: &str,
+    vault: &VaultInfo,
+) -> Result<(), String> {
+    let vault_dir = PathBuf::from(&vault.path);
+    SCOPED_VAULT
+        .scope(vault_dir.clone(), nb::init_nb(app_handle))
+        .await
+        .map_err(|e| format!("failed to open vault {}: {e}", vault.path))?;
+
+    assign_window(label, Some(vault_dir));
+    file_watcher::init_file_watcher(app_handle.clone());
+    Ok(())
+}
+
+fn default_vault(app_handle: &AppHandle) -> Result<VaultInfo, String> {
+    let home_dir = app_handle
+        .path()
+        .home_dir()
+        .map_err(|e| format!("could not find home directory: {e}"))?;
+    Ok(VaultInfo {
+        id: DEFAULT_VAULT_ID.to_string(),
+        name: BASE_DIR_NAME.to_string(),
+        path: home_dir.join(BASE_DIR_NAME).to_string_lossy().to_string(),
+    })
+}
+
+/// the stored registry, always containing the default `~/flowrite` vault
+fn load_registry(app_handle: &AppHandle) -> Result<VaultRegistry, String> {
+    let mut registry: VaultRegistry = get_setting(app_handle, VAULTS_KEY).unwrap_or_default();
+    if !registry
+        .vaults
+        .iter()
+        .any(|vault| vault.id == DEFAULT_VAULT_ID)
+    {
+        registry.vaults.insert(0, default_vault(app_handle)?);
+    }
+    Ok(registry)
+}
+
+fn save_registry(app_handle: &AppHandle, registry: &VaultRegistry) -> Result<(), String> {
+    set_setting(app_handle, VAULTS_KEY, registry)
+}
+
+fn active_vault(registry: &VaultRegistry) -> &VaultInfo {
+    registry
+        .active
+        .as_ref()
+        .and_then(|id| registry.vaults.iter().find(|vault| &vault.id == id))
+        .unwrap_or(&registry.vaults[0])
+}
+
+fn to_list(registry: &VaultRegistry) -> VaultList {
+    VaultList {
+        active: active_vault(registry).id.clone(),
+        vaults: registry.vaults.clone(),
+    }
+}
+
+/// id derived from the folder name, suffixed when taken
+fn new_vault_id(registry: &VaultRegistry, name: &str) -> String {
+    let slug: String = name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    let slug = slug.trim_matches('-');
+    let slug = if slug.is_empty() { "vault" } else { slug };
+
+    let taken = |id: &str| registry.vaults.iter().any(|vault| vault.id == id);
+    let mut id = slug.to_string();
+    let mut counter = 2;
+    while taken(&id) {
+        id = format!("{slug}-{counter}");
+        counter += 1;
+    }
+    id
+}
+
+/// adds a folder to the registry, returning the existing entry if it is already known
+fn register(registry: &mut VaultRegistry, path: &Path, name: Option<String>) -> VaultInfo {
+    let path_str = path.to_string_lossy().to_string();
+    if let Some(vault) = registry.vaults.iter().find(|vault| vault.path == path_str) {
+        return vault.clone();
+    }
+    let name = name
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .or_else(|| {
+            path.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+        })
+        .unwrap_or_else(|| "vault".to_string());
+    let vault = VaultInfo {
+        id: new_vault_id(registry, &name),
+        name,
+        path: path_str,
+    };
+    registry.vaults.push(vault.clone());
+    vault
+}
+
+/// selects the stored active vault. must run before anything resolves the base directory.
+pub fn init(app_handle: &AppHandle) {
+    match load_registry(app_handle) {
+        Ok(registry) => {
+            let vault = active_vault(&registry);
+            log::info!("opening vault {} at {}", vault.id, vault.path);
+            *ACTIVE_VAULT.write().unwrap() = Some(PathBuf::from(&vault.path));
+        }
+        Err(e) => log::error!("failed to load vault registry: {e}"),
+    }
+}
+
+/// makes `vault` the open vault: flushes pending edits, initializes the notebook
+/// and restarts the watcher and indexes against the new folder
+async fn activate(app_handle: &AppHandle, vault: &VaultInfo) -> Result<(), String> {
+    autosave::flush_all(app_handle, "vault-switch").await;
+
+    let previous = active_vault_dir();
+    *ACTIVE_VAULT.write().unwrap() = Some(PathBuf::from(&vault.path));
+    if let Err(e) = nb::init_nb(app_handle).await {
+        *ACTIVE_VAULT.write().unwrap() = previous;
+        return Err(format!("failed to open vault {}: {e}", vault.path));
+    }
+
+    vault_settings::init(app_handle);
+    file_watcher::init_file_watcher(app_handle.clone());
+    search_index::init(app_handle);
+    note_index::init::<tags::TagIndex>(app_handle);
+    note_index::init::<links::LinkIndex>(app_handle);
+    note_index::init::<note_ids::NoteIdIndex>(app_handle);
+
+    if let Err(e) = app_handle.emit(VAULT_CHANGED_EVENT, vault) {
+        log::error!("failed to emit vault change: {e}");
+    }
+    Ok(())
+}
+
+// -----------------------------------------
+// commands
+// -----------------------------------------
+
+#[tauri::command]
+pub fn list_vaults(app_handle: AppHandle) -> Result<VaultList, String> {
+    Ok(to_list(&load_registry(&app_handle)?))
+}
+
+/// creates a new empty vault folder at `path`, registers it and switches to it
+#[tauri::command]
+pub async fn create_vault(
+    app_handle: AppHandle,
+    name: String,
+    path: String,
+) -> Result<VaultList, String> {
+    let path = PathBuf::from(&path);
+    if !path.is_absolute() {
+        return Err(format!("vault path '{}' must be absolute", path.display()));
+    }
+    log::info!("creating vault {name} at {}", path.display());
+
+    if path.exists() {
+        let mut entries = fs::read_dir(&path)
+            .await
+            .map_err(|e| format!("failed to read {}: {e}", path.display()))?;
+        let has_entries = entries
+            .next_entry()
+            .await
+            .map_err(|e| format!("failed to read {}: {e}", path.display()))?
+            .is_some();
+        if has_entries {
+            return Err(format!(
+                "folder '{}' is not empty, open it as a vault instead",
+                path.display()
+            ));
+        }
+    }
+    fs::create_dir_all(&path)
+        .await
+        .map_err(|e| format!("failed to create vault directory: {e}"))?;
+
+    let mut registry = load_registry(&app_handle)?;
+    let vault = register(&mut registry, &path, Some(name));
+    activate(&app_handle, &vault).await?;
+    registry.active = Some(vault.id.clone());
+    save_registry(&app_handle, &registry)?;
+
+    log::info!("created vault: {}", vault.id);
+    Ok(to_list(&registry))
+}
+
+/// registers an existing folder as a vault (if needed) and switches to it
+#[tauri::command]
+pub async fn open_vault(app_handle: AppHandle, path: String) -> Result<VaultList, String> {
+    let path = PathBuf::from(&path);
+    if !path.is_dir() {
+        return Err(format!("folder '{}' does not exist", path.display()));
+    }
+    log::info!("opening vault at {}", path.display());
+
+    let mut registry = load_registry(&app_handle)?;
+    let vault = register(&mut registry, &path, None);
+    activate(&app_handle, &vault).await?;
+    registry.active = Some(vault.id.clone());
+    save_registry(&app_handle, &registry)?;
+
+    Ok(to_list(&registry))
+}
+
+/// switches to a registered vault
+#[tauri::command]
+pub async fn switch_vault(app_handle: AppHandle, id: String) -> Result<VaultList, String> {
+    let mut registry = load_registry(&app_handle)?;
+    let vault = registry
+        .vaults
+        .iter()
+        .find(|vault| vault.id == id)
+        .cloned()
+        .ok_or_else(|| format!("unknown vault '{id}'"))?;
+    log::info!("switching to vault {id}");
+
+    activate(&app_handle, &vault).await?;
+    registry.active = Some(vault.id.clone());
+    save_registry(&app_handle, &registry)?;
+
+    Ok(to_list(&registry))
+}
+
+/// shows a registered vault in the calling window only, leaving other windows
+/// and the active vault unchanged. `None` makes the window follow the active vault.
+#[tauri::command]
+pub async fn set_window_vault(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+    id: Option<String>,
+) -> Result<Option<VaultInfo>, String> {
+    let label = webview_window.label().to_string();
+    autosave::flush_all(&app_handle, "vault-switch").await;
+
+    let Some(id) = id else {
+        log::info!("window {label} follows the active vault");
+        clear_window_vault(&app_handle, &label);
+        return Ok(None);
+    };
+    log::info!("opening vault {id} in window {label}");
+    let vault = find_vault(&app_handle, &id)?;
+    open_in_window(&app_handle, &label, &vault).await?;
+
+    if let Err(e) = webview_window.emit(VAULT_CHANGED_EVENT, &vault) {
+        log::error!("failed to emit vault change: {e}");
+    }
+    Ok(Some(vault))
+}
+
+/// the vault shown by the calling window
+#[tauri::command]
+pub fn get_window_vault(
+    app_handle: AppHandle,
+    webview_window: WebviewWindow,
+) -> Result<VaultInfo, String> {
+    let vault_dir =
+        window_vault_dir(webview_window.label()).ok_or_else(|| "no vault is open".to_string())?;
+    let registry = load_registry(&app_handle)?;
+    registry
+        .vaults
+        .into_iter()
+        .find(|vault| Path::new(&vault.path) == vault_dir)
+        .ok_or_else(|| format!("vault {} is not registered", vault_dir.display()))
+}
+
+/// forgets a vault. its folder is left untouched. the open vault and the default
+/// vault cannot be removed.
+#[tauri::command]
+pub fn remove_vault(app_handle: AppHandle, id: String) -> Result<VaultList, String> {
+    let mut registry = load_registry(&app_handle)?;
+    if id == DEFAULT_VAULT_ID {
+        return Err("the default vault cannot be removed".to_string());
+    }
+    if active_vault(&registry).id == id {
+        return Err("switch to another vault before removing this one".to_string());
+    }
+    let vault = find_vault(&app_handle, &id)?;
+    if open_vault_dirs().contains(&PathBuf::from(&vault.path)) {
+        return Err("close the windows showing this vault before removing it".to_string());
+    }
+    registry.vaults.retain(|vault| vault.id != id);
+    log::info!("removed vault {id}");
+
+    save_registry(&app_handle, &registry)?;
+    Ok(to_list(&registry))
+}
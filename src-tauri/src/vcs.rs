@@ -0,0 +1,336 @@
+use std::path::Path;
+
+use git2::{
+    Commit, Config, ConfigLevel, Delta, Diff, DiffFindOptions, ErrorCode, IndexAddOption, Oid,
+    Repository, RepositoryState, Signature, Sort, StatusOptions,
+};
+
+/// the author used when neither the repo nor the user has a git identity
+const FALLBACK_AUTHOR_NAME: &str = "flowrite";
+const FALLBACK_AUTHOR_EMAIL: &str = "flowrite@localhost";
+
+/// a commit as shown in history lists
+#[derive(Debug, Clone)]
+pub struct CommitInfo {
+    pub id: String,
+    pub time_ms: i64,
+    /// first line of the commit message
+    pub message: String,
+}
+
+fn commit_info(commit: &Commit) -> CommitInfo {
+    CommitInfo {
+        id: commit.id().to_string(),
+        time_ms: commit.time().seconds() * 1000,
+        message: commit.summary().unwrap_or_default().to_string(),
+    }
+}
+
+fn path_string(path: Option<&Path>) -> Option<String> {
+    path.map(|path| path.to_string_lossy().into_owned())
+}
+
+/// opens the repo at exactly `dir`, never one of its parents
+pub fn open(dir: &Path) -> Result<Repository, git2::Error> {
+    Repository::open(dir)
+}
+
+/// creates an empty repo at `dir`
+pub fn init(dir: &Path) -> Result<Repository, git2::Error> {
+    Repository::init(dir)
+}
+
+/// the commit HEAD points to, `None` before the first commit
+fn head_commit(repo: &Repository) -> Result<Option<Commit<'_>>, git2::Error> {
+    match repo.head() {
+        Ok(head) => head.peel_to_commit().map(Some),
+        Err(e) if matches!(e.code(), ErrorCode::UnbornBranch | ErrorCode::NotFound) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// refuses to stage or commit while a merge, rebase or similar is in
+/// progress, or while the index has unresolved conflicts. committing then
+/// would record conflict markers or drop the other side of the merge.
+fn ensure_clean(repo: &Repository, index: &git2::Index) -> Result<(), git2::Error> {
+    if repo.state() != RepositoryState::Clean {
+        return Err(git2::Error::from_str(&format!(
+            "the vault repo is in the middle of a {:?}; resolve it before committing",
+            repo.state()
+        )));
+    }
+    if index.has_conflicts() {
+        return Err(git2::Error::from_str(
+            "the vault repo has unresolved merge conflicts; resolve them before committing",
+        ));
+    }
+    Ok(())
+}
+
+/// true while a merge, rebase or similar is in progress, e.g. after a pull
+/// that left conflicts
+pub fn is_mid_merge(repo: &Repository) -> Result<bool, git2::Error> {
+    Ok(repo.state() != RepositoryState::Clean)
+}
+
+/// stages every change in the working tree, deletions included (`git add
+/// --all`), and returns the resulting tree
+pub fn add_all(repo: &Repository) -> Result<Oid, git2::Error> {
+    let mut index = repo.index()?;
+    ensure_clean(repo, &index)?;
+    index.add_all(["*"], IndexAddOption::DEFAULT, None)?;
+    index.update_all(["*"], None)?;
+    index.write()?;
+    index.write_tree()
+}
+
+/// stages only `paths` (files or folders, vault-relative), deletions
+/// included (`git add --all -- <paths>`), and returns the resulting tree
+pub fn add_paths(repo: &Repository, paths: &[String]) -> Result<Oid, git2::Error> {
+    let mut index = repo.index()?;
+    ensure_clean(repo, &index)?;
+    index.add_all(
+        paths.iter().map(String::as_str),
+        IndexAddOption::DEFAULT,
+        None,
+    )?;
+    index.update_all(paths.iter().map(String::as_str), None)?;
+    index.write()?;
+    index.write_tree()
+}
+
+/// removes `paths` from the index but keeps the files (`git rm --cached`)
+pub fn untrack(repo: &Repository, paths: &[String]) -> Result<(), git2::Error> {
+    if paths.is_empty() {
+        return Ok(());
+    }
+    let mut index = repo.index()?;
+    let mut changed = false;
+    for path in paths {
+        let path = Path::new(path);
+        if index.get_path(path, 0).is_some() {
+            index.remove_path(path)?;
+            changed = true;
+        }
+    }
+    if changed {
+        index.write()?;
+    }
+    Ok(())
+}
+
+/// commits `tree` on top of HEAD. returns `None` without committing when
+/// nothing changed since HEAD.
+pub fn commit(repo: &Repository, tree: Oid, message: &str) -> Result<Option<Oid>, git2::Error> {
+    ensure_clean(repo, &repo.index()?)?;
+    let parent = head_commit(repo)?;
+    if parent
+        .as_ref()
+        .is_some_and(|parent| parent.tree_id() == tree)
+    {
+        return Ok(None);
+    }
+    let tree = repo.find_tree(tree)?;
+    let signature = repo
+        .signature()
+        .or_else(|_| Signature::now(FALLBACK_AUTHOR_NAME, FALLBACK_AUTHOR_EMAIL))?;
+    let parents: Vec<&Commit> = parent.iter().collect();
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        message,
+        &tree,
+        &parents,
+    )
+    .map(Some)
+}
+
+/// stages and commits everything, like `nb git checkpoint`
+pub fn checkpoint(repo: &Repository, message: &str) -> Result<Option<Oid>, git2::Error> {
+    let tree = add_all(repo)?;
+    commit(repo, tree, message)
+}
+
+/// stages and commits only `paths`, leaving other changes uncommitted
+pub fn checkpoint_paths(
+    repo: &Repository,
+    paths: &[String],
+    message: &str,
+) -> Result<Option<Oid>, git2::Error> {
+    let tree = add_paths(repo, paths)?;
+    commit(repo, tree, message)
+}
+
+/// the changes a commit made to its first parent, with renames detected
+fn diff<'r>(repo: &'r Repository, commit: &Commit) -> Result<Diff<'r>, git2::Error> {
+    let parent_tree = match commit.parents().next() {
+        Some(parent) => Some(parent.tree()?),
+        None => None,
+    };
+    let mut diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+    Ok(diff)
+}
+
+/// calls `f` for every commit reachable from HEAD, newest first, until it
+/// returns false
+fn walk(
+    repo: &Repository,
+    mut f: impl FnMut(&Commit) -> Result<bool, git2::Error>,
+) -> Result<(), git2::Error> {
+    if head_commit(repo)?.is_none() {
+        return Ok(());
+    }
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_head()?;
+    revwalk.set_sorting(Sort::TIME)?;
+    for id in revwalk {
+        if !f(&repo.find_commit(id?)?)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// commits that changed `path`, newest first, each with the file's path in
+/// that commit. renames are followed (`git log --follow`).
+pub fn log(repo: &Repository, path: &str) -> Result<Vec<(CommitInfo, String)>, git2::Error> {
+    let mut current = path.to_string();
+    let mut versions = Vec::new();
+    walk(repo, |commit| {
+        let diff = diff(repo, commit)?;
+        let delta = diff.deltas().find(|delta| {
+            path_string(delta.new_file().path()).as_deref() == Some(current.as_str())
+        });
+        if let Some(delta) = delta {
+            versions.push((commit_info(commit), current.clone()));
+            if delta.status() == Delta::Renamed {
+                if let Some(old_path) = path_string(delta.old_file().path()) {
+                    current = old_path;
+                }
+            }
+        }
+        Ok(true)
+    })?;
+    Ok(versions)
+}
+
+/// commits that deleted files, newest first, with the deleted paths. renamed
+/// files do not count as deleted. stops at commits older than `since_ms`.
+pub fn deletions(
+    repo: &Repository,
+    since_ms: Option<i64>,
+) -> Result<Vec<(CommitInfo, Vec<String>)>, git2::Error> {
+    let mut deletions = Vec::new();
+    walk(repo, |commit| {
+        let info = commit_info(commit);
+        if since_ms.is_some_and(|since| info.time_ms < since) {
+            return Ok(false);
+        }
+        let deleted: Vec<String> = diff(repo, commit)?
+            .deltas()
+            .filter(|delta| delta.status() == Delta::Deleted)
+            .filter_map(|delta| path_string(delta.old_file().path()))
+            .collect();
+        if !deleted.is_empty() {
+            deletions.push((info, deleted));
+        }
+        Ok(true)
+    })?;
+    Ok(deletions)
+}
+
+/// the content of `path` in `commit` (`git show <commit>:<path>`), or in
+/// the commit's first parent with `before`
+pub fn show(
+    repo: &Repository,
+    commit: &str,
+    path: &str,
+    before: bool,
+) -> Result<Vec<u8>, git2::Error> {
+    let mut commit = repo.revparse_single(commit)?.peel_to_commit()?;
+    if before {
+        commit = commit.parent(0)?;
+    }
+    let entry = commit.tree()?.get_path(Path::new(path))?;
+    let blob = entry.to_object(repo)?.peel_to_blob()?;
+    Ok(blob.content().to_vec())
+}
+
+/// a path with uncommitted changes
+#[derive(Debug, Clone)]
+pub struct StatusEntry {
+    pub path: String,
+    /// never committed nor staged
+    pub untracked: bool,
+}
+
+/// paths with uncommitted changes, untracked files included (`git status`)
+pub fn status(repo: &Repository) -> Result<Vec<StatusEntry>, git2::Error> {
+    let mut options = StatusOptions::new();
+    options
+        .include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .renames_head_to_index(true);
+    let statuses = repo.statuses(Some(&mut options))?;
+    Ok(statuses
+        .iter()
+        .filter_map(|entry| {
+            Some(StatusEntry {
+                path: entry.path()?.to_string(),
+                untracked: entry.status().is_wt_new(),
+            })
+        })
+        .collect())
+}
+
+/// the commit HEAD points to, `None` before the first commit
+pub fn last_commit(repo: &Repository) -> Result<Option<CommitInfo>, git2::Error> {
+    Ok(head_commit(repo)?.map(|commit| commit_info(&commit)))
+}
+
+/// number of commits reachable from HEAD (`git rev-list --count HEAD`)
+pub fn commit_count(repo: &Repository) -> Result<usize, git2::Error> {
+    let mut count = 0;
+    walk(repo, |_| {
+        count += 1;
+        Ok(true)
+    })?;
+    Ok(count)
+}
+
+/// the repo's own config (`.git/config`), without the user's global settings
+fn local_config(repo: &Repository) -> Result<Config, git2::Error> {
+    repo.config()?.open_level(ConfigLevel::Local)
+}
+
+/// url of the remote `name`, `None` when there is no such remote
+pub fn remote_url(repo: &Repository, name: &str) -> Result<Option<String>, git2::Error> {
+    match repo.find_remote(name) {
+        Ok(remote) => Ok(remote.url().map(str::to_string)),
+        Err(e) if e.code() == ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// a value from the repo's own config, `None` when unset
+pub fn config_get(repo: &Repository, key: &str) -> Result<Option<String>, git2::Error> {
+    match local_config(repo)?.get_string(key) {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if e.code() == ErrorCode::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// sets a value in the repo's own config, or removes it with `None`
+pub fn config_set(repo: &Repository, key: &str, value: Option<&str>) -> Result<(), git2::Error> {
+    let mut config = local_config(repo)?;
+    match value {
+        Some(value) => config.set_str(key, value),
+        None => match config.remove(key) {
+            Err(e) if e.code() == ErrorCode::NotFound => Ok(()),
+            result => result,
+        },
+    }
+}
@@ -78,6 +78,11 @@ function ConnectionErrorDisplay({ error }: { error: ConnectionError }) {
       <p className="text-xs text-muted-foreground">
         check the agent configuration and try again
       </p>
+      {error.stderrTail && error.stderrTail.length > 0 && (
+        <pre className="mt-1 max-h-32 w-full overflow-auto rounded-md border border-foreground/8 p-2 text-left text-[10px] text-muted-foreground whitespace-pre-wrap">
+          {error.stderrTail.slice(-10).join("\n")}
+        </pre>
+      )}
     </div>
   );
 }
@@ -9,7 +9,7 @@ import {
   Loader2,
 } from "lucide-react";
 import { invoke } from "@tauri-apps/api/core";
-import { listen } from "@tauri-apps/api/event";
+import { getCurrentWebviewWindow } from "@tauri-apps/api/webviewWindow";
 import { save as saveDialog } from "@tauri-apps/plugin-dialog";
 import { toast } from "sonner";
 import matter from "gray-matter";
@@ -1124,7 +1124,8 @@ export function EditorPane(props: IDockviewPanelProps<EditorPaneParams>) {
     };
 
     const setup = async () => {
-      unlisten = await listen<{
+      // window-scoped: a global listener also receives other vaults' events
+      unlisten = await getCurrentWebviewWindow().listen<{
         fileChanges: { path: string; kind: string }[];
         directoryChanges: string[];
       }>(FILE_WATCHER_EVENT, (event) => {
@@ -404,6 +404,40 @@ function Workspace() {
     closeTab,
   ]);
 
+  // handle os files dropped onto the window. webview drop events only carry
+  // opaque File objects, so the real paths are read back from the backend.
+  useEffect(() => {
+    const hasOsFiles = (event: DragEvent) =>
+      event.dataTransfer?.types.includes("Files") ?? false;
+
+    const handleDragOver = (event: DragEvent) => {
+      if (event.defaultPrevented || !hasOsFiles(event)) return;
+      event.preventDefault();
+    };
+
+    const handleDrop = async (event: DragEvent) => {
+      if (event.defaultPrevented || !hasOsFiles(event)) return;
+      event.preventDefault();
+      try {
+        const paths = await invoke<string[]>("read_dropped_paths");
+        for (const path of paths) {
+          if (!/\.(md|markdown)$/i.test(path)) continue;
+          await openFileFromAbsolutePath(path, openFile, openExternalFile);
+        }
+      } catch (error) {
+        console.error("failed to open dropped files:", error);
+      }
+    };
+
+    window.addEventListener("dragover", handleDragOver);
+    window.addEventListener("drop", handleDrop);
+
+    return () => {
+      window.removeEventListener("dragover", handleDragOver);
+      window.removeEventListener("drop", handleDrop);
+    };
+  }, [openFile, openExternalFile]);
+
   // handle window close with save confirmation
   useEffect(() => {
     let unlisten: (() => void) | undefined;
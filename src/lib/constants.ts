@@ -3,6 +3,11 @@
 // -----------------------------------------
 
 export const FILE_WATCHER_EVENT = "file-watcher";
+export const NB_FAILURE_EVENT = "nb-failure";
+export const NB_INSTALL_PROGRESS_EVENT = "nb-install-progress";
+export const AUTOSAVE_FLUSHED_EVENT = "autosave-flushed";
+export const FOCUS_MODE_CHANGED_EVENT = "focus-mode-changed";
+export const VAULT_MAINTENANCE_EVENT = "vault-maintenance";
 
 // -----------------------------------------
 // app settings
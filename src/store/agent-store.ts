@@ -30,6 +30,13 @@ export interface AgentConfig {
   commandConfigured: boolean;
   downloadUrl?: string;
   lastLogFile?: string;
+  timeouts?: AgentTimeouts;
+}
+
+export interface AgentTimeouts {
+  initializeSecs?: number;
+  sessionNewSecs?: number;
+  promptInactivitySecs?: number;
 }
 
 export interface SessionMode {
@@ -45,9 +52,18 @@ export interface ModelInfo {
 }
 
 export interface ConnectionError {
-  kind: "auth_required" | "internal" | "crashed" | "timeout" | "unknown";
+  kind:
+    | "auth_required"
+    | "internal"
+    | "crashed"
+    | "timeout"
+    | "agent_not_connected"
+    | "session_not_found"
+    | "wire"
+    | "unknown";
   message: string;
   authMethods?: AuthMethodInfo[];
+  stderrTail?: string[];
 }
 
 export interface AuthMethodInfo {
@@ -216,6 +232,13 @@ interface SessionInfoResponse {
   currentModelId: string | null;
 }
 
+interface AgentDiagnostics {
+  agentId: string;
+  running: boolean;
+  stderrTail: string[];
+  logFile: string | null;
+}
+
 interface AgentCrashedPayload {
   agentId: string;
   kind?: string;
@@ -225,6 +248,7 @@ interface AgentCrashedPayload {
 type AgentEvent =
   | { event: "messageChunk"; data: { text: string } }
   | { event: "thinkingChunk"; data: { text: string } }
+  | { event: "resourceLink"; data: { path: string; title: string } }
   | {
       event: "toolCallUpdate";
       data: {
@@ -245,6 +269,10 @@ type AgentEvent =
         options: { optionId: string; name: string; kind: string }[];
       };
     }
+  | {
+      event: "permissionTimedOut";
+      data: { requestId: string; optionId: string | null };
+    }
   | {
       event: "planUpdate";
       data: {
@@ -256,8 +284,18 @@ type AgentEvent =
       event: "commandsUpdate";
       data: { commands: SlashCommand[] };
     }
-  | { event: "done"; data: { stopReason: string } }
-  | { event: "error"; data: { message: string } };
+  | {
+      event: "done";
+      data: {
+        stopReason: string;
+        durationMs: number;
+        toolCallCount: number;
+        chunkCount: number;
+        truncated: boolean;
+        canContinue: boolean;
+      };
+    }
+  | { event: "error"; data: { message: string; kind?: string } };
 
 interface RegistryResponse {
   agents: RegistryAgent[];
@@ -301,37 +339,48 @@ const createId = () =>
   globalThis.crypto?.randomUUID?.() ??
   `${Date.now()}-${Math.random().toString(16).slice(2)}`;
 
+const isConnectionError = (value: unknown): value is ConnectionError =>
+  !!value &&
+  typeof value === "object" &&
+  typeof (value as ConnectionError).kind === "string" &&
+  typeof (value as ConnectionError).message === "string";
+
+// acp commands reject with a structured `{ kind, message, ... }` object
 const parseConnectionError = (error: unknown): ConnectionError => {
+  if (isConnectionError(error)) {
+    return error;
+  }
   if (typeof error === "string") {
-    try {
-      const parsed = JSON.parse(error);
-      if (
-        parsed &&
-        typeof parsed.kind === "string" &&
-        typeof parsed.message === "string"
-      ) {
-        return parsed as ConnectionError;
-      }
-    } catch {
-      // not JSON, fall through
-    }
     return { kind: "unknown", message: error };
   }
   const message = error instanceof Error ? error.message : "unknown error";
-  // try parsing the error message as JSON (tauri wraps errors as strings)
+  return { kind: "unknown", message };
+};
+
+const getErrorMessage = (error: unknown, fallback: string): string => {
+  if (isConnectionError(error)) return error.message;
+  if (typeof error === "string") return error;
+  if (error instanceof Error) return error.message;
+  return fallback;
+};
+
+const saveTranscript = async (session: ChatSession | undefined) => {
+  if (!session || session.messages.length === 0) return;
   try {
-    const parsed = JSON.parse(message);
-    if (
-      parsed &&
-      typeof parsed.kind === "string" &&
-      typeof parsed.message === "string"
-    ) {
-      return parsed as ConnectionError;
-    }
-  } catch {
-    // not JSON
+    await invoke("save_transcript", {
+      transcript: {
+        sessionId: session.sessionId,
+        agentId: session.agentConfigId,
+        agentName: session.agentName,
+        messages: session.messages.map((message) => ({
+          role: message.role,
+          content: message.content,
+        })),
+      },
+    });
+  } catch (error) {
+    console.warn("failed to save transcript:", error);
   }
-  return { kind: "unknown", message };
 };
 
 const normalizeToolStatus = (status: string): ToolCallStatus => {
@@ -524,7 +573,13 @@ const ensureCrashListener = () => {
   if (crashListenerRegistered) return;
   crashListenerRegistered = true;
 
-  void listen<AgentCrashedPayload>("acp-agent-crashed", (event) => {
+  void listen<AgentCrashedPayload>("acp-agent-crashed", async (event) => {
+    const stderrTail = await invoke<AgentDiagnostics>(
+      "acp_get_agent_diagnostics",
+      { agentId: event.payload.agentId },
+    )
+      .then((diagnostics) => diagnostics.stderrTail)
+      .catch(() => undefined);
     const { sessions, chatTabs } = useAgentStore.getState();
 
     const rawMessage =
@@ -563,6 +618,7 @@ const ensureCrashListener = () => {
           connectionError: {
             kind: kind as ConnectionError["kind"],
             message: rawMessage,
+            stderrTail,
           },
         };
       }
@@ -755,6 +811,7 @@ export const useAgentStore = create<AgentStore>((set, get) => ({
       const info = await invoke<AgentInfoResponse>("acp_connect", {
         command: agent.command,
         env: agent.env,
+        timeouts: agent.timeouts,
       });
 
       // Check if tab still exists
@@ -900,8 +957,7 @@ export const useAgentStore = create<AgentStore>((set, get) => ({
           lastSentModeId: session.currentModeId,
         });
       } catch (error) {
-        const message =
-          error instanceof Error ? error.message : "failed to set mode";
+        const message = getErrorMessage(error, "failed to set mode");
         toast.error("mode sync failed", { description: message });
         return;
       }
@@ -926,8 +982,7 @@ export const useAgentStore = create<AgentStore>((set, get) => ({
           lastSentModelId: session.currentModelId,
         });
       } catch (error) {
-        const message =
-          error instanceof Error ? error.message : "failed to set model";
+        const message = getErrorMessage(error, "failed to set model");
         toast.error("model sync failed", { description: message });
         return;
       }
@@ -1074,6 +1129,11 @@ export const useAgentStore = create<AgentStore>((set, get) => ({
           pendingThinkingChunks += event.data.text;
           scheduleFlush();
           return;
+        case "resourceLink":
+          // vault-relative path, rendered as an inline markdown link
+          pendingTextChunks += `[${event.data.title}](<${event.data.path}>)`;
+          scheduleFlush();
+          return;
       }
 
       // For all other events, flush pending chunks first to preserve order
@@ -1155,6 +1215,11 @@ export const useAgentStore = create<AgentStore>((set, get) => ({
               },
             ];
             break;
+          case "permissionTimedOut":
+            updated.pendingPermissions = sess.pendingPermissions.filter(
+              (permission) => permission.requestId !== event.data.requestId,
+            );
+            break;
           case "modeUpdate":
             updated.currentModeId = event.data.currentModeId;
             updated.lastSentModeId = event.data.currentModeId;
@@ -1202,8 +1267,7 @@ export const useAgentStore = create<AgentStore>((set, get) => ({
         onEvent,
       });
     } catch (error) {
-      const message =
-        error instanceof Error ? error.message : "failed to send prompt";
+      const message = getErrorMessage(error, "failed to send prompt");
       updateSession(set, sessionId, (s) => ({
         isResponding: false,
         pendingPermissions: [],
@@ -1220,6 +1284,8 @@ export const useAgentStore = create<AgentStore>((set, get) => ({
         ),
       }));
     }
+
+    void saveTranscript(get().sessions[sessionId]);
   },
 
   respondPermission: async (sessionId, requestId, optionId) => {
@@ -1238,10 +1304,10 @@ export const useAgentStore = create<AgentStore>((set, get) => ({
         ),
       }));
     } catch (error) {
-      const message =
-        error instanceof Error
-          ? error.message
-          : "failed to send permission response";
+      const message = getErrorMessage(
+        error,
+        "failed to send permission response",
+      );
       toast.error("permission response failed", { description: message });
     }
   },
@@ -1257,8 +1323,7 @@ export const useAgentStore = create<AgentStore>((set, get) => ({
       });
       updateSession(set, sessionId, { pendingPermissions: [] });
     } catch (error) {
-      const message =
-        error instanceof Error ? error.message : "failed to cancel prompt";
+      const message = getErrorMessage(error, "failed to cancel prompt");
       toast.error("cancel failed", { description: message });
     }
   },